@@ -1,8 +1,8 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_json_binary, Binary, Coin, Decimal, Deps, DepsMut, Env, Fraction, MessageInfo, Reply, Response,
-    StdResult, SubMsg, Uint128, WasmMsg,
+    to_json_binary, Addr, Binary, Coin, Decimal, Deps, DepsMut, Env, Fraction, MessageInfo, Reply,
+    Response, StdError, StdResult, SubMsg, Uint128, WasmMsg,
 };
 use cw2::set_contract_version;
 use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
@@ -10,17 +10,23 @@ use cw_utils::must_pay;
 use wyndex::asset::{Asset, AssetInfo};
 
 use wynd_lsd_hub::msg::{
-    ConfigResponse as HubConfigResponse, ExchangeRateResponse, ExecuteMsg as HubExecuteMsg,
-    QueryMsg as HubQueryMsg, SupplyResponse as HubSupplyResponse,
+    AssetInfoResponse as HubAssetInfoResponse, ConfigResponse as HubConfigResponse,
+    ExchangeRateResponse, ExecuteMsg as HubExecuteMsg, QueryMsg as HubQueryMsg, RewardRateResponse,
+    SupplyResponse as HubSupplyResponse,
 };
+use wynd_lsd_hub::slippage::ensure_min;
+use wynd_lsd_hub::ContractError as HubContractError;
 use wyndex::pair::{
-    ExecuteMsg as PairExecuteMsg, QueryMsg as PairQueryMsg, SimulationResponse,
-    SpotPricePredictionResponse,
+    ExecuteMsg as PairExecuteMsg, PairInfo, PoolResponse, QueryMsg as PairQueryMsg,
+    SimulationResponse, SpotPricePredictionResponse, SpotPriceResponse,
 };
 
 use crate::error::ContractError;
-use crate::msg::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, SimulateResponse};
-use crate::state::{Config, CONFIG, REPLY_INFO};
+use crate::msg::{
+    CompareRoutesResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+    ShouldRouteResponse, SimulateResponse, StatsResponse,
+};
+use crate::state::{Config, PendingBond, PendingClaim, CONFIG, PENDING_BONDS, PENDING_CLAIM};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:bond-router";
@@ -28,6 +34,8 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 // ids for reply handlers
 const REPLY_BOND_ID: u64 = 1;
+const REPLY_CLAIM_ID: u64 = 2;
+const REPLY_PROVIDE_ID: u64 = 3;
 
 // Number of iterations to use to predict spot price.
 // TODO: check gas usage and see if we tune up or down
@@ -46,16 +54,24 @@ pub fn instantiate(
     let pair = deps.api.addr_validate(&msg.pair)?;
 
     // get static info from the lsd hub
-    let cfg: HubConfigResponse = deps
-        .querier
-        .query_wasm_smart(&hub, &HubQueryMsg::Config {})
-        .map_err(|_| ContractError::NotLsdHub)?;
-    let sup: HubSupplyResponse = deps
+    let (lsd_token, bond_denom) = match deps
         .querier
-        .query_wasm_smart(&hub, &HubQueryMsg::Supply {})
-        .map_err(|_| ContractError::NotLsdHub)?;
-    let lsd_token = cfg.token_contract;
-    let bond_denom = sup.supply.bond_denom;
+        .query_wasm_smart::<HubAssetInfoResponse>(&hub, &HubQueryMsg::AssetInfo {})
+    {
+        Ok(info) => (info.lsd_token, info.bond_denom),
+        // older hubs don't support the `AssetInfo` query yet
+        Err(_) => {
+            let cfg: HubConfigResponse = deps
+                .querier
+                .query_wasm_smart(&hub, &HubQueryMsg::Config {})
+                .map_err(|_| ContractError::NotLsdHub)?;
+            let sup: HubSupplyResponse = deps
+                .querier
+                .query_wasm_smart(&hub, &HubQueryMsg::Supply {})
+                .map_err(|_| ContractError::NotLsdHub)?;
+            (cfg.token_contract, sup.supply.bond_denom)
+        }
+    };
 
     // save config
     let config = Config {
@@ -63,6 +79,7 @@ pub fn instantiate(
         pair,
         lsd_token,
         bond_denom,
+        min_pool_depth: msg.min_pool_depth,
     };
     CONFIG.save(deps.storage, &config)?;
 
@@ -72,23 +89,26 @@ pub fn instantiate(
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::Bond {} => execute_bond(deps, info),
+        ExecuteMsg::Bond {} => execute_bond(deps, env, info),
+        ExecuteMsg::ClaimAndProvide { min_lp_amount } => {
+            execute_claim_and_provide(deps, env, info, min_lp_amount)
+        }
     }
 }
 
 /// Algorithm:
 ///   1. Ensure we are sent some of the proper tokens in funds
 ///   2. Check the current exchange rate for bonding
-///   3. Check how many tokens can be swapped up to that rate on the pool
+///   3. Check how many tokens can be swapped up to that rate on the pool (none if the pool is too shallow)
 ///   4. Create messages swapping those tokens (if any) and bonding remaining tokens (if any)
-///   5. Temp store the sender to get rewards
-///   6. Reply::on_success for last message, sending all lsd_token to this temp.sender
-pub fn execute_bond(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+///   5. Queue the sender and our current lsd_token balance for the reply
+///   6. Reply::on_success for last message, sending the newly received lsd_token to the queued sender
+pub fn execute_bond(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
     let cfg = CONFIG.load(deps.storage)?;
     let mut pay = must_pay(&info, &cfg.bond_denom)?;
 
@@ -96,29 +116,20 @@ pub fn execute_bond(deps: DepsMut, info: MessageInfo) -> Result<Response, Contra
         .querier
         .query_wasm_smart(&cfg.hub, &HubQueryMsg::ExchangeRate {})?;
 
-    let SpotPricePredictionResponse { trade } = deps.querier.query_wasm_smart(
-        &cfg.pair,
-        &PairQueryMsg::SpotPricePrediction {
-            offer: AssetInfo::Native(cfg.bond_denom.clone()),
-            ask: AssetInfo::Token(cfg.lsd_token.to_string()),
-            max_trade: pay,
-            target_price: exchange_rate,
-            iterations: ITERATIONS,
-        },
-    )?;
+    let trade = swap_amount(deps.as_ref(), &cfg, pay, exchange_rate)?;
 
     let mut res = Response::new().add_attribute("execute", "bond");
 
     // if there is something to swap, swap it
     if let Some(to_swap) = trade {
         let msg = WasmMsg::Execute {
-            contract_addr: cfg.pair.into_string(),
+            contract_addr: cfg.pair.to_string(),
             msg: to_json_binary(&PairExecuteMsg::Swap {
                 offer_asset: Asset {
                     info: AssetInfo::Native(cfg.bond_denom.clone()),
                     amount: to_swap,
                 },
-                ask_asset_info: Some(AssetInfo::Token(cfg.lsd_token.into_string())),
+                ask_asset_info: Some(AssetInfo::Token(cfg.lsd_token.to_string())),
                 belief_price: None,
                 max_spread: Some(Decimal::percent(50)),
                 // send back directly to original sender, so no reply needed
@@ -142,6 +153,9 @@ pub fn execute_bond(deps: DepsMut, info: MessageInfo) -> Result<Response, Contra
 
     // anything left should be bonded, this
     if !pay.is_zero() {
+        // remember how many lsd tokens we had before, so we only forward the newly minted ones
+        let balance_before = query_lsd_balance(deps.as_ref(), &env, &cfg)?;
+
         // just bond
         let msg = WasmMsg::Execute {
             contract_addr: cfg.hub.into_string(),
@@ -158,37 +172,82 @@ pub fn execute_bond(deps: DepsMut, info: MessageInfo) -> Result<Response, Contra
             .add_attribute("amount", pay);
 
         // store some state for the reply block
-        REPLY_INFO.save(deps.storage, &info.sender)?;
+        PENDING_BONDS.push_back(
+            deps.storage,
+            &PendingBond {
+                recipient: info.sender,
+                balance_before,
+            },
+        )?;
     }
 
     // TODO: add some events here?
     Ok(res)
 }
 
+/// Algorithm:
+///   1. Claim the sender's mature claims on the hub, which sends the tokens to this contract
+///   2. Queue the sender and our current native balance for the reply
+///   3. Reply::on_success, providing the newly received native tokens as liquidity
+///   4. Reply::on_success, sending the newly received LP tokens to the queued sender if they are enough
+pub fn execute_claim_and_provide(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    min_lp_amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let balance_before = deps
+        .querier
+        .query_balance(&env.contract.address, &cfg.bond_denom)?
+        .amount;
+
+    let msg = WasmMsg::Execute {
+        contract_addr: cfg.hub.into_string(),
+        msg: to_json_binary(&HubExecuteMsg::ClaimFor {
+            owner: info.sender.to_string(),
+        })?,
+        funds: vec![],
+    };
+    PENDING_CLAIM.save(
+        deps.storage,
+        &PendingClaim {
+            recipient: info.sender,
+            balance_before,
+            min_lp_amount,
+            lp_balance_before: Uint128::zero(),
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(msg, REPLY_CLAIM_ID))
+        .add_attribute("execute", "claim_and_provide"))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn reply(deps: DepsMut, env: Env, reply: Reply) -> Result<Response, ContractError> {
     match reply.id {
         // only on success and we just query current state, ignore response data
         REPLY_BOND_ID => reply_bond_callback(deps, env),
+        REPLY_CLAIM_ID => reply_claim_callback(deps, env),
+        REPLY_PROVIDE_ID => reply_provide_callback(deps, env),
         _ => Err(ContractError::InvalidReplyId(reply.id)),
     }
 }
 
 pub fn reply_bond_callback(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
     // figure out who to send back to
-    let recipient = REPLY_INFO.load(deps.storage)?.to_string();
-
-    // figure out how much we received
+    let PendingBond {
+        recipient,
+        balance_before,
+    } = PENDING_BONDS
+        .pop_front(deps.storage)?
+        .ok_or(ContractError::NoPendingBond)?;
+
+    // figure out how much we received from this bond
     let cfg = CONFIG.load(deps.storage)?;
-    let amount = deps
-        .querier
-        .query_wasm_smart::<BalanceResponse>(
-            cfg.lsd_token.clone(),
-            &Cw20QueryMsg::Balance {
-                address: env.contract.address.to_string(),
-            },
-        )?
-        .balance;
+    let amount = query_lsd_balance(deps.as_ref(), &env, &cfg)?.saturating_sub(balance_before);
+    let recipient = recipient.into_string();
 
     let mut response = Response::new();
     if !amount.is_zero() {
@@ -203,6 +262,149 @@ pub fn reply_bond_callback(deps: DepsMut, env: Env) -> Result<Response, Contract
     Ok(response)
 }
 
+pub fn reply_claim_callback(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let mut pending = PENDING_CLAIM
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingClaim)?;
+
+    // figure out how much we received from the claim.
+    // The hub pays everything else (like external rewards) to the owner of the claims directly
+    let cfg = CONFIG.load(deps.storage)?;
+    let amount = deps
+        .querier
+        .query_balance(&env.contract.address, &cfg.bond_denom)?
+        .amount
+        .saturating_sub(pending.balance_before);
+
+    // the LP tokens are minted to this contract, so the reply can check the minimum
+    pending.lp_balance_before = query_lp_token_and_balance(deps.as_ref(), &env, &cfg)?.1;
+    PENDING_CLAIM.save(deps.storage, &pending)?;
+
+    // provide it single-sided. The slippage is bounded by `min_lp_amount` in the reply instead
+    let msg = WasmMsg::Execute {
+        contract_addr: cfg.pair.to_string(),
+        msg: to_json_binary(&PairExecuteMsg::ProvideLiquidity {
+            assets: vec![Asset {
+                info: AssetInfo::Native(cfg.bond_denom.clone()),
+                amount,
+            }],
+            slippage_tolerance: None,
+            receiver: None,
+        })?,
+        funds: vec![Coin {
+            denom: cfg.bond_denom,
+            amount,
+        }],
+    };
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(msg, REPLY_PROVIDE_ID))
+        .add_attribute("provide", amount)
+        .add_attribute("recipient", pending.recipient))
+}
+
+pub fn reply_provide_callback(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let PendingClaim {
+        recipient,
+        min_lp_amount,
+        lp_balance_before,
+        ..
+    } = PENDING_CLAIM
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingClaim)?;
+    PENDING_CLAIM.remove(deps.storage);
+
+    let cfg = CONFIG.load(deps.storage)?;
+    let (lp_token, lp_balance) = query_lp_token_and_balance(deps.as_ref(), &env, &cfg)?;
+    let amount = lp_balance.saturating_sub(lp_balance_before);
+    // same check and error as the slippage limits of the hub
+    ensure_min(amount, min_lp_amount).map_err(|err| match err {
+        HubContractError::SlippageExceeded { expected, actual } => {
+            ContractError::SlippageExceeded { expected, actual }
+        }
+        err => StdError::generic_err(err.to_string()).into(),
+    })?;
+
+    Ok(Response::new()
+        .add_message(WasmMsg::Execute {
+            contract_addr: lp_token.into_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        })
+        .add_attribute("lp_amount", amount))
+}
+
+/// Returns the LP token of the pair and the balance of this contract
+fn query_lp_token_and_balance(deps: Deps, env: &Env, cfg: &Config) -> StdResult<(Addr, Uint128)> {
+    let PairInfo {
+        liquidity_token, ..
+    } = deps
+        .querier
+        .query_wasm_smart(&cfg.pair, &PairQueryMsg::Pair {})?;
+    let BalanceResponse { balance } = deps.querier.query_wasm_smart(
+        &liquidity_token,
+        &Cw20QueryMsg::Balance {
+            address: env.contract.address.to_string(),
+        },
+    )?;
+    Ok((liquidity_token, balance))
+}
+
+/// Returns how much of `amount` should be swapped on the pool, `None` if everything should be bonded.
+/// The swap leg is skipped if the pool is too shallow for the trade, see [`Config::min_pool_depth`].
+fn swap_amount(
+    deps: Deps,
+    cfg: &Config,
+    amount: Uint128,
+    exchange_rate: Decimal,
+) -> StdResult<Option<Uint128>> {
+    let SpotPricePredictionResponse { trade } = deps.querier.query_wasm_smart(
+        &cfg.pair,
+        &PairQueryMsg::SpotPricePrediction {
+            offer: AssetInfo::Native(cfg.bond_denom.clone()),
+            ask: AssetInfo::Token(cfg.lsd_token.to_string()),
+            max_trade: amount,
+            target_price: exchange_rate,
+            iterations: ITERATIONS,
+        },
+    )?;
+
+    let to_swap = match trade {
+        Some(to_swap) if !cfg.min_pool_depth.is_zero() => to_swap,
+        trade => return Ok(trade),
+    };
+    let PoolResponse { assets, .. } = deps
+        .querier
+        .query_wasm_smart(&cfg.pair, &PairQueryMsg::Pool {})?;
+    let reserve = assets
+        .into_iter()
+        .find(|asset| asset.info == AssetInfo::Native(cfg.bond_denom.clone()))
+        .map(|asset| asset.amount)
+        .unwrap_or_default();
+
+    if reserve < to_swap.mul_ceil(cfg.min_pool_depth) {
+        Ok(None)
+    } else {
+        Ok(Some(to_swap))
+    }
+}
+
+/// Returns the lsd token balance of this contract
+fn query_lsd_balance(deps: Deps, env: &Env, cfg: &Config) -> StdResult<Uint128> {
+    Ok(deps
+        .querier
+        .query_wasm_smart::<BalanceResponse>(
+            &cfg.lsd_token,
+            &Cw20QueryMsg::Balance {
+                address: env.contract.address.to_string(),
+            },
+        )?
+        .balance)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -211,9 +413,28 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             to_json_binary(&cfg)
         }
         QueryMsg::Simulate { bond } => to_json_binary(&query_simulate(deps, bond)?),
+        QueryMsg::CompareRoutes { bond } => to_json_binary(&query_compare_routes(deps, bond)?),
+        QueryMsg::Stats {} => to_json_binary(&query_stats(deps)?),
+        QueryMsg::ShouldRoute { amount } => to_json_binary(&query_should_route(deps, amount)?),
     }
 }
 
+pub fn query_stats(deps: Deps) -> StdResult<StatsResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+
+    let ExchangeRateResponse { exchange_rate } = deps
+        .querier
+        .query_wasm_smart(&cfg.hub, &HubQueryMsg::ExchangeRate {})?;
+    let RewardRateResponse { apr, .. } = deps
+        .querier
+        .query_wasm_smart(&cfg.hub, &HubQueryMsg::RewardRate {})?;
+
+    Ok(StatsResponse {
+        exchange_rate,
+        estimated_apr: apr,
+    })
+}
+
 pub fn query_simulate(deps: Deps, bond: Uint128) -> StdResult<SimulateResponse> {
     let cfg = CONFIG.load(deps.storage)?;
 
@@ -221,40 +442,122 @@ pub fn query_simulate(deps: Deps, bond: Uint128) -> StdResult<SimulateResponse>
         .querier
         .query_wasm_smart(&cfg.hub, &HubQueryMsg::ExchangeRate {})?;
 
-    let SpotPricePredictionResponse { trade } = deps.querier.query_wasm_smart(
-        &cfg.pair,
-        &PairQueryMsg::SpotPricePrediction {
-            offer: AssetInfo::Native(cfg.bond_denom.clone()),
-            ask: AssetInfo::Token(cfg.lsd_token.to_string()),
-            max_trade: bond,
-            target_price: exchange_rate,
-            iterations: ITERATIONS,
-        },
-    )?;
+    // this is the same split `execute_bond` uses
+    let swap_in = swap_amount(deps, &cfg, bond, exchange_rate)?.unwrap_or_default();
+    let bond_in = bond - swap_in;
 
-    // how many lsd we get from bonding
-    let bond = bond - trade.unwrap_or_default();
-    // let mut lsd_val = bond / exchange_rate;
-    let mut lsd_val = bond * exchange_rate.denominator() / exchange_rate.numerator();
+    let swap_out = if swap_in.is_zero() {
+        Uint128::zero()
+    } else {
+        simulate_swap(deps, &cfg, swap_in)?
+    };
+    let bond_out = simulate_exact_bond(deps, &cfg, bond_in, exchange_rate)?;
 
-    if let Some(trade) = trade {
-        // simulate swap to see how much would be there
-        let res: SimulationResponse = deps.querier.query_wasm_smart(
+    // without a swap, the pool stays as it is
+    let pool_price_after = if swap_in.is_zero() {
+        let SpotPriceResponse { price } = deps.querier.query_wasm_smart(
             &cfg.pair,
-            &PairQueryMsg::Simulation {
-                offer_asset: Asset {
-                    info: AssetInfo::Native(cfg.bond_denom.clone()),
-                    amount: trade,
-                },
-                ask_asset_info: Some(AssetInfo::Token(cfg.lsd_token.to_string())),
-                referral: false,
-                referral_commission: None,
+            &PairQueryMsg::SpotPrice {
+                offer: AssetInfo::Native(cfg.bond_denom.clone()),
+                ask: AssetInfo::Token(cfg.lsd_token.to_string()),
             },
         )?;
+        Some(price)
+    } else {
+        None
+    };
+
+    Ok(SimulateResponse {
+        lsd_val: swap_out + bond_out,
+        swap_in,
+        swap_out,
+        bond_in,
+        bond_out,
+        pool_price_after,
+    })
+}
+
+pub fn query_compare_routes(deps: Deps, bond: Uint128) -> StdResult<CompareRoutesResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+
+    let ExchangeRateResponse { exchange_rate } = deps
+        .querier
+        .query_wasm_smart(&cfg.hub, &HubQueryMsg::ExchangeRate {})?;
+
+    Ok(CompareRoutesResponse {
+        optimal: query_simulate(deps, bond)?.lsd_val,
+        all_swap: simulate_swap(deps, &cfg, bond)?,
+        // same calculation as the bond part of `optimal`, so the difference is not just rounding
+        all_bond: simulate_exact_bond(deps, &cfg, bond, exchange_rate)?,
+    })
+}
+
+pub fn query_should_route(deps: Deps, amount: Uint128) -> StdResult<ShouldRouteResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+
+    let ExchangeRateResponse { exchange_rate } = deps
+        .querier
+        .query_wasm_smart(&cfg.hub, &HubQueryMsg::ExchangeRate {})?;
+
+    let simulation = query_simulate(deps, amount)?;
+    // without a trade, the router just bonds everything on the hub
+    let expected_gain_lsd = if simulation.swap_in.is_zero() {
+        Uint128::zero()
+    } else {
+        simulation
+            .lsd_val
+            .saturating_sub(simulate_exact_bond(deps, &cfg, amount, exchange_rate)?)
+    };
+
+    Ok(ShouldRouteResponse {
+        route_via_pool: !expected_gain_lsd.is_zero(),
+        expected_gain_lsd,
+        breakeven_gas_native: expected_gain_lsd.mul_floor(exchange_rate),
+    })
+}
 
-        // add this to what we get from bonding
-        lsd_val += res.return_amount;
+/// Approximates how many lsd tokens we get from bonding `amount` on the hub at the given exchange rate.
+/// Only used as the fallback of [`simulate_exact_bond`].
+fn simulate_bond(amount: Uint128, exchange_rate: Decimal) -> Uint128 {
+    // amount / exchange_rate
+    amount * exchange_rate.denominator() / exchange_rate.numerator()
+}
+
+/// Returns how many lsd tokens the hub mints for `amount`, using the same calculation as the hub.
+/// Falls back to [`simulate_bond`] for hubs that don't report their backing assets.
+fn simulate_exact_bond(
+    deps: Deps,
+    cfg: &Config,
+    amount: Uint128,
+    exchange_rate: Decimal,
+) -> StdResult<Uint128> {
+    let HubSupplyResponse {
+        supply,
+        backing_assets,
+        ..
+    } = deps
+        .querier
+        .query_wasm_smart(&cfg.hub, &HubQueryMsg::Supply {})?;
+    if supply.issued.is_zero() || backing_assets.is_zero() {
+        Ok(simulate_bond(amount, exchange_rate))
+    } else {
+        Ok(amount.multiply_ratio(supply.issued, backing_assets))
     }
+}
 
-    Ok(SimulateResponse { lsd_val })
+/// Returns how many lsd tokens we get from swapping `amount` on the pool
+fn simulate_swap(deps: Deps, cfg: &Config, amount: Uint128) -> StdResult<Uint128> {
+    let res: SimulationResponse = deps.querier.query_wasm_smart(
+        &cfg.pair,
+        &PairQueryMsg::Simulation {
+            offer_asset: Asset {
+                info: AssetInfo::Native(cfg.bond_denom.clone()),
+                amount,
+            },
+            ask_asset_info: Some(AssetInfo::Token(cfg.lsd_token.to_string())),
+            referral: false,
+            referral_commission: None,
+        },
+    )?;
+    Ok(res.return_amount)
 }
@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
 use cw_utils::PaymentError;
 use thiserror::Error;
 
@@ -15,4 +15,15 @@ pub enum ContractError {
 
     #[error("Recevied unexpected reply id: {0}")]
     InvalidReplyId(u64),
+
+    #[error("Received bond reply without a pending bond")]
+    NoPendingBond,
+
+    #[error("Received claim reply without a pending claim")]
+    NoPendingClaim,
+
+    /// Same as the `SlippageExceeded` of the hub, `expected` is the minimum the sender set
+    /// and `actual` what they would have received
+    #[error("Slippage exceeded, expected at least {expected}, but got {actual}")]
+    SlippageExceeded { expected: Uint128, actual: Uint128 },
 }
@@ -1,5 +1,5 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::Uint128;
+use cosmwasm_std::{Decimal, Uint128};
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -7,12 +7,24 @@ pub struct InstantiateMsg {
     pub hub: String,
     /// Address of the staking swap pool to trade on
     pub pair: String,
+    /// The swap leg is skipped (and everything is bonded) if the pool holds less than
+    /// `min_pool_depth` times the amount to swap of the bond denom. `0` disables the check
+    #[serde(default)]
+    pub min_pool_depth: Decimal,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
     /// Set staking Asset to bond to mint wyAsset
     Bond {},
+    /// Claims the sender's mature claims on the hub and provides the tokens as liquidity to the pair.
+    /// The LP tokens are sent to the sender, failing if they are less than `min_lp_amount`.
+    /// A single-sided provide moves the price of the pool, so without a minimum it can be sandwiched.
+    /// This requires the sender to set this contract as their claim recipient on the hub first.
+    ClaimAndProvide {
+        #[serde(default)]
+        min_lp_amount: Option<Uint128>,
+    },
 }
 
 #[cw_serde]
@@ -22,6 +34,16 @@ pub enum QueryMsg {
     Config {},
     #[returns(SimulateResponse)]
     Simulate { bond: Uint128 },
+    /// Compares the result of the optimal routing with just swapping or just bonding everything
+    #[returns(CompareRoutesResponse)]
+    CompareRoutes { bond: Uint128 },
+    /// Headline numbers of the hub, so frontends only integrating the router can display them
+    #[returns(StatsResponse)]
+    Stats {},
+    /// Tells wallets whether bonding `amount` through this router is worth the extra gas
+    /// compared to bonding directly on the hub
+    #[returns(ShouldRouteResponse)]
+    ShouldRoute { amount: Uint128 },
 }
 
 #[cw_serde]
@@ -34,6 +56,8 @@ pub struct ConfigResponse {
     pub bond_denom: String,
     /// This is the lsd token the users wishes to receive
     pub lsd_token: String,
+    /// The minimum pool reserve of the bond denom needed to swap, relative to the amount to swap
+    pub min_pool_depth: Decimal,
 }
 
 impl From<crate::state::Config> for ConfigResponse {
@@ -43,11 +67,55 @@ impl From<crate::state::Config> for ConfigResponse {
             pair: cfg.pair.into_string(),
             bond_denom: cfg.bond_denom,
             lsd_token: cfg.lsd_token.into_string(),
+            min_pool_depth: cfg.min_pool_depth,
         }
     }
 }
 
 #[cw_serde]
 pub struct SimulateResponse {
+    /// The total amount of lsd tokens received, `swap_out + bond_out`
     pub lsd_val: Uint128,
+    /// The amount of native tokens swapped on the pool
+    pub swap_in: Uint128,
+    /// The amount of lsd tokens received from the swap
+    pub swap_out: Uint128,
+    /// The amount of native tokens bonded on the hub
+    pub bond_in: Uint128,
+    /// The amount of lsd tokens minted by the hub
+    pub bond_out: Uint128,
+    /// The spot price of the pool after routing.
+    /// This is `None` if anything is swapped, since the pool state after the swap cannot be queried.
+    pub pool_price_after: Option<Decimal>,
+}
+
+#[cw_serde]
+pub struct CompareRoutesResponse {
+    /// The amount of lsd tokens received when routing optimally (same as [`QueryMsg::Simulate`])
+    pub optimal: Uint128,
+    /// The amount of lsd tokens received when swapping everything on the pool
+    pub all_swap: Uint128,
+    /// The amount of lsd tokens received when bonding everything on the hub
+    pub all_bond: Uint128,
+}
+
+#[cw_serde]
+pub struct StatsResponse {
+    /// The hub's current exchange rate of lsd tokens to native tokens
+    pub exchange_rate: Decimal,
+    /// The hub's estimated yearly reward rate, e.g. `0.1` for 10%.
+    /// This is an estimate derived from the exchange rate growth between the hub's last two reinvests
+    /// (see the hub's `RewardRate` query), not a prediction.
+    pub estimated_apr: Decimal,
+}
+
+#[cw_serde]
+pub struct ShouldRouteResponse {
+    /// `true` if the router would swap anything, so it gives more than bonding on the hub directly
+    pub route_via_pool: bool,
+    /// The extra lsd tokens received through the router compared to bonding everything on the hub
+    pub expected_gain_lsd: Uint128,
+    /// `expected_gain_lsd` in native tokens at the hub's exchange rate.
+    /// Routing is only worth it if the extra gas costs less than this.
+    pub breakeven_gas_native: Uint128,
 }
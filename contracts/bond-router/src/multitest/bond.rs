@@ -165,3 +165,357 @@ fn bond_with_uneven_pool_liquidity() {
     let lsd_balance = suite.query_cw20_balance(user, &lsd_token).unwrap();
     assert!(lsd_balance > 250_000_000u128);
 }
+
+#[test]
+fn compare_routes_in_skewed_pool() {
+    let user = "user";
+    let admin = "admin";
+    let mut suite = SuiteBuilder::new()
+        .with_funds(user, (500_000_000u128, NATIVE))
+        .with_funds(admin, (400_000_000u128, NATIVE))
+        .with_lsd_funds(admin, 500_000_000u128)
+        .build();
+
+    let lsd_pool = suite.lsd_pool.clone();
+    let lsd_token = suite.lsd_token.clone();
+
+    suite
+        .increase_allowance(admin, &lsd_token, lsd_pool.as_str(), 500_000_000u128)
+        .unwrap();
+    // pool has less native tokens than lsd tokens, so lsd is cheap on the pool
+    suite
+        .provide_liquidity(
+            admin,
+            &lsd_pool,
+            &[
+                Asset {
+                    info: AssetInfo::Token(lsd_token.to_string()),
+                    amount: 500_000_000u128.into(),
+                },
+                Asset {
+                    info: AssetInfo::Native(NATIVE.to_owned()),
+                    amount: 300_000_000u128.into(),
+                },
+            ],
+            &coins(300_000_000, NATIVE),
+        )
+        .unwrap();
+
+    for bond in [10_000_000u128, 250_000_000u128, 500_000_000u128] {
+        let routes = suite.query_compare_routes(bond).unwrap();
+        assert_eq!(
+            routes.optimal.u128(),
+            suite.query_simulate(bond).unwrap(),
+            "optimal route should match simulate"
+        );
+        assert_eq!(routes.all_bond, Uint128::new(bond), "exchange rate is 1");
+        assert!(
+            routes.optimal >= routes.all_swap.max(routes.all_bond),
+            "optimal route should be at least as good as the naive ones for bond {}: {:?}",
+            bond,
+            routes
+        );
+    }
+}
+
+#[test]
+fn simulate_reports_the_split_used_by_bond() {
+    let user = "user";
+    let admin = "admin";
+    let mut suite = SuiteBuilder::new()
+        .with_funds(user, (500_000_000u128, NATIVE))
+        .with_funds(admin, (400_000_000u128, NATIVE))
+        .with_lsd_funds(admin, 500_000_000u128)
+        .build();
+
+    let lsd_pool = suite.lsd_pool.clone();
+    let lsd_token = suite.lsd_token.clone();
+
+    suite
+        .increase_allowance(admin, &lsd_token, lsd_pool.as_str(), 500_000_000u128)
+        .unwrap();
+    suite
+        .provide_liquidity(
+            admin,
+            &lsd_pool,
+            &[
+                Asset {
+                    info: AssetInfo::Token(lsd_token.to_string()),
+                    amount: 500_000_000u128.into(),
+                },
+                Asset {
+                    info: AssetInfo::Native(NATIVE.to_owned()),
+                    amount: 300_000_000u128.into(),
+                },
+            ],
+            &coins(300_000_000, NATIVE),
+        )
+        .unwrap();
+
+    // bonding this much swaps until the pool price reaches the exchange rate and bonds the rest
+    let amount = 250_000_000u128;
+    let simulation = suite.query_simulate_response(amount).unwrap();
+    assert!(!simulation.swap_in.is_zero());
+    assert!(!simulation.bond_in.is_zero());
+    assert_eq!(
+        simulation.swap_in + simulation.bond_in,
+        Uint128::new(amount)
+    );
+    assert_eq!(
+        simulation.lsd_val,
+        simulation.swap_out + simulation.bond_out
+    );
+    assert_eq!(simulation.pool_price_after, None);
+
+    let pool_native_before = suite
+        .app
+        .wrap()
+        .query_balance(&lsd_pool, NATIVE)
+        .unwrap()
+        .amount;
+    let issued_before = suite.query_lsd_supply().unwrap().issued;
+
+    suite.bond(user, (amount, NATIVE)).unwrap();
+
+    let pool_native_after = suite
+        .app
+        .wrap()
+        .query_balance(&lsd_pool, NATIVE)
+        .unwrap()
+        .amount;
+    let issued_after = suite.query_lsd_supply().unwrap().issued;
+    let received = suite.query_cw20_balance(user, &lsd_token).unwrap();
+
+    assert_eq!(pool_native_after - pool_native_before, simulation.swap_in);
+    assert_eq!(
+        Uint128::new(amount) - (pool_native_after - pool_native_before),
+        simulation.bond_in
+    );
+    assert_eq!(issued_after - issued_before, simulation.bond_out);
+    assert_eq!(
+        Uint128::new(received) - (issued_after - issued_before),
+        simulation.swap_out
+    );
+    assert_eq!(Uint128::new(received), simulation.lsd_val);
+}
+
+#[test]
+fn bonds_in_one_tx_receive_their_own_tokens() {
+    let user = "user";
+    let admin = "admin";
+    let mut suite = SuiteBuilder::new()
+        .with_funds(user, (500_000_000u128, NATIVE))
+        .with_funds(admin, (500_000_000u128, NATIVE))
+        .with_lsd_funds(admin, 200_000_000u128)
+        .build();
+
+    let lsd_pool = suite.lsd_pool.clone();
+    let lsd_token = suite.lsd_token.clone();
+    let bond_router = suite.bond_router.clone();
+
+    suite
+        .increase_allowance(admin, &lsd_token, lsd_pool.as_str(), 100_000_000u128)
+        .unwrap();
+    // lsd is expensive on the pool, so everything gets bonded on the hub
+    suite
+        .provide_liquidity(
+            admin,
+            &lsd_pool,
+            &[
+                Asset {
+                    info: AssetInfo::Token(lsd_token.to_string()),
+                    amount: 100_000_000u128.into(),
+                },
+                Asset {
+                    info: AssetInfo::Native(NATIVE.to_owned()),
+                    amount: 500_000_000u128.into(),
+                },
+            ],
+            &coins(500_000_000, NATIVE),
+        )
+        .unwrap();
+
+    // someone sends lsd tokens to the router, they must not end up with the next bonder
+    suite.transfer_lsd(admin, &bond_router, 1_000_000).unwrap();
+
+    let first = suite.instantiate_proxy("first").unwrap();
+    let second = suite.instantiate_proxy("second").unwrap();
+    suite
+        .bond_via_proxies(
+            user,
+            &[(first.clone(), 100_000_000), (second.clone(), 50_000_000)],
+        )
+        .unwrap();
+
+    // nothing was swapped, and exchange rate is 1
+    assert_eq!(
+        suite.query_lsd_supply().unwrap().issued,
+        Uint128::new(150_000_000)
+    );
+    assert_eq!(
+        suite
+            .query_cw20_balance(first.as_str(), &lsd_token)
+            .unwrap(),
+        100_000_000
+    );
+    assert_eq!(
+        suite
+            .query_cw20_balance(second.as_str(), &lsd_token)
+            .unwrap(),
+        50_000_000
+    );
+    assert_eq!(
+        suite
+            .query_cw20_balance(bond_router.as_str(), &lsd_token)
+            .unwrap(),
+        1_000_000
+    );
+}
+
+#[test]
+fn bond_skips_swap_on_shallow_pool() {
+    let user = "user";
+    let admin = "admin";
+    let mut suite = SuiteBuilder::new()
+        .with_funds(user, (100_000_000u128, NATIVE))
+        .with_funds(admin, (1_000_000u128, NATIVE))
+        .with_lsd_funds(admin, 1_000_000u128)
+        .with_min_pool_depth(Decimal::from_atomics(10u128, 0).unwrap())
+        .build();
+
+    let lsd_pool = suite.lsd_pool.clone();
+    let lsd_token = suite.lsd_token.clone();
+
+    suite
+        .increase_allowance(admin, &lsd_token, lsd_pool.as_str(), 1_000_000u128)
+        .unwrap();
+    // the pool is balanced, so the lsd token is cheaper there, but it is very shallow
+    suite
+        .provide_liquidity(
+            admin,
+            &lsd_pool,
+            &[
+                Asset {
+                    info: AssetInfo::Token(lsd_token.to_string()),
+                    amount: 1_000_000u128.into(),
+                },
+                Asset {
+                    info: AssetInfo::Native(NATIVE.to_owned()),
+                    amount: 1_000_000u128.into(),
+                },
+            ],
+            &coins(1_000_000, NATIVE),
+        )
+        .unwrap();
+    let price_before = suite.query_spot_price().unwrap();
+    assert!(price_before > suite.query_exchange_rate().unwrap());
+
+    let simulation = suite.query_simulate_response(100_000_000u128).unwrap();
+    assert_eq!(simulation.swap_in, Uint128::zero());
+    assert_eq!(simulation.bond_in, Uint128::new(100_000_000));
+    let routes = suite.query_compare_routes(100_000_000u128).unwrap();
+    assert_eq!(routes.optimal, routes.all_bond);
+
+    suite.bond(user, (100_000_000u128, NATIVE)).unwrap();
+
+    // everything was bonded at the exchange rate and the pool was not touched
+    let issued_lsd = suite.query_lsd_supply().unwrap().issued;
+    assert_eq!(issued_lsd, Uint128::new(100_000_000));
+    assert_eq!(
+        suite.query_cw20_balance(user, &lsd_token).unwrap(),
+        100_000_000u128
+    );
+    assert_eq!(suite.query_spot_price().unwrap(), price_before);
+
+    let config = suite.query_router_config(&suite.bond_router).unwrap();
+    assert_eq!(
+        config.min_pool_depth,
+        Decimal::from_atomics(10u128, 0).unwrap()
+    );
+}
+
+#[test]
+fn should_route_through_discounted_pool() {
+    let admin = "admin";
+    let mut suite = SuiteBuilder::new()
+        .with_funds(admin, (300_000_000u128, NATIVE))
+        .with_lsd_funds(admin, 500_000_000u128)
+        .build();
+
+    let lsd_pool = suite.lsd_pool.clone();
+    let lsd_token = suite.lsd_token.clone();
+
+    suite
+        .increase_allowance(admin, &lsd_token, lsd_pool.as_str(), 500_000_000u128)
+        .unwrap();
+    // lsd is cheap on the pool
+    suite
+        .provide_liquidity(
+            admin,
+            &lsd_pool,
+            &[
+                Asset {
+                    info: AssetInfo::Token(lsd_token.to_string()),
+                    amount: 500_000_000u128.into(),
+                },
+                Asset {
+                    info: AssetInfo::Native(NATIVE.to_owned()),
+                    amount: 300_000_000u128.into(),
+                },
+            ],
+            &coins(300_000_000, NATIVE),
+        )
+        .unwrap();
+
+    let bond = 10_000_000u128;
+    let route = suite.query_should_route(bond).unwrap();
+    assert!(route.route_via_pool);
+    let simulated = suite.query_simulate(bond).unwrap();
+    assert_eq!(route.expected_gain_lsd.u128(), simulated - bond);
+    // the exchange rate is 1
+    assert_eq!(route.breakeven_gas_native, route.expected_gain_lsd);
+}
+
+#[test]
+fn should_not_route_without_a_trade() {
+    let admin = "admin";
+    let mut suite = SuiteBuilder::new()
+        .with_funds(admin, (500_000_000u128, NATIVE))
+        .with_lsd_funds(admin, 100_000_000u128)
+        .build();
+
+    let lsd_pool = suite.lsd_pool.clone();
+    let lsd_token = suite.lsd_token.clone();
+
+    suite
+        .increase_allowance(admin, &lsd_token, lsd_pool.as_str(), 100_000_000u128)
+        .unwrap();
+    // lsd is more expensive on the pool than on the hub, so everything is bonded
+    suite
+        .provide_liquidity(
+            admin,
+            &lsd_pool,
+            &[
+                Asset {
+                    info: AssetInfo::Token(lsd_token.to_string()),
+                    amount: 100_000_000u128.into(),
+                },
+                Asset {
+                    info: AssetInfo::Native(NATIVE.to_owned()),
+                    amount: 500_000_000u128.into(),
+                },
+            ],
+            &coins(500_000_000, NATIVE),
+        )
+        .unwrap();
+
+    let bond = 10_000_000u128;
+    assert_eq!(
+        suite.query_simulate_response(bond).unwrap().swap_in,
+        Uint128::zero()
+    );
+    let route = suite.query_should_route(bond).unwrap();
+    assert!(!route.route_via_pool);
+    assert_eq!(route.expected_gain_lsd, Uint128::zero());
+    assert_eq!(route.breakeven_gas_native, Uint128::zero());
+}
@@ -0,0 +1,80 @@
+use super::suite::{SuiteBuilder, NATIVE};
+
+use wyndex::asset::{Asset, AssetInfo};
+
+use cosmwasm_std::coins;
+
+const DAY: u64 = 24 * 3600;
+
+#[test]
+fn claim_and_provide_sends_lp_tokens() {
+    let user = "user";
+    let admin = "admin";
+    let mut suite = SuiteBuilder::new()
+        .with_funds(user, (1_000_000u128, NATIVE))
+        .with_funds(admin, (500_000_000u128, NATIVE))
+        .with_lsd_funds(admin, 500_000_000u128)
+        .build();
+
+    let lsd_pool = suite.lsd_pool.clone();
+    let lsd_token = suite.lsd_token.clone();
+    let router = suite.bond_router.clone();
+
+    // single-sided liquidity needs a pool that already has some
+    suite
+        .increase_allowance(admin, &lsd_token, lsd_pool.as_str(), 500_000_000u128)
+        .unwrap();
+    suite
+        .provide_liquidity(
+            admin,
+            &lsd_pool,
+            &[
+                Asset {
+                    info: AssetInfo::Token(lsd_token.to_string()),
+                    amount: 500_000_000u128.into(),
+                },
+                Asset {
+                    info: AssetInfo::Native(NATIVE.to_owned()),
+                    amount: 500_000_000u128.into(),
+                },
+            ],
+            &coins(500_000_000, NATIVE),
+        )
+        .unwrap();
+
+    // bond and unbond right away, the claim is released after the next epoch and the unbonding period
+    suite.bond_on_hub(user, 1_000_000).unwrap();
+    suite.unbond_on_hub(user, 1_000_000).unwrap();
+    suite.next_block(42 * DAY);
+
+    // the router can only claim once the user allowed it
+    let err = suite.claim_and_provide(user, None).unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Unauthorized");
+
+    suite.set_claim_recipient(user, Some(&router)).unwrap();
+    // the claimed 1_000_000 are worth at most a thousandth of the pool, so they get less than that of its shares
+    let lp_supply = suite.query_lp_supply().unwrap();
+    let err = suite
+        .claim_and_provide(user, Some(lp_supply / 1000))
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .starts_with("Slippage exceeded"));
+    suite
+        .claim_and_provide(user, Some(lp_supply / 2000))
+        .unwrap();
+
+    // the user received LP tokens instead of the native tokens
+    let lp_token = suite.query_lp_token().unwrap();
+    assert!(suite.query_cw20_balance(user, &lp_token).unwrap() > 0);
+    assert_eq!(suite.query_balance(user, NATIVE).unwrap(), 0);
+    // and nothing is left in the router
+    assert_eq!(suite.query_balance(router.as_str(), NATIVE).unwrap(), 0);
+    assert_eq!(
+        suite
+            .query_cw20_balance(router.as_str(), &lp_token)
+            .unwrap(),
+        0
+    );
+}
@@ -0,0 +1,36 @@
+use super::suite::{SuiteBuilder, NATIVE};
+
+use crate::ContractError;
+
+#[test]
+fn instantiate_reads_hub_asset_info() {
+    let suite = SuiteBuilder::new().build();
+
+    let config = suite.query_router_config(&suite.bond_router).unwrap();
+    assert_eq!(config.hub, suite.lsd_hub.to_string());
+    assert_eq!(config.lsd_token, suite.lsd_token.to_string());
+    assert_eq!(config.bond_denom, NATIVE);
+}
+
+#[test]
+fn instantiate_falls_back_for_old_hubs() {
+    let mut suite = SuiteBuilder::new().build();
+
+    let old_hub = suite.instantiate_old_hub().unwrap();
+    let router = suite.instantiate_bond_router(&old_hub).unwrap();
+
+    let config = suite.query_router_config(&router).unwrap();
+    assert_eq!(config.hub, old_hub.to_string());
+    assert_eq!(config.lsd_token, suite.lsd_token.to_string());
+    assert_eq!(config.bond_denom, NATIVE);
+}
+
+#[test]
+fn instantiate_rejects_non_hub() {
+    let mut suite = SuiteBuilder::new().build();
+
+    // the lsd token supports neither the new nor the old queries
+    let lsd_token = suite.lsd_token.clone();
+    let err = suite.instantiate_bond_router(&lsd_token).unwrap_err();
+    assert_eq!(ContractError::NotLsdHub, err.downcast().unwrap());
+}
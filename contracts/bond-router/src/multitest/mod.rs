@@ -1,2 +1,5 @@
 mod bond;
+mod claim_and_provide;
+mod instantiate;
+mod stats;
 mod suite;
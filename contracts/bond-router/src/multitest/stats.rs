@@ -0,0 +1,32 @@
+use super::suite::{SuiteBuilder, NATIVE};
+
+use cosmwasm_std::Decimal;
+
+const DAY: u64 = 24 * 3600;
+
+#[test]
+fn stats_match_the_hub() {
+    let user = "user";
+    let mut suite = SuiteBuilder::new()
+        .with_funds(user, (1_000_000_000u128, NATIVE))
+        .build();
+
+    // no estimate before the hub reinvested twice
+    let stats = suite.query_stats().unwrap();
+    assert_eq!(stats.estimated_apr, Decimal::zero());
+    assert_eq!(stats.exchange_rate, Decimal::one());
+
+    suite.bond_on_hub(user, 1_000_000_000).unwrap();
+    for _ in 0..3 {
+        suite.next_block(21 * DAY);
+        suite.reinvest_on_hub().unwrap();
+    }
+
+    let stats = suite.query_stats().unwrap();
+    assert!(stats.estimated_apr > Decimal::zero());
+    assert_eq!(
+        stats.estimated_apr,
+        suite.query_hub_reward_rate().unwrap().apr
+    );
+    assert_eq!(stats.exchange_rate, suite.query_exchange_rate().unwrap());
+}
@@ -1,15 +1,17 @@
 use anyhow::Result as AnyResult;
 
+use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    coin, coins, testing::mock_env, to_json_binary, Addr, Coin, Decimal, Uint128, Validator,
+    coin, coins, testing::mock_env, to_json_binary, Addr, Binary, Coin, CosmosMsg, Decimal, Deps,
+    DepsMut, Empty, Env, MessageInfo, Response, StdError, StdResult, Uint128, Validator, WasmMsg,
 };
-use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg};
+use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg, TokenInfoResponse};
 use cw_multi_test::{App, AppResponse, ContractWrapper, Executor, StakingInfo};
 
 use wynd_lsd_hub::msg::{
-    ConfigResponse as LsdHubConfigResponse, ExchangeRateResponse,
-    InstantiateMsg as HubInstantiateMsg, QueryMsg as LsdHubQueryMsg, Supply, SupplyResponse,
-    TokenInitInfo,
+    ConfigResponse as LsdHubConfigResponse, ExchangeRateResponse, ExecuteMsg as HubExecuteMsg,
+    InstantiateMsg as HubInstantiateMsg, QueryMsg as LsdHubQueryMsg, ReceiveMsg as HubReceiveMsg,
+    RewardRateResponse, Supply, SupplyResponse, TokenInitInfo,
 };
 use wyndex::{
     asset::{Asset, AssetInfo},
@@ -22,7 +24,10 @@ use wyndex::{
     pair::{LsdInfo, PairInfo, SpotPriceResponse, StablePoolParams},
 };
 
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, SimulateResponse};
+use crate::msg::{
+    CompareRoutesResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+    ShouldRouteResponse, SimulateResponse, StatsResponse,
+};
 
 pub const NATIVE: &str = "ujuno";
 
@@ -92,10 +97,151 @@ fn store_staking(app: &mut App) -> u64 {
     app.store_code(contract)
 }
 
+/// Minimal contract that forwards its funds to the bond router, so several
+/// contracts can bond within a single transaction
+mod proxy {
+    use super::*;
+
+    #[cw_serde]
+    pub enum ProxyExecuteMsg {
+        Bond { router: String },
+    }
+
+    pub fn instantiate(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    pub fn execute(
+        _deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        msg: ProxyExecuteMsg,
+    ) -> StdResult<Response> {
+        match msg {
+            ProxyExecuteMsg::Bond { router } => Ok(Response::new().add_message(WasmMsg::Execute {
+                contract_addr: router,
+                msg: to_json_binary(&ExecuteMsg::Bond {})?,
+                funds: info.funds,
+            })),
+        }
+    }
+
+    pub fn query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        Err(StdError::generic_err("proxy has no queries"))
+    }
+}
+
+/// Mock of an lsd hub from before the `AssetInfo` query was added
+mod old_hub {
+    use super::*;
+    use cw_storage_plus::Item;
+
+    #[cw_serde]
+    pub struct OldHubInstantiateMsg {
+        pub token_contract: String,
+        pub bond_denom: String,
+    }
+
+    #[cw_serde]
+    pub enum OldHubQueryMsg {
+        Config {},
+        Supply {},
+    }
+
+    const INFO: Item<OldHubInstantiateMsg> = Item::new("info");
+
+    pub fn instantiate(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: OldHubInstantiateMsg,
+    ) -> StdResult<Response> {
+        INFO.save(deps.storage, &msg)?;
+        Ok(Response::new())
+    }
+
+    pub fn execute(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    pub fn query(deps: Deps, _env: Env, msg: OldHubQueryMsg) -> StdResult<Binary> {
+        let info = INFO.load(deps.storage)?;
+        match msg {
+            OldHubQueryMsg::Config {} => to_json_binary(&LsdHubConfigResponse {
+                owner: Addr::unchecked("owner"),
+                token_contract: Addr::unchecked(info.token_contract),
+                treasury: Addr::unchecked("treasury"),
+                commission: Decimal::percent(10),
+                epoch_period: 3600,
+                unbond_period: 3600,
+                surplus_strategy: Default::default(),
+                delegations_suspended: false,
+                reinvest_hook: None,
+                new_validator_probation: 0,
+                probation_max_weight: Decimal::zero(),
+                bond_denom_decimals: 6,
+                token_decimals: 6,
+                expected_bond_decimals: None,
+                max_commission_increase_alert: Decimal::percent(10),
+                auto_zero_weight_on_spike: false,
+                delegate_every_n_epochs: 0,
+                strict_supply_check: false,
+                unbond_fee: Decimal::zero(),
+                issuance_cap: None,
+                voucher_contract: None,
+                rebalance_on_reinvest: false,
+                operator: None,
+                redelegation_hops: false,
+                max_rate_growth_per_epoch: None,
+                min_delegation: Uint128::one(),
+                valoper_prefix: String::new(),
+            }),
+            OldHubQueryMsg::Supply {} => to_json_binary(&SupplyResponse {
+                supply: Supply {
+                    bond_denom: info.bond_denom,
+                    ..Default::default()
+                },
+                balance: Uint128::zero(),
+                backing_assets: Uint128::zero(),
+                exchange_rate: Decimal::one(),
+            }),
+        }
+    }
+}
+
+fn store_old_hub(app: &mut App) -> u64 {
+    let contract = Box::new(ContractWrapper::new_with_empty(
+        old_hub::execute,
+        old_hub::instantiate,
+        old_hub::query,
+    ));
+    app.store_code(contract)
+}
+
+fn store_proxy(app: &mut App) -> u64 {
+    let contract = Box::new(ContractWrapper::new_with_empty(
+        proxy::execute,
+        proxy::instantiate,
+        proxy::query,
+    ));
+    app.store_code(contract)
+}
+
 #[derive(Debug)]
 pub struct SuiteBuilder {
     funds: Vec<(Addr, Vec<Coin>)>,
     lsd_funds: Vec<Cw20Coin>,
+    min_pool_depth: Decimal,
 }
 
 impl SuiteBuilder {
@@ -103,6 +249,7 @@ impl SuiteBuilder {
         Self {
             funds: vec![],
             lsd_funds: vec![],
+            min_pool_depth: Decimal::zero(),
         }
     }
 
@@ -120,6 +267,11 @@ impl SuiteBuilder {
         self
     }
 
+    pub fn with_min_pool_depth(mut self, min_pool_depth: Decimal) -> Self {
+        self.min_pool_depth = min_pool_depth;
+        self
+    }
+
     #[track_caller]
     pub fn build(self) -> Suite {
         let mut app = App::default();
@@ -220,6 +372,12 @@ impl SuiteBuilder {
                     liquidity_discount: Decimal::percent(3),
                     slashing_safety_margin: 10,
                     tombstone_treshold: Decimal::percent(10),
+                    bond_denom_decimals: None,
+                    expected_bond_decimals: None,
+                    issuance_cap: None,
+                    voucher_init: None,
+                    bootstrap_min_deposit: None,
+                    valoper_prefix: String::new(),
                 },
                 &[],
                 "Wyndex LSD Hub",
@@ -284,6 +442,7 @@ impl SuiteBuilder {
                 &InstantiateMsg {
                     hub: lsd_hub.to_string(),
                     pair: lsd_pool.to_string(),
+                    min_pool_depth: self.min_pool_depth,
                 },
                 &[],
                 "Bond router",
@@ -329,6 +488,147 @@ impl Suite {
         )
     }
 
+    pub fn claim_and_provide(
+        &mut self,
+        sender: &str,
+        min_lp_amount: Option<u128>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.bond_router.clone(),
+            &ExecuteMsg::ClaimAndProvide {
+                min_lp_amount: min_lp_amount.map(Uint128::new),
+            },
+            &[],
+        )
+    }
+
+    pub fn reinvest_on_hub(&mut self) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked("anyone"),
+            self.lsd_hub.clone(),
+            &HubExecuteMsg::Reinvest { validators: None },
+            &[],
+        )
+    }
+
+    /// Bonds directly on the hub, without going through the router
+    pub fn bond_on_hub(&mut self, sender: &str, amount: u128) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.lsd_hub.clone(),
+            &HubExecuteMsg::Bond {},
+            &coins(amount, NATIVE),
+        )
+    }
+
+    pub fn unbond_on_hub(&mut self, sender: &str, amount: u128) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.lsd_token.clone(),
+            &Cw20ExecuteMsg::Send {
+                contract: self.lsd_hub.to_string(),
+                amount: amount.into(),
+                msg: to_json_binary(&HubReceiveMsg::Unbond {})?,
+            },
+            &[],
+        )
+    }
+
+    pub fn set_claim_recipient(
+        &mut self,
+        sender: &str,
+        recipient: Option<&Addr>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.lsd_hub.clone(),
+            &HubExecuteMsg::SetClaimRecipient {
+                recipient: recipient.map(Addr::to_string),
+            },
+            &[],
+        )
+    }
+
+    /// Instantiates a new proxy contract that can bond on behalf of itself
+    pub fn instantiate_proxy(&mut self, label: &str) -> AnyResult<Addr> {
+        let code_id = store_proxy(&mut self.app);
+        self.app
+            .instantiate_contract(code_id, self.owner.clone(), &Empty {}, &[], label, None)
+    }
+
+    /// Instantiates a hub mock that only supports the `Config` and `Supply` queries,
+    /// reporting the same lsd token and bond denom as the real hub
+    pub fn instantiate_old_hub(&mut self) -> AnyResult<Addr> {
+        let code_id = store_old_hub(&mut self.app);
+        self.app.instantiate_contract(
+            code_id,
+            self.owner.clone(),
+            &old_hub::OldHubInstantiateMsg {
+                token_contract: self.lsd_token.to_string(),
+                bond_denom: NATIVE.to_string(),
+            },
+            &[],
+            "Old hub",
+            None,
+        )
+    }
+
+    /// Instantiates another bond router for the given hub and the suite's pool
+    pub fn instantiate_bond_router(&mut self, hub: &Addr) -> AnyResult<Addr> {
+        let code_id = store_bond_router(&mut self.app);
+        self.app.instantiate_contract(
+            code_id,
+            self.owner.clone(),
+            &InstantiateMsg {
+                hub: hub.to_string(),
+                pair: self.lsd_pool.to_string(),
+                min_pool_depth: Decimal::zero(),
+            },
+            &[],
+            "Bond router",
+            None,
+        )
+    }
+
+    /// Bonds through all given proxies in a single transaction
+    pub fn bond_via_proxies(
+        &mut self,
+        sender: &str,
+        bonds: &[(Addr, u128)],
+    ) -> AnyResult<Vec<AppResponse>> {
+        let msgs = bonds
+            .iter()
+            .map(|(proxy, amount)| {
+                Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: proxy.to_string(),
+                    msg: to_json_binary(&proxy::ProxyExecuteMsg::Bond {
+                        router: self.bond_router.to_string(),
+                    })?,
+                    funds: coins(*amount, NATIVE),
+                }))
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        self.app.execute_multi(Addr::unchecked(sender), msgs)
+    }
+
+    pub fn transfer_lsd(
+        &mut self,
+        sender: &str,
+        recipient: &Addr,
+        amount: u128,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.lsd_token.clone(),
+            &Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount: amount.into(),
+            },
+            &[],
+        )
+    }
+
     pub fn increase_allowance(
         &mut self,
         owner: &str,
@@ -367,6 +667,13 @@ impl Suite {
         )
     }
 
+    pub fn query_router_config(&self, router: &Addr) -> AnyResult<ConfigResponse> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart(router.clone(), &QueryMsg::Config {})?)
+    }
+
     // simulate bond tx query in bond router contract
     pub fn query_simulate(&self, bond: u128) -> AnyResult<u128> {
         Ok(self
@@ -380,6 +687,44 @@ impl Suite {
             .into())
     }
 
+    pub fn query_simulate_response(&self, bond: u128) -> AnyResult<SimulateResponse> {
+        Ok(self.app.wrap().query_wasm_smart(
+            self.bond_router.clone(),
+            &QueryMsg::Simulate { bond: bond.into() },
+        )?)
+    }
+
+    // compare optimal routing with swapping or bonding everything
+    pub fn query_compare_routes(&self, bond: u128) -> AnyResult<CompareRoutesResponse> {
+        Ok(self.app.wrap().query_wasm_smart(
+            self.bond_router.clone(),
+            &QueryMsg::CompareRoutes { bond: bond.into() },
+        )?)
+    }
+
+    pub fn query_should_route(&self, amount: u128) -> AnyResult<ShouldRouteResponse> {
+        Ok(self.app.wrap().query_wasm_smart(
+            self.bond_router.clone(),
+            &QueryMsg::ShouldRoute {
+                amount: amount.into(),
+            },
+        )?)
+    }
+
+    pub fn query_stats(&self) -> AnyResult<StatsResponse> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart(self.bond_router.clone(), &QueryMsg::Stats {})?)
+    }
+
+    pub fn query_hub_reward_rate(&self) -> AnyResult<RewardRateResponse> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart(self.lsd_hub.clone(), &LsdHubQueryMsg::RewardRate {})?)
+    }
+
     pub fn query_exchange_rate(&self) -> AnyResult<Decimal> {
         Ok(self
             .app
@@ -413,6 +758,28 @@ impl Suite {
             .price)
     }
 
+    pub fn query_lp_token(&self) -> AnyResult<Addr> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart::<PairInfo>(&self.lsd_pool, &PairQueryMsg::Pair {})?
+            .liquidity_token)
+    }
+
+    pub fn query_lp_supply(&self) -> AnyResult<u128> {
+        let lp_token = self.query_lp_token()?;
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart::<TokenInfoResponse>(lp_token, &Cw20QueryMsg::TokenInfo {})?
+            .total_supply
+            .u128())
+    }
+
+    pub fn query_balance(&self, address: &str, denom: &str) -> AnyResult<u128> {
+        Ok(self.app.wrap().query_balance(address, denom)?.amount.u128())
+    }
+
     pub fn query_cw20_balance(&self, sender: &str, address: &Addr) -> AnyResult<u128> {
         Ok(self
             .app
@@ -1,6 +1,6 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Addr;
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, Decimal, Uint128};
+use cw_storage_plus::{Deque, Item};
 
 #[cw_serde]
 pub struct Config {
@@ -12,9 +12,37 @@ pub struct Config {
     pub bond_denom: String,
     /// This is the lsd token the users wishes to receive
     pub lsd_token: Addr,
+    /// The minimum pool reserve of the bond denom needed to swap, relative to the amount to swap
+    #[serde(default)]
+    pub min_pool_depth: Decimal,
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
 
-/// Stack to push/pop on replies
-pub const REPLY_INFO: Item<Addr> = Item::new("reply");
+#[cw_serde]
+pub struct PendingBond {
+    /// Address that receives the lsd tokens of the bond
+    pub recipient: Addr,
+    /// The lsd token balance of this contract before bonding
+    pub balance_before: Uint128,
+}
+
+/// Queue of bonds waiting for their reply, pushed in `execute_bond` and popped (FIFO) on replies
+pub const PENDING_BONDS: Deque<PendingBond> = Deque::new("pending_bonds");
+
+#[cw_serde]
+pub struct PendingClaim {
+    /// Address whose claims are claimed and that receives the LP tokens
+    pub recipient: Addr,
+    /// The native balance of this contract before claiming
+    pub balance_before: Uint128,
+    /// The least LP tokens the recipient accepts
+    #[serde(default)]
+    pub min_lp_amount: Option<Uint128>,
+    /// The LP token balance of this contract before providing, set once the claim was received
+    #[serde(default)]
+    pub lp_balance_before: Uint128,
+}
+
+/// The claim waiting for its replies, saved in `execute_claim_and_provide` and removed on the provide reply
+pub const PENDING_CLAIM: Item<PendingClaim> = Item::new("pending_claim");
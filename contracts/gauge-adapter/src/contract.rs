@@ -2,19 +2,23 @@
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
     ensure, to_json_binary, Addr, Binary, Decimal, Deps, DepsMut, Empty, Env, MessageInfo,
-    Response, StdResult, WasmMsg,
+    Response, StdError, StdResult, WasmMsg,
 };
 use cw2::set_contract_version;
 use cw_utils::ensure_from_older_version;
 
 use cw_placeholder::contract::CONTRACT_NAME as PLACEHOLDER_CONTRACT_NAME;
-use wynd_lsd_hub::msg::ExecuteMsg as HubExecuteMsg;
+use wynd_lsd_hub::msg::{
+    ConfigResponse as HubConfigResponse, ExecuteMsg as HubExecuteMsg, QueryMsg as HubQueryMsg,
+    TombstonedValidatorsResponse, ValidatorSetResponse,
+};
+use wynd_lsd_hub::valoper::is_valid_valoper;
 
 use semver::Version;
 
 use crate::error::ContractError;
 use crate::msg::{AdapterQueryMsg, InstantiateMsg, MigrateMsg};
-use crate::state::{Config, CONFIG};
+use crate::state::{Config, CONFIG, DEFAULT_TOMBSTONE_COOLDOWN};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:gauge-adapter";
@@ -33,10 +37,18 @@ pub fn instantiate(
         msg.max_commission > Decimal::zero() && msg.max_commission <= Decimal::one(),
         ContractError::InvalidMaxCommission {}
     );
+    ensure!(
+        msg.weight_precision
+            .map_or(true, |p| p <= Decimal::DECIMAL_PLACES),
+        ContractError::InvalidWeightPrecision {}
+    );
 
     let config = Config {
         hub: deps.api.addr_validate(&msg.hub)?,
         max_commission: msg.max_commission,
+        weight_precision: msg.weight_precision,
+        tombstone_cooldown: msg.tombstone_cooldown.unwrap_or(DEFAULT_TOMBSTONE_COOLDOWN),
+        intent_ttl: msg.intent_ttl,
     };
     CONFIG.save(deps.storage, &config)?;
 
@@ -54,15 +66,15 @@ pub fn execute(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: AdapterQueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: AdapterQueryMsg) -> StdResult<Binary> {
     match msg {
         AdapterQueryMsg::Config {} => to_json_binary(&CONFIG.load(deps.storage)?),
-        AdapterQueryMsg::AllOptions {} => to_json_binary(&query::all_options(deps)?),
+        AdapterQueryMsg::AllOptions {} => to_json_binary(&query::all_options(deps, env)?),
         AdapterQueryMsg::CheckOption { option } => {
-            to_json_binary(&query::check_option(deps, option)?)
+            to_json_binary(&query::check_option(deps, env, option)?)
         }
-        AdapterQueryMsg::SampleGaugeMsgs { selected } => {
-            to_json_binary(&query::sample_gauge_msgs(deps, selected)?)
+        AdapterQueryMsg::SampleGaugeMsgs { selected, epoch } => {
+            to_json_binary(&query::sample_gauge_msgs(deps, env, selected, epoch)?)
         }
     }
 }
@@ -71,44 +83,157 @@ mod query {
     use cosmwasm_std::Decimal;
 
     use crate::{
-        msg::{AllOptionsResponse, CheckOptionResponse, SampleGaugeMsgsResponse},
+        msg::{AllOptionsResponse, CheckOptionResponse, OptionsSource, SampleGaugeMsgsResponse},
         state::CONFIG,
     };
 
     use super::*;
 
-    pub fn all_options(deps: Deps) -> StdResult<AllOptionsResponse> {
-        let max_commission = CONFIG.load(deps.storage)?.max_commission;
+    pub fn all_options(deps: Deps, env: Env) -> StdResult<AllOptionsResponse> {
+        let Config {
+            hub,
+            max_commission,
+            weight_precision: _,
+            tombstone_cooldown,
+            intent_ttl: _,
+        } = CONFIG.load(deps.storage)?;
+        let tombstoned = recently_tombstoned(deps, &env, &hub, tombstone_cooldown)?;
+
+        let validators = deps.querier.query_all_validators()?;
+        if validators.is_empty() {
+            // fresh chains can return no validators at all, so we fall back to the hub's valset
+            // in order to not break gauge creation. We cannot filter by commission here.
+            return Ok(AllOptionsResponse {
+                options: hub_validators(deps, &hub)?
+                    .into_iter()
+                    .filter(|v| !tombstoned.contains(v))
+                    .collect(),
+                source: OptionsSource::Hub,
+            });
+        }
 
         Ok(AllOptionsResponse {
-            options: deps
-                .querier
-                .query_all_validators()?
+            options: validators
                 .into_iter()
-                .filter(|v| v.commission <= max_commission)
+                .filter(|v| v.commission <= max_commission && !tombstoned.contains(&v.address))
                 .map(|v| v.address)
                 .collect(),
+            source: OptionsSource::Chain,
         })
     }
 
-    pub fn check_option(deps: Deps, option: String) -> StdResult<CheckOptionResponse> {
+    pub fn check_option(deps: Deps, env: Env, option: String) -> StdResult<CheckOptionResponse> {
+        let Config {
+            hub,
+            tombstone_cooldown,
+            ..
+        } = CONFIG.load(deps.storage)?;
+        // the hub would reject a valset with an address of the wrong format
+        let HubConfigResponse { valoper_prefix, .. } = deps
+            .querier
+            .query_wasm_smart(&hub, &HubQueryMsg::Config {})?;
+        if !is_valid_valoper(&option, &valoper_prefix)
+            || recently_tombstoned(deps, &env, &hub, tombstone_cooldown)?.contains(&option)
+        {
+            return Ok(CheckOptionResponse {
+                valid: false,
+                source: None,
+            });
+        }
+
+        let source = if deps.querier.query_validator(&option)?.is_some() {
+            Some(OptionsSource::Chain)
+        } else {
+            // the chain's validator query can lag behind, so also accept validators the hub knows
+            hub_validators(deps, &hub)?
+                .contains(&option)
+                .then_some(OptionsSource::Hub)
+        };
+
         Ok(CheckOptionResponse {
-            valid: deps.querier.query_validator(option)?.is_some(),
+            valid: source.is_some(),
+            source,
         })
     }
 
+    /// Returns the addresses of the validators the hub is currently delegating to
+    fn hub_validators(deps: Deps, hub: &Addr) -> StdResult<Vec<String>> {
+        let ValidatorSetResponse { validator_set } = deps
+            .querier
+            .query_wasm_smart(hub, &HubQueryMsg::ValidatorSet {})?;
+        Ok(validator_set.into_iter().map(|(v, _)| v).collect())
+    }
+
+    /// Returns the validators the hub detected as tombstoned within the last `cooldown` seconds
+    fn recently_tombstoned(
+        deps: Deps,
+        env: &Env,
+        hub: &Addr,
+        cooldown: u64,
+    ) -> StdResult<Vec<String>> {
+        if cooldown == 0 {
+            return Ok(vec![]);
+        }
+        let TombstonedValidatorsResponse { validators } = deps
+            .querier
+            .query_wasm_smart(hub, &HubQueryMsg::TombstonedValidators {})?;
+        let now = env.block.time.seconds();
+        Ok(validators
+            .into_iter()
+            .filter(|(_, tombstoned_at)| now < tombstoned_at + cooldown)
+            .map(|(v, _)| v)
+            .collect())
+    }
+
     pub fn sample_gauge_msgs(
         deps: Deps,
+        env: Env,
         new_validators: Vec<(String, Decimal)>,
+        epoch: Option<u64>,
     ) -> StdResult<SampleGaugeMsgsResponse> {
         let Config {
             hub,
             max_commission: _,
+            weight_precision,
+            tombstone_cooldown,
+            intent_ttl,
         } = CONFIG.load(deps.storage)?;
+
+        let new_validators = match weight_precision {
+            Some(precision) => quantize_weights(new_validators, precision),
+            None => new_validators,
+        };
+
+        // don't let a vote bring back a validator right after it was tombstoned
+        let tombstoned = recently_tombstoned(deps, &env, &hub, tombstone_cooldown)?;
+        if let Some((validator, _)) = new_validators.iter().find(|(v, _)| tombstoned.contains(v)) {
+            return Err(StdError::generic_err(format!(
+                "Validator {validator} was tombstoned recently"
+            )));
+        }
+
+        // no need to redelegate anything if the validator set did not change
+        let ValidatorSetResponse { validator_set } = deps
+            .querier
+            .query_wasm_smart(&hub, &HubQueryMsg::ValidatorSet {})?;
+        if same_validators(validator_set, new_validators.clone()) {
+            return Ok(SampleGaugeMsgsResponse { execute: vec![] });
+        }
+
         Ok(SampleGaugeMsgsResponse {
             execute: vec![WasmMsg::Execute {
                 contract_addr: hub.to_string(),
-                msg: to_json_binary(&HubExecuteMsg::SetValidators { new_validators })?,
+                msg: to_json_binary(&HubExecuteMsg::SetValidators {
+                    new_validators: new_validators.into_iter().map(Into::into).collect(),
+                    // lets the hub attribute the change to the gauge
+                    source: Some(format!(
+                        "gauge:{}",
+                        epoch.unwrap_or_else(|| env.block.time.seconds())
+                    )),
+                    // the votes are outdated if the change waits too long before it is executed
+                    deadline: (intent_ttl != 0).then(|| env.block.time.seconds() + intent_ttl),
+                    expected_total_bonded: None,
+                })?,
                 funds: vec![],
             }
             .into()],
@@ -116,6 +241,48 @@ mod query {
     }
 }
 
+/// Rounds each weight half-up to `precision` decimal places and drops validators whose weight becomes zero.
+/// The rounding residue is moved to the (first) largest weight, so that the weights add up to exactly 1.
+fn quantize_weights(weights: Vec<(String, Decimal)>, precision: u32) -> Vec<(String, Decimal)> {
+    let step = 10u128.pow(Decimal::DECIMAL_PLACES - precision);
+    let mut quantized: Vec<_> = weights
+        .into_iter()
+        .map(|(validator, weight)| {
+            (
+                validator,
+                (weight.atomics().u128() + step / 2) / step * step,
+            )
+        })
+        .filter(|(_, weight)| *weight != 0)
+        .collect();
+
+    if !quantized.is_empty() {
+        let mut largest = 0;
+        for (i, (_, weight)) in quantized.iter().enumerate() {
+            if *weight > quantized[largest].1 {
+                largest = i;
+            }
+        }
+
+        let sum: u128 = quantized.iter().map(|(_, weight)| weight).sum();
+        let one = Decimal::one().atomics().u128();
+        let weight = &mut quantized[largest].1;
+        *weight = (*weight + one).saturating_sub(sum);
+    }
+
+    quantized
+        .into_iter()
+        .map(|(validator, weight)| (validator, Decimal::raw(weight)))
+        .collect()
+}
+
+/// Returns true if both lists contain the same validators with the same weights, ignoring the order
+fn same_validators(mut a: Vec<(String, Decimal)>, mut b: Vec<(String, Decimal)>) -> bool {
+    a.sort();
+    b.sort();
+    a == b
+}
+
 pub mod migration {
     use cosmwasm_schema::cw_serde;
 
@@ -163,6 +330,9 @@ pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, Con
                 let new_config = Config {
                     hub: Addr::unchecked(old_config.hub),
                     max_commission,
+                    weight_precision: None,
+                    tombstone_cooldown: DEFAULT_TOMBSTONE_COOLDOWN,
+                    intent_ttl: 0,
                 };
                 CONFIG.save(deps.storage, &new_config)?;
             }
@@ -174,19 +344,52 @@ pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, Con
 
 #[cfg(test)]
 mod tests {
+    use std::str::FromStr;
+
     use cosmwasm_std::{
-        testing::{mock_dependencies, mock_env, mock_info},
-        CosmosMsg, Decimal, WasmMsg,
+        from_json,
+        testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage},
+        ContractResult, CosmosMsg, Decimal, OwnedDeps, SystemResult, WasmMsg, WasmQuery,
     };
 
     use super::*;
 
+    /// Makes the mocked hub return the given validator set and no tombstoned validators
+    fn mock_hub_validators(
+        deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+        validator_set: Vec<(String, Decimal)>,
+    ) {
+        let validators = to_json_binary(&ValidatorSetResponse { validator_set }).unwrap();
+        let tombstoned =
+            to_json_binary(&TombstonedValidatorsResponse { validators: vec![] }).unwrap();
+        deps.querier.update_wasm(move |query| {
+            let response = match query {
+                WasmQuery::Smart { msg, .. } => match from_json(msg).unwrap() {
+                    HubQueryMsg::TombstonedValidators {} => tombstoned.clone(),
+                    _ => validators.clone(),
+                },
+                _ => validators.clone(),
+            };
+            SystemResult::Ok(ContractResult::Ok(response))
+        });
+    }
+
+    fn weights(weights: &[(&str, &str)]) -> Vec<(String, Decimal)> {
+        weights
+            .iter()
+            .map(|(v, w)| (v.to_string(), Decimal::from_str(w).unwrap()))
+            .collect()
+    }
+
     #[test]
     fn proper_initialization() {
         let mut deps = mock_dependencies();
         let msg = InstantiateMsg {
             hub: "hub".to_string(),
             max_commission: Decimal::percent(30),
+            weight_precision: None,
+            tombstone_cooldown: None,
+            intent_ttl: 0,
         };
         instantiate(deps.as_mut(), mock_env(), mock_info("user", &[]), msg).unwrap();
 
@@ -202,6 +405,9 @@ mod tests {
         let msg = InstantiateMsg {
             hub: "hub".to_string(),
             max_commission: Decimal::zero(),
+            weight_precision: None,
+            tombstone_cooldown: None,
+            intent_ttl: 0,
         };
 
         let err = instantiate(
@@ -247,6 +453,7 @@ mod tests {
     #[test]
     fn basic_sample() {
         let mut deps = mock_dependencies();
+        mock_hub_validators(&mut deps, vec![]);
 
         instantiate(
             deps.as_mut(),
@@ -255,6 +462,9 @@ mod tests {
             InstantiateMsg {
                 hub: "hub".to_string(),
                 max_commission: Decimal::percent(30),
+                weight_precision: None,
+                tombstone_cooldown: None,
+                intent_ttl: 0,
             },
         )
         .unwrap();
@@ -273,7 +483,7 @@ mod tests {
                 Decimal::permille(250),
             ),
         ];
-        let res = query::sample_gauge_msgs(deps.as_ref(), selected).unwrap();
+        let res = query::sample_gauge_msgs(deps.as_ref(), mock_env(), selected, None).unwrap();
         assert_eq!(res.execute.len(), 1);
         assert_eq!(
             res.execute[0],
@@ -294,10 +504,102 @@ mod tests {
                             Decimal::permille(250),
                         ),
                     ]
+                    .into_iter()
+                    .map(Into::into)
+                    .collect(),
+                    source: Some(format!("gauge:{}", mock_env().block.time.seconds())),
+                    deadline: None,
+                    expected_total_bonded: None,
                 })
                 .unwrap(),
                 funds: vec![],
             })
         );
     }
+
+    #[test]
+    fn quantize_equal_thirds() {
+        let third = Decimal::from_ratio(1u128, 3u128);
+        let selected = vec![
+            ("a".to_string(), third),
+            ("b".to_string(), third),
+            ("c".to_string(), third),
+        ];
+        // the residue goes to the first of the largest weights
+        assert_eq!(
+            quantize_weights(selected, 3),
+            weights(&[("a", "0.334"), ("b", "0.333"), ("c", "0.333")])
+        );
+    }
+
+    #[test]
+    fn quantize_rounds_half_up_and_drops_zero_weights() {
+        let selected = weights(&[("a", "0.6004"), ("b", "0.3995"), ("c", "0.0001")]);
+        assert_eq!(
+            quantize_weights(selected, 3),
+            weights(&[("a", "0.6"), ("b", "0.4")])
+        );
+    }
+
+    #[test]
+    fn quantize_removes_excess_from_largest_weight() {
+        // rounds up to 0.501 + 0.5 = 1.001
+        let selected = weights(&[("a", "0.4995"), ("b", "0.5005")]);
+        assert_eq!(
+            quantize_weights(selected, 3),
+            weights(&[("a", "0.5"), ("b", "0.5")])
+        );
+
+        // full precision does not change anything that already adds up to 1
+        let selected = weights(&[("a", "0.333333333333333333"), ("b", "0.666666666666666667")]);
+        assert_eq!(quantize_weights(selected.clone(), 18), selected);
+    }
+
+    #[test]
+    fn sample_skips_unchanged_validator_set() {
+        let mut deps = mock_dependencies();
+        mock_hub_validators(&mut deps, weights(&[("a", "0.5"), ("b", "0.5")]));
+
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("user", &[]),
+            InstantiateMsg {
+                hub: "hub".to_string(),
+                max_commission: Decimal::percent(30),
+                weight_precision: Some(3),
+                tombstone_cooldown: None,
+                intent_ttl: 0,
+            },
+        )
+        .unwrap();
+
+        // noisy votes are quantized to the current set
+        let selected = weights(&[("b", "0.4999999997"), ("a", "0.5000000003")]);
+        let res = query::sample_gauge_msgs(deps.as_ref(), mock_env(), selected, None).unwrap();
+        assert!(res.execute.is_empty());
+
+        let selected = weights(&[("a", "0.6"), ("b", "0.4")]);
+        let res = query::sample_gauge_msgs(deps.as_ref(), mock_env(), selected, None).unwrap();
+        assert_eq!(res.execute.len(), 1);
+    }
+
+    #[test]
+    fn invalid_weight_precision() {
+        let mut deps = mock_dependencies();
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("user", &[]),
+            InstantiateMsg {
+                hub: "hub".to_string(),
+                max_commission: Decimal::percent(30),
+                weight_precision: Some(19),
+                tombstone_cooldown: None,
+                intent_ttl: 0,
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidWeightPrecision {});
+    }
 }
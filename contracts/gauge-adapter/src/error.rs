@@ -11,4 +11,7 @@ pub enum ContractError {
 
     #[error("Invalid max_commission; must be higher then 0.0 and smaller or equal then 1.0")]
     InvalidMaxCommission {},
+
+    #[error("Invalid weight_precision; must be at most 18 decimal places")]
+    InvalidWeightPrecision {},
 }
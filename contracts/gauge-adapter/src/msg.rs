@@ -7,6 +7,17 @@ pub struct InstantiateMsg {
     pub hub: String,
     /// Maximum allowed commision by validator to be included in voting set
     pub max_commission: Decimal,
+    /// Number of decimal places the selected weights are rounded to, e.g. 3 for permille.
+    /// This avoids redelegations because of tiny changes in the votes. `None` keeps the weights as they are.
+    pub weight_precision: Option<u32>,
+    /// For how long (in seconds) validators the hub detected as tombstoned are not offered.
+    /// Defaults to [`crate::state::DEFAULT_TOMBSTONE_COOLDOWN`].
+    #[serde(default)]
+    pub tombstone_cooldown: Option<u64>,
+    /// For how long (in seconds) the hub accepts a valset change sampled by `AdapterQueryMsg::SampleGaugeMsgs`.
+    /// A change that is executed later is refused, `0` disables the deadline.
+    #[serde(default)]
+    pub intent_ttl: u64,
 }
 
 #[cw_serde]
@@ -33,17 +44,33 @@ pub enum AdapterQueryMsg {
         /// option along with weight
         /// sum of all weights should be 1.0 (within rounding error)
         selected: Vec<(String, Decimal)>,
+        /// The gauge epoch the selection is for. It is passed to the hub in the `source` of the
+        /// valset change (`gauge:{epoch}`). Defaults to the current block time in seconds.
+        #[serde(default)]
+        epoch: Option<u64>,
     },
 }
 
 #[cw_serde]
 pub struct AllOptionsResponse {
     pub options: Vec<String>,
+    /// Where the options were taken from
+    pub source: OptionsSource,
 }
 
 #[cw_serde]
 pub struct CheckOptionResponse {
     pub valid: bool,
+    /// Where the option was found, `None` if it is not valid
+    pub source: Option<OptionsSource>,
+}
+
+#[cw_serde]
+pub enum OptionsSource {
+    /// The validators were queried from the chain's staking module
+    Chain,
+    /// The chain did not know about the validators, so the hub's current validator set was used
+    Hub,
 }
 
 #[cw_serde]
@@ -1,5 +1,6 @@
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Decimal};
 
+use crate::msg::OptionsSource;
 use crate::multitest::suite::SuiteBuilder;
 
 #[test]
@@ -14,9 +15,10 @@ fn option_queries() {
         .build();
 
     // get all options
-    let options = suite.query_all_options().unwrap();
+    let options = suite.query_all_options_response().unwrap();
+    assert_eq!(options.source, OptionsSource::Chain);
     assert_eq!(
-        options,
+        options.options,
         validators
             .iter()
             .map(|v| v.0.to_string())
@@ -56,3 +58,119 @@ fn query_validators_with_commission_cap() {
         ]
     );
 }
+
+#[test]
+fn options_fall_back_to_hub_validators() {
+    let hub_validators = vec![
+        (
+            "junovaloper196ax4vc0lwpxndu9dyhvca7jhxp70rmcqcnylw",
+            Decimal::percent(60),
+        ),
+        (
+            "junovaloper1y0us8xvsvfvqkk9c6nt5cfyu5au5tww2wsdcwk",
+            Decimal::percent(40),
+        ),
+    ];
+    let suite = SuiteBuilder::new()
+        .with_chain_validators(vec![])
+        .with_initial_validators(hub_validators.clone())
+        .build();
+
+    // chain returns no validators, so the hub's validator set is used
+    let options = suite.query_all_options_response().unwrap();
+    assert_eq!(options.source, OptionsSource::Hub);
+    assert_eq!(
+        options.options,
+        hub_validators
+            .iter()
+            .map(|v| v.0.to_string())
+            .collect::<Vec<_>>()
+    );
+
+    // validators in the hub set are valid, even though the chain doesn't know them
+    for (v, _) in hub_validators {
+        let res = suite.query_check_option_response(v.to_string()).unwrap();
+        assert!(res.valid);
+        assert_eq!(res.source, Some(OptionsSource::Hub));
+    }
+    let res = suite
+        .query_check_option_response(
+            "junovaloper1t8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaaa".to_string(),
+        )
+        .unwrap();
+    assert!(!res.valid);
+    assert_eq!(res.source, None);
+}
+
+#[test]
+fn recently_tombstoned_validators_are_not_offered() {
+    let tombstoned = "junovaloper196ax4vc0lwpxndu9dyhvca7jhxp70rmcqcnylw";
+    let other = "junovaloper1y0us8xvsvfvqkk9c6nt5cfyu5au5tww2wsdcwk";
+    let cooldown = 30 * 24 * 60 * 60;
+    let mut suite = SuiteBuilder::new()
+        .with_tombstone_cooldown(cooldown)
+        .build();
+    assert!(suite.query_check_option(tombstoned.to_string()).unwrap());
+
+    // the hub delegates to the validator, so it notices the tombstoning
+    suite.tombstone(tombstoned).unwrap();
+
+    assert!(!suite
+        .query_all_options()
+        .unwrap()
+        .contains(&tombstoned.to_string()));
+    let res = suite
+        .query_check_option_response(tombstoned.to_string())
+        .unwrap();
+    assert!(!res.valid);
+    assert_eq!(res.source, None);
+    let err = suite
+        .try_sample_gauge_msgs(vec![
+            (tombstoned.to_string(), Decimal::percent(50)),
+            (other.to_string(), Decimal::percent(50)),
+        ])
+        .unwrap_err();
+    assert!(err.to_string().contains("was tombstoned recently"));
+    // the others can still be voted for
+    assert_eq!(
+        suite
+            .sample_gauge_msgs(vec![(other.to_string(), Decimal::one())])
+            .len(),
+        1
+    );
+
+    // once the cooldown passed, it is offered again
+    suite.next_block(cooldown);
+    assert!(suite
+        .query_all_options()
+        .unwrap()
+        .contains(&tombstoned.to_string()));
+    assert!(suite.query_check_option(tombstoned.to_string()).unwrap());
+    assert_eq!(
+        suite
+            .sample_gauge_msgs(vec![(tombstoned.to_string(), Decimal::one())])
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn options_without_the_valoper_prefix_are_invalid() {
+    let account = "juno1t8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaaa";
+    let suite = SuiteBuilder::new()
+        .with_chain_validators(vec![
+            ("junovaloper1t8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaaa", "1.0"),
+            (account, "1.0"),
+        ])
+        .build();
+
+    // the chain knows the address, but the hub would reject it
+    assert!(suite
+        .query_check_option("junovaloper1t8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaaa".to_string())
+        .unwrap());
+    let res = suite
+        .query_check_option_response(account.to_string())
+        .unwrap();
+    assert!(!res.valid);
+    assert_eq!(res.source, None);
+}
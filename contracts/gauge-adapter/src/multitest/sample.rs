@@ -1,4 +1,7 @@
-use cosmwasm_std::Decimal;
+use std::str::FromStr;
+
+use cosmwasm_std::{Decimal, Event};
+use wynd_lsd_hub::ContractError as HubContractError;
 
 use super::suite::SuiteBuilder;
 
@@ -29,3 +32,85 @@ fn updating_validators_works() {
     let validators = suite.query_validator_set().unwrap();
     assert_eq!(validators, selected);
 }
+
+#[test]
+fn noisy_votes_do_not_cause_churn() {
+    let mut suite = SuiteBuilder::new().with_weight_precision(3).build();
+
+    let weights = |a: &str, b: &str| {
+        vec![
+            (
+                "junovaloper196ax4vc0lwpxndu9dyhvca7jhxp70rmcqcnylw".to_string(),
+                Decimal::from_str(a).unwrap(),
+            ),
+            (
+                "junovaloper1y0us8xvsvfvqkk9c6nt5cfyu5au5tww2wsdcwk".to_string(),
+                Decimal::from_str(b).unwrap(),
+            ),
+        ]
+    };
+
+    // first selection changes the validator set
+    let messages = suite.sample_gauge_msgs(weights("0.500000001", "0.499999999"));
+    assert_eq!(messages.len(), 1);
+    suite
+        .app
+        .execute_multi(suite.owner.clone(), messages)
+        .unwrap();
+    assert_eq!(suite.query_validator_set().unwrap(), weights("0.5", "0.5"));
+
+    // second selection only differs by dust, so there is nothing to do
+    let messages = suite.sample_gauge_msgs(weights("0.499999997", "0.500000003"));
+    assert!(messages.is_empty());
+}
+
+#[test]
+fn sampled_messages_are_attributed_to_the_gauge() {
+    let mut suite = SuiteBuilder::new().build();
+
+    let selected = vec![(
+        "junovaloper196ax4vc0lwpxndu9dyhvca7jhxp70rmcqcnylw".to_string(),
+        Decimal::one(),
+    )];
+    let messages = suite.sample_gauge_msgs_for_epoch(selected, 42);
+    let res = suite
+        .app
+        .execute_multi(suite.owner.clone(), messages)
+        .unwrap();
+
+    // the hub tags the change with the source
+    assert!(res[0].has_event(
+        &Event::new(format!(
+            "wasm-{}",
+            wynd_lsd_hub::events::VALSET_CHANGE_EVENT
+        ))
+        .add_attribute("source", "gauge:42")
+    ));
+}
+
+#[test]
+fn sampled_messages_expire() {
+    let ttl = 60 * 60;
+    let mut suite = SuiteBuilder::new().with_intent_ttl(ttl).build();
+
+    let selected = vec![(
+        "junovaloper1y0us8xvsvfvqkk9c6nt5cfyu5au5tww2wsdcwk".to_string(),
+        Decimal::one(),
+    )];
+    let messages = suite.sample_gauge_msgs(selected.clone());
+    suite.next_block(ttl + 1);
+    let err = suite
+        .app
+        .execute_multi(suite.owner.clone(), messages)
+        .unwrap_err();
+    assert_eq!(HubContractError::IntentExpired {}, err.downcast().unwrap());
+
+    // executing right away works
+    let messages = suite.sample_gauge_msgs(selected.clone());
+    suite.next_block(ttl);
+    suite
+        .app
+        .execute_multi(suite.owner.clone(), messages)
+        .unwrap();
+    assert_eq!(suite.query_validator_set().unwrap(), selected);
+}
@@ -2,14 +2,14 @@ use std::str::FromStr;
 
 use anyhow::Result as AnyResult;
 
-use cosmwasm_std::{testing::mock_env, Addr, CosmosMsg, Decimal, Validator};
+use cosmwasm_std::{coins, testing::mock_env, Addr, CosmosMsg, Decimal, Validator};
 use cw20::{BalanceResponse, Cw20QueryMsg};
-use cw_multi_test::{App, ContractWrapper, Executor};
+use cw_multi_test::{App, BankSudo, ContractWrapper, Executor, StakingSudo};
 
 use cw_placeholder::msg::InstantiateMsg as PlaceholderContractInstantiateMsg;
 use wynd_lsd_hub::msg::{
-    InstantiateMsg as HubInstantiateMsg, QueryMsg as HubQueryMsg, TokenInitInfo,
-    ValidatorSetResponse,
+    ExecuteMsg as HubExecuteMsg, InstantiateMsg as HubInstantiateMsg, QueryMsg as HubQueryMsg,
+    TokenInitInfo, ValidatorSetResponse,
 };
 
 use crate::msg::{
@@ -65,6 +65,9 @@ fn store_placeholder_code(app: &mut App) -> u64 {
 #[derive(Debug)]
 pub struct SuiteBuilder {
     max_allowed_commission: Decimal,
+    weight_precision: Option<u32>,
+    tombstone_cooldown: Option<u64>,
+    intent_ttl: u64,
     // validator / commission
     chain_validators: Vec<(String, Decimal)>,
 
@@ -80,6 +83,9 @@ impl SuiteBuilder {
     pub fn new() -> Self {
         Self {
             max_allowed_commission: Decimal::one(),
+            weight_precision: None,
+            tombstone_cooldown: None,
+            intent_ttl: 0,
             chain_validators: vec![
                 (
                     "junovaloper1t8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaaa".to_string(),
@@ -111,6 +117,21 @@ impl SuiteBuilder {
         self
     }
 
+    pub fn with_weight_precision(mut self, precision: u32) -> Self {
+        self.weight_precision = Some(precision);
+        self
+    }
+
+    pub fn with_tombstone_cooldown(mut self, cooldown: u64) -> Self {
+        self.tombstone_cooldown = Some(cooldown);
+        self
+    }
+
+    pub fn with_intent_ttl(mut self, intent_ttl: u64) -> Self {
+        self.intent_ttl = intent_ttl;
+        self
+    }
+
     pub fn with_chain_validators(mut self, chain_validators: Vec<(&str, &str)>) -> Self {
         self.chain_validators = chain_validators
             .into_iter()
@@ -194,6 +215,12 @@ impl SuiteBuilder {
                     liquidity_discount: Decimal::percent(4),
                     tombstone_treshold: Decimal::percent(3),
                     slashing_safety_margin: 10 * 60,
+                    bond_denom_decimals: None,
+                    expected_bond_decimals: None,
+                    issuance_cap: None,
+                    voucher_init: None,
+                    bootstrap_min_deposit: None,
+                    valoper_prefix: "junovaloper".to_string(),
                 },
                 &[],
                 "Wyndex LSD Hub",
@@ -204,6 +231,9 @@ impl SuiteBuilder {
         let adapter_init_msg = crate::msg::InstantiateMsg {
             hub: hub.to_string(),
             max_commission: self.max_allowed_commission,
+            weight_precision: self.weight_precision,
+            tombstone_cooldown: self.tombstone_cooldown,
+            intent_ttl: self.intent_ttl,
         };
         let adapter_label = "Gauge Adapter";
 
@@ -264,6 +294,7 @@ impl SuiteBuilder {
             hub,
             gauge_adapter,
             epoch_length,
+            epoch_period: self.epoch_period,
         }
     }
 }
@@ -274,6 +305,7 @@ pub struct Suite {
     pub hub: Addr,
     pub gauge_adapter: Addr,
     pub epoch_length: u64,
+    pub epoch_period: u64,
 }
 
 impl Suite {
@@ -285,14 +317,83 @@ impl Suite {
         });
     }
 
+    /// Bonds through the hub and slashes `validator` so much that the hub detects it as tombstoned
+    #[allow(unused)]
+    pub fn tombstone(&mut self, validator: &str) -> AnyResult<()> {
+        let delegator = Addr::unchecked("delegator");
+        let denom = self.app.wrap().query_bonded_denom()?;
+        self.app.sudo(
+            BankSudo::Mint {
+                to_address: delegator.to_string(),
+                amount: coins(1_000_000, &denom),
+            }
+            .into(),
+        )?;
+        self.app.execute_contract(
+            delegator,
+            self.hub.clone(),
+            &HubExecuteMsg::Bond {},
+            &coins(1_000_000, denom),
+        )?;
+        self.next_block(self.epoch_period);
+        self.app.execute_contract(
+            self.owner.clone(),
+            self.hub.clone(),
+            &HubExecuteMsg::Reinvest { validators: None },
+            &[],
+        )?;
+
+        self.app.sudo(
+            StakingSudo::Slash {
+                validator: validator.to_string(),
+                percentage: Decimal::percent(50),
+            }
+            .into(),
+        )?;
+        self.app.execute_contract(
+            self.owner.clone(),
+            self.hub.clone(),
+            &HubExecuteMsg::CheckSlash {},
+            &[],
+        )?;
+        Ok(())
+    }
+
     #[allow(unused)]
     pub fn sample_gauge_msgs(&self, selected: Vec<(String, Decimal)>) -> Vec<CosmosMsg> {
+        self.try_sample_gauge_msgs(selected).unwrap()
+    }
+
+    #[allow(unused)]
+    pub fn try_sample_gauge_msgs(
+        &self,
+        selected: Vec<(String, Decimal)>,
+    ) -> AnyResult<Vec<CosmosMsg>> {
+        let msgs: SampleGaugeMsgsResponse = self.app.wrap().query_wasm_smart(
+            self.gauge_adapter.clone(),
+            &AdapterQueryMsg::SampleGaugeMsgs {
+                selected,
+                epoch: None,
+            },
+        )?;
+        Ok(msgs.execute)
+    }
+
+    #[allow(unused)]
+    pub fn sample_gauge_msgs_for_epoch(
+        &self,
+        selected: Vec<(String, Decimal)>,
+        epoch: u64,
+    ) -> Vec<CosmosMsg> {
         let msgs: SampleGaugeMsgsResponse = self
             .app
             .wrap()
             .query_wasm_smart(
                 self.gauge_adapter.clone(),
-                &AdapterQueryMsg::SampleGaugeMsgs { selected },
+                &AdapterQueryMsg::SampleGaugeMsgs {
+                    selected,
+                    epoch: Some(epoch),
+                },
             )
             .unwrap();
         msgs.execute
@@ -319,20 +420,24 @@ impl Suite {
     }
 
     pub fn query_all_options(&self) -> AnyResult<Vec<String>> {
-        let res: AllOptionsResponse = self
+        Ok(self.query_all_options_response()?.options)
+    }
+
+    pub fn query_all_options_response(&self) -> AnyResult<AllOptionsResponse> {
+        Ok(self
             .app
             .wrap()
-            .query_wasm_smart(self.gauge_adapter.clone(), &AdapterQueryMsg::AllOptions {})?;
-
-        Ok(res.options)
+            .query_wasm_smart(self.gauge_adapter.clone(), &AdapterQueryMsg::AllOptions {})?)
     }
 
     pub fn query_check_option(&self, option: String) -> AnyResult<bool> {
-        let res: CheckOptionResponse = self.app.wrap().query_wasm_smart(
+        Ok(self.query_check_option_response(option)?.valid)
+    }
+
+    pub fn query_check_option_response(&self, option: String) -> AnyResult<CheckOptionResponse> {
+        Ok(self.app.wrap().query_wasm_smart(
             self.gauge_adapter.clone(),
             &AdapterQueryMsg::CheckOption { option },
-        )?;
-
-        Ok(res.valid)
+        )?)
     }
 }
@@ -8,6 +8,21 @@ pub struct Config {
     pub hub: Addr,
     /// Maximum allowed commision by validator to be included in voting set
     pub max_commission: Decimal,
+    /// Number of decimal places the selected weights are rounded to, `None` keeps the weights as they are
+    pub weight_precision: Option<u32>,
+    /// For how long (in seconds) validators the hub detected as tombstoned are not offered, `0` to offer them right away
+    #[serde(default = "default_tombstone_cooldown")]
+    pub tombstone_cooldown: u64,
+    /// For how long (in seconds) the hub accepts a sampled valset change, `0` for no deadline
+    #[serde(default)]
+    pub intent_ttl: u64,
+}
+
+/// The `Config::tombstone_cooldown` of adapters that were instantiated without one or migrated from an older version
+pub const DEFAULT_TOMBSTONE_COOLDOWN: u64 = 90 * 24 * 60 * 60;
+
+fn default_tombstone_cooldown() -> u64 {
+    DEFAULT_TOMBSTONE_COOLDOWN
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
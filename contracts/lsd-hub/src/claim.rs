@@ -1,18 +1,81 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, BlockInfo, CustomQuery, Deps, StdResult, Storage, Timestamp, Uint128};
-use cw_storage_plus::Map;
+use cosmwasm_std::{
+    Addr, BlockInfo, CustomQuery, Decimal, Deps, Order, StdResult, Storage, Timestamp, Uint128,
+};
+use cw_storage_plus::{Bound, Map};
+
+use crate::pagination::paginate_vec;
+use crate::state::Slashing;
 
 // copied and adapted from cw-controllers
 
 #[cw_serde]
 pub struct ClaimsResponse {
     pub claims: Vec<Claim>,
+    /// The `start_after` for the next page, only set if the query was paginated and there are more claims
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_start_after: Option<u32>,
+    /// Where each of the `claims` is in its lifecycle, in the same order.
+    /// Only set for the native claims, not for the deferred ones.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub estimates: Vec<ClaimEstimate>,
+}
+
+/// Where a claim is in its lifecycle
+#[cw_serde]
+pub enum ClaimProgress {
+    /// The undelegation backing the claim was not issued yet, it waits for the next unbonding epoch
+    AwaitingUnbondEpoch,
+    /// The undelegation backing the claim was issued, but did not complete yet
+    Unbonding,
+    /// The undelegation completed, but the balance of the hub does not cover the claim yet
+    Mature,
+    /// The claim is released and the balance of the hub covers it
+    Payable,
+}
+
+#[cw_serde]
+pub struct ClaimEstimate {
+    /// When the claim can be expected to be paid. This is never before its `release_at`, which assumes
+    /// a timely reinvest, but later if the undelegation backing it was issued late or is still waiting.
+    pub estimated_available_at: Timestamp,
+    pub status: ClaimProgress,
+}
+
+/// Which claims are returned by the claims queries
+#[cw_serde]
+#[derive(Default)]
+pub enum ClaimStatus {
+    /// Only claims whose release time has passed
+    Matured,
+    /// Only claims that are not released yet
+    Pending,
+    #[default]
+    All,
+}
+
+impl ClaimStatus {
+    pub fn matches(&self, claim: &Claim, block: &BlockInfo) -> bool {
+        match self {
+            ClaimStatus::Matured => claim.release_at <= block.time,
+            ClaimStatus::Pending => claim.release_at > block.time,
+            ClaimStatus::All => true,
+        }
+    }
 }
 
 #[cw_serde]
 pub struct Claim {
     pub amount: Uint128,
     pub release_at: Timestamp,
+    /// The start of the unbonding epoch (in seconds) whose undelegation backs the claim.
+    /// It links the claim to the unbonding batch, `None` for claims created before it was stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unbond_epoch: Option<u64>,
+    /// If set, a registered claim buyer can buy the claim with `ExecuteMsg::BuyClaim`,
+    /// paying its amount minus this discount. Set by `ReceiveMsg::UnbondForSale`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sale_discount: Option<Decimal>,
 }
 
 impl Claim {
@@ -20,8 +83,40 @@ impl Claim {
         Claim {
             amount: amount.into(),
             release_at: released,
+            unbond_epoch: None,
+            sale_discount: None,
         }
     }
+
+    /// Returns the amount of the claim after applying all slashings that happened during its unbonding
+    pub fn slashed_amount(&self, slashings: &[Slashing]) -> Uint128 {
+        let release_at = self.release_at.seconds();
+        slashings
+            .iter()
+            .filter(|s| s.start < release_at && s.end > release_at)
+            .fold(self.amount, |amount, s| amount.mul_floor(s.multiplier))
+    }
+}
+
+/// Returns the checkpoint of a complete scan of the claims, which has to be passed back to repair the counter.
+/// It changes whenever the stored counter changes, so it can only be used until the next claim is created or paid.
+pub fn claims_checkpoint(
+    stored_counter: Uint128,
+    computed_sum: Uint128,
+    scanned_until: Option<&str>,
+) -> String {
+    // 64-bit FNV-1a, this only has to detect stale results, not be collision resistant
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in stored_counter
+        .to_be_bytes()
+        .into_iter()
+        .chain(computed_sum.to_be_bytes())
+        .chain(scanned_until.unwrap_or_default().bytes())
+    {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{hash:016x}")
 }
 
 pub struct Claims<'a>(Map<&'a Addr, Vec<Claim>>);
@@ -32,23 +127,89 @@ impl<'a> Claims<'a> {
     }
 
     /// This creates a claim, such that the given address can claim an amount of tokens after
-    /// the release date.
+    /// the release date. `unbond_epoch` is when the undelegation backing it is expected to start.
     pub fn create_claim(
         &self,
         storage: &mut dyn Storage,
         addr: &Addr,
         amount: Uint128,
         release_at: Timestamp,
+        unbond_epoch: u64,
     ) -> StdResult<()> {
         // add a claim to this user to get their tokens after the unbonding period
+        self.push(
+            storage,
+            addr,
+            Claim {
+                amount,
+                release_at,
+                unbond_epoch: Some(unbond_epoch),
+                sale_discount: None,
+            },
+        )
+    }
+
+    /// Same as [`Self::create_claim`], but the claim can be bought at the given `discount`
+    pub fn create_claim_for_sale(
+        &self,
+        storage: &mut dyn Storage,
+        addr: &Addr,
+        amount: Uint128,
+        release_at: Timestamp,
+        unbond_epoch: u64,
+        discount: Decimal,
+    ) -> StdResult<()> {
+        self.push(
+            storage,
+            addr,
+            Claim {
+                amount,
+                release_at,
+                unbond_epoch: Some(unbond_epoch),
+                sale_discount: Some(discount),
+            },
+        )
+    }
+
+    fn push(&self, storage: &mut dyn Storage, addr: &Addr, claim: Claim) -> StdResult<()> {
         self.0.update(storage, addr, |old| -> StdResult<_> {
             let mut claims = old.unwrap_or_default();
-            claims.push(Claim { amount, release_at });
+            claims.push(claim);
             Ok(claims)
         })?;
         Ok(())
     }
 
+    /// Moves the first claim of `owner` released at `release_at` that is for sale to `buyer`.
+    /// Returns the claim as it was offered, `None` if there is no such claim.
+    /// The buyer's claim is not for sale anymore.
+    pub fn transfer_claim_for_sale(
+        &self,
+        storage: &mut dyn Storage,
+        owner: &Addr,
+        buyer: &Addr,
+        release_at: Timestamp,
+    ) -> StdResult<Option<Claim>> {
+        let mut claims = self.load(storage, owner)?;
+        let Some(pos) = claims
+            .iter()
+            .position(|c| c.release_at == release_at && c.sale_discount.is_some())
+        else {
+            return Ok(None);
+        };
+        let claim = claims.remove(pos);
+        self.0.save(storage, owner, &claims)?;
+        self.push(
+            storage,
+            buyer,
+            Claim {
+                sale_discount: None,
+                ..claim.clone()
+            },
+        )?;
+        Ok(Some(claim))
+    }
+
     /// This iterates over all mature claims for the address, and removes them, up to an optional cap.
     /// it removes the finished claims and returns the total amount of tokens to be released.
     pub fn claim_tokens(
@@ -83,12 +244,166 @@ impl<'a> Claims<'a> {
         Ok(to_send)
     }
 
+    /// Pays `amount` from the mature claims of the address, oldest first.
+    /// The last claim needed is only reduced by what is left to pay, splitting it, so the rest stays claimable.
+    /// Returns the amount that was paid, which is less than `amount` if the mature claims don't cover it.
+    pub fn claim_exact(
+        &self,
+        storage: &mut dyn Storage,
+        addr: &Addr,
+        block: &BlockInfo,
+        claim_amount: impl Fn(&Claim) -> Uint128,
+        amount: Uint128,
+    ) -> StdResult<Uint128> {
+        let mut claims = self.load(storage, addr)?;
+        let mut mature: Vec<_> = (0..claims.len())
+            .filter(|&i| claims[i].release_at <= block.time)
+            .collect();
+        mature.sort_by_key(|&i| claims[i].release_at);
+
+        let mut paid = Uint128::zero();
+        for i in mature {
+            let c_amount = claim_amount(&claims[i]);
+            if paid + c_amount <= amount {
+                paid += c_amount;
+                claims[i].amount = Uint128::zero();
+            } else {
+                // the claim amount was slashed, so the stored amount is reduced proportionally
+                let left = c_amount - (amount - paid);
+                claims[i].amount = claims[i].amount.multiply_ratio(left, c_amount);
+                paid = amount;
+                break;
+            }
+        }
+        claims.retain(|c| !c.amount.is_zero());
+        self.0.save(storage, addr, &claims)?;
+        Ok(paid)
+    }
+
+    /// Same as [`Self::claim_tokens`], but for claims whose amount is denominated in lsd shares.
+    /// `to_tokens` converts the shares of a claim to native tokens and `cap` limits the converted amount.
+    /// Returns the total amount of shares and of native tokens to be released.
+    pub fn claim_shares(
+        &self,
+        storage: &mut dyn Storage,
+        addr: &Addr,
+        block: &BlockInfo,
+        to_tokens: impl Fn(Uint128) -> Uint128,
+        cap: Uint128,
+    ) -> StdResult<(Uint128, Uint128)> {
+        let mut shares = Uint128::zero();
+        let mut to_send = Uint128::zero();
+        self.0.update(storage, addr, |claim| -> StdResult<_> {
+            let (_send, waiting): (Vec<_>, _) =
+                claim.unwrap_or_default().into_iter().partition(|c| {
+                    // if mature and we can pay fully at the current rate, then include in _send
+                    if c.release_at <= block.time {
+                        let c_amount = to_tokens(c.amount);
+                        if to_send + c_amount > cap {
+                            return false;
+                        }
+                        shares += c.amount;
+                        to_send += c_amount;
+                        true
+                    } else {
+                        false
+                    }
+                });
+            Ok(waiting)
+        })?;
+        Ok((shares, to_send))
+    }
+
+    /// Sums up the claims of at most `limit` addresses after `start_after`, converted with `claim_amount`.
+    /// Returns the sum, the number of addresses scanned and the last of them.
+    pub fn sum_claims(
+        &self,
+        storage: &dyn Storage,
+        start_after: Option<&Addr>,
+        limit: usize,
+        claim_amount: impl Fn(&Claim) -> Uint128,
+    ) -> StdResult<(Uint128, u64, Option<Addr>)> {
+        let mut sum = Uint128::zero();
+        let mut scanned = 0;
+        let mut last = None;
+        for entry in self
+            .0
+            .range(
+                storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+        {
+            let (addr, claims) = entry?;
+            sum += claims.iter().map(&claim_amount).sum::<Uint128>();
+            scanned += 1;
+            last = Some(addr);
+        }
+        Ok((sum, scanned, last))
+    }
+
+    /// Returns whether any address after `start_after` has claims stored
+    pub fn has_claims_after(&self, storage: &dyn Storage, start_after: Option<&Addr>) -> bool {
+        self.0
+            .keys_raw(
+                storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .next()
+            .is_some()
+    }
+
+    /// Returns all claims of the given address, mature or not
+    pub fn load(&self, storage: &dyn Storage, addr: &Addr) -> StdResult<Vec<Claim>> {
+        Ok(self.0.may_load(storage, addr)?.unwrap_or_default())
+    }
+
+    /// Returns the claims of the given address.
+    /// If neither `start_after` nor `limit` is given, all of them are returned at once.
     pub fn query_claims<Q: CustomQuery>(
         &self,
         deps: Deps<Q>,
         address: &Addr,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    ) -> StdResult<ClaimsResponse> {
+        self.query_claims_where(deps, address, |_| true, start_after, limit)
+    }
+
+    /// Same as [`Self::query_claims`], but only for the claims matching `filter`.
+    /// `start_after` is the position in the filtered claims.
+    pub fn query_claims_where<Q: CustomQuery>(
+        &self,
+        deps: Deps<Q>,
+        address: &Addr,
+        filter: impl Fn(&Claim) -> bool,
+        start_after: Option<u32>,
+        limit: Option<u32>,
     ) -> StdResult<ClaimsResponse> {
-        let claims = self.0.may_load(deps.storage, address)?.unwrap_or_default();
-        Ok(ClaimsResponse { claims })
+        let claims: Vec<_> = self
+            .0
+            .may_load(deps.storage, address)?
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|c| filter(c))
+            .collect();
+        if start_after.is_none() && limit.is_none() {
+            return Ok(ClaimsResponse {
+                claims,
+                next_start_after: None,
+                estimates: vec![],
+            });
+        }
+
+        let page = paginate_vec(claims, start_after, limit);
+        Ok(ClaimsResponse {
+            claims: page.items,
+            next_start_after: page.next_start_after,
+            estimates: vec![],
+        })
     }
 }
@@ -1,8 +1,10 @@
+use std::collections::BTreeMap;
+
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    ensure, to_json_binary, Addr, Binary, Decimal, Deps, DepsMut, Env, MessageInfo, Reply,
-    Response, StdError, StdResult, SubMsg, WasmMsg,
+    coin, ensure, to_json_binary, Addr, Binary, Decimal, Deps, DepsMut, Env, MessageInfo, Reply,
+    Response, StakingMsg, StdError, StdResult, Storage, SubMsg, Uint128, WasmMsg,
 };
 use cw2::ensure_from_older_version;
 use cw2::set_contract_version;
@@ -10,13 +12,22 @@ use cw20::MinterResponse;
 use cw20_base::msg::InstantiateMsg as Cw20InstantiateMsg;
 
 use crate::error::ContractError;
+use crate::events::{self, LegacyAttributes};
 use crate::msg::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, ValidatorSetResponse,
+    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, SudoMsg, SurplusStrategy,
+    TokenInitInfo, ValidatorConfig, ValidatorSetResponse,
 };
 use crate::state::{
-    Config, StakeInfo, Supply, BONDED, CLAIMS, CONFIG, SLASHINGS, STAKE_INFO, SUPPLY, TMP_STATE,
+    ensure_not_bootstrapping, pending_redelegation_completions, pending_redelegations,
+    receiving_redelegations, record_redelegation, redelegation_caps, Bootstrap, Config,
+    RedelegationHop, StakeInfo, Supply, BONDED, BOOTSTRAP, BOOTSTRAP_DEPOSITS, CLAIMS, CONFIG,
+    DEFAULT_BOND_DENOM_DECIMALS, DEFAULT_MAX_COMMISSION_INCREASE_ALERT,
+    DEFAULT_MAX_DISCOUNT_CHANGE_PER_UPDATE, DEFAULT_MIN_DELEGATION,
+    DEFAULT_MIN_DISCOUNT_UPDATE_INTERVAL, DEFAULT_MIN_REINVEST_AMOUNT, DEFERRED_CLAIMS,
+    REDELEGATION_HOPS, SLASHINGS, STAKE_INFO, SUPPLY, TMP_STATE,
 };
-use crate::valset::valset_change_redelegation_messages;
+use crate::valoper;
+use crate::valset::{split_validator_configs, valset_change_redelegation_messages};
 
 use semver::Version;
 
@@ -30,6 +41,58 @@ const AFTER_TOKEN_CREATION_REPLY: u64 = 1;
 const AFTER_WITHDRAW_REPLY: u64 = 2;
 /// Extra id for all but the last withdrawal submessage
 const AFTER_WITHDRAW_INTERMITTENT_REPLY: u64 = 3;
+/// This id will be set on the last withdrawal submessage of a reinvest that only processes some validators
+const AFTER_PARTIAL_WITHDRAW_REPLY: u64 = 4;
+/// Id of the notification of the reinvest hook, we only get a reply if it failed
+const REINVEST_HOOK_REPLY: u64 = 5;
+/// Reply after instantiating the claim voucher, see `InstantiateMsg::voucher_init`
+const AFTER_VOUCHER_CREATION_REPLY: u64 = 6;
+
+/// The minimum number of seconds between the last reinvest and an `ExecuteMsg::ForceReinvest`
+pub const FORCE_REINVEST_INTERVAL: u64 = 60 * 60;
+
+/// How far `Supply::issued` may differ from the lsd token's total supply with `Config::strict_supply_check`
+pub const ISSUED_DUST_TOLERANCE: Uint128 = Uint128::new(10);
+
+/// The default `max_entries` of the staking module. It limits the pending redelegations for each pair of validators.
+pub const MAX_REDELEGATION_ENTRIES: u32 = 7;
+
+/// The most redelegations a reinvest issues with `Config::rebalance_on_reinvest`.
+/// Each one takes an entry of its pair of validators, so the drift is corrected over several epochs.
+pub const MAX_REBALANCE_REDELEGATIONS: usize = 2;
+
+/// The smallest redelegation a reinvest issues with `Config::rebalance_on_reinvest`,
+/// so the limited entries are not used up by rounding differences
+pub const MIN_REBALANCE_REDELEGATION: Uint128 = Uint128::new(1000);
+
+/// How long after its registration the owner can sweep what is left of an external reward to the treasury,
+/// see `ExecuteMsg::SweepExternalReward`
+pub const EXTERNAL_REWARD_SWEEP_DELAY: u64 = 180 * 24 * 60 * 60;
+
+/// The longest `Config::min_discount_update_interval` governance can set, so the discount cannot be frozen
+pub const MAX_DISCOUNT_UPDATE_INTERVAL: u64 = 30 * 24 * 60 * 60;
+
+/// The highest `Config::unbond_fee` the owner can set
+pub const MAX_UNBOND_FEE: Decimal = Decimal::percent(2);
+
+/// Maximum number of recipients in one `ExecuteMsg::BondMany`
+pub const MAX_BOND_MANY_RECIPIENTS: usize = 100;
+
+/// Maximum number of recipients in one `ReceiveMsg::Redistribute`
+pub const MAX_REDISTRIBUTE_RECIPIENTS: usize = 50;
+
+/// Maximum number of escrowed deposits minted in one call at the end of the bootstrap phase
+pub const MAX_BOOTSTRAP_MINTS: usize = 30;
+
+/// The biggest discount a claim can be offered at with `ReceiveMsg::UnbondForSale`
+pub const MAX_CLAIM_SALE_DISCOUNT: Decimal = Decimal::percent(10);
+
+/// Maximum length of the `source` of an `ExecuteMsg::SetValidators`
+pub const MAX_VALSET_CHANGE_SOURCE_LEN: usize = 64;
+
+/// How far `Supply::total_bonded` may differ from the `expected_total_bonded` of an `ExecuteMsg::SetValidators`,
+/// relative to the expectation
+pub const MAX_TOTAL_BONDED_DRIFT: Decimal = Decimal::percent(5);
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -40,8 +103,11 @@ pub fn instantiate(
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-    // Store the bonded denom for later and create the LSD token
-    let supply = Supply::new(deps.querier.query_bonded_denom()?);
+    // Store the bonded denom for later and create the LSD token.
+    // Without one, the hub could never accept any bonds
+    let bond_denom = deps.querier.query_bonded_denom()?;
+    ensure!(!bond_denom.is_empty(), ContractError::InvalidBondDenom {});
+    let supply = Supply::new(bond_denom);
     SUPPLY.save(deps.storage, &supply)?;
 
     // Verify commission is greater than 0.0 and no higher than 0.50
@@ -55,27 +121,49 @@ pub fn instantiate(
         return Err(ContractError::InvalidValidatorWeights {});
     }
 
+    // An account address passed by mistake would only fail once the delegations execute
+    valoper::ensure_valopers(
+        msg.validators.iter().map(|(v, _)| v.as_str()),
+        &msg.valoper_prefix,
+    )?;
+
     // Verify the liquidity discount
     ensure!(
         msg.liquidity_discount < Decimal::percent(50),
         ContractError::InvalidLiquidityDiscount {}
     );
 
+    // one lsd token should be displayed like one native token
+    if let Some(expected) = msg.expected_bond_decimals {
+        ensure!(
+            msg.cw20_init.decimals == expected,
+            ContractError::DecimalsMismatch {
+                token: msg.cw20_init.decimals,
+                expected
+            }
+        );
+    }
+
     let info = StakeInfo {
         validators: msg.validators.clone(),
+        max_delegations: vec![],
     };
     STAKE_INFO.save(deps.storage, &info)?;
 
     SLASHINGS.save(deps.storage, &vec![])?;
     BONDED.save(deps.storage, &vec![])?;
 
-    let mut response = Response::default();
+    let mut response = Response::default()
+        .add_attribute("bond_denom", &supply.bond_denom)
+        .add_event(events::valset_change(&msg.validators, None));
 
-    // add validator attributes
-    for (i, (validator, weight)) in msg.validators.into_iter().enumerate() {
+    // add validator attributes, in address order like the event
+    let mut validators = msg.validators;
+    validators.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (i, (validator, weight)) in validators.into_iter().enumerate() {
         response = response
-            .add_attribute(format!("validator_{}", i), validator)
-            .add_attribute(format!("validator_{}_weight", i), weight.to_string());
+            .add_legacy_attribute(format!("validator_{}", i), validator)
+            .add_legacy_attribute(format!("validator_{}_weight", i), weight.to_string());
     }
 
     // sanity checks
@@ -106,29 +194,324 @@ pub fn instantiate(
         liquidity_discount: msg.liquidity_discount,
         tombstone_treshold: msg.tombstone_treshold,
         slashing_safety_margin: msg.slashing_safety_margin,
+        min_reinvest_spacing: None,
+        min_blocks_per_epoch: None,
+        last_epoch_height: env.block.height,
+        last_unbond_height: env.block.height,
+        commission_vesting: None,
+        min_holding_period: 0,
+        min_reinvest_amount: DEFAULT_MIN_REINVEST_AMOUNT,
+        max_discount_change_per_update: DEFAULT_MAX_DISCOUNT_CHANGE_PER_UPDATE,
+        min_discount_update_interval: DEFAULT_MIN_DISCOUNT_UPDATE_INTERVAL,
+        last_discount_update: 0,
+        surplus_strategy: SurplusStrategy::TargetWeights,
+        delegations_suspended: false,
+        reinvest_hook: None,
+        new_validator_probation: 0,
+        probation_max_weight: Decimal::zero(),
+        bond_denom_decimals: msg
+            .bond_denom_decimals
+            .unwrap_or(DEFAULT_BOND_DENOM_DECIMALS),
+        expected_bond_decimals: msg.expected_bond_decimals,
+        max_commission_increase_alert: DEFAULT_MAX_COMMISSION_INCREASE_ALERT,
+        auto_zero_weight_on_spike: false,
+        delegate_every_n_epochs: 0,
+        strict_supply_check: false,
+        unbond_fee: Decimal::zero(),
+        last_unbond_fee_update: 0,
+        issuance_cap: msg.issuance_cap,
+        voucher_contract: None,
+        rebalance_on_reinvest: false,
+        operator: None,
+        redelegation_hops: false,
+        max_rate_growth_per_epoch: None,
+        min_delegation: DEFAULT_MIN_DELEGATION,
+        valoper_prefix: msg.valoper_prefix,
     };
+    ensure!(
+        config.is_valid_slashing_safety_margin(config.slashing_safety_margin),
+        ContractError::InvalidSlashingSafetyMargin {}
+    );
     CONFIG.save(deps.storage, &config)?;
+    if let Some(min_deposit) = msg.bootstrap_min_deposit.filter(|m| !m.is_zero()) {
+        BOOTSTRAP.save(
+            deps.storage,
+            &Bootstrap {
+                min_deposit,
+                deposited: Uint128::zero(),
+                minted: Uint128::zero(),
+            },
+        )?;
+    }
 
-    Ok(response.add_submessage(SubMsg::reply_on_success(
-        WasmMsg::Instantiate {
-            admin: Some(env.contract.address.to_string()), // use this contract as the initial admin so it can be changed later by a `MsgUpdateAdmin`
-            code_id: msg.cw20_init.cw20_code_id,
-            msg: to_json_binary(&Cw20InstantiateMsg {
-                name: msg.cw20_init.name,
-                symbol: msg.cw20_init.symbol,
-                decimals: msg.cw20_init.decimals,
-                initial_balances: msg.cw20_init.initial_balances,
-                mint: Some(MinterResponse {
-                    minter: env.contract.address.to_string(),
-                    cap: None,
-                }),
-                marketing: msg.cw20_init.marketing,
-            })?,
-            funds: vec![],
-            label: msg.cw20_init.label,
-        },
+    let mut response = response.add_submessage(SubMsg::reply_on_success(
+        instantiate_token(&env, msg.cw20_init, msg.issuance_cap)?,
         AFTER_TOKEN_CREATION_REPLY,
-    )))
+    ));
+    if let Some(voucher_init) = msg.voucher_init {
+        // every voucher has to be backed by a claim
+        ensure!(
+            voucher_init.initial_balances.is_empty(),
+            ContractError::InvalidVoucherInit {}
+        );
+        response = response.add_submessage(SubMsg::reply_on_success(
+            instantiate_token(&env, voucher_init, None)?,
+            AFTER_VOUCHER_CREATION_REPLY,
+        ));
+    }
+    Ok(response)
+}
+
+/// Instantiates a cw20 that only this contract can mint
+fn instantiate_token(env: &Env, init: TokenInitInfo, cap: Option<Uint128>) -> StdResult<WasmMsg> {
+    Ok(WasmMsg::Instantiate {
+        admin: Some(env.contract.address.to_string()), // use this contract as the initial admin so it can be changed later by a `MsgUpdateAdmin`
+        code_id: init.cw20_code_id,
+        msg: to_json_binary(&Cw20InstantiateMsg {
+            name: init.name,
+            symbol: init.symbol,
+            decimals: init.decimals,
+            initial_balances: init.initial_balances,
+            mint: Some(MinterResponse {
+                minter: env.contract.address.to_string(),
+                cap,
+            }),
+            marketing: init.marketing,
+        })?,
+        funds: vec![],
+        label: init.label,
+    })
+}
+
+/// The redelegations of a valset change, split by whether their pair of validators has a free redelegation entry
+struct ValsetStage {
+    /// The redelegations that can be executed now
+    ready: Vec<StakingMsg>,
+    /// The redelegations that have to wait for earlier ones of their pair to complete
+    waiting: Vec<StakingMsg>,
+    /// When the first waiting redelegation can be executed, `None` if none is waiting
+    ready_at: Option<u64>,
+}
+
+fn split_by_free_entries(
+    storage: &dyn Storage,
+    messages: Vec<StakingMsg>,
+    now: u64,
+) -> StdResult<ValsetStage> {
+    let mut stage = ValsetStage {
+        ready: vec![],
+        waiting: vec![],
+        ready_at: None,
+    };
+    for msg in messages {
+        let completions = match &msg {
+            StakingMsg::Redelegate {
+                src_validator,
+                dst_validator,
+                ..
+            } => pending_redelegation_completions(storage, src_validator, dst_validator, now)?,
+            _ => vec![],
+        };
+        // every pair is redelegated at most once per valset change
+        if completions.len() < MAX_REDELEGATION_ENTRIES as usize {
+            stage.ready.push(msg);
+        } else {
+            let frees_at = completions.into_iter().min().unwrap_or(now);
+            stage.ready_at = Some(stage.ready_at.map_or(frees_at, |at| at.min(frees_at)));
+            stage.waiting.push(msg);
+        }
+    }
+    Ok(stage)
+}
+
+/// Routes the waiting redelegations of `stage` through the first validator of `target` (by address)
+/// whose pairs with the source and the destination have a free entry. The first hop is added to the ready
+/// redelegations, the second one is returned. It can only be executed after the first completed at `completes_at`.
+fn route_waiting_redelegations(
+    storage: &dyn Storage,
+    now: u64,
+    completes_at: u64,
+    target: &[(String, Decimal)],
+    stage: &mut ValsetStage,
+) -> StdResult<Vec<RedelegationHop>> {
+    let mut candidates: Vec<_> = target
+        .iter()
+        .filter(|(_, weight)| !weight.is_zero())
+        .map(|(validator, _)| validator)
+        .collect();
+    candidates.sort();
+
+    let mut hops = vec![];
+    stage.ready_at = None;
+    for msg in std::mem::take(&mut stage.waiting) {
+        let StakingMsg::Redelegate {
+            src_validator,
+            dst_validator,
+            amount,
+        } = &msg
+        else {
+            stage.waiting.push(msg);
+            continue;
+        };
+        let mut intermediate = None;
+        for candidate in candidates.iter().copied() {
+            if candidate == src_validator || candidate == dst_validator {
+                continue;
+            }
+            // the stage may redelegate from the source to the candidate already
+            let used = stage
+                .ready
+                .iter()
+                .filter(|ready| {
+                    matches!(ready, StakingMsg::Redelegate { src_validator: src, dst_validator: dst, .. }
+                        if src == src_validator && dst == candidate)
+                })
+                .count() as u32;
+            if pending_redelegations(storage, src_validator, candidate, now)? + used
+                < MAX_REDELEGATION_ENTRIES
+                && pending_redelegations(storage, candidate, dst_validator, now)?
+                    < MAX_REDELEGATION_ENTRIES
+            {
+                intermediate = Some(candidate);
+                break;
+            }
+        }
+
+        match intermediate {
+            Some(intermediate) => {
+                hops.push(RedelegationHop {
+                    intermediate: intermediate.clone(),
+                    dst_validator: dst_validator.clone(),
+                    amount: amount.amount,
+                    ready_at: completes_at,
+                });
+                stage.ready.push(StakingMsg::Redelegate {
+                    src_validator: src_validator.clone(),
+                    dst_validator: intermediate.clone(),
+                    amount: amount.clone(),
+                });
+            }
+            None => {
+                let frees_at =
+                    pending_redelegation_completions(storage, src_validator, dst_validator, now)?
+                        .into_iter()
+                        .min()
+                        .unwrap_or(now);
+                stage.ready_at = Some(stage.ready_at.map_or(frees_at, |at| at.min(frees_at)));
+                stage.waiting.push(msg);
+            }
+        }
+    }
+    Ok(hops)
+}
+
+/// Returns the delegations as if the second hops were executed already.
+/// Undelegations and slashings can leave less on the intermediate than the hop moves, then it moves what is left.
+fn bonded_after_hops(
+    bonded: &[(String, Uint128)],
+    hops: &[RedelegationHop],
+) -> Vec<(String, Uint128)> {
+    let mut bonded: BTreeMap<_, _> = bonded.iter().cloned().collect();
+    for hop in hops {
+        let moved = match bonded.get_mut(&hop.intermediate) {
+            Some(available) => {
+                let moved = hop.amount.min(*available);
+                *available -= moved;
+                moved
+            }
+            None => continue,
+        };
+        *bonded.entry(hop.dst_validator.clone()).or_default() += moved;
+    }
+    bonded.into_iter().filter(|(_, b)| !b.is_zero()).collect()
+}
+
+/// Limits the redelegations to what is actually delegated to their source.
+/// The destination of a hop does not hold its tokens yet, so it can only give away the rest.
+fn limit_to_bonded(bonded: &[(String, Uint128)], messages: Vec<StakingMsg>) -> Vec<StakingMsg> {
+    let mut available: BTreeMap<_, _> = bonded.iter().cloned().collect();
+    messages
+        .into_iter()
+        .filter_map(|msg| match msg {
+            StakingMsg::Redelegate {
+                src_validator,
+                dst_validator,
+                mut amount,
+            } => {
+                let left = available.entry(src_validator.clone()).or_default();
+                amount.amount = amount.amount.min(*left);
+                *left -= amount.amount;
+                (!amount.amount.is_zero()).then_some(StakingMsg::Redelegate {
+                    src_validator,
+                    dst_validator,
+                    amount,
+                })
+            }
+            msg => Some(msg),
+        })
+        .collect()
+}
+
+/// Executes the second hops that are ready, see [`REDELEGATION_HOPS`].
+/// Hops whose pair has no free entry wait for the next reinvest, hops to a validator that was removed
+/// from the validator set are dropped and their tokens stay with the intermediate.
+fn complete_redelegation_hops(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &Config,
+    bond_denom: &str,
+) -> StdResult<Vec<StakingMsg>> {
+    let Some(hops) = REDELEGATION_HOPS.may_load(storage)? else {
+        return Ok(vec![]);
+    };
+    let now = env.block.time.seconds();
+    let validators = STAKE_INFO.load(storage)?.validators;
+    let mut bonded: BTreeMap<_, _> = BONDED.load(storage)?.into_iter().collect();
+
+    let mut messages = vec![];
+    let mut waiting = vec![];
+    for hop in hops {
+        if hop.ready_at > now
+            || pending_redelegations(storage, &hop.intermediate, &hop.dst_validator, now)?
+                >= MAX_REDELEGATION_ENTRIES
+        {
+            waiting.push(hop);
+            continue;
+        }
+        if !validators.iter().any(|(v, _)| v == &hop.dst_validator) {
+            continue;
+        }
+        let Some(available) = bonded.get_mut(&hop.intermediate) else {
+            continue;
+        };
+        let amount = hop.amount.min(*available);
+        if amount.is_zero() {
+            continue;
+        }
+        *available -= amount;
+        *bonded.entry(hop.dst_validator.clone()).or_default() += amount;
+        record_redelegation(
+            storage,
+            &hop.intermediate,
+            &hop.dst_validator,
+            now,
+            now + config.unbond_period,
+        )?;
+        messages.push(StakingMsg::Redelegate {
+            src_validator: hop.intermediate,
+            dst_validator: hop.dst_validator,
+            amount: coin(amount.u128(), bond_denom),
+        });
+    }
+
+    if waiting.is_empty() {
+        REDELEGATION_HOPS.remove(storage);
+    } else {
+        REDELEGATION_HOPS.save(storage, &waiting)?;
+    }
+    let bonded: Vec<_> = bonded.into_iter().filter(|(_, b)| !b.is_zero()).collect();
+    BONDED.save(storage, &bonded)?;
+    Ok(messages)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -140,34 +523,166 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::Receive(msg) => execute::handle_receive(deps, env, info, msg),
-        ExecuteMsg::Claim {} => execute::claim(deps, env, info),
-        ExecuteMsg::Bond {} => execute::bond(deps, env, info),
-        ExecuteMsg::Reinvest {} => execute::reinvest(deps, env),
-        ExecuteMsg::SetValidators { new_validators } => {
-            execute::set_validators(deps, info, env, new_validators)
+        ExecuteMsg::UnbondFrom { owner, amount } => {
+            execute::unbond_from(deps, env, info, owner, amount)
+        }
+        ExecuteMsg::Claim {} => execute::claim(deps, env, info.sender.clone(), info.sender, None),
+        ExecuteMsg::ClaimUpTo { max_amount } => execute::claim(
+            deps,
+            env,
+            info.sender.clone(),
+            info.sender,
+            Some(max_amount),
+        ),
+        ExecuteMsg::ClaimAmount { amount } => execute::claim_amount(deps, env, info.sender, amount),
+        ExecuteMsg::SetClaimRecipient { recipient } => {
+            execute::set_claim_recipient(deps, info, recipient)
+        }
+        ExecuteMsg::ClaimFor { owner } => execute::claim_for(deps, env, info, owner),
+        ExecuteMsg::BuyClaim { owner, release_at } => {
+            execute::buy_claim(deps, info, owner, release_at)
         }
+        ExecuteMsg::Bond {} => execute::bond(deps, env, info),
+        ExecuteMsg::FinalizeBootstrap { limit } => execute::finalize_bootstrap(deps, limit),
+        ExecuteMsg::BondMany { recipients } => execute::bond_many(deps, env, info, recipients),
+        ExecuteMsg::Reinvest { validators } => execute::reinvest(deps, env, validators),
+        ExecuteMsg::ForceReinvest {} => execute::force_reinvest(deps, env, info),
+        ExecuteMsg::SetValidators {
+            new_validators,
+            source,
+            deadline,
+            expected_total_bonded,
+        } => execute::set_validators(
+            deps,
+            info,
+            env,
+            new_validators.into_iter().map(Into::into).collect(),
+            source,
+            deadline,
+            expected_total_bonded,
+        ),
+        ExecuteMsg::ContinueValsetChange {} => execute::continue_valset_change(deps, env),
         ExecuteMsg::UpdateLiquidityDiscount { new_discount } => {
-            execute::update_liquidity_discount(deps, info, new_discount)
+            execute::update_liquidity_discount(deps, env, info, new_discount)
+        }
+        ExecuteMsg::UpdateTombstoneThreshold { new_threshold } => {
+            execute::update_tombstone_threshold(deps, info, new_threshold)
+        }
+        ExecuteMsg::UpdateSlashingSafetyMargin { new_margin } => {
+            execute::update_slashing_safety_margin(deps, info, new_margin)
+        }
+        ExecuteMsg::UpdateMinReinvestSpacing {
+            min_reinvest_spacing,
+        } => execute::update_min_reinvest_spacing(deps, info, min_reinvest_spacing),
+        ExecuteMsg::UpdateMinBlocksPerEpoch {
+            min_blocks_per_epoch,
+        } => execute::update_min_blocks_per_epoch(deps, info, min_blocks_per_epoch),
+        ExecuteMsg::UpdateMinHoldingPeriod { min_holding_period } => {
+            execute::update_min_holding_period(deps, info, min_holding_period)
+        }
+        ExecuteMsg::UpdateMinReinvestAmount {
+            min_reinvest_amount,
+        } => execute::update_min_reinvest_amount(deps, info, min_reinvest_amount),
+        ExecuteMsg::UpdateMinDelegation { min_delegation } => {
+            execute::update_min_delegation(deps, info, min_delegation)
+        }
+        ExecuteMsg::UpdateSurplusStrategy { surplus_strategy } => {
+            execute::update_surplus_strategy(deps, info, surplus_strategy)
+        }
+        ExecuteMsg::SuspendDelegations {} => execute::set_delegations_suspended(deps, info, true),
+        ExecuteMsg::ResumeDelegations {} => execute::set_delegations_suspended(deps, info, false),
+        ExecuteMsg::UpdateReinvestHook { hook } => execute::update_reinvest_hook(deps, info, hook),
+        ExecuteMsg::UpdateValidatorProbation {
+            new_validator_probation,
+            probation_max_weight,
+        } => execute::update_validator_probation(
+            deps,
+            info,
+            new_validator_probation,
+            probation_max_weight,
+        ),
+        ExecuteMsg::UpdateCommissionSpikeHandling {
+            max_commission_increase_alert,
+            auto_zero_weight_on_spike,
+        } => execute::update_commission_spike_handling(
+            deps,
+            info,
+            max_commission_increase_alert,
+            auto_zero_weight_on_spike,
+        ),
+        ExecuteMsg::UpdateDelegationCadence {
+            delegate_every_n_epochs,
+        } => execute::update_delegation_cadence(deps, info, delegate_every_n_epochs),
+        ExecuteMsg::UpdateUnbondFee { unbond_fee } => {
+            execute::update_unbond_fee(deps, env, info, unbond_fee)
+        }
+        ExecuteMsg::UpdateStrictSupplyCheck {
+            strict_supply_check,
+        } => execute::update_strict_supply_check(deps, info, strict_supply_check),
+        ExecuteMsg::UpdateRebalanceOnReinvest {
+            rebalance_on_reinvest,
+        } => execute::update_rebalance_on_reinvest(deps, info, rebalance_on_reinvest),
+        ExecuteMsg::UpdateRedelegationHops { redelegation_hops } => {
+            execute::update_redelegation_hops(deps, info, redelegation_hops)
+        }
+        ExecuteMsg::UpdateMaxRateGrowth {
+            max_rate_growth_per_epoch,
+        } => execute::update_max_rate_growth(deps, info, max_rate_growth_per_epoch),
+        ExecuteMsg::RegisterClaimBuyer { contract } => {
+            execute::register_claim_buyer(deps, info, contract)
+        }
+        ExecuteMsg::DeregisterClaimBuyer { contract } => {
+            execute::deregister_claim_buyer(deps, info, contract)
+        }
+        ExecuteMsg::UpdateIssuanceCap { issuance_cap } => {
+            execute::update_issuance_cap(deps, info, issuance_cap)
+        }
+        ExecuteMsg::SyncIssued {} => execute::sync_issued(deps, info),
+        ExecuteMsg::RepairClaimsCounter {
+            computed_sum,
+            scanned_until,
+            checkpoint,
+        } => execute::repair_claims_counter(deps, info, computed_sum, scanned_until, checkpoint),
+        ExecuteMsg::UpdateCommissionVesting { commission_vesting } => {
+            execute::update_commission_vesting(deps, info, commission_vesting)
+        }
+        ExecuteMsg::ClaimVestedCommission {} => execute::claim_vested_commission(deps, env, info),
+        ExecuteMsg::SkipNextUnbond {} => execute::skip_next_unbond(deps, env, info),
+        ExecuteMsg::DepositBoost {} => execute::deposit_boost(deps, info),
+        ExecuteMsg::WithdrawBoost { amount } => execute::withdraw_boost(deps, env, info, amount),
+        ExecuteMsg::RegisterExternalReward { denom } => {
+            execute::register_external_reward(deps, env, info, denom)
+        }
+        ExecuteMsg::SweepExternalReward { denom } => {
+            execute::sweep_external_reward(deps, env, info, denom)
         }
         ExecuteMsg::CheckSlash {} => execute::check_slash(deps, env),
+        ExecuteMsg::SetOperator { operator } => execute::set_operator(deps, info, operator),
     }
 }
 
 mod execute {
     use std::collections::HashMap;
 
-    use crate::{
-        msg::ReceiveMsg,
-        state::{unbondings_expiring_between, Slashing, TmpState, CLAIMS, SLASHINGS, UNBONDING},
-        valset::ValsetChange,
-    };
-    use std::cmp::max;
-
     use super::*;
     use crate::state::CleanedSupply;
+    use crate::{
+        claim::{claims_checkpoint, Claim},
+        msg::{BondedChangeReason, CommissionVesting, ReceiveMsg},
+        state::{
+            ensure_holding_period_met, last_reinvest, range_unbondings, record_bonded_change,
+            record_fresh_shares, record_rate_snapshot, record_redelegation, record_reinvest,
+            redeem_vouchers, released_vouchers, save_unbondings, take_external_rewards,
+            unbondings_expiring_between, ExternalReward, ReinvestProgress, Slashing, TmpState,
+            BOOST_PRINCIPAL, CLAIMS, CLAIM_BUYERS, CLAIM_RECIPIENTS, COMMISSION_VESTING,
+            EXTERNAL_REWARDS, LAST_BOND, PENDING_VALSET, REINVEST_PROGRESS, SLASHINGS,
+            TOMBSTONED_VALIDATORS, VALIDATOR_COMMISSIONS, VALIDATOR_FIRST_SEEN, VOUCHER_BATCHES,
+        },
+        valset::{apply_redelegations, remove_weights, ValsetChange},
+    };
     use cosmwasm_std::{
-        ensure, ensure_eq, from_json, to_json_binary, BankMsg, Coin, CosmosMsg, DistributionMsg,
-        Order, Timestamp, Uint128, WasmMsg,
+        coins, ensure, ensure_eq, from_json, to_json_binary, BankMsg, Coin, CosmosMsg,
+        DistributionMsg, Empty, Event, Order, StakingMsg, Storage, Timestamp, Uint128, WasmMsg,
     };
     use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
     use cw_utils::must_pay;
@@ -176,68 +691,444 @@ mod execute {
         deps: DepsMut,
         info: MessageInfo,
         env: Env,
-        new_validators: Vec<(String, Decimal)>,
+        new_validators: Vec<ValidatorConfig>,
+        source: Option<String>,
+        deadline: Option<u64>,
+        expected_total_bonded: Option<Uint128>,
     ) -> Result<Response, ContractError> {
         // Only the 'owner' set in Instantiate can update the validator set
         let config = CONFIG.load(deps.storage)?;
-        ensure_eq!(info.sender, config.owner, ContractError::Unauthorized {});
+        config.ensure_owner(&info.sender)?;
+        ensure!(
+            deadline.map_or(true, |deadline| env.block.time.seconds() <= deadline),
+            ContractError::IntentExpired {}
+        );
+        ensure!(
+            source
+                .as_ref()
+                .map_or(true, |s| s.chars().count() <= MAX_VALSET_CHANGE_SOURCE_LEN),
+            ContractError::ValsetChangeSourceTooLong {
+                max: MAX_VALSET_CHANGE_SOURCE_LEN
+            }
+        );
 
         let mut supply = CleanedSupply::load(deps.storage, &env)?;
+        // the weights were chosen for a different state if the delegations changed too much since
+        if let Some(expected) = expected_total_bonded {
+            let actual = supply.total_bonded;
+            ensure!(
+                actual.abs_diff(expected) <= expected.mul_floor(MAX_TOTAL_BONDED_DRIFT),
+                ContractError::StateDrifted { expected, actual }
+            );
+        }
         let mut stake_info = STAKE_INFO.load(deps.storage)?;
+        let (new_validators, max_delegations) = split_validator_configs(new_validators);
+        valoper::ensure_valopers(
+            new_validators.iter().map(|(v, _)| v.as_str()),
+            &config.valoper_prefix,
+        )?;
 
         let mut response = Response::new();
         // If the sum of all balances is non zero, then we need to redelegate. Otherwise just update the valset
         if supply.total_bonded != Uint128::zero() {
-            let bonded = BONDED.load(deps.storage)?;
-
-            let ValsetChange {
-                messages,
-                new_balances,
-            } = valset_change_redelegation_messages(
-                &supply,
-                bonded.iter().map(|(k, v)| (k, *v)),
-                new_validators.iter().map(|(k, v)| (k, *v)),
+            let stage = execute_valset_stage(
+                deps.storage,
+                &env,
+                &config,
+                &mut supply,
+                &stake_info.validators,
+                &new_validators,
+                &max_delegations,
             )?;
-            response = response.add_messages(messages);
-            BONDED.save(deps.storage, &new_balances)?;
-            supply.total_bonded = new_balances.into_iter().map(|(_, v)| v).sum();
-            SUPPLY.save(deps.storage, &supply)?;
+            if !stage.waiting.is_empty() {
+                response = response
+                    .add_attribute("waiting_redelegations", stage.waiting.len().to_string());
+            }
+            response = response.add_messages(stage.ready);
+        } else {
+            PENDING_VALSET.remove(deps.storage);
+        }
+
+        // remember when new validators were added, for their probation
+        for (validator, _) in &new_validators {
+            if !stake_info.validators.iter().any(|(v, _)| v == validator)
+                && !VALIDATOR_FIRST_SEEN.has(deps.storage, validator)
+            {
+                VALIDATOR_FIRST_SEEN.save(deps.storage, validator, &env.block.time.seconds())?;
+            }
         }
 
+        response = response.add_event(events::valset_change(&new_validators, source.as_deref()));
         stake_info.validators = new_validators;
+        stake_info.max_delegations = max_delegations;
         STAKE_INFO.save(deps.storage, &stake_info)?;
 
+        if let Some(source) = source {
+            response = response.add_legacy_attribute("valset_change_source", source);
+        }
         Ok(response)
     }
 
+    /// Executes the redelegations from the current delegations to `target` that fit into the free redelegation entries.
+    /// If some have to wait, `target` is stored as the `PENDING_VALSET`, until `ExecuteMsg::ContinueValsetChange`
+    /// executed them all.
+    fn execute_valset_stage(
+        storage: &mut dyn Storage,
+        env: &Env,
+        config: &Config,
+        supply: &mut CleanedSupply,
+        current_validators: &[(String, Decimal)],
+        target: &[(String, Decimal)],
+        max_delegations: &[(String, Uint128)],
+    ) -> Result<ValsetStage, ContractError> {
+        let old_total_bonded = supply.total_bonded;
+        let bonded = BONDED.load(storage)?;
+        let now = env.block.time.seconds();
+
+        // validators on probation only get a small part of the redelegations
+        let caps = redelegation_caps(
+            storage,
+            config,
+            now,
+            current_validators,
+            target,
+            max_delegations,
+            supply.total_bonded,
+        )?;
+        // the tokens of routed redelegations already count for their destination, so they are not moved again
+        let hops = REDELEGATION_HOPS.may_load(storage)?.unwrap_or_default();
+        let ValsetChange { messages, .. } = valset_change_redelegation_messages(
+            supply,
+            bonded_after_hops(&bonded, &hops)
+                .iter()
+                .map(|(k, v)| (k, *v)),
+            target.iter().map(|(k, v)| (k, *v)),
+            &caps,
+        )?;
+        let messages = limit_to_bonded(&bonded, messages);
+        let mut stage = split_by_free_entries(storage, messages, now)?;
+        if config.redelegation_hops && !stage.waiting.is_empty() {
+            let routed = route_waiting_redelegations(
+                storage,
+                now,
+                now + config.unbond_period,
+                target,
+                &mut stage,
+            )?;
+            if !routed.is_empty() {
+                REDELEGATION_HOPS.save(storage, &[hops, routed].concat())?;
+            }
+        }
+
+        // remember the redelegations, so `QueryMsg::SimulateValsetChange` can warn about the entry limit
+        for msg in &stage.ready {
+            if let StakingMsg::Redelegate {
+                src_validator,
+                dst_validator,
+                ..
+            } = msg
+            {
+                record_redelegation(
+                    storage,
+                    src_validator,
+                    dst_validator,
+                    now,
+                    now + config.unbond_period,
+                )?;
+            }
+        }
+        if stage.waiting.is_empty() {
+            PENDING_VALSET.remove(storage);
+        } else {
+            PENDING_VALSET.save(storage, &target.to_vec())?;
+        }
+
+        let new_balances = apply_redelegations(bonded, &stage.ready);
+        BONDED.save(storage, &new_balances)?;
+        supply.total_bonded = new_balances.into_iter().map(|(_, v)| v).sum();
+        SUPPLY.save(storage, supply)?;
+        record_bonded_change(
+            storage,
+            env,
+            BondedChangeReason::Redelegate,
+            old_total_bonded,
+            supply.total_bonded,
+        )?;
+        Ok(stage)
+    }
+
+    pub fn continue_valset_change(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+        let target = PENDING_VALSET
+            .may_load(deps.storage)?
+            .ok_or(ContractError::NoPendingValsetChange {})?;
+        let config = CONFIG.load(deps.storage)?;
+        let stake_info = STAKE_INFO.load(deps.storage)?;
+        let mut supply = CleanedSupply::load(deps.storage, &env)?;
+
+        let response = Response::new().add_attribute("action", "continue_valset_change");
+        // with nothing bonded, there is nothing left to move
+        if supply.total_bonded.is_zero() {
+            PENDING_VALSET.remove(deps.storage);
+            return Ok(response.add_attribute("waiting_redelegations", "0"));
+        }
+
+        let stage = execute_valset_stage(
+            deps.storage,
+            &env,
+            &config,
+            &mut supply,
+            &stake_info.validators,
+            &target,
+            &stake_info.max_delegations,
+        )?;
+        // nothing can be redelegated before an entry of the waiting pairs is free again
+        if let Some(ready_at) = stage.ready_at {
+            ensure!(
+                !stage.ready.is_empty(),
+                ContractError::ValsetChangeNotReady { ready_at }
+            );
+        }
+        Ok(response
+            .add_attribute("waiting_redelegations", stage.waiting.len().to_string())
+            .add_messages(stage.ready))
+    }
+
     pub fn bond(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        config.ensure_initialized()?;
+        if let Some(bootstrap) = BOOTSTRAP.may_load(deps.storage)? {
+            return bootstrap_deposit(deps, env, &config, info, bootstrap);
+        }
         let mut supply = CleanedSupply::load(deps.storage, &env)?;
 
-        // determine the ratio before these funds were received
+        // determine the ratio before these funds were received.
+        // `query::simulate_bond` has to use the balance as is instead, keep both in sync
         let paid = must_pay(&info, &supply.bond_denom)?;
         let balance = supply.balance(deps.as_ref(), &env)?;
 
         // calculate how many shares to issue, this is determined by the exchange rate
-        let issue = paid.mul_floor(supply.shares_per_token(balance - paid));
+        let issue = supply.shares_for_tokens(paid, balance - paid);
         supply.issued += issue;
+        ensure!(
+            !config.exceeds_issuance_cap(supply.issued),
+            ContractError::IssuanceCapReached {}
+        );
         SUPPLY.save(deps.storage, &supply)?;
+        LAST_BOND.save(deps.storage, &info.sender, &env.block.time.seconds())?;
+        record_fresh_shares(deps.storage, &info.sender, issue)?;
 
-        let config = CONFIG.load(deps.storage)?;
         // issue the stake token for sender
         let mint_msg = Cw20ExecuteMsg::Mint {
             recipient: info.sender.to_string(),
             amount: issue,
         };
 
-        let res: Response = Response::new().add_message(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: config.token_contract.to_string(),
-            msg: to_json_binary(&mint_msg)?,
-            funds: vec![],
-        }));
+        let res: Response = Response::new()
+            .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: config.token_contract.to_string(),
+                msg: to_json_binary(&mint_msg)?,
+                funds: vec![],
+            }))
+            .add_event(events::bond(&info.sender, paid, issue));
 
         Ok(res)
     }
 
+    /// Escrows the deposit of the sender during the bootstrap phase.
+    /// The deposit that reaches `Bootstrap::min_deposit` mints the first batch of the escrowed deposits.
+    fn bootstrap_deposit(
+        deps: DepsMut,
+        env: Env,
+        config: &Config,
+        info: MessageInfo,
+        mut bootstrap: Bootstrap,
+    ) -> Result<Response, ContractError> {
+        let supply = SUPPLY.load(deps.storage)?;
+        let paid = must_pay(&info, &supply.bond_denom)?;
+
+        // everything is minted 1:1, so the cap applies to the deposits directly
+        bootstrap.deposited += paid;
+        ensure!(
+            !config.exceeds_issuance_cap(supply.issued + bootstrap.deposited - bootstrap.minted),
+            ContractError::IssuanceCapReached {}
+        );
+        BOOTSTRAP_DEPOSITS.update(deps.storage, &info.sender, |deposit| -> StdResult<_> {
+            Ok(deposit.unwrap_or_default() + paid)
+        })?;
+        LAST_BOND.save(deps.storage, &info.sender, &env.block.time.seconds())?;
+
+        let res = Response::new()
+            .add_attribute("action", "bootstrap_deposit")
+            .add_attribute("depositor", &info.sender)
+            .add_attribute("amount", paid);
+        if !bootstrap.threshold_reached() {
+            BOOTSTRAP.save(deps.storage, &bootstrap)?;
+            return Ok(res);
+        }
+        mint_bootstrap_deposits(deps.storage, config, bootstrap, MAX_BOOTSTRAP_MINTS, res)
+    }
+
+    /// Mints the escrowed bootstrap deposits once they reached the threshold, see [`bootstrap_deposit`]
+    pub fn finalize_bootstrap(
+        deps: DepsMut,
+        limit: Option<u32>,
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        let bootstrap = BOOTSTRAP
+            .may_load(deps.storage)?
+            .ok_or(ContractError::NotBootstrapping {})?;
+        ensure!(
+            bootstrap.threshold_reached(),
+            ContractError::BootstrapThresholdNotReached {
+                deposited: bootstrap.deposited,
+                min_deposit: bootstrap.min_deposit,
+            }
+        );
+        let limit = limit.map_or(MAX_BOOTSTRAP_MINTS, |l| {
+            (l as usize).clamp(1, MAX_BOOTSTRAP_MINTS)
+        });
+
+        let res = Response::new().add_attribute("action", "finalize_bootstrap");
+        mint_bootstrap_deposits(deps.storage, &config, bootstrap, limit, res)
+    }
+
+    /// Mints up to `limit` escrowed deposits 1:1 and ends the bootstrap phase once none are left.
+    /// The native tokens are in the balance already, they are delegated by the next reinvest.
+    /// The mints are added to `res`.
+    fn mint_bootstrap_deposits(
+        storage: &mut dyn Storage,
+        config: &Config,
+        mut bootstrap: Bootstrap,
+        limit: usize,
+        mut res: Response,
+    ) -> Result<Response, ContractError> {
+        let deposits = BOOTSTRAP_DEPOSITS
+            .range(storage, None, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut supply = SUPPLY.load(storage)?;
+        for (depositor, amount) in deposits {
+            BOOTSTRAP_DEPOSITS.remove(storage, &depositor);
+            record_fresh_shares(storage, &depositor, amount)?;
+            supply.issued += amount;
+            bootstrap.minted += amount;
+            res = res
+                .add_message(WasmMsg::Execute {
+                    contract_addr: config.token_contract.to_string(),
+                    msg: to_json_binary(&Cw20ExecuteMsg::Mint {
+                        recipient: depositor.to_string(),
+                        amount,
+                    })?,
+                    funds: vec![],
+                })
+                .add_event(events::bond(&depositor, amount, amount));
+        }
+        SUPPLY.save(storage, &supply)?;
+
+        if BOOTSTRAP_DEPOSITS.is_empty(storage) {
+            BOOTSTRAP.remove(storage);
+            Ok(res.add_attribute("bootstrap", "finalized"))
+        } else {
+            BOOTSTRAP.save(storage, &bootstrap)?;
+            Ok(res.add_attribute("bootstrap", "minting"))
+        }
+    }
+
+    pub fn bond_many(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        recipients: Vec<(String, Uint128)>,
+    ) -> Result<Response, ContractError> {
+        ensure!(
+            recipients.len() <= MAX_BOND_MANY_RECIPIENTS,
+            ContractError::TooManyRecipients {
+                max: MAX_BOND_MANY_RECIPIENTS
+            }
+        );
+        let recipients = recipients
+            .into_iter()
+            .map(|(addr, amount)| {
+                ensure!(!amount.is_zero(), ContractError::ZeroBondAmount {});
+                Ok((deps.api.addr_validate(&addr)?, amount))
+            })
+            .collect::<Result<Vec<_>, ContractError>>()?;
+
+        let config = CONFIG.load(deps.storage)?;
+        config.ensure_initialized()?;
+        ensure_not_bootstrapping(deps.storage)?;
+        let mut supply = CleanedSupply::load(deps.storage, &env)?;
+
+        let paid = must_pay(&info, &supply.bond_denom)?;
+        let expected: Uint128 = recipients.iter().map(|(_, amount)| *amount).sum();
+        ensure_eq!(
+            paid,
+            expected,
+            ContractError::PaymentMismatch { paid, expected }
+        );
+        let balance = supply.balance(deps.as_ref(), &env)?;
+
+        // calculate the shares once for the whole payment, same as in `bond`
+        let issue = supply.shares_for_tokens(paid, balance - paid);
+        supply.issued += issue;
+        ensure!(
+            !config.exceeds_issuance_cap(supply.issued),
+            ContractError::IssuanceCapReached {}
+        );
+        SUPPLY.save(deps.storage, &supply)?;
+
+        // split the shares according to the paid amounts
+        let recipients_paid: Vec<_> = recipients.iter().map(|(_, amount)| *amount).collect();
+        let mut shares: Vec<_> = recipients
+            .into_iter()
+            .map(|(addr, amount)| (addr, amount.multiply_ratio(issue, paid)))
+            .collect();
+        // first recipient gets the rounding remainder on top
+        let remainder = issue - shares.iter().map(|(_, amount)| amount).sum::<Uint128>();
+        shares[0].1 += remainder;
+        // recipients whose amount is worth less than one share get neither a mint nor a bond event,
+        // their tokens end up in the remainder of the first recipient
+        let (recipients_paid, shares): (Vec<_>, Vec<_>) = recipients_paid
+            .into_iter()
+            .zip(shares)
+            .filter(|(_, (_, amount))| !amount.is_zero())
+            .unzip();
+
+        // only the sender's own holding period restarts, otherwise anyone could keep a holder
+        // from unbonding by bonding dust for them
+        LAST_BOND.save(deps.storage, &info.sender, &env.block.time.seconds())?;
+        for (recipient, amount) in &shares {
+            record_fresh_shares(deps.storage, recipient, *amount)?;
+        }
+
+        let bond_events: Vec<_> = recipients_paid
+            .iter()
+            .zip(&shares)
+            .map(|(amount, (recipient, issued))| events::bond(recipient, *amount, *issued))
+            .collect();
+        let messages = shares
+            .into_iter()
+            .map(|(recipient, amount)| {
+                Ok(WasmMsg::Execute {
+                    contract_addr: config.token_contract.to_string(),
+                    msg: to_json_binary(&Cw20ExecuteMsg::Mint {
+                        recipient: recipient.into_string(),
+                        amount,
+                    })?,
+                    funds: vec![],
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_events(bond_events)
+            .add_legacy_attribute("action", "bond_many")
+            .add_legacy_attribute("amount", paid)
+            .add_legacy_attribute("shares", issue))
+    }
+
     pub fn handle_receive(
         deps: DepsMut,
         env: Env,
@@ -246,591 +1137,3685 @@ mod execute {
     ) -> Result<Response, ContractError> {
         match from_json(&msg.msg)? {
             ReceiveMsg::Unbond {} => unbond(deps, env, info.sender, msg.amount, msg.sender),
+            ReceiveMsg::UnbondDeferredRate {} => {
+                unbond_deferred(deps, env, info.sender, msg.amount, msg.sender)
+            }
+            ReceiveMsg::Redistribute { recipients } => {
+                redistribute(deps, info.sender, msg.amount, recipients)
+            }
+            ReceiveMsg::RedeemVoucher {} => {
+                redeem_voucher(deps, env, info.sender, msg.amount, msg.sender)
+            }
+            ReceiveMsg::UnbondForSale { discount } => {
+                unbond_for_sale(deps, env, info.sender, msg.amount, msg.sender, discount)
+            }
         }
     }
 
-    pub fn unbond(
+    /// Burns `amount` claim vouchers sent by `sender` and pays out their native value
+    pub fn redeem_voucher(
         deps: DepsMut,
         env: Env,
         contract_sender: Addr,
         amount: Uint128,
         sender: String,
     ) -> Result<Response, ContractError> {
-        // make sure the sender is the token contract
+        // make sure the sender is the voucher contract
         let config = CONFIG.load(deps.storage)?;
-        if config.token_contract != contract_sender {
+        if config.voucher_contract.as_ref() != Some(&contract_sender) {
             return Err(ContractError::InvalidToken {});
         }
+        ensure!(!amount.is_zero(), ContractError::NothingToClaim {});
+        let sender = deps.api.addr_validate(&sender)?;
+
+        let now = env.block.time.seconds();
+        let available = released_vouchers(deps.storage, now)?;
+        ensure!(
+            amount <= available,
+            ContractError::ImmatureVoucher { available }
+        );
 
         let mut supply = CleanedSupply::load(deps.storage, &env)?;
         let balance = supply.balance(deps.as_ref(), &env)?;
-
-        let native_amount = supply.unbond(amount, balance);
+        let slashing_events = SLASHINGS.may_load(deps.storage)?.unwrap_or_default();
+        let paid = redeem_vouchers(deps.storage, now, amount, &slashing_events)?;
+        // same as in `claim_amount`
+        if balance < paid {
+            if balance.is_zero() {
+                return Err(ContractError::InsufficientBuffer { shortfall: paid });
+            }
+            return Err(ContractError::AwaitingUndelegation {});
+        }
+        supply.claim(paid)?;
         SUPPLY.save(deps.storage, &supply)?;
 
-        // create a claim
-        let sender = deps.api.addr_validate(&sender)?;
-        // We don't update next_unbond if we never unbond... we must wait at least until next epoch
-        let next_unbond = max(config.next_unbond, config.next_epoch);
-        CLAIMS.create_claim(
-            deps.storage,
-            &sender,
-            native_amount,
-            Timestamp::from_seconds(
-                // this might be a little tight because it assumes we immediately call reinvest at next_unbond,
-                // but it should not be a problem in practice, since the claiming will just fail until the funds are available
-                next_unbond + config.unbond_period,
-            ),
-        )?;
+        Ok(Response::new()
+            .add_message(WasmMsg::Execute {
+                contract_addr: contract_sender.into_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Burn { amount })?,
+                funds: vec![],
+            })
+            .add_message(BankMsg::Send {
+                to_address: sender.to_string(),
+                amount: vec![Coin {
+                    denom: supply.bond_denom.clone(),
+                    amount: paid,
+                }],
+            })
+            .add_legacy_attribute("action", "redeem_voucher")
+            .add_legacy_attribute("from", &sender)
+            .add_legacy_attribute("vouchers", amount)
+            .add_event(events::claim(events::ClaimReport {
+                owner: &sender,
+                recipient: &sender,
+                requested: amount,
+                paid,
+                remaining_matured: available - amount,
+                deferred_shares: Uint128::zero(),
+                shortfall_reason: None,
+            })))
+    }
+
+    pub fn redistribute(
+        deps: DepsMut,
+        contract_sender: Addr,
+        amount: Uint128,
+        recipients: Vec<(String, Decimal)>,
+    ) -> Result<Response, ContractError> {
+        // make sure the sender is the token contract
+        let config = CONFIG.load(deps.storage)?;
+        if config.token_contract != contract_sender {
+            return Err(ContractError::InvalidToken {});
+        }
+        ensure!(
+            recipients.len() <= MAX_REDISTRIBUTE_RECIPIENTS,
+            ContractError::TooManyRecipients {
+                max: MAX_REDISTRIBUTE_RECIPIENTS
+            }
+        );
+        let total_weight: Decimal = recipients.iter().map(|(_, weight)| weight).sum();
+        ensure!(
+            total_weight == Decimal::one(),
+            ContractError::InvalidRedistributeWeights {}
+        );
+
+        let mut shares = recipients
+            .into_iter()
+            .map(|(addr, weight)| Ok((deps.api.addr_validate(&addr)?, amount.mul_floor(weight))))
+            .collect::<StdResult<Vec<_>>>()?;
+        // first recipient gets the rounding remainder on top
+        let remainder = amount - shares.iter().map(|(_, amount)| amount).sum::<Uint128>();
+        shares[0].1 += remainder;
+
+        let messages = shares
+            .into_iter()
+            .filter(|(_, amount)| !amount.is_zero())
+            .map(|(recipient, amount)| {
+                Ok(WasmMsg::Execute {
+                    contract_addr: config.token_contract.to_string(),
+                    msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                        recipient: recipient.into_string(),
+                        amount,
+                    })?,
+                    funds: vec![],
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_attribute("action", "redistribute")
+            .add_attribute("amount", amount))
+    }
+
+    pub fn unbond(
+        deps: DepsMut,
+        env: Env,
+        contract_sender: Addr,
+        amount: Uint128,
+        sender: String,
+    ) -> Result<Response, ContractError> {
+        // make sure the sender is the token contract
+        let config = CONFIG.load(deps.storage)?;
+        config.ensure_initialized()?;
+        if config.token_contract != contract_sender {
+            return Err(ContractError::InvalidToken {});
+        }
+        let sender = deps.api.addr_validate(&sender)?;
 
         // burn the sent tokens
+        unbond_tokens(
+            deps,
+            env,
+            &config,
+            sender,
+            amount,
+            Cw20ExecuteMsg::Burn { amount },
+            None,
+        )
+    }
+
+    /// Same as [`unbond`], but the claim can be bought by a registered claim buyer at `discount`
+    pub fn unbond_for_sale(
+        deps: DepsMut,
+        env: Env,
+        contract_sender: Addr,
+        amount: Uint128,
+        sender: String,
+        discount: Decimal,
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        config.ensure_initialized()?;
+        if config.token_contract != contract_sender {
+            return Err(ContractError::InvalidToken {});
+        }
+        ensure!(
+            discount <= MAX_CLAIM_SALE_DISCOUNT,
+            ContractError::InvalidClaimDiscount {
+                max: MAX_CLAIM_SALE_DISCOUNT
+            }
+        );
+        // vouchers can be sold like any other cw20 token already
+        ensure!(
+            config.voucher_contract.is_none(),
+            ContractError::ClaimSaleWithVouchers {}
+        );
+        let sender = deps.api.addr_validate(&sender)?;
+
+        let res = unbond_tokens(
+            deps,
+            env,
+            &config,
+            sender,
+            amount,
+            Cw20ExecuteMsg::Burn { amount },
+            Some(discount),
+        )?;
+        Ok(res.add_attribute("sale_discount", discount.to_string()))
+    }
+
+    /// Unbonds the lsd tokens of `owner` without the `Send` hook.
+    /// The owner has to allow the hub to burn them first, the burn fails otherwise.
+    pub fn unbond_from(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        owner: String,
+        amount: Uint128,
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        config.ensure_initialized()?;
+        let owner = deps.api.addr_validate(&owner)?;
+        // nobody else can make the owner pay the fee by using their allowance
+        ensure!(
+            config.unbond_fee.is_zero() || info.sender == owner,
+            ContractError::UnbondFromWithFee {}
+        );
+
+        let burn_msg = Cw20ExecuteMsg::BurnFrom {
+            owner: owner.to_string(),
+            amount,
+        };
+        let res = unbond_tokens(deps, env, &config, owner.clone(), amount, burn_msg, None)?;
+        Ok(res
+            .add_attribute("action", "unbond_from")
+            .add_attribute("owner", owner))
+    }
+
+    /// Creates a claim for `amount` lsd tokens of `sender`, which are burned by `burn_msg`.
+    /// With a `sale_discount`, the claim is offered to the claim buyers.
+    fn unbond_tokens(
+        deps: DepsMut,
+        env: Env,
+        config: &Config,
+        sender: Addr,
+        amount: Uint128,
+        burn_msg: Cw20ExecuteMsg,
+        sale_discount: Option<Decimal>,
+    ) -> Result<Response, ContractError> {
+        ensure!(!amount.is_zero(), ContractError::ZeroUnbond {});
+        ensure_not_bootstrapping(deps.storage)?;
+        ensure_holding_period_met(deps.storage, config, &env, &sender)?;
+
+        let mut supply = CleanedSupply::load(deps.storage, &env)?;
+        let balance = supply.balance(deps.as_ref(), &env)?;
+
+        let (native_amount, fee_amount) =
+            supply.unbond_with_fee(amount, balance, config.unbond_fee);
+        SUPPLY.save(deps.storage, &supply)?;
+
+        // create a claim
+        // We don't update next_unbond if we never unbond... we must wait at least until the reinvest that undelegates
+        let next_unbond = config.next_undelegation();
+        // this assumes we call reinvest as soon as the epoch starts. If it is called later,
+        // the claiming will just fail until the funds are available
+        let release_at = next_unbond + config.unbond_period;
+        let mut res = Response::new();
+        match &config.voucher_contract {
+            // the claim is represented by the vouchers, so only the batch is remembered
+            Some(voucher_contract) => {
+                VOUCHER_BATCHES.update(deps.storage, release_at, |batch| -> StdResult<_> {
+                    Ok(batch.unwrap_or_default() + native_amount)
+                })?;
+                res = res.add_message(WasmMsg::Execute {
+                    contract_addr: voucher_contract.to_string(),
+                    msg: to_json_binary(&Cw20ExecuteMsg::Mint {
+                        recipient: sender.to_string(),
+                        amount: native_amount,
+                    })?,
+                    funds: vec![],
+                });
+            }
+            None => match sale_discount {
+                Some(discount) => CLAIMS.create_claim_for_sale(
+                    deps.storage,
+                    &sender,
+                    native_amount,
+                    Timestamp::from_seconds(release_at),
+                    next_unbond,
+                    discount,
+                )?,
+                None => CLAIMS.create_claim(
+                    deps.storage,
+                    &sender,
+                    native_amount,
+                    Timestamp::from_seconds(release_at),
+                    next_unbond,
+                )?,
+            },
+        }
+
         let burn_msg = WasmMsg::Execute {
             contract_addr: config.token_contract.to_string(),
-            msg: to_json_binary(&Cw20ExecuteMsg::Burn { amount })?,
+            msg: to_json_binary(&burn_msg)?,
             funds: vec![],
         };
+        let mut res = res.add_message(burn_msg).add_event(events::unbond(
+            &sender,
+            amount,
+            native_amount,
+            fee_amount,
+            release_at,
+            false,
+        ));
 
-        Ok(Response::new().add_message(burn_msg))
+        // pay out the external rewards of the burned tokens
+        let external_rewards = take_external_rewards(deps.storage, &sender, amount)?;
+        if !external_rewards.is_empty() {
+            res = res.add_message(BankMsg::Send {
+                to_address: sender.into_string(),
+                amount: external_rewards,
+            });
+        }
+        Ok(res)
     }
 
-    pub fn claim(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    pub fn unbond_deferred(
+        deps: DepsMut,
+        env: Env,
+        contract_sender: Addr,
+        amount: Uint128,
+        sender: String,
+    ) -> Result<Response, ContractError> {
+        // make sure the sender is the token contract
+        let config = CONFIG.load(deps.storage)?;
+        if config.token_contract != contract_sender {
+            return Err(ContractError::InvalidToken {});
+        }
+        ensure!(!amount.is_zero(), ContractError::ZeroUnbond {});
+        ensure_not_bootstrapping(deps.storage)?;
+
+        // the shares stay issued (and held by this contract) until they are claimed,
+        // so they keep their part of the rewards and slashings until then
+        let sender = deps.api.addr_validate(&sender)?;
+        ensure_holding_period_met(deps.storage, &config, &env, &sender)?;
+
         let mut supply = SUPPLY.load(deps.storage)?;
-        let balance = deps
-            .querier
-            .query_balance(&env.contract.address, &supply.bond_denom)?;
+        supply.deferred_claim_shares += amount;
+        SUPPLY.save(deps.storage, &supply)?;
+
+        let next_unbond = config.next_undelegation();
+        let release_at = next_unbond + config.unbond_period;
+        DEFERRED_CLAIMS.create_claim(
+            deps.storage,
+            &sender,
+            amount,
+            Timestamp::from_seconds(release_at),
+            next_unbond,
+        )?;
+
+        Ok(Response::new()
+            .add_event(events::unbond(
+                &sender,
+                amount,
+                Uint128::zero(),
+                Uint128::zero(),
+                release_at,
+                true,
+            ))
+            .add_legacy_attribute("action", "unbond_deferred")
+            .add_legacy_attribute("from", sender)
+            .add_legacy_attribute("shares", amount))
+    }
+
+    /// Pays out the mature claims of `owner` to `recipient`
+    pub fn claim(
+        deps: DepsMut,
+        env: Env,
+        owner: Addr,
+        recipient: Addr,
+        max_amount: Option<Uint128>,
+    ) -> Result<Response, ContractError> {
+        let mut supply = CleanedSupply::load(deps.storage, &env)?;
+        let balance = supply.balance(deps.as_ref(), &env)?;
+        // if the maximum is below the balance, that is what limits the claims
+        let limited_by_max = max_amount.map_or(false, |max| max < balance);
+        let max_amount = max_amount.unwrap_or(Uint128::MAX);
+
+        let slashing_events = SLASHINGS.may_load(deps.storage)?.unwrap_or_default();
 
-        let slashing_events = SLASHINGS.load(deps.storage)?;
+        // adjust the claim amounts for slashing
+        let claim_amount = |c: &Claim| c.slashed_amount(&slashing_events);
 
         // check how much to send - min(balance, claims[sender]), and reduce the claim
         // Ensure we have enough balance to cover this and only send some claims if that is all we can cover
         let to_send = CLAIMS.claim_tokens(
             deps.storage,
-            &info.sender,
+            &owner,
             &env.block,
-            |c| {
-                let mut amount = c.amount;
-                // adjust the claim amounts for slashing
-                for slashing in slashing_events
-                    .iter()
-                    .filter(|s| s.start < c.release_at.seconds() && s.end > c.release_at.seconds())
-                {
-                    amount = amount.mul_floor(slashing.multiplier);
-                }
-                amount
-            },
-            Some(balance.amount),
+            claim_amount,
+            Some(std::cmp::min(balance, max_amount)),
         )?;
-        if to_send.is_zero() {
-            return Err(ContractError::NothingToClaim {});
-        }
         // update total supply (lower claims)
         supply.claim(to_send)?;
+
+        // deferred claims are converted at the current rate, using what is left after the claims above.
+        // The part of the remaining claims that is not covered by unbonding tokens is reserved for them.
+        let balance = balance - to_send;
+        let reserved = supply.claims.saturating_sub(supply.total_unbonding);
+        let (deferred_shares, deferred_send) = DEFERRED_CLAIMS.claim_shares(
+            deps.storage,
+            &owner,
+            &env.block,
+            |shares| supply.tokens_for_shares(shares, balance),
+            std::cmp::min(balance.saturating_sub(reserved), max_amount - to_send),
+        )?;
+        // the shares are only removed from the supply now
+        supply.issued -= deferred_shares;
+        supply.deferred_claim_shares -= deferred_shares;
+
+        // whatever is still mature now could not be paid, because the balance is too low
+        let is_mature = |c: &&Claim| c.release_at <= env.block.time;
+        let remaining = CLAIMS.load(deps.storage, &owner)?;
+        let remaining_deferred = DEFERRED_CLAIMS.load(deps.storage, &owner)?;
+        let has_mature = remaining
+            .iter()
+            .chain(&remaining_deferred)
+            .any(|c| is_mature(&c));
+        let remaining_mature = remaining
+            .iter()
+            .filter(is_mature)
+            .map(claim_amount)
+            .chain(
+                remaining_deferred
+                    .iter()
+                    .filter(is_mature)
+                    .map(|c| supply.tokens_for_shares(c.amount, balance - deferred_send)),
+            )
+            .sum::<Uint128>();
+
+        let to_send = to_send + deferred_send;
+        if to_send.is_zero() {
+            if remaining.is_empty() && remaining_deferred.is_empty() {
+                return Err(ContractError::NothingToClaim {});
+            }
+            if !has_mature {
+                let next_release_at = remaining
+                    .iter()
+                    .chain(&remaining_deferred)
+                    .map(|c| c.release_at.seconds())
+                    .min()
+                    .unwrap_or_default();
+                return Err(ContractError::ClaimsNotMature { next_release_at });
+            }
+            if limited_by_max {
+                return Err(ContractError::MaxAmountTooLow { max_amount });
+            }
+            if balance.is_zero() {
+                return Err(ContractError::InsufficientBuffer {
+                    shortfall: remaining_mature,
+                });
+            }
+            return Err(ContractError::AwaitingUndelegation {});
+        }
         SUPPLY.save(deps.storage, &supply)?;
 
         // transfer tokens to the sender
-        let res = Response::new()
+        let mut res = Response::new()
             .add_message(BankMsg::Send {
-                to_address: info.sender.to_string(),
+                to_address: recipient.to_string(),
                 amount: vec![Coin {
-                    denom: supply.bond_denom,
+                    denom: supply.bond_denom.clone(),
                     amount: to_send,
                 }],
             })
-            .add_attribute("action", "claim")
-            .add_attribute("from", info.sender)
-            .add_attribute("amount", to_send);
+            .add_legacy_attribute("action", "claim")
+            .add_legacy_attribute("from", &owner)
+            .add_legacy_attribute("amount", to_send)
+            .add_legacy_attribute("requested", to_send + remaining_mature)
+            .add_legacy_attribute("paid", to_send)
+            .add_legacy_attribute("remaining_matured", remaining_mature);
+        // only part of the mature claims could be paid
+        let shortfall_reason = match (has_mature, limited_by_max) {
+            (false, _) => None,
+            (true, true) => Some("max_amount"),
+            (true, false) => Some("awaiting_undelegation"),
+        };
+        if let Some(shortfall_reason) = shortfall_reason {
+            res = res.add_legacy_attribute("shortfall_reason", shortfall_reason);
+        }
+        res = res.add_event(events::claim(events::ClaimReport {
+            owner: &owner,
+            recipient: &recipient,
+            requested: to_send + remaining_mature,
+            paid: to_send,
+            remaining_matured: remaining_mature,
+            deferred_shares,
+            shortfall_reason,
+        }));
+        if !deferred_shares.is_zero() {
+            // burn the shares this contract held for the deferred claims
+            let config = CONFIG.load(deps.storage)?;
+            res = res
+                .add_message(WasmMsg::Execute {
+                    contract_addr: config.token_contract.to_string(),
+                    msg: to_json_binary(&Cw20ExecuteMsg::Burn {
+                        amount: deferred_shares,
+                    })?,
+                    funds: vec![],
+                })
+                .add_legacy_attribute("deferred_shares", deferred_shares);
+
+            // the entitlement belongs to the owner, who held the shares before deferring them
+            let external_rewards = take_external_rewards(deps.storage, &owner, deferred_shares)?;
+            if !external_rewards.is_empty() {
+                res = res.add_message(BankMsg::Send {
+                    to_address: owner.to_string(),
+                    amount: external_rewards,
+                });
+            }
+        }
         Ok(res)
     }
 
-    pub fn reinvest(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
-        // only allow this to be called once per epoch
-        let mut config = CONFIG.load(deps.storage)?;
-        config.next_epoch_after(&env)?;
-        CONFIG.save(deps.storage, &config)?;
-
-        let mut resp = Response::new();
+    pub fn set_claim_recipient(
+        deps: DepsMut,
+        info: MessageInfo,
+        recipient: Option<String>,
+    ) -> Result<Response, ContractError> {
+        let res = Response::new()
+            .add_attribute("action", "set_claim_recipient")
+            .add_attribute("owner", &info.sender);
+        match recipient {
+            Some(recipient) => {
+                let recipient = deps.api.addr_validate(&recipient)?;
+                CLAIM_RECIPIENTS.save(deps.storage, &info.sender, &recipient)?;
+                Ok(res.add_attribute("recipient", recipient))
+            }
+            None => {
+                CLAIM_RECIPIENTS.remove(deps.storage, &info.sender);
+                Ok(res)
+            }
+        }
+    }
 
-        // get all validators, skipping any with zero weight
-        let validators: Vec<_> = STAKE_INFO
-            .load(deps.storage)?
-            .validators
-            .into_iter()
-            .filter(|(_, w)| !w.is_zero())
-            .collect();
+    /// Moves the claim of `owner` released at `release_at` that is for sale to the sender,
+    /// after checking that they paid its (slashed) amount minus the discount, which is sent to `owner`
+    pub fn buy_claim(
+        deps: DepsMut,
+        info: MessageInfo,
+        owner: String,
+        release_at: u64,
+    ) -> Result<Response, ContractError> {
+        ensure!(
+            CLAIM_BUYERS.has(deps.storage, &info.sender),
+            ContractError::Unauthorized {}
+        );
+        let owner = deps.api.addr_validate(&owner)?;
+        let bond_denom = SUPPLY.load(deps.storage)?.bond_denom;
+        let paid = must_pay(&info, &bond_denom)?;
+
+        let claim = CLAIMS
+            .transfer_claim_for_sale(
+                deps.storage,
+                &owner,
+                &info.sender,
+                Timestamp::from_seconds(release_at),
+            )?
+            .ok_or_else(|| ContractError::ClaimNotForSale {
+                owner: owner.to_string(),
+                release_at,
+            })?;
+        // the buyer gets what the claim pays out, so a slashing during its unbonding lowers the price as well
+        let slashings = SLASHINGS.may_load(deps.storage)?.unwrap_or_default();
+        let discount = claim.sale_discount.unwrap_or_default();
+        let price = claim
+            .slashed_amount(&slashings)
+            .mul_floor(Decimal::one() - discount);
+        ensure_eq!(
+            paid,
+            price,
+            ContractError::ClaimPriceMismatch { price, paid }
+        );
 
-        let supply = SUPPLY.load(deps.storage)?;
+        Ok(Response::new()
+            .add_message(BankMsg::Send {
+                to_address: owner.to_string(),
+                amount: coins(paid.u128(), bond_denom),
+            })
+            .add_attribute("action", "buy_claim")
+            .add_attribute("owner", owner)
+            .add_attribute("buyer", info.sender)
+            .add_attribute("release_at", release_at.to_string())
+            .add_attribute("claim_amount", claim.amount)
+            .add_attribute("price", price))
+    }
 
-        // save current balance for comparison in reply
+    /// Pays exactly `amount` from the mature claims of `owner`, splitting the last claim needed.
+    /// Deferred claims are not touched. Unlike [`claim`], this fails instead of paying less.
+    pub fn claim_amount(
+        deps: DepsMut,
+        env: Env,
+        owner: Addr,
+        amount: Uint128,
+    ) -> Result<Response, ContractError> {
+        ensure!(!amount.is_zero(), ContractError::NothingToClaim {});
+        let mut supply = CleanedSupply::load(deps.storage, &env)?;
         let balance = supply.balance(deps.as_ref(), &env)?;
-        TMP_STATE.save(deps.storage, &TmpState { balance })?;
 
-        // withdraw rewards from all delegations
-        if supply.total_bonded.is_zero() {
-            // if we have never staked before, we can skip the withdraw step
-            return reply::after_withdraw_rewards(deps, env).map_err(Into::into);
-        } else {
-            let len = validators.len();
-            for (i, (validator, _)) in validators.into_iter().enumerate() {
-                if i == len - 1 {
-                    // for the last message, we need to get a reply in any case to continue in
-                    // `reply::after_withdraw_rewards`
-                    resp = resp.add_submessage(SubMsg::reply_always(
-                        DistributionMsg::WithdrawDelegatorReward { validator },
-                        AFTER_WITHDRAW_REPLY,
-                    ));
-                } else {
-                    // we need to catch intermittent errors, so they don't fail the whole transaction
-                    resp = resp.add_submessage(SubMsg::reply_on_error(
-                        DistributionMsg::WithdrawDelegatorReward { validator },
-                        AFTER_WITHDRAW_INTERMITTENT_REPLY,
-                    ));
-                }
+        let slashing_events = SLASHINGS.may_load(deps.storage)?.unwrap_or_default();
+        let claim_amount = |c: &Claim| c.slashed_amount(&slashing_events);
+
+        let paid = CLAIMS.claim_exact(deps.storage, &owner, &env.block, claim_amount, amount)?;
+        if paid < amount {
+            return Err(ContractError::InsufficientMatureClaims { available: paid });
+        }
+        if balance < amount {
+            if balance.is_zero() {
+                return Err(ContractError::InsufficientBuffer { shortfall: amount });
             }
+            return Err(ContractError::AwaitingUndelegation {});
         }
+        supply.claim(amount)?;
+        SUPPLY.save(deps.storage, &supply)?;
 
-        // reinvest execution will continue in `reply::after_withdraw_rewards`
-        Ok(resp)
+        let remaining_mature = CLAIMS
+            .load(deps.storage, &owner)?
+            .iter()
+            .filter(|c| c.release_at <= env.block.time)
+            .map(claim_amount)
+            .sum::<Uint128>();
+        Ok(Response::new()
+            .add_message(BankMsg::Send {
+                to_address: owner.to_string(),
+                amount: vec![Coin {
+                    denom: supply.bond_denom.clone(),
+                    amount,
+                }],
+            })
+            .add_legacy_attribute("action", "claim_amount")
+            .add_legacy_attribute("from", &owner)
+            .add_legacy_attribute("amount", amount)
+            .add_legacy_attribute("remaining_matured", remaining_mature)
+            .add_event(events::claim(events::ClaimReport {
+                owner: &owner,
+                recipient: &owner,
+                requested: amount,
+                paid: amount,
+                remaining_matured: remaining_mature,
+                deferred_shares: Uint128::zero(),
+                shortfall_reason: None,
+            })))
     }
 
-    pub fn update_liquidity_discount(
+    pub fn claim_for(
         deps: DepsMut,
+        env: Env,
         info: MessageInfo,
-        new_discount: Decimal,
+        owner: String,
     ) -> Result<Response, ContractError> {
-        let mut config = CONFIG.load(deps.storage)?;
-
-        // validation
-        ensure_eq!(config.owner, info.sender, ContractError::Unauthorized {});
+        let owner = deps.api.addr_validate(&owner)?;
         ensure!(
-            new_discount < Decimal::percent(50),
-            ContractError::InvalidLiquidityDiscount {}
+            CLAIM_RECIPIENTS.may_load(deps.storage, &owner)? == Some(info.sender.clone()),
+            ContractError::Unauthorized {}
         );
+        claim(deps, env, owner, info.sender, None)
+    }
 
-        config.liquidity_discount = new_discount;
-        CONFIG.save(deps.storage, &config)?;
-
-        Ok(Response::new()
-            .add_attribute("action", "update_liquidity_discount")
-            .add_attribute("liquidity_discount", new_discount.to_string()))
+    pub fn reinvest(
+        deps: DepsMut,
+        env: Env,
+        validators: Option<Vec<String>>,
+    ) -> Result<Response, ContractError> {
+        run_reinvest(deps, env, validators, false)
     }
 
-    pub fn check_slash(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
-        /// 0.00001 = 0.001%
-        const SLASHING_THRESHOLD: Decimal = Decimal::raw(10u128.pow(18 - 5));
+    /// Reinvests all validators right away, without checking or advancing the epoch.
+    /// This is rate limited to once per [`FORCE_REINVEST_INTERVAL`], so it cannot be used to reinvest continuously.
+    pub fn force_reinvest(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
+
+        if let Some(last) = last_reinvest(deps.storage)? {
+            let next_reinvest = last + FORCE_REINVEST_INTERVAL;
+            ensure!(
+                env.block.time.seconds() >= next_reinvest,
+                ContractError::ReinvestTooSoon { next_reinvest }
+            );
+        }
 
-        let supply: Supply = SUPPLY.load(deps.storage)?;
+        Ok(run_reinvest(deps, env, None, true)?.add_attribute("action", "force_reinvest"))
+    }
 
-        // ensure safety margin around unbonding periods
+    fn run_reinvest(
+        mut deps: DepsMut,
+        env: Env,
+        validators: Option<Vec<String>>,
+        forced: bool,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        // the escrowed deposits are not minted yet, so there is nothing to reinvest for
+        ensure_not_bootstrapping(deps.storage)?;
+        if config.strict_supply_check {
+            check_issued(deps.as_ref(), &config)?;
+        }
         let now = env.block.time.seconds();
-        let slashing_safety_margin = CONFIG.load(deps.storage)?.slashing_safety_margin;
-        // we do not call `supply.cleanup_unbonding` yet,
-        // since we want to also check supposedly finished unbondings
-        let unbonding = unbondings_expiring_between(
-            deps.storage,
-            now - slashing_safety_margin,
-            now + slashing_safety_margin,
-        )
-        .next()
-        .is_some();
-        ensure!(!unbonding, ContractError::UnbondingTooClose {});
+        if !forced {
+            // only allow this to be called once per epoch
+            // (we only check here, the epoch advances once all validators are processed)
+            config.clone().next_epoch_after(&env)?;
+
+            // even if the epoch allows it, don't reinvest more often than configured
+            if let (Some(spacing), Some(last)) =
+                (config.min_reinvest_spacing, last_reinvest(deps.storage)?)
+            {
+                let next_reinvest = last + spacing;
+                ensure!(
+                    now >= next_reinvest,
+                    ContractError::ReinvestTooSoon { next_reinvest }
+                );
+            }
+        }
 
-        // check if we have any slashing events by comparing queried delegations to our state
-        // now we have to cleanup old unbondings
-        let mut supply = supply.cleanup_unbonding(deps.storage, &env)?;
+        // get all validators that were not processed in this epoch yet, skipping any with zero weight
+        let mut progress = REINVEST_PROGRESS
+            .may_load(deps.storage)?
+            .filter(|progress| progress.epoch == config.next_epoch)
+            .unwrap_or(ReinvestProgress {
+                epoch: config.next_epoch,
+                processed: vec![],
+            });
+        let remaining: Vec<_> = STAKE_INFO
+            .load(deps.storage)?
+            .validators
+            .into_iter()
+            .filter(|(v, w)| !w.is_zero() && !progress.processed.contains(v))
+            .map(|(v, _)| v)
+            .collect();
 
-        let mut bonded = BONDED.load(deps.storage)?;
-        let stored_delegations: HashMap<_, _> = bonded.iter().map(|(v, b)| (v, *b)).collect();
+        let to_process = match validators {
+            None => remaining.clone(),
+            Some(mut validators) => {
+                validators.sort_unstable();
+                validators.dedup();
+                if let Some(validator) = validators.iter().find(|v| !remaining.contains(v)) {
+                    return Err(ContractError::ValidatorNotPending {
+                        validator: validator.clone(),
+                    });
+                }
+                validators
+            }
+        };
 
-        let queried_delegations = deps.querier.query_all_delegations(env.contract.address)?;
-        let slashed_validators: HashMap<_, _> = queried_delegations
-            .iter()
-            .filter_map(|d| {
-                let stored = stored_delegations
-                    .get(&d.validator)
-                    .copied()
-                    .unwrap_or_default();
+        let supply = SUPPLY.load(deps.storage)?;
 
-                // if difference is larger than threshold, this validator was slashed
-                if stored.saturating_sub(d.amount.amount) >= stored.mul_floor(SLASHING_THRESHOLD) {
-                    // keep track of multiplier
-                    Some((&d.validator, Decimal::from_ratio(d.amount.amount, stored)))
-                } else {
-                    None
+        // if we have never staked before, there are no rewards to withdraw
+        let complete =
+            supply.total_bonded.is_zero() || remaining.iter().all(|v| to_process.contains(v));
+        ensure!(
+            complete || !to_process.is_empty(),
+            ContractError::EmptyValidatorSubset {}
+        );
+
+        // report the validators that raised their commission since the last reinvest
+        let commission_spikes = check_commissions(deps.branch(), &config, &to_process)?;
+
+        // save current balance for comparison in reply
+        let balance = supply.balance(deps.as_ref(), &env)?;
+        TMP_STATE.save(deps.storage, &TmpState { balance })?;
+
+        let final_reply = if complete {
+            if !forced {
+                config.next_epoch_after(&env)?;
+                CONFIG.save(deps.storage, &config)?;
+            }
+            record_reinvest(deps.storage, now)?;
+            REINVEST_PROGRESS.remove(deps.storage);
+
+            if supply.total_bonded.is_zero() || to_process.is_empty() {
+                // nothing (left) to withdraw, so we can skip the withdraw step
+                return reply::after_withdraw_rewards(deps, env)
+                    .map(|res| res.add_events(commission_spikes));
+            }
+            AFTER_WITHDRAW_REPLY
+        } else {
+            progress.processed.extend(to_process.iter().cloned());
+            REINVEST_PROGRESS.save(deps.storage, &progress)?;
+            AFTER_PARTIAL_WITHDRAW_REPLY
+        };
+
+        // withdraw rewards from the delegations
+        let mut resp = Response::new().add_events(commission_spikes);
+        let len = to_process.len();
+        for (i, validator) in to_process.into_iter().enumerate() {
+            if i == len - 1 {
+                // for the last message, we need to get a reply in any case to continue in
+                // `reply::after_withdraw_rewards` or `reply::after_partial_withdraw_rewards`
+                resp = resp.add_submessage(SubMsg::reply_always(
+                    DistributionMsg::WithdrawDelegatorReward { validator },
+                    final_reply,
+                ));
+            } else {
+                // we need to catch intermittent errors, so they don't fail the whole transaction
+                resp = resp.add_submessage(SubMsg::reply_on_error(
+                    DistributionMsg::WithdrawDelegatorReward { validator },
+                    AFTER_WITHDRAW_INTERMITTENT_REPLY,
+                ));
+            }
+        }
+
+        // reinvest execution will continue in the reply
+        Ok(resp)
+    }
+
+    /// Compares the commissions of the `validators` with the ones seen by the previous reinvest and reports
+    /// the ones that increased by more than `Config::max_commission_increase_alert`.
+    /// With `Config::auto_zero_weight_on_spike`, their weights are set to zero as well.
+    fn check_commissions(
+        deps: DepsMut,
+        config: &Config,
+        validators: &[String],
+    ) -> Result<Vec<Event>, ContractError> {
+        let mut spikes = vec![];
+        for validator in validators {
+            let commission = match deps.querier.query_validator(validator)? {
+                Some(info) => info.commission,
+                // a validator unknown to the staking module has no commission to compare
+                None => continue,
+            };
+            let previous = VALIDATOR_COMMISSIONS.may_load(deps.storage, validator)?;
+            VALIDATOR_COMMISSIONS.save(deps.storage, validator, &commission)?;
+            if let Some(previous) = previous {
+                if commission.saturating_sub(previous) > config.max_commission_increase_alert {
+                    spikes.push((validator.as_str(), previous, commission));
                 }
-            })
-            .collect();
-        if slashed_validators.is_empty() {
-            // no slashing detected
-            return Ok(Response::new().add_attribute("slashed", "false"));
+            }
+        }
+        if spikes.is_empty() {
+            return Ok(vec![]);
         }
 
-        // we were slashed, so we need to update our state
-        // we also keep track of the old total for calculating the global multiplier to adjust claims
-        let (old_total_bonded, old_total_unbonding) = (supply.total_bonded, supply.total_unbonding);
-        bonded = bonded
+        // like a manual valset change to zero weight, but the delegation is only moved by the next one
+        let mut weight_removed = false;
+        if config.auto_zero_weight_on_spike {
+            let mut stake_info = STAKE_INFO.load(deps.storage)?;
+            let spiked: Vec<_> = spikes.iter().map(|(validator, _, _)| *validator).collect();
+            weight_removed = remove_weights(&mut stake_info.validators, &spiked);
+            STAKE_INFO.save(deps.storage, &stake_info)?;
+        }
+
+        Ok(spikes
             .into_iter()
-            .map(|(validator, mut amount)| {
-                if let Some(multiplier) = slashed_validators.get(&validator) {
-                    amount = amount.mul_floor(*multiplier);
-                }
-                (validator, amount)
+            .map(|(validator, before, after)| {
+                events::commission_spike(validator, before, after, weight_removed)
             })
-            .collect();
-        supply.total_bonded = bonded.iter().map(|(_, b)| *b).sum();
-        BONDED.save(deps.storage, &bonded)?;
+            .collect())
+    }
 
-        let mut unbondings = UNBONDING
-            .range(deps.storage, None, None, Order::Ascending)
-            .collect::<StdResult<Vec<_>>>()?;
-        for (expiration, unbondings) in unbondings.iter_mut() {
-            let mut changed = false;
-            for ub in unbondings.iter_mut() {
-                if let Some(multiplier) = slashed_validators.get(&ub.validator) {
-                    ub.amount = ub.amount.mul_floor(*multiplier);
-                    changed = true;
-                }
-            }
-            // only change entries if there actually was a slashed validator
-            if changed {
-                UNBONDING.save(deps.storage, *expiration, unbondings)?;
+    pub fn update_liquidity_discount(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        new_discount: Decimal,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+
+        // validation
+        config.ensure_operator_or_owner(&info.sender)?;
+        ensure!(
+            new_discount < Decimal::percent(50),
+            ContractError::InvalidLiquidityDiscount {}
+        );
+        // move the target value of the pool gradually
+        ensure!(
+            new_discount.abs_diff(config.liquidity_discount)
+                <= config.max_discount_change_per_update,
+            ContractError::DiscountChangeTooLarge {
+                max_step: config.max_discount_change_per_update
             }
+        );
+        let now = env.block.time.seconds();
+        if config.last_discount_update != 0 {
+            let next_allowed = config.last_discount_update + config.min_discount_update_interval;
+            ensure!(
+                now >= next_allowed,
+                ContractError::DiscountUpdateTooSoon { next_allowed }
+            );
         }
-        supply.total_unbonding = unbondings
-            .iter()
-            .flat_map(|(_, u)| u)
-            .map(|u| u.amount)
-            .sum();
 
-        // sanity check
-        #[cfg(debug_assertions)]
-        cosmwasm_std::assert_approx_eq!(
-            supply.total_bonded,
-            queried_delegations
-                .iter()
-                .map(|d| d.amount.amount)
-                .sum::<Uint128>(),
-            "0.0001"
-        );
+        config.liquidity_discount = new_discount;
+        config.last_discount_update = now;
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_liquidity_discount")
+            .add_attribute("liquidity_discount", new_discount.to_string()))
+    }
 
-        let response = Response::new()
-            .add_attribute("slashed", "true")
-            .add_attribute("bonded_slashed", old_total_bonded - supply.total_bonded);
+    /// Uses the same limits as `update_liquidity_discount`, so the fee can only be raised gradually as well.
+    pub fn update_unbond_fee(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        unbond_fee: Decimal,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
 
-        // we also need to update the pending claims
-        if old_total_unbonding.is_zero() {
-            SUPPLY.save(deps.storage, &supply)?;
-            return Ok(response.add_attribute("unbonded_slashed", Uint128::zero()));
+        config.ensure_owner(&info.sender)?;
+        ensure!(
+            unbond_fee <= MAX_UNBOND_FEE,
+            ContractError::InvalidUnbondFee {}
+        );
+        ensure!(
+            unbond_fee.abs_diff(config.unbond_fee) <= config.max_discount_change_per_update,
+            ContractError::UnbondFeeChangeTooLarge {
+                max_step: config.max_discount_change_per_update
+            }
+        );
+        let now = env.block.time.seconds();
+        if config.last_unbond_fee_update != 0 {
+            let next_allowed = config.last_unbond_fee_update + config.min_discount_update_interval;
+            ensure!(
+                now >= next_allowed,
+                ContractError::UnbondFeeUpdateTooSoon { next_allowed }
+            );
         }
-        let global_unbonding_multiplier =
-            Decimal::from_ratio(supply.total_unbonding, old_total_unbonding);
 
-        // we need to update the pending claims, but only the part that is actually unbonding
-        // (part of the claims can be in the contract balance, which is not slashed)
-        supply.claims = (supply.claims - old_total_unbonding) + supply.total_unbonding;
-        SUPPLY.save(deps.storage, &supply)?;
+        config.unbond_fee = unbond_fee;
+        config.last_unbond_fee_update = now;
+        CONFIG.save(deps.storage, &config)?;
 
-        let unbonding_period = CONFIG.load(deps.storage)?.unbond_period;
-        SLASHINGS.update(deps.storage, |mut slashings| -> StdResult<_> {
-            slashings.push(Slashing {
-                start: env.block.time.seconds(),
-                end: env.block.time.plus_seconds(unbonding_period).seconds(),
-                multiplier: global_unbonding_multiplier,
-            });
-            Ok(slashings)
-        })?;
+        Ok(Response::new()
+            .add_attribute("action", "update_unbond_fee")
+            .add_attribute("unbond_fee", unbond_fee.to_string()))
+    }
+
+    pub fn update_min_reinvest_spacing(
+        deps: DepsMut,
+        info: MessageInfo,
+        min_reinvest_spacing: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        config.ensure_operator_or_owner(&info.sender)?;
 
-        Ok(response.add_attribute(
-            "unbonded_slashed",
-            old_total_unbonding - supply.total_unbonding,
-        ))
+        config.min_reinvest_spacing = min_reinvest_spacing;
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_min_reinvest_spacing")
+            .add_attribute(
+                "min_reinvest_spacing",
+                min_reinvest_spacing.map_or("none".to_string(), |s| s.to_string()),
+            ))
     }
-}
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(deps: DepsMut, env: Env, reply: Reply) -> Result<Response, ContractError> {
-    match reply.id {
-        AFTER_TOKEN_CREATION_REPLY => {
-            let result = reply
-                .result
-                .into_result()
-                .map_err(|e| StdError::generic_err(e))?;
-            let res = cw_utils::parse_instantiate_response_data(
-                result
-                    .msg_responses
-                    .get(0)
-                    .cloned()
-                    .map(|v| v.value)
-                    .or(result.data)
-                    .unwrap()
-                    .as_slice(),
-            )
-            .map_err(|_| {
-                StdError::parse_err("MsgInstantiateContractResponse", "failed to parse data")
-            })?;
+    pub fn update_min_holding_period(
+        deps: DepsMut,
+        info: MessageInfo,
+        min_holding_period: u64,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
 
-            // Pass the contract admin of this contract to the Token contract
-            let contract_info = deps
-                .querier
-                .query_wasm_contract_info(env.contract.address)?;
+        config.min_holding_period = min_holding_period;
+        CONFIG.save(deps.storage, &config)?;
 
-            let admin = contract_info.admin.unwrap();
+        Ok(Response::new()
+            .add_attribute("action", "update_min_holding_period")
+            .add_attribute("min_holding_period", min_holding_period.to_string()))
+    }
 
-            let mut config = CONFIG.load(deps.storage)?;
-            config.token_contract = deps.api.addr_validate(&res.contract_address)?;
+    pub fn update_min_reinvest_amount(
+        deps: DepsMut,
+        info: MessageInfo,
+        min_reinvest_amount: Uint128,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
 
-            // update the contract admin
-            let msg = WasmMsg::UpdateAdmin {
-                contract_addr: res.contract_address,
-                admin: admin.to_string(),
-            };
-            let resp = Response::new().add_submessage(SubMsg::new(msg));
-            CONFIG.save(deps.storage, &config)?;
-            Ok(resp)
-        }
-        AFTER_WITHDRAW_INTERMITTENT_REPLY => {
-            // ignore intermittent replies
-            Ok(Response::default())
-        }
-        AFTER_WITHDRAW_REPLY => {
-            // reinvest all received rewards, even if some of the withdrawals failed
-            reply::after_withdraw_rewards(deps, env)
-        }
-        id => Err(StdError::generic_err(format!("invalid reply id: {}; must be 1", id)).into()),
+        config.min_reinvest_amount = min_reinvest_amount;
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_min_reinvest_amount")
+            .add_attribute("min_reinvest_amount", min_reinvest_amount))
     }
-}
 
-mod reply {
-    use std::{cmp::Ordering, collections::BTreeMap};
+    pub fn update_min_delegation(
+        deps: DepsMut,
+        info: MessageInfo,
+        min_delegation: Uint128,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
 
-    use crate::state::{CleanedSupply, Unbonding, UNBONDING};
-    use cosmwasm_std::{coins, BankMsg, Coin, StakingMsg, Uint128};
+        config.min_delegation = min_delegation;
+        CONFIG.save(deps.storage, &config)?;
 
-    use super::*;
+        Ok(Response::new()
+            .add_attribute("action", "update_min_delegation")
+            .add_attribute("min_delegation", min_delegation))
+    }
 
-    pub fn after_withdraw_rewards(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
-        let mut supply = CleanedSupply::load(deps.storage, &env)?;
-        let mut balance = supply.balance(deps.as_ref(), &env)?;
+    pub fn update_reinvest_hook(
+        deps: DepsMut,
+        info: MessageInfo,
+        hook: Option<String>,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
 
-        // early return if nothing to delegate
-        if balance.is_zero() {
-            return Ok(Response::new());
+        config.reinvest_hook = hook.map(|h| deps.api.addr_validate(&h)).transpose()?;
+        CONFIG.save(deps.storage, &config)?;
+
+        let mut res = Response::new().add_attribute("action", "update_reinvest_hook");
+        if let Some(hook) = &config.reinvest_hook {
+            res = res.add_attribute("reinvest_hook", hook);
         }
+        Ok(res)
+    }
+
+    pub fn update_validator_probation(
+        deps: DepsMut,
+        info: MessageInfo,
+        new_validator_probation: u64,
+        probation_max_weight: Decimal,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
+        ensure!(
+            probation_max_weight <= Decimal::one(),
+            ContractError::InvalidProbationMaxWeight {}
+        );
+
+        config.new_validator_probation = new_validator_probation;
+        config.probation_max_weight = probation_max_weight;
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_validator_probation")
+            .add_attribute(
+                "new_validator_probation",
+                new_validator_probation.to_string(),
+            )
+            .add_attribute("probation_max_weight", probation_max_weight.to_string()))
+    }
+
+    pub fn update_commission_spike_handling(
+        deps: DepsMut,
+        info: MessageInfo,
+        max_commission_increase_alert: Decimal,
+        auto_zero_weight_on_spike: bool,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
+        ensure!(
+            max_commission_increase_alert <= Decimal::one(),
+            ContractError::InvalidCommissionIncreaseAlert {}
+        );
+
+        config.max_commission_increase_alert = max_commission_increase_alert;
+        config.auto_zero_weight_on_spike = auto_zero_weight_on_spike;
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_commission_spike_handling")
+            .add_attribute(
+                "max_commission_increase_alert",
+                max_commission_increase_alert.to_string(),
+            )
+            .add_attribute(
+                "auto_zero_weight_on_spike",
+                auto_zero_weight_on_spike.to_string(),
+            ))
+    }
 
+    pub fn update_delegation_cadence(
+        deps: DepsMut,
+        info: MessageInfo,
+        delegate_every_n_epochs: u64,
+    ) -> Result<Response, ContractError> {
         let mut config = CONFIG.load(deps.storage)?;
-        let mut resp = Response::new();
+        config.ensure_operator_or_owner(&info.sender)?;
+
+        config.delegate_every_n_epochs = delegate_every_n_epochs;
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_delegation_cadence")
+            .add_attribute(
+                "delegate_every_n_epochs",
+                delegate_every_n_epochs.to_string(),
+            ))
+    }
+
+    pub fn update_strict_supply_check(
+        deps: DepsMut,
+        info: MessageInfo,
+        strict_supply_check: bool,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
+
+        config.strict_supply_check = strict_supply_check;
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_strict_supply_check")
+            .add_attribute("strict_supply_check", strict_supply_check.to_string()))
+    }
+
+    pub fn update_rebalance_on_reinvest(
+        deps: DepsMut,
+        info: MessageInfo,
+        rebalance_on_reinvest: bool,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
+
+        config.rebalance_on_reinvest = rebalance_on_reinvest;
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_rebalance_on_reinvest")
+            .add_attribute("rebalance_on_reinvest", rebalance_on_reinvest.to_string()))
+    }
+
+    pub fn update_redelegation_hops(
+        deps: DepsMut,
+        info: MessageInfo,
+        redelegation_hops: bool,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
+
+        config.redelegation_hops = redelegation_hops;
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_redelegation_hops")
+            .add_attribute("redelegation_hops", redelegation_hops.to_string()))
+    }
+
+    pub fn update_max_rate_growth(
+        deps: DepsMut,
+        info: MessageInfo,
+        max_rate_growth_per_epoch: Option<Decimal>,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
+        // a zero cap would defer all rewards forever
+        ensure!(
+            max_rate_growth_per_epoch != Some(Decimal::zero()),
+            ContractError::InvalidMaxRateGrowth {}
+        );
+
+        config.max_rate_growth_per_epoch = max_rate_growth_per_epoch;
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_max_rate_growth")
+            .add_attribute(
+                "max_rate_growth_per_epoch",
+                max_rate_growth_per_epoch.map_or("none".to_string(), |g| g.to_string()),
+            ))
+    }
+
+    pub fn register_claim_buyer(
+        deps: DepsMut,
+        info: MessageInfo,
+        contract: String,
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
+
+        let contract = deps.api.addr_validate(&contract)?;
+        CLAIM_BUYERS.save(deps.storage, &contract, &Empty {})?;
+
+        Ok(Response::new()
+            .add_attribute("action", "register_claim_buyer")
+            .add_attribute("contract", contract))
+    }
+
+    pub fn deregister_claim_buyer(
+        deps: DepsMut,
+        info: MessageInfo,
+        contract: String,
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
+
+        let contract = deps.api.addr_validate(&contract)?;
+        CLAIM_BUYERS.remove(deps.storage, &contract);
+
+        Ok(Response::new()
+            .add_attribute("action", "deregister_claim_buyer")
+            .add_attribute("contract", contract))
+    }
+
+    pub fn update_issuance_cap(
+        deps: DepsMut,
+        info: MessageInfo,
+        issuance_cap: Option<Uint128>,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
+
+        // the lsd token would reject the mints above its own cap anyway
+        if let Some(token_cap) = config.token_cap(deps.as_ref())? {
+            ensure!(
+                issuance_cap.map_or(false, |cap| cap <= token_cap),
+                ContractError::IssuanceCapAboveTokenCap { token_cap }
+            );
+        }
+        config.issuance_cap = issuance_cap;
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_issuance_cap")
+            .add_attribute(
+                "issuance_cap",
+                issuance_cap.map_or_else(|| "none".to_string(), |cap| cap.to_string()),
+            ))
+    }
+
+    /// Sets `issued` to the total supply of the lsd token, e.g. after tokens were burned without the hub.
+    pub fn sync_issued(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
+
+        let cw20_total_supply = config.token_total_supply(deps.as_ref())?;
+        let mut supply = SUPPLY.load(deps.storage)?;
+        let issued = supply.issued;
+        supply.issued = cw20_total_supply;
+        SUPPLY.save(deps.storage, &supply)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "sync_issued")
+            .add_attribute("old_issued", issued)
+            .add_attribute("issued", cw20_total_supply))
+    }
+
+    /// Fails if `issued` and the lsd token's total supply differ by more than [`ISSUED_DUST_TOLERANCE`]
+    fn check_issued(deps: Deps, config: &Config) -> Result<(), ContractError> {
+        let issued = SUPPLY.load(deps.storage)?.issued;
+        let cw20_total_supply = config.token_total_supply(deps)?;
+        ensure!(
+            issued.abs_diff(cw20_total_supply) <= ISSUED_DUST_TOLERANCE,
+            ContractError::SupplyMismatch {
+                issued,
+                cw20_total_supply,
+            }
+        );
+        Ok(())
+    }
+
+    pub fn repair_claims_counter(
+        deps: DepsMut,
+        info: MessageInfo,
+        computed_sum: Uint128,
+        scanned_until: Option<String>,
+        checkpoint: String,
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
+
+        // the checkpoint only matches as long as the counter did not change since the scan,
+        // so no claims were created or paid in between
+        let mut supply = SUPPLY.load(deps.storage)?;
+        ensure_eq!(
+            claims_checkpoint(supply.claims, computed_sum, scanned_until.as_deref()),
+            checkpoint,
+            ContractError::InvalidClaimsCheckpoint {}
+        );
+        // and the scan must have covered all addresses
+        let scanned_until = scanned_until.map(Addr::unchecked);
+        ensure!(
+            !CLAIMS.has_claims_after(deps.storage, scanned_until.as_ref()),
+            ContractError::InvalidClaimsCheckpoint {}
+        );
+
+        let old_counter = supply.claims;
+        supply.claims = computed_sum;
+        SUPPLY.save(deps.storage, &supply)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "repair_claims_counter")
+            .add_attribute("old_claims", old_counter)
+            .add_attribute("claims", computed_sum))
+    }
+
+    pub fn update_surplus_strategy(
+        deps: DepsMut,
+        info: MessageInfo,
+        surplus_strategy: SurplusStrategy,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
+
+        let attribute = surplus_strategy.to_string();
+        config.surplus_strategy = surplus_strategy;
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_surplus_strategy")
+            .add_attribute("surplus_strategy", attribute))
+    }
+
+    pub fn set_operator(
+        deps: DepsMut,
+        info: MessageInfo,
+        operator: Option<String>,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
+
+        config.operator = operator
+            .map(|operator| deps.api.addr_validate(&operator))
+            .transpose()?;
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_operator")
+            .add_attribute(
+                "operator",
+                config
+                    .operator
+                    .map_or("none".to_string(), |o| o.to_string()),
+            ))
+    }
+
+    pub fn set_delegations_suspended(
+        deps: DepsMut,
+        info: MessageInfo,
+        suspended: bool,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        config.ensure_operator_or_owner(&info.sender)?;
+
+        config.delegations_suspended = suspended;
+        CONFIG.save(deps.storage, &config)?;
+
+        let action = if suspended {
+            "suspend_delegations"
+        } else {
+            "resume_delegations"
+        };
+        Ok(Response::new().add_attribute("action", action))
+    }
+
+    pub fn update_commission_vesting(
+        deps: DepsMut,
+        info: MessageInfo,
+        commission_vesting: Option<CommissionVesting>,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
+
+        if let Some(vesting) = &commission_vesting {
+            ensure!(
+                vesting.duration_seconds > 0 && vesting.cliff_seconds <= vesting.duration_seconds,
+                ContractError::InvalidCommissionVesting {}
+            );
+        }
+
+        let mut resp = Response::new().add_attribute("action", "update_commission_vesting");
+        resp = match &commission_vesting {
+            Some(vesting) => resp
+                .add_attribute("cliff_seconds", vesting.cliff_seconds.to_string())
+                .add_attribute("duration_seconds", vesting.duration_seconds.to_string()),
+            None => resp.add_attribute("commission_vesting", "none"),
+        };
+        config.commission_vesting = commission_vesting;
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(resp)
+    }
+
+    pub fn claim_vested_commission(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        ensure_eq!(config.treasury, info.sender, ContractError::Unauthorized {});
+
+        let now = env.block.time.seconds();
+        let tranches = COMMISSION_VESTING
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        let mut to_send = Uint128::zero();
+        for (created, mut tranche) in tranches {
+            let claimable = tranche.vested(created, now) - tranche.claimed;
+            if claimable.is_zero() {
+                continue;
+            }
+            to_send += claimable;
+            tranche.claimed += claimable;
+            // fully claimed tranches are not needed anymore
+            if tranche.claimed == tranche.amount {
+                COMMISSION_VESTING.remove(deps.storage, created);
+            } else {
+                COMMISSION_VESTING.save(deps.storage, created, &tranche)?;
+            }
+        }
+        ensure!(!to_send.is_zero(), ContractError::NothingToClaim {});
+
+        let transfer_msg = WasmMsg::Execute {
+            contract_addr: config.token_contract.to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: config.treasury.to_string(),
+                amount: to_send,
+            })?,
+            funds: vec![],
+        };
+
+        Ok(Response::new()
+            .add_message(transfer_msg)
+            .add_attribute("action", "claim_vested_commission")
+            .add_attribute("amount", to_send))
+    }
+
+    pub fn skip_next_unbond(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
+
+        let next_unbond = config.skip_next_unbond(&env);
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "skip_next_unbond")
+            .add_attribute("next_unbond", next_unbond.to_string()))
+    }
+
+    pub fn deposit_boost(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
+
+        let supply = SUPPLY.load(deps.storage)?;
+        let paid = must_pay(&info, &supply.bond_denom)?;
+
+        // the tokens are delegated with the next reinvest, but don't count as assets backing the lsd tokens
+        let principal = BOOST_PRINCIPAL.may_load(deps.storage)?.unwrap_or_default() + paid;
+        BOOST_PRINCIPAL.save(deps.storage, &principal)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "deposit_boost")
+            .add_attribute("amount", paid)
+            .add_attribute("boost_principal", principal))
+    }
+
+    pub fn register_external_reward(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        denom: String,
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
+
+        let supply = SUPPLY.load(deps.storage)?;
+        let amount = deps
+            .querier
+            .query_balance(&env.contract.address, &denom)?
+            .amount;
+        ensure!(
+            denom != supply.bond_denom && !amount.is_zero() && !supply.issued.is_zero(),
+            ContractError::InvalidExternalReward { denom }
+        );
+
+        // whatever is left from a previous registration is part of the balance and distributed again.
+        // The fresh shares of the previous registration become stale with the new `registered_at`.
+        EXTERNAL_REWARDS.save(
+            deps.storage,
+            &denom,
+            &ExternalReward {
+                amount,
+                shares: supply.issued,
+                registered_at: env.block.time.seconds(),
+            },
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("action", "register_external_reward")
+            .add_attribute("denom", denom)
+            .add_attribute("amount", amount)
+            .add_attribute("shares", supply.issued))
+    }
+
+    pub fn sweep_external_reward(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        denom: String,
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
+
+        let reward = EXTERNAL_REWARDS
+            .may_load(deps.storage, &denom)?
+            .ok_or_else(|| ContractError::InvalidExternalReward {
+                denom: denom.clone(),
+            })?;
+        let sweepable_at = reward.registered_at + EXTERNAL_REWARD_SWEEP_DELAY;
+        ensure!(
+            env.block.time.seconds() >= sweepable_at,
+            ContractError::ExternalRewardSweepTooEarly { sweepable_at }
+        );
+        EXTERNAL_REWARDS.remove(deps.storage, &denom);
+
+        let mut res = Response::new()
+            .add_attribute("action", "sweep_external_reward")
+            .add_attribute("denom", &denom)
+            .add_attribute("amount", reward.amount);
+        if !reward.amount.is_zero() {
+            res = res.add_message(BankMsg::Send {
+                to_address: config.treasury.into_string(),
+                amount: coins(reward.amount.u128(), denom),
+            });
+        }
+        Ok(res)
+    }
+
+    pub fn withdraw_boost(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        amount: Uint128,
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        config.ensure_owner(&info.sender)?;
+        ensure!(!amount.is_zero(), ContractError::ZeroUnbond {});
+
+        let principal = BOOST_PRINCIPAL.may_load(deps.storage)?.unwrap_or_default();
+        ensure!(
+            amount <= principal,
+            ContractError::InsufficientBoostPrincipal { principal }
+        );
+        BOOST_PRINCIPAL.save(deps.storage, &(principal - amount))?;
+
+        // moving the amount from the principal to the claims keeps the assets unchanged,
+        // the claim is then undelegated with the next unbonding like any other
+        let mut supply = CleanedSupply::load(deps.storage, &env)?;
+        supply.claims += amount;
+        SUPPLY.save(deps.storage, &supply)?;
+
+        let next_unbond = config.next_undelegation();
+        CLAIMS.create_claim(
+            deps.storage,
+            &config.treasury,
+            amount,
+            Timestamp::from_seconds(next_unbond + config.unbond_period),
+            next_unbond,
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("action", "withdraw_boost")
+            .add_attribute("amount", amount)
+            .add_attribute("boost_principal", principal - amount))
+    }
+
+    pub fn update_tombstone_threshold(
+        deps: DepsMut,
+        info: MessageInfo,
+        new_threshold: Decimal,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+
+        // validation
+        config.ensure_owner(&info.sender)?;
+        ensure!(
+            !new_threshold.is_zero() && new_threshold <= Decimal::percent(50),
+            ContractError::InvalidTombstoneThreshold {}
+        );
+
+        config.tombstone_treshold = new_threshold;
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_tombstone_threshold")
+            .add_attribute("tombstone_threshold", new_threshold.to_string()))
+    }
+
+    pub fn update_slashing_safety_margin(
+        deps: DepsMut,
+        info: MessageInfo,
+        new_margin: u64,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+
+        // validation
+        config.ensure_owner(&info.sender)?;
+        ensure!(
+            config.is_valid_slashing_safety_margin(new_margin),
+            ContractError::InvalidSlashingSafetyMargin {}
+        );
+
+        config.slashing_safety_margin = new_margin;
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_slashing_safety_margin")
+            .add_attribute("slashing_safety_margin", new_margin.to_string()))
+    }
+
+    pub fn update_min_blocks_per_epoch(
+        deps: DepsMut,
+        info: MessageInfo,
+        min_blocks_per_epoch: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        config.ensure_operator_or_owner(&info.sender)?;
+
+        config.min_blocks_per_epoch = min_blocks_per_epoch;
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_min_blocks_per_epoch")
+            .add_attribute(
+                "min_blocks_per_epoch",
+                min_blocks_per_epoch.map_or("none".to_string(), |b| b.to_string()),
+            ))
+    }
+
+    pub fn check_slash(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+        /// 0.00001 = 0.001%
+        const SLASHING_THRESHOLD: Decimal = Decimal::raw(10u128.pow(18 - 5));
+        /// The staking module truncates when converting delegator shares to tokens,
+        /// so a difference this small is never considered a slashing, no matter the delegation size
+        const ROUNDING_TOLERANCE: Uint128 = Uint128::one();
+
+        let supply: Supply = SUPPLY.load(deps.storage)?;
+
+        // ensure safety margin around unbonding periods
+        let now = env.block.time.seconds();
+        let config = CONFIG.load(deps.storage)?;
+        let slashing_safety_margin = config.slashing_safety_margin;
+        // we do not call `supply.cleanup_unbonding` yet,
+        // since we want to also check supposedly finished unbondings
+        let unbonding = unbondings_expiring_between(
+            deps.storage,
+            now - slashing_safety_margin,
+            now + slashing_safety_margin,
+        )
+        .next()
+        .is_some();
+        ensure!(!unbonding, ContractError::UnbondingTooClose {});
+
+        // check if we have any slashing events by comparing queried delegations to our state
+        // now we have to cleanup old unbondings
+        let mut supply = supply.cleanup_unbonding(deps.storage, &env)?;
+
+        let mut bonded = BONDED.load(deps.storage)?;
+        let stored_delegations: HashMap<_, _> = bonded.iter().map(|(v, b)| (v, *b)).collect();
+
+        let queried_delegations = deps.querier.query_all_delegations(env.contract.address)?;
+
+        // the unbondings still reference validators that were removed from the set since.
+        // Those that have no delegation left can not be compared against anything,
+        // but their unbondings still complete and are paid out like all others
+        let mut unobserved: Vec<_> = range_unbondings(deps.storage, None, None, Order::Ascending)
+            .map(|r| r.map(|(_, unbondings)| unbondings))
+            .collect::<StdResult<Vec<_>>>()?
+            .into_iter()
+            .flatten()
+            .map(|ub| ub.validator)
+            .filter(|validator| {
+                !queried_delegations
+                    .iter()
+                    .any(|d| &d.validator == validator)
+            })
+            .collect();
+        unobserved.sort_unstable();
+        unobserved.dedup();
+        let unobserved: Vec<_> = unobserved.iter().map(String::as_str).collect();
+
+        let slashed_validators: HashMap<_, _> = queried_delegations
+            .iter()
+            .filter_map(|d| {
+                let stored = stored_delegations
+                    .get(&d.validator)
+                    .copied()
+                    .unwrap_or_default();
+
+                // if difference is larger than threshold, this validator was slashed
+                let drift = stored.saturating_sub(d.amount.amount);
+                if drift > ROUNDING_TOLERANCE && drift >= stored.mul_floor(SLASHING_THRESHOLD) {
+                    // keep track of multiplier
+                    Some((&d.validator, Decimal::from_ratio(d.amount.amount, stored)))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        if slashed_validators.is_empty() {
+            // no slashing detected
+            return Ok(Response::new()
+                .add_event(events::slash(
+                    false,
+                    Uint128::zero(),
+                    Uint128::zero(),
+                    &[],
+                    &unobserved,
+                ))
+                .add_legacy_attribute("slashed", "false"));
+        }
+
+        // we were slashed, so we need to update our state
+        let balance = supply.balance(deps.as_ref(), &env)?;
+        let old_rate = supply.tokens_per_share(balance);
+        // we also keep track of the old total for calculating the global multiplier to adjust claims
+        let (old_total_bonded, old_total_unbonding) = (supply.total_bonded, supply.total_unbonding);
+        bonded = bonded
+            .into_iter()
+            .map(|(validator, mut amount)| {
+                if let Some(multiplier) = slashed_validators.get(&validator) {
+                    amount = amount.mul_floor(*multiplier);
+                }
+                (validator, amount)
+            })
+            .collect();
+        supply.total_bonded = bonded.iter().map(|(_, b)| *b).sum();
+        BONDED.save(deps.storage, &bonded)?;
+        record_bonded_change(
+            deps.storage,
+            &env,
+            BondedChangeReason::Slash,
+            old_total_bonded,
+            supply.total_bonded,
+        )?;
+
+        let mut unbondings = range_unbondings(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (expiration, unbondings) in unbondings.iter_mut() {
+            let mut changed = false;
+            for ub in unbondings.iter_mut() {
+                if let Some(multiplier) = slashed_validators.get(&ub.validator) {
+                    ub.amount = ub.amount.mul_floor(*multiplier);
+                    changed = true;
+                }
+            }
+            // only change entries if there actually was a slashed validator
+            if changed {
+                save_unbondings(deps.storage, *expiration, unbondings)?;
+            }
+        }
+        supply.total_unbonding = unbondings
+            .iter()
+            .flat_map(|(_, u)| u)
+            .map(|u| u.amount)
+            .sum();
+
+        // sanity check
+        #[cfg(debug_assertions)]
+        cosmwasm_std::assert_approx_eq!(
+            supply.total_bonded,
+            queried_delegations
+                .iter()
+                .map(|d| d.amount.amount)
+                .sum::<Uint128>(),
+            "0.0001"
+        );
+
+        let bonded_slashed = old_total_bonded - supply.total_bonded;
+        let mut response = Response::new()
+            .add_legacy_attribute("slashed", "true")
+            .add_legacy_attribute("bonded_slashed", bonded_slashed);
+
+        // report validators that lost so much stake that they were most likely tombstoned
+        let mut tombstoned: Vec<_> = slashed_validators
+            .iter()
+            .filter(|(_, multiplier)| Decimal::one() - **multiplier >= config.tombstone_treshold)
+            .map(|(validator, _)| validator.as_str())
+            .collect();
+        tombstoned.sort_unstable();
+        if !tombstoned.is_empty() {
+            response = response.add_legacy_attribute("tombstoned", tombstoned.join(","));
+        }
+        // remembered for `QueryMsg::TombstonedValidators`, so the gauge does not vote them back in right away
+        for validator in &tombstoned {
+            TOMBSTONED_VALIDATORS.save(deps.storage, validator, &env.block.time.seconds())?;
+        }
+
+        // we also need to update the pending claims
+        if old_total_unbonding.is_zero() {
+            record_slash_snapshot(
+                deps.storage,
+                &env,
+                old_rate,
+                supply.tokens_per_share(balance),
+            )?;
+            SUPPLY.save(deps.storage, &supply)?;
+            return Ok(response
+                .add_event(events::slash(
+                    true,
+                    bonded_slashed,
+                    Uint128::zero(),
+                    &tombstoned,
+                    &unobserved,
+                ))
+                .add_legacy_attribute("unbonded_slashed", Uint128::zero()));
+        }
+        let global_unbonding_multiplier =
+            Decimal::from_ratio(supply.total_unbonding, old_total_unbonding);
+
+        // we need to update the pending claims, but only the part that is actually unbonding
+        // (part of the claims can be in the contract balance, which is not slashed).
+        // The unbonding tokens back the claims first. Anything above that is backing deferred claims,
+        // which are affected by the slashing through the exchange rate instead.
+        supply.claims = if old_total_unbonding <= supply.claims {
+            (supply.claims - old_total_unbonding) + supply.total_unbonding
+        } else {
+            supply.claims.mul_floor(global_unbonding_multiplier)
+        };
+        SUPPLY.save(deps.storage, &supply)?;
+        record_slash_snapshot(
+            deps.storage,
+            &env,
+            old_rate,
+            supply.tokens_per_share(balance),
+        )?;
+
+        let unbonding_period = config.unbond_period;
+        let mut slashings = SLASHINGS.may_load(deps.storage)?.unwrap_or_default();
+        slashings.push(Slashing {
+            start: env.block.time.seconds(),
+            end: env.block.time.plus_seconds(unbonding_period).seconds(),
+            multiplier: global_unbonding_multiplier,
+        });
+        SLASHINGS.save(deps.storage, &slashings)?;
+
+        let unbonded_slashed = old_total_unbonding - supply.total_unbonding;
+        Ok(response
+            .add_event(events::slash(
+                true,
+                bonded_slashed,
+                unbonded_slashed,
+                &tombstoned,
+                &unobserved,
+            ))
+            .add_legacy_attribute("unbonded_slashed", unbonded_slashed))
+    }
+
+    /// Records the exchange rate after a slashing, remembering how much the holders lost
+    fn record_slash_snapshot(
+        storage: &mut dyn Storage,
+        env: &Env,
+        old_rate: Decimal,
+        new_rate: Decimal,
+    ) -> StdResult<()> {
+        let multiplier = if old_rate.is_zero() {
+            Decimal::one()
+        } else {
+            std::cmp::min(new_rate / old_rate, Decimal::one())
+        };
+        record_rate_snapshot(storage, env, new_rate, multiplier)
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::Reinvest {} => execute::reinvest(deps, env, None),
+        SudoMsg::CheckSlash {} => execute::check_slash(deps, env),
+        SudoMsg::AssertInvariants {} => sudo::assert_invariants(deps.as_ref(), env),
+        SudoMsg::UpdateDiscountLimits {
+            max_discount_change_per_update,
+            min_discount_update_interval,
+        } => sudo::update_discount_limits(
+            deps,
+            max_discount_change_per_update,
+            min_discount_update_interval,
+        ),
+    }
+}
+
+mod sudo {
+    use cosmwasm_std::{Order, Uint128};
+
+    use crate::state::{range_unbondings, CleanedSupply};
+
+    use super::*;
+
+    pub fn update_discount_limits(
+        deps: DepsMut,
+        max_discount_change_per_update: Decimal,
+        min_discount_update_interval: u64,
+    ) -> Result<Response, ContractError> {
+        // a zero step would freeze the discount and one above 100% is no limit at all
+        ensure!(
+            !max_discount_change_per_update.is_zero()
+                && max_discount_change_per_update <= Decimal::one()
+                && min_discount_update_interval <= MAX_DISCOUNT_UPDATE_INTERVAL,
+            ContractError::InvalidDiscountLimits {}
+        );
+        let mut config = CONFIG.load(deps.storage)?;
+        config.max_discount_change_per_update = max_discount_change_per_update;
+        config.min_discount_update_interval = min_discount_update_interval;
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_discount_limits")
+            .add_attribute(
+                "max_discount_change_per_update",
+                max_discount_change_per_update.to_string(),
+            )
+            .add_attribute(
+                "min_discount_update_interval",
+                min_discount_update_interval.to_string(),
+            ))
+    }
+
+    pub fn assert_invariants(deps: Deps, env: Env) -> Result<Response, ContractError> {
+        let supply = SUPPLY.load(deps.storage)?;
+
+        // the stored per-validator delegations have to add up to the total
+        let bonded: Uint128 = BONDED
+            .load(deps.storage)?
+            .into_iter()
+            .map(|(_, amount)| amount)
+            .sum();
+        ensure!(
+            bonded == supply.total_bonded,
+            ContractError::InvariantViolated {
+                reason: format!(
+                    "bonded sum {} does not match total_bonded {}",
+                    bonded, supply.total_bonded
+                )
+            }
+        );
+
+        // same for the unbonding queue, including the mature entries that were not cleaned up yet
+        let unbonding = range_unbondings(deps.storage, None, None, Order::Ascending)
+            .map(|r| r.map(|(_, ubs)| ubs.into_iter().map(|ub| ub.amount).sum::<Uint128>()))
+            .sum::<StdResult<Uint128>>()?;
+        ensure!(
+            unbonding == supply.total_unbonding,
+            ContractError::InvariantViolated {
+                reason: format!(
+                    "unbonding sum {} does not match total_unbonding {}",
+                    unbonding, supply.total_unbonding
+                )
+            }
+        );
+
+        // the deferred claims are part of the issued tokens
+        ensure!(
+            supply.deferred_claim_shares <= supply.issued,
+            ContractError::InvariantViolated {
+                reason: format!(
+                    "deferred claim shares {} exceed the issued tokens {}",
+                    supply.deferred_claim_shares, supply.issued
+                )
+            }
+        );
+
+        // the claims must be backed and the exchange rate has to be representable
+        let supply = CleanedSupply::load_for_query(deps.storage, &env)?;
+        let balance = supply.balance(deps, &env)?;
+        ensure!(
+            supply.claims <= supply.total_bonded + supply.total_unbonding + balance,
+            ContractError::InvariantViolated {
+                reason: format!("claims {} exceed the available assets", supply.claims),
+            }
+        );
+        // the same assets the exchange rate is based on, without the boost principal and deferred rewards
+        let assets = supply.assets(balance);
+        if !supply.issued.is_zero() {
+            Decimal::checked_from_ratio(assets, supply.issued).map_err(|_| {
+                ContractError::InvariantViolated {
+                    reason: format!(
+                        "exchange rate {} / {} is out of range",
+                        assets, supply.issued
+                    ),
+                }
+            })?;
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "assert_invariants")
+            .add_attribute("valid", "true"))
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(mut deps: DepsMut, env: Env, reply: Reply) -> Result<Response, ContractError> {
+    match reply.id {
+        AFTER_TOKEN_CREATION_REPLY => {
+            let (token, resp) = reply::adopt_instantiated_token(deps.as_ref(), env, reply)?;
+            let mut config = CONFIG.load(deps.storage)?;
+            config.token_contract = token;
+            CONFIG.save(deps.storage, &config)?;
+            Ok(resp)
+        }
+        AFTER_VOUCHER_CREATION_REPLY => {
+            let (voucher, resp) = reply::adopt_instantiated_token(deps.as_ref(), env, reply)?;
+            let mut config = CONFIG.load(deps.storage)?;
+            config.voucher_contract = Some(voucher);
+            CONFIG.save(deps.storage, &config)?;
+            Ok(resp)
+        }
+        AFTER_WITHDRAW_INTERMITTENT_REPLY => {
+            // ignore intermittent replies
+            Ok(Response::default())
+        }
+        AFTER_WITHDRAW_REPLY => {
+            // reinvest all received rewards, even if some of the withdrawals failed
+            let res = reply::after_withdraw_rewards(deps.branch(), env)?;
+            reply::notify_reinvest_hook(deps.as_ref(), res)
+        }
+        AFTER_PARTIAL_WITHDRAW_REPLY => reply::after_partial_withdraw_rewards(deps, env),
+        REINVEST_HOOK_REPLY => {
+            // a broken hook must not block reinvesting
+            let error = reply.result.into_result().err().unwrap_or_default();
+            Ok(Response::new().add_attribute("reinvest_hook_error", error))
+        }
+        id => Err(StdError::generic_err(format!("invalid reply id: {}; must be 1", id)).into()),
+    }
+}
+
+mod reply {
+    use std::{cmp::Ordering, collections::BTreeMap};
+
+    use crate::events::ReinvestStatus;
+    use crate::msg::{BondedChangeReason, ReinvestHookMsg};
+    use crate::state::{
+        latest_rate_snapshot, on_probation, pending_redelegations, record_bonded_change,
+        record_rate_snapshot, record_redelegation, released_in_block, save_unbondings,
+        CleanedSupply, Unbonding, VestingTranche, COMMISSION_VESTING, REINVESTS_WITHOUT_DELEGATION,
+        SKIPPED_REWARDS, UNDELEGATIONS,
+    };
+    use crate::valset::{spill_over_caps, ValsetChange};
+    use cosmwasm_std::{coins, BankMsg, Coin, CosmosMsg, StakingMsg, Storage, Uint128};
+    use cw20::Cw20ExecuteMsg;
+
+    use super::*;
+
+    /// Returns the address of the cw20 instantiated by `instantiate_token` and passes the contract admin
+    /// of this contract on to it
+    pub fn adopt_instantiated_token(
+        deps: Deps,
+        env: Env,
+        reply: Reply,
+    ) -> Result<(Addr, Response), ContractError> {
+        let result = reply
+            .result
+            .into_result()
+            .map_err(|e| StdError::generic_err(e))?;
+        let res = cw_utils::parse_instantiate_response_data(
+            result
+                .msg_responses
+                .get(0)
+                .cloned()
+                .map(|v| v.value)
+                .or(result.data)
+                .unwrap()
+                .as_slice(),
+        )
+        .map_err(|_| {
+            StdError::parse_err("MsgInstantiateContractResponse", "failed to parse data")
+        })?;
+
+        // Pass the contract admin of this contract to the Token contract
+        let contract_info = deps
+            .querier
+            .query_wasm_contract_info(env.contract.address)?;
+
+        let admin = contract_info.admin.unwrap();
+        let token = deps.api.addr_validate(&res.contract_address)?;
+
+        // update the contract admin
+        let msg = WasmMsg::UpdateAdmin {
+            contract_addr: res.contract_address,
+            admin: admin.to_string(),
+        };
+        Ok((token, Response::new().add_submessage(SubMsg::new(msg))))
+    }
+
+    /// Sends the exchange rate recorded by the reinvest to the `reinvest_hook`, if there is one.
+    /// Failures only trigger a reply, so they don't revert the reinvest.
+    pub fn notify_reinvest_hook(deps: Deps, res: Response) -> Result<Response, ContractError> {
+        let hook = match CONFIG.load(deps.storage)?.reinvest_hook {
+            Some(hook) => hook,
+            None => return Ok(res),
+        };
+        let snapshot = match latest_rate_snapshot(deps.storage)? {
+            Some(snapshot) => snapshot,
+            None => return Ok(res),
+        };
+
+        let msg = WasmMsg::Execute {
+            contract_addr: hook.to_string(),
+            msg: to_json_binary(&ReinvestHookMsg::Reinvested {
+                exchange_rate: snapshot.rate,
+            })?,
+            funds: vec![],
+        };
+        Ok(res.add_submessage(SubMsg::reply_on_error(msg, REINVEST_HOOK_REPLY)))
+    }
+
+    /// Takes the commission of a partial reinvest. The rewards stay in the balance and are delegated
+    /// once the reinvest is completed.
+    pub fn after_partial_withdraw_rewards(
+        deps: DepsMut,
+        env: Env,
+    ) -> Result<Response, ContractError> {
+        let mut supply = CleanedSupply::load(deps.storage, &env)?;
+        let mut balance = supply.balance(deps.as_ref(), &env)?;
+        let config = CONFIG.load(deps.storage)?;
+
+        // the balance can also decrease in the meantime, that is no reward
+        let rewards = balance.saturating_sub(TMP_STATE.load(deps.storage)?.balance);
+        let messages = take_commission(
+            deps.storage,
+            &env,
+            &config,
+            &mut supply,
+            &mut balance,
+            rewards,
+        )?;
+        SUPPLY.save(deps.storage, &supply)?;
+
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_attribute("action", "partial_reinvest")
+            .add_attribute("rewards", rewards))
+    }
+
+    /// Sends the commission for the given `rewards` to the treasury, or bonds it for the vesting.
+    /// `balance` is reduced by the commission that is sent away.
+    fn take_commission(
+        storage: &mut dyn Storage,
+        env: &Env,
+        config: &Config,
+        supply: &mut CleanedSupply,
+        balance: &mut Uint128,
+        rewards: Uint128,
+    ) -> StdResult<Vec<CosmosMsg>> {
+        let commission_amount = rewards.mul_floor(config.commission);
+        if commission_amount.is_zero() {
+            return Ok(vec![]);
+        }
+
+        // once the issuance cap is reached, the commission is paid out instead of being bonded
+        let vesting = config.commission_vesting.clone().filter(|_| {
+            let issue = supply.shares_for_tokens(commission_amount, *balance - commission_amount);
+            !config.exceeds_issuance_cap(supply.issued + issue)
+        });
+        match vesting {
+            None => {
+                *balance -= commission_amount;
+                Ok(vec![BankMsg::Send {
+                    to_address: config.treasury.to_string(),
+                    amount: coins(commission_amount.u128(), &supply.bond_denom),
+                }
+                .into()])
+            }
+            Some(vesting) => {
+                // bond the commission like in `execute::bond`, but keep the lsd tokens in this contract
+                // until they vested. The native tokens stay in the balance and are delegated with the reinvest.
+                let issue =
+                    supply.shares_for_tokens(commission_amount, *balance - commission_amount);
+                if issue.is_zero() {
+                    return Ok(vec![]);
+                }
+                supply.issued += issue;
+                COMMISSION_VESTING.update(
+                    storage,
+                    env.block.time.seconds(),
+                    |tranche| -> StdResult<_> {
+                        let mut tranche = tranche.unwrap_or(VestingTranche {
+                            amount: Uint128::zero(),
+                            claimed: Uint128::zero(),
+                            vesting,
+                        });
+                        tranche.amount += issue;
+                        Ok(tranche)
+                    },
+                )?;
+                Ok(vec![WasmMsg::Execute {
+                    contract_addr: config.token_contract.to_string(),
+                    msg: to_json_binary(&Cw20ExecuteMsg::Mint {
+                        recipient: env.contract.address.to_string(),
+                        amount: issue,
+                    })?,
+                    funds: vec![],
+                }
+                .into()])
+            }
+        }
+    }
+
+    /// Redelegates part of the drift between `bonded` and the weights of the validator set, see `Config::rebalance_on_reinvest`.
+    /// Redelegations below [`MIN_REBALANCE_REDELEGATION`], pairs of validators without free entries
+    /// and sources that still receive a redelegation are skipped.
+    /// `bonded` is updated with the redelegations.
+    fn rebalance_redelegations(
+        storage: &mut dyn Storage,
+        env: &Env,
+        config: &Config,
+        supply: &Supply,
+        bonded: &mut BTreeMap<String, Uint128>,
+    ) -> Result<Vec<StakingMsg>, ContractError> {
+        if supply.total_bonded.is_zero() {
+            return Ok(vec![]);
+        }
+        let stake_info = STAKE_INFO.load(storage)?;
+        let now = env.block.time.seconds();
+        let caps = redelegation_caps(
+            storage,
+            config,
+            now,
+            &stake_info.validators,
+            &stake_info.validators,
+            &stake_info.max_delegations,
+            supply.total_bonded,
+        )?;
+        let ValsetChange { messages, .. } = valset_change_redelegation_messages(
+            supply,
+            bonded.iter().map(|(k, v)| (k, *v)),
+            stake_info.validators.iter().map(|(k, v)| (k, *v)),
+            &caps,
+        )?;
+
+        // validators that still receive a redelegation cannot redelegate themselves,
+        // which would fail the whole reinvest
+        let mut receiving = receiving_redelegations(storage, now)?;
+        let mut redelegations = vec![];
+        for msg in messages {
+            if redelegations.len() >= MAX_REBALANCE_REDELEGATIONS {
+                break;
+            }
+            let (src, dst, amount) = match &msg {
+                StakingMsg::Redelegate {
+                    src_validator,
+                    dst_validator,
+                    amount,
+                } => (src_validator.clone(), dst_validator.clone(), amount.amount),
+                _ => continue,
+            };
+            if amount < MIN_REBALANCE_REDELEGATION
+                || receiving.contains(&src)
+                || pending_redelegations(storage, &src, &dst, now)? >= MAX_REDELEGATION_ENTRIES
+            {
+                continue;
+            }
+            record_redelegation(storage, &src, &dst, now, now + config.unbond_period)?;
+            receiving.insert(dst.clone());
+            *bonded
+                .get_mut(&src)
+                .expect("tried to redelegate non-existent stake") -= amount;
+            *bonded.entry(dst).or_default() += amount;
+            redelegations.push(msg);
+        }
+        Ok(redelegations)
+    }
+
+    pub fn after_withdraw_rewards(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+        let mut supply = CleanedSupply::load(deps.storage, &env)?;
+        let mut balance = supply.balance(deps.as_ref(), &env)?;
+        let mut config = CONFIG.load(deps.storage)?;
+
+        // this does not change the total bonded, so it is done even if there is nothing to delegate
+        let hops = complete_redelegation_hops(deps.storage, &env, &config, &supply.bond_denom)?;
+        let mut resp = Response::new().add_messages(hops);
+
+        // early return if nothing to delegate
+        if balance.is_zero() {
+            return Ok(resp.add_event(events::reinvest(
+                ReinvestStatus::Empty,
+                Uint128::zero(),
+                supply.tokens_per_share(balance),
+                Uint128::zero(),
+                0,
+            )));
+        }
+
+        // send commission to the treasury
+        // if nothing was bonded, there cannot be any rewards, so we don't take commission from
+        // what is effectively backing the claims.
+        // If the balance decreased since the rewards were withdrawn, there are no rewards either,
+        // failing here would block all reinvests until the balance recovers
+        let withdrawn = if supply.total_bonded.is_zero() {
+            Uint128::zero()
+        } else {
+            balance.saturating_sub(TMP_STATE.load(deps.storage)?.balance)
+        };
+        let rewards = withdrawn;
+        // windfalls are spread over the next reinvests, so the exchange rate grows by at most the cap.
+        // The previous rate is the one without the new rewards, the deferred ones are not part of the assets.
+        let deferred = supply.deferred_rewards();
+        let pending = rewards + deferred;
+        let recognized = match config.max_rate_growth_per_epoch {
+            Some(max_growth) if !supply.issued.is_zero() => {
+                let previous_assets = supply.assets(balance).saturating_sub(rewards);
+                pending.min(previous_assets.mul_floor(max_growth))
+            }
+            _ => pending,
+        };
+        if pending - recognized != deferred {
+            supply.set_deferred_rewards(deps.storage, pending - recognized)?;
+        }
+        if recognized < pending {
+            resp = resp.add_event(events::rate_growth_capped(
+                rewards,
+                recognized,
+                pending - recognized,
+            ));
+        }
+        let rewards = recognized;
+        // the rewards of skipped reinvests are still in the balance, but their commission was not taken yet
+        let skipped = SKIPPED_REWARDS.may_load(deps.storage)?.unwrap_or_default();
+        let rewards = rewards + skipped;
+
+        // skip the commission and delegation of dust rewards, unless we need to undelegate for the claims
+        // or something else is waiting to be delegated, like the bonds since the last reinvest.
+        // The rewards of skipped reinvests count as well, so they are delegated at once when enough accumulated
+        let claim_coverage = balance + supply.total_unbonding;
+        let required_liquidity =
+            supply.claims + supply.tokens_for_shares(supply.deferred_claim_shares, balance);
+        let dust_rewards = withdrawn + skipped;
+        if claim_coverage >= required_liquidity
+            && dust_rewards < config.min_reinvest_amount
+            && std::cmp::min(claim_coverage - required_liquidity, balance) <= dust_rewards
+        {
+            SKIPPED_REWARDS.save(deps.storage, &rewards)?;
+            SUPPLY.save(deps.storage, &supply)?;
+            // no rate snapshot, the balance still includes the commission on the skipped rewards
+            let exchange_rate = supply.tokens_per_share(balance);
+            return Ok(resp
+                .add_event(events::reinvest(
+                    ReinvestStatus::SkippedDust,
+                    rewards,
+                    exchange_rate,
+                    Uint128::zero(),
+                    0,
+                ))
+                .add_legacy_attribute("reinvest_skipped", "dust"));
+        }
+        SKIPPED_REWARDS.remove(deps.storage);
+
+        resp = resp.add_messages(take_commission(
+            deps.storage,
+            &env,
+            &config,
+            &mut supply,
+            &mut balance,
+            rewards,
+        )?);
+
+        // (un)delegating doesn't change the exchange rate, so we can take the snapshot here
+        let exchange_rate = supply.tokens_per_share(balance);
+        record_rate_snapshot(deps.storage, &env, exchange_rate, Decimal::one())?;
+
+        let mut bonded = BONDED
+            .load(deps.storage)?
+            .into_iter()
+            .collect::<BTreeMap<_, _>>();
+
+        // this is the amount of assets we (will) have available to pay claims
+        let claim_coverage = balance + supply.total_unbonding;
+        // deferred claims are still backed by the delegations like any other lsd token,
+        // but they will be paid out at the then current rate, so we free up their current value as well
+        let required_liquidity =
+            supply.claims + supply.tokens_for_shares(supply.deferred_claim_shares, balance);
+        let old_total_bonded = supply.total_bonded;
+        let mut change_reason = None;
+        // what is reported in the reinvest event
+        let mut status = ReinvestStatus::Balanced;
+        let mut amount_changed = Uint128::zero();
+        let mut unbonding_release_at = 0;
+
+        // the surplus is only delegated every `delegate_every_n_epochs` reinvests
+        let reinvests_without_delegation = REINVESTS_WITHOUT_DELEGATION
+            .may_load(deps.storage)?
+            .unwrap_or_default()
+            + 1;
+        let delegation_deferred = reinvests_without_delegation < config.delegate_every_n_epochs;
+
+        // the (un)delegations are calculated and sent in address order, so the messages and who gets
+        // the rounding remainder do not depend on the order the validator set was given in
+        let mut stake_info = STAKE_INFO.load(deps.storage)?;
+        stake_info.validators.sort_by(|(a, _), (b, _)| a.cmp(b));
+        match claim_coverage.cmp(&required_liquidity) {
+            Ordering::Greater if config.delegations_suspended => {
+                // the surplus stays in the balance, it is still part of the backing assets
+                status = ReinvestStatus::Suspended;
+                resp = resp.add_legacy_attribute("delegations", "suspended");
+            }
+            Ordering::Greater if delegation_deferred => {
+                // same as above, until the reinvest that delegates everything accumulated so far
+                REINVESTS_WITHOUT_DELEGATION.save(deps.storage, &reinvests_without_delegation)?;
+                status = ReinvestStatus::Deferred;
+                resp = resp.add_legacy_attribute("delegations", "deferred");
+            }
+            Ordering::Greater => {
+                // we have enough to pay all claims
+                // delegate the surplus to the validators according to their weight
+                // (funds in the unbonding queue cannot be delegated, so if more is unbonding than required,
+                // we can only delegate our balance)
+                let surplus = std::cmp::min(claim_coverage - required_liquidity, balance);
+
+                // calculate how much each validator gets
+                let valset_bonded: Uint128 = stake_info
+                    .validators
+                    .iter()
+                    .filter_map(|(addr, _)| bonded.get(addr))
+                    .sum();
+                let weights: Vec<_> = stake_info.validators.iter().map(|(_, w)| *w).collect();
+                // a cap limits what is delegated in total, so only the room left under it can be used.
+                // Validators on probation get at most a small part of the surplus on top of that.
+                let probation_cap = surplus.mul_floor(config.probation_max_weight);
+                let caps = stake_info
+                    .validators
+                    .iter()
+                    .map(|(addr, _)| {
+                        let room = stake_info.max_delegation(addr).map(|cap| {
+                            cap.saturating_sub(bonded.get(addr).copied().unwrap_or_default())
+                        });
+                        let probation = on_probation(
+                            deps.storage,
+                            &config,
+                            env.block.time.seconds(),
+                            &stake_info.validators,
+                            addr,
+                        )?;
+                        Ok(match room {
+                            Some(room) if probation => Some(room.min(probation_cap)),
+                            None if probation => Some(probation_cap),
+                            room => room,
+                        })
+                    })
+                    .collect::<StdResult<Vec<_>>>()?;
+                let mut val_payments: Vec<_> = match config.surplus_strategy {
+                    SurplusStrategy::ProportionalToBonded if !valset_bonded.is_zero() => stake_info
+                        .validators
+                        .into_iter()
+                        .map(|(addr, _)| {
+                            let current = bonded.get(&addr).copied().unwrap_or_default();
+                            (addr, surplus.multiply_ratio(current, valset_bonded))
+                        })
+                        .collect(),
+                    // with nothing bonded to the validator set yet, there are no proportions to keep
+                    _ => stake_info
+                        .validators
+                        .into_iter()
+                        .map(|(addr, weight)| (addr, surplus.mul_floor(weight)))
+                        .collect(),
+                };
+
+                // calculate how much is rounded off when multiplying by the weight
+                let remainder = surplus - val_payments.iter().map(|(_, amt)| amt).sum::<Uint128>();
+                // first validator gets this on top
+                val_payments[0].1 += remainder;
+
+                // whatever does not fit under the caps stays in the balance until the next reinvest
+                let mut amounts: Vec<_> = val_payments.iter().map(|(_, amt)| *amt).collect();
+                spill_over_caps(&mut amounts, &weights, &caps);
+                for ((_, payment), amount) in val_payments.iter_mut().zip(amounts) {
+                    *payment = amount;
+                }
+                // the chain might reject smaller delegations, the dust stays in the balance as well
+                for (_, payment) in val_payments.iter_mut() {
+                    if *payment < config.min_delegation {
+                        *payment = Uint128::zero();
+                    }
+                }
+
+                // update bonded
+                for (address, amount) in &val_payments {
+                    match bonded.get_mut(address) {
+                        Some(bonded) => *bonded += amount,
+                        None => {
+                            bonded.insert(address.clone(), *amount);
+                        }
+                    }
+                }
+                REINVESTS_WITHOUT_DELEGATION.remove(deps.storage);
+                change_reason = Some(BondedChangeReason::Delegate);
+                status = ReinvestStatus::Delegated;
+                amount_changed = val_payments.iter().map(|(_, amount)| amount).sum();
+                // create the messages
+                resp = resp.add_messages(
+                    val_payments
+                        .into_iter()
+                        .filter(|(_, amount)| !amount.is_zero())
+                        .map(|(address, amount)| StakingMsg::Delegate {
+                            validator: address,
+                            amount: Coin {
+                                amount,
+                                denom: supply.bond_denom.clone(),
+                            },
+                        }),
+                );
+            }
+            Ordering::Less => {
+                // unbondings that completed in this block were already removed from `total_unbonding`,
+                // but their tokens only arrive at the end of the block, so they are not part of the balance yet.
+                // We must not undelegate those again. If they did arrive already, we undelegate the rest
+                // in the next unbonding epoch instead.
+                let missing_liquidity = (required_liquidity - claim_coverage)
+                    .saturating_sub(released_in_block(deps.storage, &env)?);
+
+                // only execute this at most `config.max_concurrent_unbondings` times per unbonding period,
+                // in order to avoid hitting the unbonding queue limit
+                if !missing_liquidity.is_zero() && config.next_unbond_after(&env).is_ok() {
+                    CONFIG.save(deps.storage, &config)?;
+
+                    // undelegate the difference from the validators according to their weight
+
+                    // calculate how much each validator gets
+                    let mut val_payments: Vec<_> = stake_info
+                        .validators
+                        .into_iter()
+                        .map(|(addr, weight)| (addr, missing_liquidity.mul_floor(weight)))
+                        .collect();
+
+                    // calculate how much is rounded off when multiplying by the weight
+                    let mut remainder = missing_liquidity
+                        - val_payments.iter().map(|(_, amt)| amt).sum::<Uint128>();
+                    // take the remainder from the first validators that have enough stake
+                    for (address, amount) in val_payments.iter_mut() {
+                        if remainder.is_zero() {
+                            break;
+                        }
+
+                        // if we have a remainder, add as much of it to the unbond amount as possible
+                        let new_amount = std::cmp::min(*amount + remainder, bonded[address]);
+                        // subtract the amount we added from the remainder
+                        remainder -= new_amount - *amount;
+                        *amount = new_amount;
+                    }
+
+                    change_reason = Some(BondedChangeReason::Undelegate);
+                    // update bonded
+                    for (address, amount) in &val_payments {
+                        *bonded
+                            .get_mut(address)
+                            .expect("tried to undelegate non-existent stake") -= amount;
+                    }
+
+                    // store the unbondings
+                    let unbondings: Vec<_> = val_payments
+                        .into_iter()
+                        .filter(|(_, amt)| !amt.is_zero())
+                        .map(|(validator, amount)| Unbonding {
+                            validator,
+                            amount,
+                            started_at: env.block.time.seconds(),
+                        })
+                        .collect();
+                    let unbond_time = env.block.time.plus_seconds(config.unbond_period);
+                    save_unbondings(deps.storage, unbond_time.seconds(), &unbondings)?;
+                    UNDELEGATIONS.save(
+                        deps.storage,
+                        env.block.time.seconds(),
+                        &unbond_time.seconds(),
+                    )?;
+                    // the claims waiting for this unbonding are released at the earliest then
+                    unbonding_release_at = unbond_time.seconds();
+                    resp = resp.add_legacy_attribute(
+                        "unbonding_release_at",
+                        unbond_time.seconds().to_string(),
+                    );
+
+                    // update total_unbonding
+                    let total_unbonded: Uint128 = unbondings.iter().map(|u| u.amount).sum();
+                    supply.total_unbonding += total_unbonded;
+                    status = ReinvestStatus::Undelegated;
+                    amount_changed = total_unbonded;
+
+                    // generate the messages
+                    let messages: Vec<_> = unbondings
+                        .into_iter()
+                        .map(
+                            |Unbonding {
+                                 validator, amount, ..
+                             }| StakingMsg::Undelegate {
+                                validator,
+                                amount: Coin {
+                                    amount,
+                                    denom: supply.bond_denom.clone(),
+                                },
+                            },
+                        )
+                        .collect();
+
+                    resp = resp.add_messages(messages);
+                }
+            }
+            _ => {}
+        }
+
+        // nudge the delegations toward the weights, this does not change the total bonded.
+        // The intermediates of pending hops can not redelegate before their incoming redelegation completed
+        if config.rebalance_on_reinvest
+            && !config.delegations_suspended
+            && !REDELEGATION_HOPS.exists(deps.storage)
+        {
+            supply.total_bonded = bonded.values().sum();
+            resp = resp.add_messages(rebalance_redelegations(
+                deps.storage,
+                &env,
+                &config,
+                &supply,
+                &mut bonded,
+            )?);
+        }
+
+        // update how much is bonded
+        let new_balances = bonded.into_iter().filter(|(_, b)| !b.is_zero()).collect();
+        BONDED.save(deps.storage, &new_balances)?;
+        supply.total_bonded = new_balances.iter().map(|(_, v)| *v).sum();
+        SUPPLY.save(deps.storage, &supply)?;
+        if let Some(reason) = change_reason {
+            record_bonded_change(
+                deps.storage,
+                &env,
+                reason,
+                old_total_bonded,
+                supply.total_bonded,
+            )?;
+        }
+
+        Ok(resp.add_event(events::reinvest(
+            status,
+            rewards,
+            exchange_rate,
+            amount_changed,
+            unbonding_release_at,
+        )))
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    use QueryMsg::*;
+    match msg {
+        Config {} => to_json_binary(&query::config(deps)?),
+        Claims {
+            address,
+            start_after,
+            limit,
+            status,
+        } => to_json_binary(&query::claims(
+            deps,
+            env,
+            address,
+            start_after,
+            limit,
+            status.unwrap_or_default(),
+        )?),
+        DeferredClaims {
+            address,
+            start_after,
+            limit,
+        } => to_json_binary(&DEFERRED_CLAIMS.query_claims(
+            deps,
+            &deps.api.addr_validate(&address)?,
+            start_after,
+            limit,
+        )?),
+        ValidatorSet {} => to_json_binary(&ValidatorSetResponse {
+            validator_set: STAKE_INFO.load(deps.storage)?.validators,
+        }),
+        ValidatorSetWithBonded {} => to_json_binary(&query::validator_set_with_bonded(deps)?),
+        LastReinvest {} => to_json_binary(&query::last_reinvest(deps)?),
+        ReinvestHistory {} => to_json_binary(&query::reinvest_history(deps)?),
+        RewardRate {} => to_json_binary(&query::reward_rate(deps)?),
+        Supply {} => to_json_binary(&query::supply(deps, env)?),
+        ExchangeRate {} => to_json_binary(&query::exchange_rate(deps, env)?),
+        ExchangeRateRaw {} => to_json_binary(&query::exchange_rate_raw(deps, env)?),
+        ExchangeRateAt { time } => to_json_binary(&query::exchange_rate_at(deps, time)?),
+        TargetValue {} => to_json_binary(&query::target_value(deps, env)?),
+        CanUnbond {} => to_json_binary(&query::can_unbond(deps)?),
+        CheckSlashAvailable {} => to_json_binary(&query::check_slash_available(deps, env)?),
+        SlashingEvents {} => to_json_binary(&query::slashing_events(deps)?),
+        ClaimsIntegrity { continuation } => {
+            to_json_binary(&query::claims_integrity(deps, continuation)?)
+        }
+        AssetInfo {} => to_json_binary(&query::asset_info(deps, env)?),
+        MaxConcurrentUnbondings {} => to_json_binary(&query::max_concurrent_unbondings(deps)?),
+        PreviewCommission {} => to_json_binary(&query::preview_commission(deps, env)?),
+        BondedJournal { start_after, limit } => {
+            to_json_binary(&query::bonded_journal(deps, start_after, limit)?)
+        }
+        CommissionVesting {} => to_json_binary(&query::commission_vesting(deps, env)?),
+        ValueChange { address, since } => {
+            to_json_binary(&query::value_change(deps, env, address, since)?)
+        }
+        Health {} => to_json_binary(&query::health(deps, env)?),
+        IssuedReconciliation {} => to_json_binary(&query::issued_reconciliation(deps)?),
+        PreviewValsetChange { new_validators } => to_json_binary(&query::preview_valset_change(
+            deps,
+            env,
+            new_validators.into_iter().map(Into::into).collect(),
+        )?),
+        SimulateValsetChange { new_validators } => to_json_binary(&query::simulate_valset_change(
+            deps,
+            env,
+            new_validators.into_iter().map(Into::into).collect(),
+        )?),
+        ExternalRewards { address } => to_json_binary(&query::external_rewards(deps, address)?),
+        ValidatorCommissions {} => to_json_binary(&query::validator_commissions(deps)?),
+        TombstonedValidators {} => to_json_binary(&query::tombstoned_validators(deps)?),
+        AccruedButUnbondedValue {} => {
+            to_json_binary(&query::accrued_but_unbonded_value(deps, env)?)
+        }
+        VoucherBatches {} => to_json_binary(&query::voucher_batches(deps, env)?),
+        PendingValsetChange {} => to_json_binary(&query::pending_valset_change(deps, env)?),
+        Dashboard { address } => to_json_binary(&query::dashboard(deps, env, address)?),
+        SimulateBond { amount } => to_json_binary(&query::simulate_bond(deps, env, amount)?),
+        SharesToRedeem { native } => to_json_binary(&query::shares_to_redeem(deps, env, native)?),
+        BootstrapStatus {} => to_json_binary(&query::bootstrap_status(deps)?),
+        BufferUtilization {} => to_json_binary(&query::buffer_utilization(deps, env)?),
+    }
+}
+
+pub mod query {
+    use crate::claim::{
+        claims_checkpoint, Claim, ClaimEstimate, ClaimProgress, ClaimStatus, ClaimsResponse,
+    };
+    use crate::msg::{
+        AccruedButUnbondedValueResponse, AssetInfoResponse, BondedJournalResponse,
+        BootstrapStatusResponse, BufferUtilizationResponse, CanUnbondResponse,
+        CheckSlashAvailableResponse, ClaimsIntegrityResponse, ClaimsScanContinuation,
+        CommissionVestingResponse, DashboardAccount, DashboardResponse, ExchangeRateAtResponse,
+        ExchangeRateRawResponse, ExchangeRateResponse, ExternalRewardResponse,
+        ExternalRewardsResponse, HealthResponse, IssuedReconciliationResponse,
+        MaxConcurrentUnbondingsResponse, PendingValsetChangeResponse, PreviewCommissionResponse,
+        PreviewValsetChangeResponse, Redelegation, RedelegationPair, ReinvestHistoryResponse,
+        ReinvestResponse, RewardRateResponse, SharesToRedeemResponse, SimulateBondResponse,
+        SimulateValsetChangeResponse, SlashingEventsResponse, SupplyResponse, TargetValueResponse,
+        TombstonedValidatorsResponse, ValidatorCommissionsResponse, ValidatorSetWithBondedResponse,
+        ValidatorWithBonded, ValueChangeResponse, VestingTrancheResponse, VoucherBatchesResponse,
+    };
+    use crate::pagination::paginate_map;
+    use crate::state::{
+        average_reinvest_spacing, count_unbonding, fresh_shares, latest_rate_snapshot,
+        next_slash_check, pending_redelegations, range_unbondings, rate_snapshot_at,
+        released_in_block, released_vouchers, voucher_claims, CleanedSupply, Slashing,
+        BONDED_JOURNAL, COMMISSION_VESTING, EXTERNAL_REWARDS, PENDING_VALSET, RATE_SNAPSHOTS,
+        REINVEST_HISTORY, TOMBSTONED_VALIDATORS, UNDELEGATIONS, VALIDATOR_COMMISSIONS,
+        VOUCHER_BATCHES,
+    };
+    use crate::valset::ValsetChange;
+    use cosmwasm_std::{Coin, Order, StakingMsg, Timestamp};
+    use cw_storage_plus::Bound;
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    pub fn config(deps: Deps) -> StdResult<ConfigResponse> {
+        let config = CONFIG.load(deps.storage)?;
+        let cw20::TokenInfoResponse { decimals, .. } = deps
+            .querier
+            .query_wasm_smart(&config.token_contract, &cw20::Cw20QueryMsg::TokenInfo {})?;
+        let resp: ConfigResponse = ConfigResponse {
+            owner: config.owner,
+            token_contract: config.token_contract,
+            treasury: config.treasury,
+            commission: config.commission,
+            epoch_period: config.epoch_period,
+            unbond_period: config.unbond_period,
+            surplus_strategy: config.surplus_strategy,
+            delegations_suspended: config.delegations_suspended,
+            reinvest_hook: config.reinvest_hook,
+            new_validator_probation: config.new_validator_probation,
+            probation_max_weight: config.probation_max_weight,
+            bond_denom_decimals: config.bond_denom_decimals,
+            token_decimals: decimals,
+            expected_bond_decimals: config.expected_bond_decimals,
+            max_commission_increase_alert: config.max_commission_increase_alert,
+            auto_zero_weight_on_spike: config.auto_zero_weight_on_spike,
+            delegate_every_n_epochs: config.delegate_every_n_epochs,
+            strict_supply_check: config.strict_supply_check,
+            unbond_fee: config.unbond_fee,
+            issuance_cap: config.issuance_cap,
+            voucher_contract: config.voucher_contract,
+            rebalance_on_reinvest: config.rebalance_on_reinvest,
+            operator: config.operator,
+            redelegation_hops: config.redelegation_hops,
+            max_rate_growth_per_epoch: config.max_rate_growth_per_epoch,
+            min_delegation: config.min_delegation,
+            valoper_prefix: config.valoper_prefix,
+        };
+        Ok(resp)
+    }
+
+    pub fn last_reinvest(deps: Deps) -> StdResult<ReinvestResponse> {
+        Ok(ReinvestResponse {
+            last_reinvest: crate::state::last_reinvest(deps.storage)?.unwrap_or_default(),
+            next_reinvest: CONFIG.load(deps.storage)?.next_epoch,
+        })
+    }
+
+    pub fn validator_set_with_bonded(deps: Deps) -> StdResult<ValidatorSetWithBondedResponse> {
+        let targets = STAKE_INFO.load(deps.storage)?.validators;
+        let bonded = BONDED.load(deps.storage)?;
+        let total_bonded: Uint128 = bonded.iter().map(|(_, amount)| *amount).sum();
+
+        let bonded_to = |validator: &str| {
+            bonded
+                .iter()
+                .find(|(v, _)| v == validator)
+                .map(|(_, amount)| *amount)
+                .unwrap_or_default()
+        };
+        let actual_weight = |amount: Uint128| {
+            if total_bonded.is_zero() {
+                Decimal::zero()
+            } else {
+                Decimal::from_ratio(amount, total_bonded)
+            }
+        };
+
+        // the validator set first, then removed validators that still have tokens bonded
+        let removed = bonded
+            .iter()
+            .filter(|(v, _)| !targets.iter().any(|(t, _)| t == v))
+            .map(|(v, _)| (v.clone(), Decimal::zero()));
+        let validators = targets
+            .iter()
+            .cloned()
+            .chain(removed)
+            .map(|(validator, target_weight)| {
+                let bonded = bonded_to(&validator);
+                ValidatorWithBonded {
+                    validator,
+                    target_weight,
+                    bonded,
+                    actual_weight: actual_weight(bonded),
+                }
+            })
+            .collect();
+
+        Ok(ValidatorSetWithBondedResponse { validators })
+    }
+
+    pub fn reinvest_history(deps: Deps) -> StdResult<ReinvestHistoryResponse> {
+        let reinvests = REINVEST_HISTORY.may_load(deps.storage)?.unwrap_or_default();
+        Ok(ReinvestHistoryResponse {
+            average_spacing: average_reinvest_spacing(&reinvests),
+            reinvests,
+            min_reinvest_spacing: CONFIG.load(deps.storage)?.min_reinvest_spacing,
+        })
+    }
+
+    pub fn reward_rate(deps: Deps) -> StdResult<RewardRateResponse> {
+        const YEAR: u64 = 365 * 24 * 60 * 60;
+
+        let reinvests = REINVEST_HISTORY.may_load(deps.storage)?.unwrap_or_default();
+        let (from, to) = match reinvests.as_slice() {
+            [.., from, to] if to > from => (*from, *to),
+            _ => return Ok(RewardRateResponse::default()),
+        };
+        // the snapshots are taken in the same block as the reinvest
+        let (then, now) = match (
+            RATE_SNAPSHOTS.may_load(deps.storage, from)?,
+            RATE_SNAPSHOTS.may_load(deps.storage, to)?,
+        ) {
+            (Some(then), Some(now)) => (then, now),
+            _ => return Ok(RewardRateResponse::default()),
+        };
+
+        // same as in `value_change`, remove the effect of the slashings in between
+        let slash_multiplier = ratio(now.slash_factor, then.slash_factor);
+        let growth = ratio(ratio(now.rate, then.rate), slash_multiplier);
+        let apr = growth.saturating_sub(Decimal::one()) * Decimal::from_ratio(YEAR, to - from);
+
+        Ok(RewardRateResponse { apr, from, to })
+    }
+
+    pub fn exchange_rate(deps: Deps, env: Env) -> StdResult<ExchangeRateResponse> {
+        let supply = CleanedSupply::load_for_query(deps.storage, &env)?;
+        let exchange_rate = supply.tokens_per_share(supply.balance(deps, &env)?);
+
+        Ok(ExchangeRateResponse { exchange_rate })
+    }
+
+    pub fn exchange_rate_raw(deps: Deps, env: Env) -> StdResult<ExchangeRateRawResponse> {
+        let supply = CleanedSupply::load_for_query(deps.storage, &env)?;
+        let assets = supply.assets(supply.balance(deps, &env)?);
+
+        Ok(ExchangeRateRawResponse {
+            assets,
+            issued: supply.issued,
+        })
+    }
+
+    pub fn exchange_rate_at(deps: Deps, time: u64) -> StdResult<ExchangeRateAtResponse> {
+        let (snapshot_time, snapshot) = rate_snapshot_at(deps.storage, time)?;
+
+        Ok(ExchangeRateAtResponse {
+            exchange_rate: snapshot.rate,
+            snapshot_time,
+        })
+    }
+
+    pub fn value_change(
+        deps: Deps,
+        env: Env,
+        address: String,
+        since: u64,
+    ) -> StdResult<ValueChangeResponse> {
+        let address = deps.api.addr_validate(&address)?;
+
+        let (_, then) = rate_snapshot_at(deps.storage, since)?;
+        // there is at least one snapshot, otherwise we would have returned above
+        let slash_factor = latest_rate_snapshot(deps.storage)?
+            .map(|snapshot| snapshot.slash_factor)
+            .unwrap_or(then.slash_factor);
+        let rate_now = exchange_rate(deps, env)?.exchange_rate;
+
+        let config = CONFIG.load(deps.storage)?;
+        let cw20::BalanceResponse {
+            balance: lsd_balance,
+        } = deps.querier.query_wasm_smart(
+            &config.token_contract,
+            &cw20::Cw20QueryMsg::Balance {
+                address: address.to_string(),
+            },
+        )?;
+
+        // `slash_factor` only ever decreases, so this is at most 1
+        let slash_multiplier = ratio(slash_factor, then.slash_factor);
+        // the rate change that is not explained by slashings must come from rewards
+        let reward_multiplier = ratio(ratio(rate_now, then.rate), slash_multiplier);
+
+        Ok(ValueChangeResponse {
+            lsd_balance,
+            value_then: lsd_balance.mul_floor(then.rate),
+            value_now: lsd_balance.mul_floor(rate_now),
+            slash_impact: Decimal::one().saturating_sub(slash_multiplier),
+            reward_growth: reward_multiplier.saturating_sub(Decimal::one()),
+        })
+    }
+
+    /// The messages `execute::set_validators` would currently execute and the resulting delegations
+    fn valset_change(
+        deps: Deps,
+        env: &Env,
+        new_validators: Vec<ValidatorConfig>,
+    ) -> StdResult<ValsetChange> {
+        let (new_validators, max_delegations) = split_validator_configs(new_validators);
+        redelegations_to(deps, env, &new_validators, &max_delegations)
+    }
+
+    /// Same as in `execute::execute_valset_stage`, but without saving anything
+    fn redelegations_to(
+        deps: Deps,
+        env: &Env,
+        new_validators: &[(String, Decimal)],
+        max_delegations: &[(String, Uint128)],
+    ) -> StdResult<ValsetChange> {
+        let supply = CleanedSupply::load_for_query(deps.storage, env)?;
+        let bonded = BONDED.load(deps.storage)?;
+        if supply.total_bonded.is_zero() {
+            return Ok(ValsetChange {
+                messages: vec![],
+                new_balances: bonded,
+            });
+        }
+        let caps = redelegation_caps(
+            deps.storage,
+            &CONFIG.load(deps.storage)?,
+            env.block.time.seconds(),
+            &STAKE_INFO.load(deps.storage)?.validators,
+            new_validators,
+            max_delegations,
+            supply.total_bonded,
+        )?;
+
+        valset_change_redelegation_messages(
+            &supply,
+            bonded.iter().map(|(k, v)| (k, *v)),
+            new_validators.iter().map(|(k, v)| (k, *v)),
+            &caps,
+        )
+        .map_err(|err| StdError::generic_err(err.to_string()))
+    }
+
+    fn redelegations(messages: &[StakingMsg]) -> Vec<Redelegation> {
+        messages
+            .iter()
+            .filter_map(|msg| match msg {
+                StakingMsg::Redelegate {
+                    src_validator,
+                    dst_validator,
+                    amount,
+                } => Some(Redelegation {
+                    src: src_validator.clone(),
+                    dst: dst_validator.clone(),
+                    amount: amount.amount,
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn preview_valset_change(
+        deps: Deps,
+        env: Env,
+        new_validators: Vec<ValidatorConfig>,
+    ) -> StdResult<PreviewValsetChangeResponse> {
+        let ValsetChange {
+            messages,
+            new_balances,
+        } = valset_change(deps, &env, new_validators)?;
+
+        Ok(PreviewValsetChangeResponse {
+            redelegations: redelegations(&messages),
+            new_balances,
+        })
+    }
+
+    pub fn simulate_valset_change(
+        deps: Deps,
+        env: Env,
+        new_validators: Vec<ValidatorConfig>,
+    ) -> StdResult<SimulateValsetChangeResponse> {
+        let ValsetChange {
+            messages,
+            new_balances,
+        } = valset_change(deps, &env, new_validators)?;
+        let redelegations = redelegations(&messages);
+
+        let mut counts = BTreeMap::<(&str, &str), u32>::new();
+        for redelegation in &redelegations {
+            *counts
+                .entry((&redelegation.src, &redelegation.dst))
+                .or_default() += 1;
+        }
+        let now = env.block.time.seconds();
+        let pairs = counts
+            .into_iter()
+            .map(|((src, dst), redelegations)| {
+                let pending = pending_redelegations(deps.storage, src, dst, now)?;
+                Ok(RedelegationPair {
+                    src: src.to_string(),
+                    dst: dst.to_string(),
+                    redelegations,
+                    pending,
+                    exceeds_entry_cap: pending + redelegations > MAX_REDELEGATION_ENTRIES,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(SimulateValsetChangeResponse {
+            message_count: messages.len() as u32,
+            exceeds_entry_cap: pairs.iter().any(|p| p.exceeds_entry_cap),
+            pairs,
+            redelegations,
+            new_balances,
+        })
+    }
+
+    pub fn pending_valset_change(deps: Deps, env: Env) -> StdResult<PendingValsetChangeResponse> {
+        let hops = REDELEGATION_HOPS
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        let Some(target) = PENDING_VALSET.may_load(deps.storage)? else {
+            return Ok(PendingValsetChangeResponse {
+                target: None,
+                remaining: vec![],
+                ready_at: None,
+                hops,
+            });
+        };
+        let stake_info = STAKE_INFO.load(deps.storage)?;
+        let ValsetChange { messages, .. } =
+            redelegations_to(deps, &env, &target, &stake_info.max_delegations)?;
+        let remaining = redelegations(&messages);
+
+        let now = env.block.time.seconds();
+        let stage = split_by_free_entries(deps.storage, messages, now)?;
+        let ready_at = match stage.ready_at {
+            Some(ready_at) if stage.ready.is_empty() => ready_at,
+            // `ExecuteMsg::ContinueValsetChange` can execute some of them right away, or clear the finished change
+            _ => now,
+        };
+
+        Ok(PendingValsetChangeResponse {
+            target: Some(target),
+            remaining,
+            ready_at: Some(ready_at),
+            hops,
+        })
+    }
+
+    pub fn health(deps: Deps, env: Env) -> StdResult<HealthResponse> {
+        let supply = SUPPLY.load(deps.storage)?;
+
+        let bonded: Uint128 = BONDED
+            .load(deps.storage)?
+            .into_iter()
+            .map(|(_, amount)| amount)
+            .sum();
+        let unbonding = range_unbondings(deps.storage, None, None, Order::Ascending)
+            .map(|r| r.map(|(_, ubs)| ubs.into_iter().map(|ub| ub.amount).sum::<Uint128>()))
+            .sum::<StdResult<Uint128>>()?;
+
+        let config = CONFIG.load(deps.storage)?;
+        let total_supply = config.token_total_supply(deps)?;
+
+        let cleaned = CleanedSupply::load_for_query(deps.storage, &env)?;
+        let balance = cleaned.balance(deps, &env)?;
+        let coverage = cleaned.total_bonded + cleaned.total_unbonding + balance;
+
+        Ok(HealthResponse {
+            bonded_sum_matches_total_bonded: bonded == supply.total_bonded,
+            issued_matches_cw20_supply: supply.issued == total_supply,
+            claims_backed_by_coverage: cleaned.claims <= coverage,
+            total_unbonding_matches_unbonding_map: unbonding == supply.total_unbonding,
+        })
+    }
+
+    pub fn issued_reconciliation(deps: Deps) -> StdResult<IssuedReconciliationResponse> {
+        let issued_counter = SUPPLY.load(deps.storage)?.issued;
+        let cw20_total_supply = CONFIG.load(deps.storage)?.token_total_supply(deps)?;
+
+        Ok(IssuedReconciliationResponse {
+            issued_counter,
+            cw20_total_supply,
+            diff: issued_counter.abs_diff(cw20_total_supply),
+        })
+    }
+
+    /// Returns `a / b`, or zero if `b` is zero
+    fn ratio(a: Decimal, b: Decimal) -> Decimal {
+        if b.is_zero() {
+            Decimal::zero()
+        } else {
+            a / b
+        }
+    }
+
+    pub fn target_value(deps: Deps, env: Env) -> StdResult<TargetValueResponse> {
+        let supply = CleanedSupply::load_for_query(deps.storage, &env)?;
+        let exchange_rate = supply.tokens_per_share(supply.balance(deps, &env)?);
+        let target_value = CONFIG.load(deps.storage)?.target_value(exchange_rate);
+
+        Ok(TargetValueResponse { target_value })
+    }
+
+    pub fn asset_info(deps: Deps, env: Env) -> StdResult<AssetInfoResponse> {
+        let supply = CleanedSupply::load_for_query(deps.storage, &env)?;
+        let exchange_rate = supply.tokens_per_share(supply.balance(deps, &env)?);
+        let config = CONFIG.load(deps.storage)?;
+
+        Ok(AssetInfoResponse {
+            target_value: config.target_value(exchange_rate),
+            bond_denom: supply.bond_denom.clone(),
+            lsd_token: config.token_contract,
+            exchange_rate,
+        })
+    }
+
+    pub fn max_concurrent_unbondings(deps: Deps) -> StdResult<MaxConcurrentUnbondingsResponse> {
+        let config = CONFIG.load(deps.storage)?;
+        Ok(MaxConcurrentUnbondingsResponse {
+            max_concurrent_unbondings: config.max_concurrent_unbondings,
+            unbond_epoch_seconds: config.unbond_epoch(),
+            // same as in `execute::unbond`, we never unbond before the next epoch
+            next_unbond: std::cmp::max(config.next_unbond, config.next_epoch),
+            unbond_period: config.unbond_period,
+        })
+    }
+
+    pub fn preview_commission(deps: Deps, env: Env) -> StdResult<PreviewCommissionResponse> {
+        let supply = SUPPLY.load(deps.storage)?;
+        let config = CONFIG.load(deps.storage)?;
+
+        // same as `execute::reinvest`, we only withdraw from validators with non-zero weight
+        // and skip withdrawing if nothing was bonded yet
+        let mut gross_rewards = Uint128::zero();
+        if !supply.total_bonded.is_zero() {
+            for (validator, weight) in STAKE_INFO.load(deps.storage)?.validators {
+                if weight.is_zero() {
+                    continue;
+                }
+                if let Some(delegation) = deps
+                    .querier
+                    .query_delegation(&env.contract.address, validator)?
+                {
+                    gross_rewards += delegation
+                        .accumulated_rewards
+                        .into_iter()
+                        .filter(|c| c.denom == supply.bond_denom)
+                        .map(|c| c.amount)
+                        .sum::<Uint128>();
+                }
+            }
+        }
+
+        // same calculation as in `reply::after_withdraw_rewards`
+        let commission = gross_rewards.mul_floor(config.commission);
+        Ok(PreviewCommissionResponse {
+            gross_rewards,
+            commission,
+            net_rewards: gross_rewards - commission,
+        })
+    }
+
+    pub fn accrued_but_unbonded_value(
+        deps: Deps,
+        env: Env,
+    ) -> StdResult<AccruedButUnbondedValueResponse> {
+        let supply = CleanedSupply::load_for_query(deps.storage, &env)?;
+        let balance = supply.balance(deps, &env)?;
+        // the claims that are not covered by the unbondings and the balance are undelegated with the next unbond
+        let still_delegated = supply
+            .claims
+            .saturating_sub(supply.total_unbonding + balance)
+            .min(supply.total_bonded);
+
+        let accrued_rewards = if still_delegated.is_zero() {
+            Uint128::zero()
+        } else {
+            preview_commission(deps, env)?
+                .net_rewards
+                .multiply_ratio(still_delegated, supply.total_bonded)
+        };
+
+        let config = CONFIG.load(deps.storage)?;
+        Ok(AccruedButUnbondedValueResponse {
+            still_delegated,
+            accrued_rewards,
+            next_unbond: std::cmp::max(config.next_unbond, config.next_epoch),
+        })
+    }
+
+    pub fn commission_vesting(deps: Deps, env: Env) -> StdResult<CommissionVestingResponse> {
+        let now = env.block.time.seconds();
+        let tranches = COMMISSION_VESTING
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|r| {
+                let (created, tranche) = r?;
+                let vested = tranche.vested(created, now);
+                Ok(VestingTrancheResponse {
+                    created,
+                    amount: tranche.amount,
+                    claimed: tranche.claimed,
+                    vested,
+                    unvested: tranche.amount - vested,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        let claimable: Uint128 = tranches.iter().map(|t| t.vested - t.claimed).sum();
+
+        Ok(CommissionVestingResponse {
+            commission_vesting: CONFIG.load(deps.storage)?.commission_vesting,
+            tranches,
+            claimable,
+        })
+    }
+
+    pub fn validator_commissions(deps: Deps) -> StdResult<ValidatorCommissionsResponse> {
+        let commissions = VALIDATOR_COMMISSIONS
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()?;
+        Ok(ValidatorCommissionsResponse { commissions })
+    }
+
+    pub fn claims(
+        deps: Deps,
+        env: Env,
+        address: String,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+        status: ClaimStatus,
+    ) -> StdResult<ClaimsResponse> {
+        let mut response = CLAIMS.query_claims_where(
+            deps,
+            &deps.api.addr_validate(&address)?,
+            |claim| status.matches(claim, &env.block),
+            start_after,
+            limit,
+        )?;
+
+        let config = CONFIG.load(deps.storage)?;
+        let balance = SUPPLY.load(deps.storage)?.balance(deps, &env)?;
+        let slashings = SLASHINGS.may_load(deps.storage)?.unwrap_or_default();
+        response.estimates = response
+            .claims
+            .iter()
+            .map(|claim| {
+                claim_estimate(
+                    deps.storage,
+                    &config,
+                    env.block.time.seconds(),
+                    balance,
+                    &slashings,
+                    claim,
+                )
+            })
+            .collect::<StdResult<_>>()?;
+        Ok(response)
+    }
 
-        // send commission to the treasury
-        let rewards = balance - TMP_STATE.load(deps.storage)?.balance;
-        let commission_amount = rewards.mul_floor(config.commission);
-        if !commission_amount.is_zero() {
-            balance -= commission_amount;
-            resp = resp.add_message(BankMsg::Send {
-                to_address: config.treasury.to_string(),
-                amount: coins(commission_amount.u128(), &supply.bond_denom),
+    /// Derives where `claim` is in its lifecycle from the unbonding batches and the balance of the hub
+    fn claim_estimate(
+        storage: &dyn Storage,
+        config: &Config,
+        now: u64,
+        balance: Uint128,
+        slashings: &[Slashing],
+        claim: &Claim,
+    ) -> StdResult<ClaimEstimate> {
+        let release_at = claim.release_at.seconds();
+        if release_at <= now && claim.slashed_amount(slashings) <= balance {
+            return Ok(ClaimEstimate {
+                estimated_available_at: claim.release_at,
+                status: ClaimProgress::Payable,
             });
         }
 
-        let mut bonded = BONDED
-            .load(deps.storage)?
-            .into_iter()
-            .collect::<BTreeMap<_, _>>();
+        // the claim is backed by the first undelegation at or after its unbonding epoch.
+        // Claims without one were created with a release time of one unbonding period after it.
+        let unbond_epoch = claim
+            .unbond_epoch
+            .unwrap_or_else(|| release_at.saturating_sub(config.unbond_period));
+        let batch = UNDELEGATIONS
+            .range(
+                storage,
+                Some(Bound::inclusive(unbond_epoch)),
+                None,
+                Order::Ascending,
+            )
+            .next()
+            .transpose()?
+            .map(|(_, completes_at)| completes_at);
 
-        // this is the amount of assets we (will) have available to pay claims
-        let claim_coverage = balance + supply.total_unbonding;
+        let (status, available_at) = match batch {
+            Some(completes_at) if completes_at > now => {
+                (ClaimProgress::Unbonding, release_at.max(completes_at))
+            }
+            // the undelegated tokens are paid once they arrived and the other claims left enough
+            Some(_) => (ClaimProgress::Mature, release_at.max(now)),
+            // if the reinvest that undelegates is late, it is the next one
+            None => (
+                ClaimProgress::AwaitingUnbondEpoch,
+                release_at.max(config.next_undelegation().max(now) + config.unbond_period),
+            ),
+        };
+        Ok(ClaimEstimate {
+            estimated_available_at: Timestamp::from_seconds(available_at),
+            status,
+        })
+    }
 
-        let stake_info = STAKE_INFO.load(deps.storage)?;
-        match claim_coverage.cmp(&supply.claims) {
-            Ordering::Greater => {
-                // we have enough to pay all claims
-                // delegate the surplus to the validators according to their weight
-                let surplus = claim_coverage - supply.claims;
+    pub fn voucher_batches(deps: Deps, env: Env) -> StdResult<VoucherBatchesResponse> {
+        let batches = VOUCHER_BATCHES
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()?;
+        Ok(VoucherBatchesResponse {
+            batches,
+            redeemable: released_vouchers(deps.storage, env.block.time.seconds())?,
+        })
+    }
 
-                // calculate how much each validator gets
-                let mut val_payments: Vec<_> = stake_info
-                    .validators
-                    .into_iter()
-                    .map(|(addr, weight)| (addr, surplus.mul_floor(weight)))
-                    .collect();
+    pub fn tombstoned_validators(deps: Deps) -> StdResult<TombstonedValidatorsResponse> {
+        let validators = TOMBSTONED_VALIDATORS
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()?;
+        Ok(TombstonedValidatorsResponse { validators })
+    }
 
-                // calculate how much is rounded off when multiplying by the weight
-                let remainder = surplus - val_payments.iter().map(|(_, amt)| amt).sum::<Uint128>();
-                // first validator gets this on top
-                val_payments[0].1 += remainder;
+    pub fn external_rewards(
+        deps: Deps,
+        address: Option<String>,
+    ) -> StdResult<ExternalRewardsResponse> {
+        let rewards = EXTERNAL_REWARDS
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
 
-                // update bonded
-                for (address, amount) in &val_payments {
-                    match bonded.get_mut(address) {
-                        Some(bonded) => *bonded += amount,
-                        None => {
-                            bonded.insert(address.clone(), *amount);
-                        }
+        let entitlements = match address {
+            Some(address) => {
+                let address = deps.api.addr_validate(&address)?;
+                let config = CONFIG.load(deps.storage)?;
+                let cw20::BalanceResponse { balance } = deps.querier.query_wasm_smart(
+                    &config.token_contract,
+                    &cw20::Cw20QueryMsg::Balance {
+                        address: address.to_string(),
+                    },
+                )?;
+                // the tokens the address bonded after the registration are not entitled
+                let mut entitlements = vec![];
+                for (denom, reward) in &rewards {
+                    let fresh = fresh_shares(deps.storage, denom, reward, &address)?;
+                    let amount = reward.share_of(balance.saturating_sub(fresh));
+                    if !amount.is_zero() {
+                        entitlements.push(Coin {
+                            denom: denom.clone(),
+                            amount,
+                        });
                     }
                 }
-                // create the messages
-                resp = resp.add_messages(
-                    val_payments
-                        .into_iter()
-                        .filter(|(_, amount)| !amount.is_zero())
-                        .map(|(address, amount)| StakingMsg::Delegate {
-                            validator: address,
-                            amount: Coin {
-                                amount,
-                                denom: supply.bond_denom.clone(),
-                            },
-                        }),
-                );
+                entitlements
             }
-            Ordering::Less => {
-                // only execute this at most `config.max_concurrent_unbondings` times per unbonding period,
-                // in order to avoid hitting the unbonding queue limit
-                if config.next_unbond_after(&env).is_ok() {
-                    CONFIG.save(deps.storage, &config)?;
+            None => vec![],
+        };
 
-                    // undelegate the difference from the validators according to their weight
-                    let missing_liquidity = supply.claims - claim_coverage;
+        Ok(ExternalRewardsResponse {
+            rewards: rewards
+                .into_iter()
+                .map(|(denom, reward)| ExternalRewardResponse {
+                    denom,
+                    amount: reward.amount,
+                    shares: reward.shares,
+                })
+                .collect(),
+            entitlements,
+        })
+    }
 
-                    // calculate how much each validator gets
-                    let mut val_payments: Vec<_> = stake_info
-                        .validators
-                        .into_iter()
-                        .map(|(addr, weight)| (addr, missing_liquidity.mul_floor(weight)))
-                        .collect();
+    pub fn bonded_journal(
+        deps: Deps,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<BondedJournalResponse> {
+        let page = paginate_map(
+            &BONDED_JOURNAL,
+            deps.storage,
+            start_after,
+            limit,
+            |_, entry| Ok(entry),
+        )?;
+        Ok(BondedJournalResponse {
+            entries: page.items,
+            next_start_after: page.next_start_after,
+        })
+    }
 
-                    // calculate how much is rounded off when multiplying by the weight
-                    let mut remainder = missing_liquidity
-                        - val_payments.iter().map(|(_, amt)| amt).sum::<Uint128>();
-                    // take the remainder from the first validators that have enough stake
-                    for (address, amount) in val_payments.iter_mut() {
-                        if remainder.is_zero() {
-                            break;
-                        }
+    pub fn slashing_events(deps: Deps) -> StdResult<SlashingEventsResponse> {
+        Ok(SlashingEventsResponse {
+            slashings: SLASHINGS.may_load(deps.storage)?.unwrap_or_default(),
+        })
+    }
 
-                        // if we have a remainder, add as much of it to the unbond amount as possible
-                        let new_amount = std::cmp::min(*amount + remainder, bonded[address]);
-                        // subtract the amount we added from the remainder
-                        remainder -= new_amount - *amount;
-                        *amount = new_amount;
-                    }
+    /// How many addresses a single `ClaimsIntegrity` query scans
+    const CLAIMS_SCAN_LIMIT: usize = 100;
 
-                    // update bonded
-                    for (address, amount) in &val_payments {
-                        *bonded
-                            .get_mut(address)
-                            .expect("tried to undelegate non-existent stake") -= amount;
-                    }
+    pub fn claims_integrity(
+        deps: Deps,
+        continuation: Option<ClaimsScanContinuation>,
+    ) -> StdResult<ClaimsIntegrityResponse> {
+        let supply = SUPPLY.load(deps.storage)?;
+        let slashing_events = SLASHINGS.may_load(deps.storage)?.unwrap_or_default();
 
-                    // store the unbondings
-                    let unbondings: Vec<_> = val_payments
-                        .into_iter()
-                        .filter(|(_, amt)| !amt.is_zero())
-                        .map(|(validator, amount)| Unbonding { validator, amount })
-                        .collect();
-                    let unbond_time = env.block.time.plus_seconds(config.unbond_period);
-                    UNBONDING.save(deps.storage, unbond_time.seconds(), &unbondings)?;
+        let (start_after, previous_sum, previous_scanned) = match continuation {
+            Some(c) => (
+                Some(deps.api.addr_validate(&c.start_after)?),
+                c.computed_sum,
+                c.scanned_addresses,
+            ),
+            // the vouchers are claims as well, they are counted on the first page
+            None => (None, voucher_claims(deps.storage, &slashing_events)?, 0),
+        };
+        let (sum, scanned, last) =
+            CLAIMS.sum_claims(deps.storage, start_after.as_ref(), CLAIMS_SCAN_LIMIT, |c| {
+                c.slashed_amount(&slashing_events)
+            })?;
 
-                    // update total_unbonding
-                    let total_unbonded: Uint128 = unbondings.iter().map(|u| u.amount).sum();
-                    supply.total_unbonding += total_unbonded;
+        let computed_sum = previous_sum + sum;
+        let scanned_addresses = previous_scanned + scanned;
+        let scanned_until = last.or(start_after);
+        let complete = !CLAIMS.has_claims_after(deps.storage, scanned_until.as_ref());
+        let scanned_until = scanned_until.map(String::from);
+
+        Ok(ClaimsIntegrityResponse {
+            stored_counter: supply.claims,
+            computed_sum,
+            scanned_addresses,
+            complete,
+            continuation: (!complete).then(|| ClaimsScanContinuation {
+                // a scan is only incomplete if it scanned something
+                start_after: scanned_until.clone().unwrap_or_default(),
+                computed_sum,
+                scanned_addresses,
+            }),
+            checkpoint: complete
+                .then(|| claims_checkpoint(supply.claims, computed_sum, scanned_until.as_deref())),
+            scanned_until,
+        })
+    }
 
-                    // generate the messages
-                    let messages: Vec<_> = unbondings
-                        .into_iter()
-                        .map(|Unbonding { validator, amount }| StakingMsg::Undelegate {
-                            validator,
-                            amount: Coin {
-                                amount,
-                                denom: supply.bond_denom.clone(),
-                            },
-                        })
-                        .collect();
+    pub fn check_slash_available(deps: Deps, env: Env) -> StdResult<CheckSlashAvailableResponse> {
+        let config = CONFIG.load(deps.storage)?;
+        let now = env.block.time.seconds();
+        let next = next_slash_check(deps.storage, now, config.slashing_safety_margin)?;
 
-                    resp = resp.add_messages(messages);
-                }
-            }
-            _ => {}
-        }
+        Ok(CheckSlashAvailableResponse {
+            available: next == now,
+            next_available: (next != now).then_some(next),
+        })
+    }
 
-        // update how much is bonded
-        let new_balances = bonded.into_iter().filter(|(_, b)| !b.is_zero()).collect();
-        BONDED.save(deps.storage, &new_balances)?;
-        supply.total_bonded = new_balances.iter().map(|(_, v)| *v).sum();
-        SUPPLY.save(deps.storage, &supply)?;
+    pub fn can_unbond(deps: Deps) -> StdResult<CanUnbondResponse> {
+        let reason = if SUPPLY.load(deps.storage)?.issued.is_zero() {
+            Some("No tokens have been issued".to_string())
+        } else if STAKE_INFO
+            .load(deps.storage)?
+            .validators
+            .iter()
+            .all(|(_, weight)| weight.is_zero())
+        {
+            Some("The validator set is empty".to_string())
+        } else {
+            None
+        };
 
-        Ok(resp)
+        Ok(CanUnbondResponse {
+            can_unbond: reason.is_none(),
+            reason,
+        })
     }
-}
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    use QueryMsg::*;
-    match msg {
-        Config {} => query::config(deps),
-        Claims { address } => {
-            to_json_binary(&CLAIMS.query_claims(deps, &deps.api.addr_validate(&address)?)?)
+    pub fn supply(deps: Deps, env: Env) -> StdResult<SupplyResponse> {
+        let loaded = CleanedSupply::load_for_query(deps.storage, &env)?;
+        let balance = loaded.balance(deps, &env)?;
+        Ok(supply_response(&loaded, balance))
+    }
+
+    fn supply_response(loaded: &CleanedSupply, balance: Uint128) -> SupplyResponse {
+        let backing_assets = loaded.assets(balance);
+        let exchange_rate = loaded.tokens_per_share(balance);
+        let supply = crate::msg::Supply {
+            bond_denom: loaded.bond_denom.clone(),
+            issued: loaded.issued,
+            total_bonded: loaded.total_bonded,
+            claims: loaded.claims,
+            total_unbonding: loaded.total_unbonding,
+            deferred_claim_shares: loaded.deferred_claim_shares,
+        };
+        SupplyResponse {
+            supply,
+            balance,
+            backing_assets,
+            exchange_rate,
         }
-        ValidatorSet {} => to_json_binary(&ValidatorSetResponse {
-            validator_set: STAKE_INFO.load(deps.storage)?.validators,
-        }),
-        LastReinvest {} => unimplemented!(),
-        Supply {} => to_json_binary(&query::supply(deps)?),
-        ExchangeRate {} => to_json_binary(&query::exchange_rate(deps, env)?),
-        TargetValue {} => to_json_binary(&query::target_value(deps, env)?),
     }
-}
 
-pub mod query {
-    use crate::msg::{ExchangeRateResponse, SupplyResponse, TargetValueResponse};
-    use crate::state::CleanedSupply;
+    pub fn dashboard(
+        deps: Deps,
+        env: Env,
+        address: Option<String>,
+    ) -> StdResult<DashboardResponse> {
+        // everything is derived from this one supply, so the numbers match each other
+        let loaded = CleanedSupply::load_for_query(deps.storage, &env)?;
+        let balance = loaded.balance(deps, &env)?;
+        let supply = supply_response(&loaded, balance);
+        let exchange_rate = supply.exchange_rate;
+        let target_value = CONFIG.load(deps.storage)?.target_value(exchange_rate);
+        let config = config(deps)?;
+
+        let account = address
+            .map(|address| {
+                let cw20::BalanceResponse {
+                    balance: lsd_balance,
+                } = deps.querier.query_wasm_smart(
+                    &config.token_contract,
+                    &cw20::Cw20QueryMsg::Balance {
+                        address: address.clone(),
+                    },
+                )?;
+                let claims = claims(
+                    deps,
+                    env.clone(),
+                    address.clone(),
+                    None,
+                    None,
+                    ClaimStatus::All,
+                )?;
+                StdResult::Ok(DashboardAccount {
+                    address,
+                    claims,
+                    lsd_balance,
+                })
+            })
+            .transpose()?;
+
+        Ok(DashboardResponse {
+            config,
+            supply,
+            exchange_rate,
+            target_value,
+            validators: validator_set_with_bonded(deps)?.validators,
+            account,
+        })
+    }
 
-    use super::*;
+    pub fn simulate_bond(deps: Deps, env: Env, amount: Uint128) -> StdResult<SimulateBondResponse> {
+        let supply = CleanedSupply::load_for_query(deps.storage, &env)?;
+        // unlike in `execute::bond`, the tokens were not received yet,
+        // so the current balance already is the balance before the deposit
+        let balance = supply.balance(deps, &env)?;
+        let lsd_tokens = supply.shares_for_tokens(amount, balance);
+        let exceeds_issuance_cap = CONFIG
+            .load(deps.storage)?
+            .exceeds_issuance_cap(supply.issued + lsd_tokens);
 
-    pub fn config(deps: Deps) -> StdResult<Binary> {
-        let config = CONFIG.load(deps.storage)?;
-        let resp: ConfigResponse = ConfigResponse {
-            owner: config.owner,
-            token_contract: config.token_contract,
-            treasury: config.treasury,
-            commission: config.commission,
-            epoch_period: config.epoch_period,
-            unbond_period: config.unbond_period,
-        };
-        to_json_binary(&resp)
+        Ok(SimulateBondResponse {
+            lsd_tokens,
+            exceeds_issuance_cap,
+        })
     }
 
-    pub fn exchange_rate(deps: Deps, env: Env) -> StdResult<ExchangeRateResponse> {
+    pub fn shares_to_redeem(
+        deps: Deps,
+        env: Env,
+        native: Uint128,
+    ) -> StdResult<SharesToRedeemResponse> {
         let supply = CleanedSupply::load_for_query(deps.storage, &env)?;
-        let exchange_rate = supply.tokens_per_share(supply.balance(deps, &env)?);
+        let balance = supply.balance(deps, &env)?;
+        let fee = CONFIG.load(deps.storage)?.unbond_fee;
 
-        Ok(ExchangeRateResponse { exchange_rate })
+        Ok(SharesToRedeemResponse {
+            shares: supply.shares_for_claim(native, balance, fee),
+        })
     }
 
-    pub fn target_value(deps: Deps, env: Env) -> StdResult<TargetValueResponse> {
+    pub fn buffer_utilization(deps: Deps, env: Env) -> StdResult<BufferUtilizationResponse> {
         let supply = CleanedSupply::load_for_query(deps.storage, &env)?;
-        let exchange_rate = supply.tokens_per_share(supply.balance(deps, &env)?);
-        let target_value =
-            exchange_rate * (Decimal::one() - CONFIG.load(deps.storage)?.liquidity_discount);
+        let buffer = supply.balance(deps, &env)?;
+
+        // same as in `reply::after_withdraw_rewards`
+        let required_liquidity =
+            supply.claims + supply.tokens_for_shares(supply.deferred_claim_shares, buffer);
+        let awaiting_payout = required_liquidity.saturating_sub(supply.total_unbonding);
+        let rewards = preview_commission(deps, env.clone())?.net_rewards;
+        // the reinvest does not undelegate again what completed unbonding in its block,
+        // which includes the unbondings it would clean up
+        let released =
+            released_in_block(deps.storage, &env)? + count_unbonding(deps.storage, &env)?;
+        let shortfall = required_liquidity
+            .saturating_sub(buffer + rewards + supply.total_unbonding)
+            .saturating_sub(released);
+        let utilization = if awaiting_payout >= buffer {
+            if awaiting_payout.is_zero() {
+                Decimal::zero()
+            } else {
+                Decimal::one()
+            }
+        } else {
+            Decimal::from_ratio(awaiting_payout, buffer)
+        };
 
-        Ok(TargetValueResponse { target_value })
+        Ok(BufferUtilizationResponse {
+            buffer,
+            unbonding: supply.total_unbonding,
+            awaiting_payout,
+            shortfall,
+            utilization,
+        })
     }
 
-    pub fn supply(deps: Deps) -> StdResult<SupplyResponse> {
-        let loaded = SUPPLY.load(deps.storage)?;
-        let supply = crate::msg::Supply {
-            bond_denom: loaded.bond_denom,
-            issued: loaded.issued,
-            total_bonded: loaded.total_bonded,
-            claims: loaded.claims,
-            total_unbonding: loaded.total_unbonding,
-        };
-        Ok(SupplyResponse { supply })
+    pub fn bootstrap_status(deps: Deps) -> StdResult<BootstrapStatusResponse> {
+        Ok(match BOOTSTRAP.may_load(deps.storage)? {
+            Some(bootstrap) => BootstrapStatusResponse {
+                active: true,
+                min_deposit: bootstrap.min_deposit,
+                deposited: bootstrap.deposited,
+                minted: bootstrap.minted,
+            },
+            None => BootstrapStatusResponse {
+                active: false,
+                min_deposit: Uint128::zero(),
+                deposited: Uint128::zero(),
+                minted: Uint128::zero(),
+            },
+        })
     }
 }
 
 pub mod migration {
     use cosmwasm_schema::cw_serde;
-    use cosmwasm_std::Uint128;
+    use cosmwasm_schema::serde::{Deserialize, Serialize};
+    use cosmwasm_std::{Order, StdResult, Storage, Uint128};
+    use cw_storage_plus::Map;
     use cw_utils::Expiration;
 
+    use crate::state::{UnbondingV1, VersionedUnbondings, UNBONDING, UNDELEGATIONS};
+
     #[cw_serde]
     pub struct OldUnbonding {
         pub amount: Uint128,
@@ -848,6 +4833,53 @@ pub mod migration {
         pub unbonding: Vec<OldUnbonding>,
         pub total_unbonding: Uint128,
     }
+
+    /// An entry of `UNBONDING` in either the versioned or the raw format that was stored before
+    #[derive(Serialize, Deserialize)]
+    #[serde(crate = "cosmwasm_schema::serde", untagged)]
+    pub enum StoredUnbondings {
+        Versioned(VersionedUnbondings),
+        Raw(Vec<UnbondingV1>),
+    }
+
+    /// Wraps the raw entries of `UNBONDING` in [`VersionedUnbondings::V1`], they are upgraded when read.
+    /// Entries that are already versioned are kept, so this can run on every migration.
+    pub fn wrap_unbondings(storage: &mut dyn Storage) -> StdResult<()> {
+        let stored: Map<u64, StoredUnbondings> = Map::new("unbonding");
+        let raw = stored
+            .range(storage, None, None, Order::Ascending)
+            .filter_map(|r| match r {
+                Ok((expiration, StoredUnbondings::Raw(unbondings))) => {
+                    Some(Ok((expiration, unbondings)))
+                }
+                Ok(_) => None,
+                Err(err) => Some(Err(err)),
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        for (expiration, unbondings) in raw {
+            UNBONDING.save(storage, expiration, &VersionedUnbondings::V1(unbondings))?;
+        }
+        Ok(())
+    }
+
+    /// Records the pending unbondings in `UNDELEGATIONS`, if nothing was recorded yet.
+    /// Older contracts did not record them, so their claims could not be linked to the undelegations otherwise.
+    pub fn seed_undelegations(storage: &mut dyn Storage, unbond_period: u64) -> StdResult<()> {
+        if !UNDELEGATIONS.is_empty(storage) {
+            return Ok(());
+        }
+        let expirations = UNBONDING
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for expiration in expirations {
+            UNDELEGATIONS.save(
+                storage,
+                expiration.saturating_sub(unbond_period),
+                &expiration,
+            )?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -865,6 +4897,7 @@ pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, Co
             total_bonded: old_supply.total_bonded,
             claims: old_supply.claims,
             total_unbonding: old_supply.total_unbonding,
+            deferred_claim_shares: Uint128::zero(),
         };
         SUPPLY.save(deps.storage, &new_supply)?;
 
@@ -877,6 +4910,15 @@ pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, Co
         );
     }
 
+    // the unbondings were stored without a version before
+    migration::wrap_unbondings(deps.storage)?;
+    migration::seed_undelegations(deps.storage, CONFIG.load(deps.storage)?.unbond_period)?;
+
+    // contracts deployed before slashings were recorded never saved them
+    if SLASHINGS.may_load(deps.storage)?.is_none() {
+        SLASHINGS.save(deps.storage, &vec![])?;
+    }
+
     if let Some(new_owner) = msg.new_owner {
         CONFIG.update::<_, StdError>(deps.storage, |mut config| {
             config.owner = deps.api.addr_validate(&new_owner)?;
@@ -892,9 +4934,9 @@ mod tests {
     use cosmwasm_std::{
         coins,
         testing::{mock_env, mock_info, MockApi, MockStorage},
-        to_json_binary, Addr, Binary, CosmosMsg, Decimal, DepsMut, Empty, Event, OwnedDeps,
-        QuerierWrapper, Reply, ReplyOn, Response, StdError, SubMsg, SubMsgResponse, SubMsgResult,
-        Uint128, Validator, WasmMsg,
+        to_json_binary, Addr, BankMsg, Binary, CosmosMsg, Decimal, DepsMut, Empty, Event,
+        OwnedDeps, QuerierWrapper, Reply, ReplyOn, Response, StdError, StdResult, SubMsg,
+        SubMsgResponse, SubMsgResult, Uint128, Validator, WasmMsg,
     };
     use cw20::{Cw20ExecuteMsg, MinterResponse};
     use cw20_base::msg::InstantiateMsg as Cw20InstantiateMsg;
@@ -902,15 +4944,19 @@ mod tests {
 
     use crate::{
         contract::{execute, instantiate},
-        mock_querier::{mock_dependencies, WasmMockQuerier},
-        msg::{InstantiateMsg, TokenInitInfo},
-        state::CLAIMS,
+        mock_querier::{mock_dependencies, WasmMockQuerier, MOCK_BOND_DENOM},
+        msg::{InstantiateMsg, MigrateMsg, SudoMsg, TokenInitInfo},
+        state::{
+            load_unbondings, TmpState, VersionedUnbondings, CLAIMS, CONFIG,
+            DEFAULT_MIN_REINVEST_AMOUNT, SLASHINGS, SUPPLY, TMP_STATE, UNBONDING,
+        },
         ContractError,
     };
 
-    use super::reply;
+    use super::{reply, sudo};
 
-    const TOKEN: &str = "ufun";
+    const TOKEN: &str = MOCK_BOND_DENOM;
+    const LSD_TOKEN: &str = "lsd_token";
     const DAY: u64 = 24 * 60 * 60;
     const EPOCH: u64 = 23 * 60 * 60;
 
@@ -947,17 +4993,138 @@ mod tests {
         querier.base.staking.update(TOKEN, &[val], &[]);
     }
 
-    fn init(deps: DepsMut, owner: &str) -> Response {
+    fn init(mut deps: DepsMut, owner: &str) -> Response {
+        let msg = InstantiateMsg {
+            treasury: "treasury".to_string(),
+            commission: Decimal::percent(10),
+            validators: vec![("val1".to_string(), Decimal::percent(100))],
+            owner: owner.to_string(),
+
+            epoch_period: EPOCH,
+            unbond_period: 28 * DAY,
+            max_concurrent_unbondings: 7,
+
+            cw20_init: TokenInitInfo {
+                label: "label".to_string(),
+                cw20_code_id: 0,
+                name: "funLSD".to_string(),
+                symbol: "fLSD".to_string(),
+                decimals: 6,
+                initial_balances: vec![],
+                marketing: None,
+            },
+            liquidity_discount: Decimal::percent(4),
+            tombstone_treshold: Decimal::percent(3),
+            slashing_safety_margin: 10 * 60,
+            bond_denom_decimals: None,
+            expected_bond_decimals: None,
+            issuance_cap: None,
+            voucher_init: None,
+            bootstrap_min_deposit: None,
+            valoper_prefix: String::new(),
+        };
+
+        let env = mock_env();
+        let info = mock_info(owner, &[]);
+        let res = instantiate(deps.branch(), env, info, msg).unwrap();
+        // the token contract is adopted in the reply to its instantiation, which is not executed here
+        CONFIG
+            .update(deps.storage, |mut config| -> StdResult<_> {
+                config.token_contract = Addr::unchecked(LSD_TOKEN);
+                Ok(config)
+            })
+            .unwrap();
+        res
+    }
+
+    #[test]
+    fn init_rejects_too_large_slashing_safety_margin() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            treasury: "treasury".to_string(),
+            commission: Decimal::percent(10),
+            validators: vec![("val1".to_string(), Decimal::percent(100))],
+            owner: "owner".to_string(),
+
+            epoch_period: EPOCH,
+            unbond_period: 28 * DAY,
+            max_concurrent_unbondings: 7,
+            cw20_init: TokenInitInfo {
+                label: "label".to_string(),
+                cw20_code_id: 0,
+                name: "funLSD".to_string(),
+                symbol: "fLSD".to_string(),
+                decimals: 6,
+                initial_balances: vec![],
+                marketing: None,
+            },
+            liquidity_discount: Decimal::percent(4),
+            tombstone_treshold: Decimal::percent(3),
+            // the unbonding epoch is 4 days, so this would always overlap with an unbonding
+            slashing_safety_margin: 2 * DAY,
+            bond_denom_decimals: None,
+            expected_bond_decimals: None,
+            issuance_cap: None,
+            voucher_init: None,
+            bootstrap_min_deposit: None,
+            valoper_prefix: String::new(),
+        };
+
+        let err = instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidSlashingSafetyMargin {});
+    }
+
+    #[test]
+    fn init_rejects_empty_bond_denom() {
+        let mut deps = mock_dependencies(&[]);
+        // e.g. a chain without a staking module
+        deps.querier.base.staking.update("", &[], &[]);
+
         let msg = InstantiateMsg {
             treasury: "treasury".to_string(),
             commission: Decimal::percent(10),
             validators: vec![("val1".to_string(), Decimal::percent(100))],
-            owner: owner.to_string(),
+            owner: "owner".to_string(),
 
             epoch_period: EPOCH,
             unbond_period: 28 * DAY,
             max_concurrent_unbondings: 7,
+            cw20_init: TokenInitInfo {
+                label: "label".to_string(),
+                cw20_code_id: 0,
+                name: "funLSD".to_string(),
+                symbol: "fLSD".to_string(),
+                decimals: 6,
+                initial_balances: vec![],
+                marketing: None,
+            },
+            liquidity_discount: Decimal::percent(4),
+            tombstone_treshold: Decimal::percent(3),
+            slashing_safety_margin: 10 * 60,
+            bond_denom_decimals: None,
+            expected_bond_decimals: None,
+            issuance_cap: None,
+            voucher_init: None,
+            bootstrap_min_deposit: None,
+            valoper_prefix: String::new(),
+        };
+
+        let err = instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidBondDenom {});
+    }
+
+    #[test]
+    fn init_checks_expected_bond_decimals() {
+        let msg = |expected_bond_decimals| InstantiateMsg {
+            treasury: "treasury".to_string(),
+            commission: Decimal::percent(10),
+            validators: vec![("val1".to_string(), Decimal::percent(100))],
+            owner: "owner".to_string(),
 
+            epoch_period: EPOCH,
+            unbond_period: 28 * DAY,
+            max_concurrent_unbondings: 7,
             cw20_init: TokenInitInfo {
                 label: "label".to_string(),
                 cw20_code_id: 0,
@@ -970,11 +5137,57 @@ mod tests {
             liquidity_discount: Decimal::percent(4),
             tombstone_treshold: Decimal::percent(3),
             slashing_safety_margin: 10 * 60,
+            bond_denom_decimals: None,
+            expected_bond_decimals,
+            issuance_cap: None,
+            voucher_init: None,
+            bootstrap_min_deposit: None,
+            valoper_prefix: String::new(),
         };
 
-        let env = mock_env();
-        let info = mock_info(owner, &[]);
-        instantiate(deps, env, info, msg).unwrap()
+        // e.g. a chain whose native token has 18 decimals
+        let mut deps = mock_dependencies(&[]);
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            msg(Some(18)),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::DecimalsMismatch {
+                token: 6,
+                expected: 18
+            }
+        );
+
+        let mut deps = mock_dependencies(&[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            msg(Some(6)),
+        )
+        .unwrap();
+        assert_eq!(
+            CONFIG.load(&deps.storage).unwrap().expected_bond_decimals,
+            Some(6)
+        );
+
+        // without an expectation, any decimals are accepted
+        let mut deps = mock_dependencies(&[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            msg(None),
+        )
+        .unwrap();
+        assert_eq!(
+            CONFIG.load(&deps.storage).unwrap().expected_bond_decimals,
+            None
+        );
     }
 
     #[test]
@@ -1001,7 +5214,13 @@ mod tests {
             },
             liquidity_discount: Decimal::percent(4),
             tombstone_treshold: Decimal::percent(3),
-            slashing_safety_margin: 10 * 60,
+            slashing_safety_margin: 60,
+            bond_denom_decimals: None,
+            expected_bond_decimals: None,
+            issuance_cap: None,
+            voucher_init: None,
+            bootstrap_min_deposit: None,
+            valoper_prefix: String::new(),
         };
 
         let sender = "addr0000";
@@ -1037,6 +5256,10 @@ mod tests {
                 payload: Binary::new(vec![])
             },]
         );
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "bond_denom" && a.value == TOKEN));
     }
 
     #[test]
@@ -1104,7 +5327,13 @@ mod tests {
             },
             liquidity_discount: Decimal::percent(4),
             tombstone_treshold: Decimal::percent(3),
-            slashing_safety_margin: 10 * 60,
+            slashing_safety_margin: 60,
+            bond_denom_decimals: None,
+            expected_bond_decimals: None,
+            issuance_cap: None,
+            voucher_init: None,
+            bootstrap_min_deposit: None,
+            valoper_prefix: String::new(),
         };
 
         let sender = "addr0000";
@@ -1137,7 +5366,13 @@ mod tests {
             },
             liquidity_discount: Decimal::percent(4),
             tombstone_treshold: Decimal::percent(3),
-            slashing_safety_margin: 10 * 60,
+            slashing_safety_margin: 60,
+            bond_denom_decimals: None,
+            expected_bond_decimals: None,
+            issuance_cap: None,
+            voucher_init: None,
+            bootstrap_min_deposit: None,
+            valoper_prefix: String::new(),
         };
 
         // Verify the error is InvalidCommission
@@ -1167,14 +5402,60 @@ mod tests {
             },
             liquidity_discount: Decimal::percent(100),
             tombstone_treshold: Decimal::percent(3),
-            slashing_safety_margin: 10 * 60,
+            slashing_safety_margin: 60,
+            bond_denom_decimals: None,
+            expected_bond_decimals: None,
+            issuance_cap: None,
+            voucher_init: None,
+            bootstrap_min_deposit: None,
+            valoper_prefix: String::new(),
         };
 
         // Verify the error is InvalidCommission
         assert!(matches!(
-            instantiate(deps.as_mut(), env, info, msg).unwrap_err(),
+            instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err(),
             ContractError::InvalidLiquidityDiscount {},
         ));
+
+        // Instantiate message with an account address instead of a validator
+        let msg = InstantiateMsg {
+            treasury: "treasury".to_string(),
+            commission: Decimal::percent(10),
+            validators: vec![(
+                "juno1t8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaaa".to_string(),
+                Decimal::percent(100),
+            )],
+            owner: "owner".to_string(),
+
+            epoch_period: 3600u64,
+            unbond_period: 3600u64,
+            max_concurrent_unbondings: 7,
+            cw20_init: TokenInitInfo {
+                label: "label".to_string(),
+                cw20_code_id: 0,
+                name: "funLSD".to_string(),
+                symbol: "fLSD".to_string(),
+                decimals: 6,
+                initial_balances: vec![],
+                marketing: None,
+            },
+            liquidity_discount: Decimal::percent(4),
+            tombstone_treshold: Decimal::percent(3),
+            slashing_safety_margin: 60,
+            bond_denom_decimals: None,
+            expected_bond_decimals: None,
+            issuance_cap: None,
+            voucher_init: None,
+            bootstrap_min_deposit: None,
+            valoper_prefix: "junovaloper".to_string(),
+        };
+
+        assert_eq!(
+            instantiate(deps.as_mut(), env, info, msg).unwrap_err(),
+            ContractError::InvalidValidatorAddress {
+                address: "juno1t8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaaa".to_string()
+            },
+        );
     }
 
     #[test]
@@ -1195,7 +5476,7 @@ mod tests {
         let res = execute::unbond(
             deps.as_mut(),
             env,
-            Addr::unchecked(""),
+            Addr::unchecked(LSD_TOKEN),
             100u128.into(),
             sender.to_string(),
         )
@@ -1203,7 +5484,7 @@ mod tests {
         assert_eq!(
             res.messages[0].msg,
             CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: "".to_string(),
+                contract_addr: LSD_TOKEN.to_string(),
                 msg: to_json_binary(&Cw20ExecuteMsg::Burn {
                     amount: 100u128.into()
                 })
@@ -1213,6 +5494,66 @@ mod tests {
         );
     }
 
+    #[test]
+    fn bond_before_token_creation_is_rejected() {
+        let mut deps = mock_dependencies(&[]);
+        init(deps.as_mut(), "owner");
+        // as if the reply to the token instantiation did not run yet
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<_> {
+                config.token_contract = Addr::unchecked("");
+                Ok(config)
+            })
+            .unwrap();
+
+        increase_contract_balance(&mut deps.querier, 1_000);
+        let err = execute::bond(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("sender", &coins(1_000, TOKEN)),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NotInitialized {});
+
+        let err = execute::unbond(
+            deps.as_mut(),
+            mock_env(),
+            Addr::unchecked(""),
+            100u128.into(),
+            "sender".to_string(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NotInitialized {});
+        assert!(SUPPLY.load(&deps.storage).unwrap().issued.is_zero());
+    }
+
+    #[test]
+    fn unbonding_zero_is_rejected() {
+        let mut deps = mock_dependencies(&[]);
+        init(deps.as_mut(), "owner");
+        do_deposit(&mut deps, "sender", 1700);
+
+        let err = execute::unbond(
+            deps.as_mut(),
+            mock_env(),
+            Addr::unchecked(LSD_TOKEN),
+            Uint128::zero(),
+            "sender".to_string(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::ZeroUnbond {});
+
+        // no claim was created and the supply is untouched
+        let claims = CLAIMS
+            .query_claims(deps.as_ref(), &Addr::unchecked("sender"), None, None)
+            .unwrap();
+        assert!(claims.claims.is_empty());
+        assert_eq!(
+            SUPPLY.load(&deps.storage).unwrap().issued,
+            Uint128::new(1700)
+        );
+    }
+
     #[test]
     fn basic_claim_creation_works() {
         const SENDER: &str = "sender";
@@ -1232,7 +5573,7 @@ mod tests {
         execute::unbond(
             deps.as_mut(),
             mock_env(),
-            Addr::unchecked(""),
+            Addr::unchecked(LSD_TOKEN),
             500u128.into(),
             SENDER.to_string(),
         )
@@ -1240,7 +5581,12 @@ mod tests {
         assert_eq!(
             1,
             CLAIMS
-                .query_claims(deps.as_ref(), &Addr::unchecked(SENDER.to_string()))
+                .query_claims(
+                    deps.as_ref(),
+                    &Addr::unchecked(SENDER.to_string()),
+                    None,
+                    None
+                )
                 .unwrap()
                 .claims
                 .len()
@@ -1250,7 +5596,7 @@ mod tests {
         execute::unbond(
             deps.as_mut(),
             mock_env(),
-            Addr::unchecked(""),
+            Addr::unchecked(LSD_TOKEN),
             500u128.into(),
             SENDER.to_string(),
         )
@@ -1258,7 +5604,12 @@ mod tests {
         assert_eq!(
             2,
             CLAIMS
-                .query_claims(deps.as_ref(), &Addr::unchecked(SENDER.to_string()))
+                .query_claims(
+                    deps.as_ref(),
+                    &Addr::unchecked(SENDER.to_string()),
+                    None,
+                    None
+                )
                 .unwrap()
                 .claims
                 .len()
@@ -1290,7 +5641,13 @@ mod tests {
             },
             liquidity_discount: Decimal::percent(4),
             tombstone_treshold: Decimal::percent(3),
-            slashing_safety_margin: 10 * 60,
+            slashing_safety_margin: 60,
+            bond_denom_decimals: None,
+            expected_bond_decimals: None,
+            issuance_cap: None,
+            voucher_init: None,
+            bootstrap_min_deposit: None,
+            valoper_prefix: String::new(),
         };
 
         let sender = "addr0000";
@@ -1300,26 +5657,441 @@ mod tests {
 
         // update the epoch timer once
         env.block.time = env.block.time.plus_seconds(3600);
-        super::execute::reinvest(deps.as_mut(), env.clone()).unwrap();
+        super::execute::reinvest(deps.as_mut(), env.clone(), None).unwrap();
 
         // wait until just before the next epoch
         env.block.time = env.block.time.plus_seconds(3599);
         assert!(matches!(
-            super::execute::reinvest(deps.as_mut(), env.clone()).unwrap_err(),
+            super::execute::reinvest(deps.as_mut(), env.clone(), None).unwrap_err(),
             ContractError::EpochNotReached { next_epoch: _ },
         ));
 
         // now right at the epoch
         env.block.time = env.block.time.plus_seconds(1);
-        super::execute::reinvest(deps.as_mut(), env.clone()).unwrap();
+        super::execute::reinvest(deps.as_mut(), env.clone(), None).unwrap();
 
         // skip a few epochs
         env.block.time = env.block.time.plus_seconds(3600 * 5 + 1);
-        super::execute::reinvest(deps.as_mut(), env.clone()).unwrap();
+        super::execute::reinvest(deps.as_mut(), env.clone(), None).unwrap();
 
         // next epoch should be sooner than epoch period, since it keeps the same rythm
         // and we triggered last epoch 1 second too late
         env.block.time = env.block.time.plus_seconds(3599);
-        super::execute::reinvest(deps.as_mut(), env).unwrap();
+        super::execute::reinvest(deps.as_mut(), env, None).unwrap();
+    }
+
+    #[test]
+    fn can_unbond() {
+        let mut deps = mock_dependencies(&[]);
+        init(deps.as_mut(), "owner");
+
+        // nothing issued yet
+        let res = super::query::can_unbond(deps.as_ref()).unwrap();
+        assert!(!res.can_unbond);
+        assert_eq!(res.reason.unwrap(), "No tokens have been issued");
+
+        do_deposit(&mut deps, "sender", 1_000);
+        let res = super::query::can_unbond(deps.as_ref()).unwrap();
+        assert!(res.can_unbond);
+        assert_eq!(res.reason, None);
+
+        // remove all validators
+        execute::set_validators(
+            deps.as_mut(),
+            mock_info("owner", &[]),
+            mock_env(),
+            vec![],
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let res = super::query::can_unbond(deps.as_ref()).unwrap();
+        assert!(!res.can_unbond);
+        assert_eq!(res.reason.unwrap(), "The validator set is empty");
+    }
+
+    #[test]
+    fn assert_invariants_detects_corruption() {
+        let mut deps = mock_dependencies(&[]);
+        init(deps.as_mut(), "owner");
+        do_deposit(&mut deps, "sender", 1_000);
+
+        // consistent state passes
+        sudo(deps.as_mut(), mock_env(), SudoMsg::AssertInvariants {}).unwrap();
+
+        // total_bonded no longer matches the stored delegations
+        let mut supply = SUPPLY.load(&deps.storage).unwrap();
+        supply.total_bonded += Uint128::new(1);
+        SUPPLY.save(&mut deps.storage, &supply).unwrap();
+        let err = sudo(deps.as_mut(), mock_env(), SudoMsg::AssertInvariants {}).unwrap_err();
+        assert!(matches!(err, ContractError::InvariantViolated { .. }));
+
+        // claims exceed the assets
+        supply.total_bonded = Uint128::zero();
+        supply.claims = Uint128::new(1_001);
+        SUPPLY.save(&mut deps.storage, &supply).unwrap();
+        let err = sudo(deps.as_mut(), mock_env(), SudoMsg::AssertInvariants {}).unwrap_err();
+        assert!(matches!(err, ContractError::InvariantViolated { .. }));
+    }
+
+    #[test]
+    fn min_reinvest_spacing_is_enforced() {
+        let mut deps = mock_dependencies(&[]);
+        let mut env = mock_env();
+        init(deps.as_mut(), "owner");
+
+        // only the owner can change the spacing
+        let err = execute::update_min_reinvest_spacing(
+            deps.as_mut(),
+            mock_info("someone", &[]),
+            Some(2 * EPOCH),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute::update_min_reinvest_spacing(
+            deps.as_mut(),
+            mock_info("owner", &[]),
+            Some(2 * EPOCH),
+        )
+        .unwrap();
+
+        // first reinvest is not limited by the spacing
+        env.block.time = env.block.time.plus_seconds(EPOCH);
+        execute::reinvest(deps.as_mut(), env.clone(), None).unwrap();
+        let first = env.block.time.seconds();
+
+        // next epoch is reached, but it is too close to the last reinvest
+        env.block.time = env.block.time.plus_seconds(EPOCH);
+        let err = execute::reinvest(deps.as_mut(), env.clone(), None).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ReinvestTooSoon {
+                next_reinvest: first + 2 * EPOCH
+            }
+        );
+
+        env.block.time = env.block.time.plus_seconds(EPOCH);
+        execute::reinvest(deps.as_mut(), env.clone(), None).unwrap();
+
+        let history = super::query::reinvest_history(deps.as_ref()).unwrap();
+        assert_eq!(history.reinvests, vec![first, first + 2 * EPOCH]);
+        assert_eq!(history.average_spacing, Some(2 * EPOCH));
+        assert_eq!(history.min_reinvest_spacing, Some(2 * EPOCH));
+
+        let last = super::query::last_reinvest(deps.as_ref()).unwrap();
+        assert_eq!(last.last_reinvest, first + 2 * EPOCH);
+        assert_eq!(last.next_reinvest, first + 3 * EPOCH);
+
+        // disabling the spacing allows the next epoch again
+        execute::update_min_reinvest_spacing(deps.as_mut(), mock_info("owner", &[]), None).unwrap();
+        env.block.time = env.block.time.plus_seconds(EPOCH);
+        execute::reinvest(deps.as_mut(), env, None).unwrap();
+    }
+
+    #[test]
+    fn max_concurrent_unbondings() {
+        let mut deps = mock_dependencies(&[]);
+        init(deps.as_mut(), "owner");
+
+        let res = super::query::max_concurrent_unbondings(deps.as_ref()).unwrap();
+        assert_eq!(res.max_concurrent_unbondings, 7);
+        // 28 days split into 7 batches
+        assert_eq!(res.unbond_epoch_seconds, 4 * DAY);
+        assert_eq!(res.unbond_period, 28 * DAY);
+        assert_eq!(res.next_unbond, mock_env().block.time.seconds() + EPOCH);
+    }
+
+    #[test]
+    fn fresh_contract_serves_slashing_events() {
+        let mut deps = mock_dependencies(&[]);
+        init(deps.as_mut(), "owner");
+
+        let res = super::query::slashing_events(deps.as_ref()).unwrap();
+        assert!(res.slashings.is_empty());
+    }
+
+    #[test]
+    fn migrate_initializes_missing_slashings() {
+        let mut deps = mock_dependencies(&[]);
+        init(deps.as_mut(), "owner");
+        // deployed before slashings were recorded
+        SLASHINGS.remove(&mut deps.storage);
+
+        super::migrate(deps.as_mut(), mock_env(), MigrateMsg { new_owner: None }).unwrap();
+        assert_eq!(SLASHINGS.load(&deps.storage).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn migrated_config_does_not_skip_dust() {
+        let mut deps = mock_dependencies(&[]);
+        init(deps.as_mut(), "owner");
+        // deployed before the dust threshold existed
+        let config = String::from_utf8(
+            cosmwasm_std::to_json_vec(&CONFIG.load(&deps.storage).unwrap()).unwrap(),
+        )
+        .unwrap();
+        let old_config = config.replace(
+            &format!(
+                r#""min_reinvest_amount":"{}","#,
+                DEFAULT_MIN_REINVEST_AMOUNT
+            ),
+            "",
+        );
+        assert_ne!(config, old_config);
+        cosmwasm_std::Storage::set(&mut deps.storage, CONFIG.as_slice(), old_config.as_bytes());
+
+        super::migrate(deps.as_mut(), mock_env(), MigrateMsg { new_owner: None }).unwrap();
+        assert_eq!(
+            CONFIG.load(&deps.storage).unwrap().min_reinvest_amount,
+            Uint128::zero()
+        );
+    }
+
+    #[test]
+    fn migrate_wraps_unversioned_unbondings() {
+        let mut deps = mock_dependencies(&[]);
+        init(deps.as_mut(), "owner");
+        // deployed before the unbondings were versioned
+        let unbondings = vec![crate::state::UnbondingV1 {
+            amount: Uint128::new(1_000),
+            validator: "val1".to_string(),
+        }];
+        let raw: cw_storage_plus::Map<u64, Vec<crate::state::UnbondingV1>> =
+            cw_storage_plus::Map::new("unbonding");
+        raw.save(&mut deps.storage, 100, &unbondings).unwrap();
+
+        super::migrate(deps.as_mut(), mock_env(), MigrateMsg { new_owner: None }).unwrap();
+        assert_eq!(
+            UNBONDING.load(&deps.storage, 100).unwrap(),
+            VersionedUnbondings::V1(unbondings.clone())
+        );
+        // migrating again keeps the wrapped entries as they are
+        super::migrate(deps.as_mut(), mock_env(), MigrateMsg { new_owner: None }).unwrap();
+        assert_eq!(
+            UNBONDING.load(&deps.storage, 100).unwrap(),
+            VersionedUnbondings::V1(unbondings)
+        );
+        assert_eq!(
+            load_unbondings(&deps.storage, 100).unwrap().unwrap()[0].started_at,
+            0
+        );
+    }
+
+    #[test]
+    fn no_commission_without_bonded_tokens() {
+        let mut deps = mock_dependencies(&[]);
+        init(deps.as_mut(), "owner");
+
+        // everything was undelegated to cover the claims
+        SUPPLY
+            .save(
+                &mut deps.storage,
+                &crate::state::Supply {
+                    bond_denom: TOKEN.to_string(),
+                    issued: Uint128::zero(),
+                    total_bonded: Uint128::zero(),
+                    claims: Uint128::new(1_000),
+                    total_unbonding: Uint128::new(1_000),
+                    deferred_claim_shares: Uint128::zero(),
+                },
+            )
+            .unwrap();
+        TMP_STATE
+            .save(
+                &mut deps.storage,
+                &TmpState {
+                    balance: Uint128::zero(),
+                },
+            )
+            .unwrap();
+        // a tiny residual arrives
+        increase_contract_balance(&mut deps.querier, 5);
+
+        let res = reply::after_withdraw_rewards(deps.as_mut(), mock_env()).unwrap();
+        assert!(
+            !res.messages
+                .iter()
+                .any(|m| matches!(&m.msg, CosmosMsg::Bank(BankMsg::Send { .. }))),
+            "no commission should be sent to the treasury"
+        );
+    }
+
+    #[test]
+    fn balance_decrease_during_reinvest_takes_no_commission() {
+        let mut deps = mock_dependencies(&[]);
+        init(deps.as_mut(), "owner");
+        execute::update_min_reinvest_amount(
+            deps.as_mut(),
+            mock_info("owner", &[]),
+            Uint128::zero(),
+        )
+        .unwrap();
+        SUPPLY
+            .save(
+                &mut deps.storage,
+                &crate::state::Supply {
+                    bond_denom: TOKEN.to_string(),
+                    issued: Uint128::new(100_000),
+                    total_bonded: Uint128::new(100_000),
+                    claims: Uint128::zero(),
+                    total_unbonding: Uint128::zero(),
+                    deferred_claim_shares: Uint128::zero(),
+                },
+            )
+            .unwrap();
+        crate::state::BONDED
+            .save(
+                &mut deps.storage,
+                &vec![("val1".to_string(), Uint128::new(100_000))],
+            )
+            .unwrap();
+        // the balance was 2_000 before withdrawing the rewards, but only 1_500 are left afterwards
+        TMP_STATE
+            .save(
+                &mut deps.storage,
+                &TmpState {
+                    balance: Uint128::new(2_000),
+                },
+            )
+            .unwrap();
+        increase_contract_balance(&mut deps.querier, 1_500);
+
+        let res = reply::after_withdraw_rewards(deps.as_mut(), mock_env()).unwrap();
+        assert!(
+            !res.messages
+                .iter()
+                .any(|m| matches!(&m.msg, CosmosMsg::Bank(BankMsg::Send { .. }))),
+            "no commission should be sent to the treasury"
+        );
+
+        let res = reply::after_partial_withdraw_rewards(deps.as_mut(), mock_env()).unwrap();
+        assert!(res.messages.is_empty());
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "rewards" && a.value == "0"));
+    }
+
+    #[test]
+    fn reinvest_messages_do_not_depend_on_validator_order() {
+        let reinvest = |validators: &[&str]| {
+            let mut deps = mock_dependencies(&[]);
+            init(deps.as_mut(), "owner");
+            execute::update_min_reinvest_amount(
+                deps.as_mut(),
+                mock_info("owner", &[]),
+                Uint128::zero(),
+            )
+            .unwrap();
+            let third = Decimal::from_ratio(1u128, 3u128);
+            crate::state::STAKE_INFO
+                .save(
+                    &mut deps.storage,
+                    &crate::state::StakeInfo {
+                        validators: validators.iter().map(|v| (v.to_string(), third)).collect(),
+                        max_delegations: vec![],
+                    },
+                )
+                .unwrap();
+            SUPPLY
+                .save(
+                    &mut deps.storage,
+                    &crate::state::Supply {
+                        bond_denom: TOKEN.to_string(),
+                        issued: Uint128::new(300_000),
+                        total_bonded: Uint128::new(300_000),
+                        claims: Uint128::zero(),
+                        total_unbonding: Uint128::zero(),
+                        deferred_claim_shares: Uint128::zero(),
+                    },
+                )
+                .unwrap();
+            crate::state::BONDED
+                .save(
+                    &mut deps.storage,
+                    &validators
+                        .iter()
+                        .map(|v| (v.to_string(), Uint128::new(100_000)))
+                        .collect(),
+                )
+                .unwrap();
+            TMP_STATE
+                .save(
+                    &mut deps.storage,
+                    &TmpState {
+                        balance: Uint128::zero(),
+                    },
+                )
+                .unwrap();
+            // does not split evenly, so someone gets the rounding remainder
+            increase_contract_balance(&mut deps.querier, 10_001);
+
+            let res = reply::after_withdraw_rewards(deps.as_mut(), mock_env()).unwrap();
+            cosmwasm_std::to_json_vec(&res.messages).unwrap()
+        };
+
+        let expected = reinvest(&["val1", "val2", "val3"]);
+        assert_eq!(reinvest(&["val1", "val2", "val3"]), expected);
+        assert_eq!(reinvest(&["val3", "val1", "val2"]), expected);
+        assert_eq!(reinvest(&["val2", "val3", "val1"]), expected);
+    }
+
+    #[test]
+    fn surplus_delegation_is_capped_to_balance() {
+        let mut deps = mock_dependencies(&[]);
+        init(deps.as_mut(), "owner");
+        execute::update_min_reinvest_amount(
+            deps.as_mut(),
+            mock_info("owner", &[]),
+            Uint128::zero(),
+        )
+        .unwrap();
+
+        // a lot more is unbonding than needed for the claims, but only a small balance is available
+        SUPPLY
+            .save(
+                &mut deps.storage,
+                &crate::state::Supply {
+                    bond_denom: TOKEN.to_string(),
+                    issued: Uint128::new(100_000),
+                    total_bonded: Uint128::new(100_000),
+                    claims: Uint128::new(1_000),
+                    total_unbonding: Uint128::new(500_000),
+                    deferred_claim_shares: Uint128::zero(),
+                },
+            )
+            .unwrap();
+        crate::state::BONDED
+            .save(
+                &mut deps.storage,
+                &vec![("val1".to_string(), Uint128::new(100_000))],
+            )
+            .unwrap();
+        TMP_STATE
+            .save(
+                &mut deps.storage,
+                &TmpState {
+                    balance: Uint128::new(2_000),
+                },
+            )
+            .unwrap();
+        increase_contract_balance(&mut deps.querier, 2_000);
+
+        let res = reply::after_withdraw_rewards(deps.as_mut(), mock_env()).unwrap();
+        let delegated: Uint128 = res
+            .messages
+            .iter()
+            .map(|m| match &m.msg {
+                CosmosMsg::Staking(cosmwasm_std::StakingMsg::Delegate { amount, .. }) => {
+                    amount.amount
+                }
+                _ => Uint128::zero(),
+            })
+            .sum();
+        assert_eq!(delegated, Uint128::new(2_000));
+
+        let supply = SUPPLY.load(&deps.storage).unwrap();
+        assert_eq!(supply.total_bonded, Uint128::new(102_000));
     }
 }
@@ -1,4 +1,4 @@
-use cosmwasm_std::{OverflowError, StdError};
+use cosmwasm_std::{Decimal, OverflowError, StdError, Uint128};
 use cw_utils::{ParseReplyError, PaymentError};
 use thiserror::Error;
 
@@ -25,21 +25,170 @@ pub enum ContractError {
     #[error("Reinvest can only be done once per epoch, next epoch is {next_epoch}")]
     EpochNotReached { next_epoch: u64 },
 
+    #[error("Epoch not reached yet, {remaining_seconds} seconds and {remaining_blocks} blocks remaining")]
+    EpochBlocksNotReached {
+        remaining_seconds: u64,
+        remaining_blocks: u64,
+    },
+
+    #[error("Reinvest is too close to the last one, next reinvest is possible at {next_reinvest}")]
+    ReinvestTooSoon { next_reinvest: u64 },
+
+    #[error("Paid amount {paid} does not match the sum of the recipient amounts {expected}")]
+    PaymentMismatch { paid: Uint128, expected: Uint128 },
+
+    #[error("Too many recipients, at most {max} are allowed per call")]
+    TooManyRecipients { max: usize },
+
+    #[error("Redistribution weights must add up to 1")]
+    InvalidRedistributeWeights {},
+
+    #[error("The liquidity discount can change by at most {max_step} per update")]
+    DiscountChangeTooLarge { max_step: Decimal },
+
+    #[error("The liquidity discount can only be updated again at {next_allowed}")]
+    DiscountUpdateTooSoon { next_allowed: u64 },
+
+    #[error("The unbond fee must be at most 2%")]
+    InvalidUnbondFee {},
+
+    #[error("The unbond fee can change by at most {max_step} per update")]
+    UnbondFeeChangeTooLarge { max_step: Decimal },
+
+    #[error("The unbond fee can only be updated again at {next_allowed}")]
+    UnbondFeeUpdateTooSoon { next_allowed: u64 },
+
+    #[error("The stored delegations add up to {bonded}, but total_bonded is {total_bonded}")]
+    BondedMismatch {
+        total_bonded: Uint128,
+        bonded: Uint128,
+    },
+
+    #[error("Valset change source must be at most {max} characters long")]
+    ValsetChangeSourceTooLong { max: usize },
+
+    #[error("The deadline of the valset change has passed")]
+    IntentExpired {},
+
+    #[error(
+        "The total bonded tokens drifted too far from the expected {expected}, they are {actual}"
+    )]
+    StateDrifted { expected: Uint128, actual: Uint128 },
+
+    #[error("Probation max weight must be at most 100%")]
+    InvalidProbationMaxWeight {},
+
+    #[error("Commission increase alert must be at most 100%")]
+    InvalidCommissionIncreaseAlert {},
+
+    #[error("Max rate growth per epoch must not be zero")]
+    InvalidMaxRateGrowth {},
+
+    #[error("Max discount change per update must be above 0% and at most 100%, the update interval at most 30 days")]
+    InvalidDiscountLimits {},
+
+    #[error("Bond amount for a recipient must not be zero")]
+    ZeroBondAmount {},
+
+    #[error(
+        "Validator {validator} is not in the validator set or was already processed in this epoch"
+    )]
+    ValidatorNotPending { validator: String },
+
+    #[error("No validators to process were given")]
+    EmptyValidatorSubset {},
+
+    #[error("The contract is paused")]
+    Paused {},
+
     #[error("Only whitelisted validators are allowed")]
     InvalidValidator {},
 
+    /// The address is not a validator operator address with the configured `Config::valoper_prefix`,
+    /// e.g. an account address
+    #[error("{address} is not a valid validator operator address")]
+    InvalidValidatorAddress { address: String },
+
+    #[error("Validator {validator} is not in the validator set")]
+    ValidatorNotInSet { validator: String },
+
     #[error("Weights must add up to 1")]
     InvalidValidatorWeights {},
 
     #[error("The next unbonding is too close to accurately detect slashing")]
     UnbondingTooClose {},
 
+    #[error("Tombstone threshold must be higher than 0% and at most 50%")]
+    InvalidTombstoneThreshold {},
+
+    #[error("Slashing safety margin must be smaller than half of the unbonding epoch")]
+    InvalidSlashingSafetyMargin {},
+
+    #[error("Commission vesting duration must be bigger than 0 and at least as long as the cliff")]
+    InvalidCommissionVesting {},
+
     #[error("Commission must be higher than 0.0% and lower than 0.50%")]
     InvalidCommission {},
 
+    #[error("Cannot unbond before the minimum holding period ends at {until}")]
+    HoldingPeriodNotMet { until: u64 },
+
+    #[error("Only the owner can unbond their tokens while an unbond fee is set")]
+    UnbondFromWithFee {},
+
+    #[error("Cannot unbond zero tokens")]
+    ZeroUnbond {},
+
+    #[error("Cannot withdraw more than the boost principal of {principal}")]
+    InsufficientBoostPrincipal { principal: Uint128 },
+
+    #[error("Cannot distribute {denom} as an external reward")]
+    InvalidExternalReward { denom: String },
+
+    #[error("The external reward can only be swept from {sweepable_at} on")]
+    ExternalRewardSweepTooEarly { sweepable_at: u64 },
+
     #[error("No tokens available to claim")]
     NothingToClaim {},
 
+    #[error("No claims are mature yet, the next one is released at {next_release_at}")]
+    ClaimsNotMature { next_release_at: u64 },
+
+    #[error("Mature claims can not be paid until the pending undelegations are completed")]
+    AwaitingUndelegation {},
+
+    #[error("The hub has no liquid tokens to pay the mature claims, {shortfall} are missing")]
+    InsufficientBuffer { shortfall: Uint128 },
+
+    #[error("No mature claim fits into the maximum amount of {max_amount}")]
+    MaxAmountTooLow { max_amount: Uint128 },
+
+    #[error("Only {available} of the mature claims are available")]
+    InsufficientMatureClaims { available: Uint128 },
+
+    #[error(
+        "{issued} tokens were issued, but the total supply of the lsd token is {cw20_total_supply}"
+    )]
+    SupplyMismatch {
+        issued: Uint128,
+        cw20_total_supply: Uint128,
+    },
+
+    #[error("Bonding this would issue more lsd tokens than the issuance cap")]
+    IssuanceCapReached {},
+
+    #[error("The issuance cap can not be higher than the mint cap of the lsd token: {token_cap}")]
+    IssuanceCapAboveTokenCap { token_cap: Uint128 },
+
+    #[error("The claim voucher can not have initial balances")]
+    InvalidVoucherInit {},
+
+    #[error("Only {available} vouchers can be redeemed yet")]
+    ImmatureVoucher { available: Uint128 },
+
+    #[error("The chain reported an empty bond denom")]
+    InvalidBondDenom {},
+
     #[error("Epoch period must be longer then 1h and shorter then 365 days")]
     InvalidEpochPeriod {},
 
@@ -51,6 +200,56 @@ pub enum ContractError {
 
     #[error("Migration failed - unbondings vector is not empty")]
     MigrationFailed {},
+
+    #[error("Invariant violated: {reason}")]
+    InvariantViolated { reason: String },
+
+    #[error("The claims scan is incomplete or outdated, query ClaimsIntegrity again")]
+    InvalidClaimsCheckpoint {},
+
+    #[error("The lsd token has {token} decimals, but the bond denom has {expected}")]
+    DecimalsMismatch { token: u8, expected: u8 },
+
+    #[error("The hub is not initialized yet, the lsd token was not created")]
+    NotInitialized {},
+
+    #[error("There is no valset change to continue")]
+    NoPendingValsetChange {},
+
+    #[error("No redelegation entries are free before {ready_at}")]
+    ValsetChangeNotReady { ready_at: u64 },
+
+    #[error("The claim discount must be at most {max}")]
+    InvalidClaimDiscount { max: Decimal },
+
+    #[error("Claim vouchers are transferable already, they can not be offered for sale")]
+    ClaimSaleWithVouchers {},
+
+    #[error("There is no claim of {owner} released at {release_at} for sale")]
+    ClaimNotForSale { owner: String, release_at: u64 },
+
+    #[error("The claim costs {price}, but {paid} were paid")]
+    ClaimPriceMismatch { price: Uint128, paid: Uint128 },
+
+    #[error(
+        "The hub is still bootstrapping, only bonding is possible until all deposits were minted"
+    )]
+    Bootstrapping {},
+
+    #[error("The hub is not bootstrapping")]
+    NotBootstrapping {},
+
+    #[error("Only {deposited} of the {min_deposit} needed to end the bootstrap were deposited")]
+    BootstrapThresholdNotReached {
+        deposited: Uint128,
+        min_deposit: Uint128,
+    },
+
+    /// Returned by [`crate::slippage::ensure_min`].
+    /// `expected` is the minimum the sender asked for and `actual` what they would have received,
+    /// so the shortfall is `expected - actual`. Both are in the unit of the checked amount.
+    #[error("Slippage exceeded, expected at least {expected}, but got {actual}")]
+    SlippageExceeded { expected: Uint128, actual: Uint128 },
 }
 
 impl From<OverflowError> for ContractError {
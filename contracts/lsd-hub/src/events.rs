@@ -0,0 +1,355 @@
+//! Typed events emitted by the hub.
+//! The event types and attribute keys are stable across versions, so indexers can rely on them.
+//! The chain prefixes the types with `wasm-`, e.g. `wasm-wyndlsd-bond`.
+//! Amounts are integer strings, decimals are fixed-point strings and times are in seconds.
+
+use cosmwasm_std::{Addr, Decimal, Event, Response, Uint128};
+
+pub const BOND_EVENT: &str = "wyndlsd-bond";
+pub const UNBOND_EVENT: &str = "wyndlsd-unbond";
+pub const CLAIM_EVENT: &str = "wyndlsd-claim";
+pub const REINVEST_EVENT: &str = "wyndlsd-reinvest";
+pub const VALSET_CHANGE_EVENT: &str = "wyndlsd-valset_change";
+pub const SLASH_EVENT: &str = "wyndlsd-slash";
+pub const COMMISSION_SPIKE_EVENT: &str = "wyndlsd-validator_commission_spike";
+pub const RATE_GROWTH_CAPPED_EVENT: &str = "wyndlsd-rate_growth_capped";
+
+/// `shares` lsd tokens were minted to `recipient` for `amount` native tokens
+pub fn bond(recipient: &Addr, amount: Uint128, shares: Uint128) -> Event {
+    Event::new(BOND_EVENT)
+        .add_attribute("recipient", recipient)
+        .add_attribute("amount", amount)
+        .add_attribute("shares", shares)
+}
+
+/// `shares` lsd tokens of `owner` were unbonded into a claim of `amount` native tokens,
+/// after `fee_amount` were kept by the hub.
+/// Deferred claims are converted when claiming, so their `amount` is `0`.
+pub fn unbond(
+    owner: &Addr,
+    shares: Uint128,
+    amount: Uint128,
+    fee_amount: Uint128,
+    release_at: u64,
+    deferred: bool,
+) -> Event {
+    Event::new(UNBOND_EVENT)
+        .add_attribute("owner", owner)
+        .add_attribute("shares", shares)
+        .add_attribute("amount", amount)
+        .add_attribute("fee_amount", fee_amount)
+        .add_attribute("release_at", release_at.to_string())
+        .add_attribute("deferred", deferred.to_string())
+}
+
+/// What `claim` paid and what is left of the mature claims
+pub struct ClaimReport<'a> {
+    pub owner: &'a Addr,
+    pub recipient: &'a Addr,
+    pub requested: Uint128,
+    pub paid: Uint128,
+    pub remaining_matured: Uint128,
+    pub deferred_shares: Uint128,
+    /// Why not all mature claims were paid, `None` if they were
+    pub shortfall_reason: Option<&'a str>,
+}
+
+pub fn claim(report: ClaimReport) -> Event {
+    Event::new(CLAIM_EVENT)
+        .add_attribute("owner", report.owner)
+        .add_attribute("recipient", report.recipient)
+        .add_attribute("requested", report.requested)
+        .add_attribute("paid", report.paid)
+        .add_attribute("remaining_matured", report.remaining_matured)
+        .add_attribute("deferred_shares", report.deferred_shares)
+        .add_attribute(
+            "shortfall_reason",
+            report.shortfall_reason.unwrap_or("none"),
+        )
+}
+
+/// How a reinvest used the balance of the hub
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReinvestStatus {
+    /// The balance was zero
+    Empty,
+    /// The surplus was too small to delegate
+    SkippedDust,
+    /// The surplus stayed in the balance, because delegations are suspended
+    Suspended,
+    /// The surplus stayed in the balance until the next delegating reinvest, see `Config::delegate_every_n_epochs`
+    Deferred,
+    Delegated,
+    Undelegated,
+    /// The balance exactly covered the claims
+    Balanced,
+}
+
+impl ReinvestStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReinvestStatus::Empty => "empty",
+            ReinvestStatus::SkippedDust => "skipped_dust",
+            ReinvestStatus::Suspended => "suspended",
+            ReinvestStatus::Deferred => "deferred",
+            ReinvestStatus::Delegated => "delegated",
+            ReinvestStatus::Undelegated => "undelegated",
+            ReinvestStatus::Balanced => "balanced",
+        }
+    }
+}
+
+/// The result of a reinvest.
+/// `amount` is how much was delegated or undelegated and `unbonding_release_at` is when the undelegation
+/// completes, `0` if nothing was undelegated.
+pub fn reinvest(
+    status: ReinvestStatus,
+    rewards: Uint128,
+    exchange_rate: Decimal,
+    amount: Uint128,
+    unbonding_release_at: u64,
+) -> Event {
+    Event::new(REINVEST_EVENT)
+        .add_attribute("status", status.as_str())
+        .add_attribute("rewards", rewards)
+        .add_attribute("exchange_rate", exchange_rate.to_string())
+        .add_attribute("amount", amount)
+        .add_attribute("unbonding_release_at", unbonding_release_at.to_string())
+}
+
+/// The validator set was replaced. `validators` are listed as `address=weight` in address order, separated by commas.
+/// `source` is the empty string if none was given.
+pub fn valset_change(validators: &[(String, Decimal)], source: Option<&str>) -> Event {
+    let mut validators: Vec<_> = validators.iter().collect();
+    validators.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let validators = validators
+        .into_iter()
+        .map(|(address, weight)| format!("{address}={weight}"))
+        .collect::<Vec<_>>()
+        .join(",");
+    Event::new(VALSET_CHANGE_EVENT)
+        .add_attribute("validators", validators)
+        .add_attribute("source", source.unwrap_or_default())
+}
+
+/// The result of a slashing check. `tombstoned` validators are separated by commas.
+/// `unobserved` are the validators that still have pending unbondings, but no delegation to compare against,
+/// so a slashing of those unbondings can not be detected.
+pub fn slash(
+    slashed: bool,
+    bonded_slashed: Uint128,
+    unbonded_slashed: Uint128,
+    tombstoned: &[&str],
+    unobserved: &[&str],
+) -> Event {
+    Event::new(SLASH_EVENT)
+        .add_attribute("slashed", slashed.to_string())
+        .add_attribute("bonded_slashed", bonded_slashed)
+        .add_attribute("unbonded_slashed", unbonded_slashed)
+        .add_attribute("tombstoned", tombstoned.join(","))
+        .add_attribute("unobserved", unobserved.join(","))
+}
+
+/// The commission of `validator` increased by more than `Config::max_commission_increase_alert` since the last reinvest.
+/// `weight_removed` is whether its weight was set to zero because of it.
+pub fn commission_spike(
+    validator: &str,
+    before: Decimal,
+    after: Decimal,
+    weight_removed: bool,
+) -> Event {
+    Event::new(COMMISSION_SPIKE_EVENT)
+        .add_attribute("validator", validator)
+        .add_attribute("before", before.to_string())
+        .add_attribute("after", after.to_string())
+        .add_attribute("weight_removed", weight_removed.to_string())
+}
+
+/// Only `recognized` of the `rewards` withdrawn by a reinvest and the earlier deferred rewards were added
+/// to the assets, because of `Config::max_rate_growth_per_epoch`. `deferred` are left for the next reinvests.
+pub fn rate_growth_capped(rewards: Uint128, recognized: Uint128, deferred: Uint128) -> Event {
+    Event::new(RATE_GROWTH_CAPPED_EVENT)
+        .add_attribute("rewards", rewards)
+        .add_attribute("recognized", recognized)
+        .add_attribute("deferred", deferred)
+}
+
+/// Adds the attributes the hub emitted before the typed events.
+/// They are only kept with the `legacy-events` feature, until indexers moved to the events.
+pub trait LegacyAttributes {
+    fn add_legacy_attribute(self, key: impl Into<String>, value: impl Into<String>) -> Self;
+}
+
+impl<T> LegacyAttributes for Response<T> {
+    fn add_legacy_attribute(self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        if cfg!(feature = "legacy-events") {
+            self.add_attribute(key, value)
+        } else {
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(event: &Event) -> Vec<&str> {
+        event.attributes.iter().map(|a| a.key.as_str()).collect()
+    }
+
+    fn value<'a>(event: &'a Event, key: &str) -> &'a str {
+        &event
+            .attributes
+            .iter()
+            .find(|a| a.key == key)
+            .unwrap()
+            .value
+    }
+
+    #[test]
+    fn bond_schema() {
+        let event = bond(&Addr::unchecked("alice"), 1_000u128.into(), 990u128.into());
+        assert_eq!(event.ty, "wyndlsd-bond");
+        assert_eq!(keys(&event), ["recipient", "amount", "shares"]);
+        assert_eq!(value(&event, "amount"), "1000");
+    }
+
+    #[test]
+    fn unbond_schema() {
+        let event = unbond(
+            &Addr::unchecked("alice"),
+            990u128.into(),
+            1_000u128.into(),
+            Uint128::zero(),
+            1_700_000_000,
+            false,
+        );
+        assert_eq!(event.ty, "wyndlsd-unbond");
+        assert_eq!(
+            keys(&event),
+            [
+                "owner",
+                "shares",
+                "amount",
+                "fee_amount",
+                "release_at",
+                "deferred"
+            ]
+        );
+        assert_eq!(value(&event, "release_at"), "1700000000");
+        assert_eq!(value(&event, "deferred"), "false");
+    }
+
+    #[test]
+    fn claim_schema() {
+        let owner = Addr::unchecked("alice");
+        let event = claim(ClaimReport {
+            owner: &owner,
+            recipient: &owner,
+            requested: 300u128.into(),
+            paid: 100u128.into(),
+            remaining_matured: 200u128.into(),
+            deferred_shares: Uint128::zero(),
+            shortfall_reason: None,
+        });
+        assert_eq!(event.ty, "wyndlsd-claim");
+        assert_eq!(
+            keys(&event),
+            [
+                "owner",
+                "recipient",
+                "requested",
+                "paid",
+                "remaining_matured",
+                "deferred_shares",
+                "shortfall_reason"
+            ]
+        );
+        assert_eq!(value(&event, "shortfall_reason"), "none");
+    }
+
+    #[test]
+    fn reinvest_schema() {
+        let event = reinvest(
+            ReinvestStatus::Undelegated,
+            10u128.into(),
+            Decimal::percent(105),
+            500u128.into(),
+            1_700_000_000,
+        );
+        assert_eq!(event.ty, "wyndlsd-reinvest");
+        assert_eq!(
+            keys(&event),
+            [
+                "status",
+                "rewards",
+                "exchange_rate",
+                "amount",
+                "unbonding_release_at"
+            ]
+        );
+        assert_eq!(value(&event, "status"), "undelegated");
+        assert_eq!(value(&event, "exchange_rate"), "1.05");
+    }
+
+    #[test]
+    fn valset_change_schema() {
+        let event = valset_change(
+            &[
+                ("val1".to_string(), Decimal::percent(75)),
+                ("val2".to_string(), Decimal::percent(25)),
+            ],
+            None,
+        );
+        assert_eq!(event.ty, "wyndlsd-valset_change");
+        assert_eq!(keys(&event), ["validators", "source"]);
+        assert_eq!(value(&event, "validators"), "val1=0.75,val2=0.25");
+        assert_eq!(value(&event, "source"), "");
+    }
+
+    #[test]
+    fn slash_schema() {
+        let event = slash(
+            true,
+            100u128.into(),
+            Uint128::zero(),
+            &["val1", "val2"],
+            &["val3"],
+        );
+        assert_eq!(event.ty, "wyndlsd-slash");
+        assert_eq!(
+            keys(&event),
+            [
+                "slashed",
+                "bonded_slashed",
+                "unbonded_slashed",
+                "tombstoned",
+                "unobserved"
+            ]
+        );
+        assert_eq!(value(&event, "tombstoned"), "val1,val2");
+        assert_eq!(value(&event, "unobserved"), "val3");
+    }
+
+    #[test]
+    fn commission_spike_schema() {
+        let event = commission_spike("val1", Decimal::percent(5), Decimal::one(), true);
+        assert_eq!(event.ty, "wyndlsd-validator_commission_spike");
+        assert_eq!(
+            keys(&event),
+            ["validator", "before", "after", "weight_removed"]
+        );
+        assert_eq!(value(&event, "before"), "0.05");
+        assert_eq!(value(&event, "after"), "1");
+    }
+
+    #[test]
+    fn legacy_attributes_follow_the_feature() {
+        let res: Response = Response::new().add_legacy_attribute("action", "claim");
+        assert_eq!(
+            res.attributes.len(),
+            usize::from(cfg!(feature = "legacy-events"))
+        );
+    }
+}
@@ -1,12 +1,16 @@
 mod claim;
 pub mod contract;
 mod error;
+pub mod events;
 #[cfg(test)]
 mod mock_querier;
 pub mod msg;
 #[cfg(test)]
 mod multitest;
+pub mod pagination;
+pub mod slippage;
 pub mod state;
+pub mod valoper;
 mod valset;
 
 pub use crate::error::ContractError;
@@ -7,13 +7,20 @@ use std::collections::HashMap;
 
 use cw20::{BalanceResponse, Cw20QueryMsg, TokenInfoResponse};
 
+/// The bonded denom the mock staking module reports, until it is updated
+pub const MOCK_BOND_DENOM: &str = "ufun";
+
 /// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies.
 /// This uses the Wyndex CustomQuerier.
 pub fn mock_dependencies(
     contract_balance: &[Coin],
 ) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
-    let custom_querier: WasmMockQuerier =
+    let mut custom_querier: WasmMockQuerier =
         WasmMockQuerier::new(MockQuerier::new(&[(MOCK_CONTRACT_ADDR, contract_balance)]));
+    custom_querier
+        .base
+        .staking
+        .update(MOCK_BOND_DENOM, &[], &[]);
 
     OwnedDeps {
         storage: MockStorage::default(),
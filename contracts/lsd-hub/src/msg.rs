@@ -1,8 +1,11 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Decimal, Uint128};
+use cosmwasm_std::{Addr, Coin, Decimal, Uint128};
 use cw20::{Cw20Coin, Cw20ReceiveMsg};
 use cw20_base::msg::InstantiateMarketingInfo;
 
+use crate::claim::ClaimStatus;
+use crate::state::Slashing;
+
 #[cw_serde]
 pub struct TokenInitInfo {
     /// Code ID of the CW20 token contract
@@ -49,34 +52,372 @@ pub struct InstantiateMsg {
     /// The idea here is that no one will want to buy the staking token at exactly the price of the underlying,
     /// because they are locked and can potentially be slashed. So we apply a discount to the price.
     pub liquidity_discount: Decimal,
+
+    /// The number of decimals of the bond denom, reported in [`ConfigResponse::bond_denom_decimals`].
+    /// Defaults to [`crate::state::DEFAULT_BOND_DENOM_DECIMALS`].
+    #[serde(default)]
+    pub bond_denom_decimals: Option<u8>,
+    /// If set, instantiation fails unless `cw20_init.decimals` is the same, so one lsd token is displayed
+    /// like one native token. The hub can not query the display exponent of the bond denom, so it has to be passed in.
+    /// Reported in [`ConfigResponse::expected_bond_decimals`].
+    #[serde(default)]
+    pub expected_bond_decimals: Option<u8>,
+    /// The most lsd tokens that can ever be issued, also set as the mint cap of the lsd token.
+    /// `None` for no limit.
+    #[serde(default)]
+    pub issuance_cap: Option<Uint128>,
+    /// If set, the hub instantiates a second cw20 as claim voucher, see [`ConfigResponse::voucher_contract`].
+    /// Unbonding then mints transferable vouchers instead of creating claims. `initial_balances` has to be empty.
+    #[serde(default)]
+    pub voucher_init: Option<TokenInitInfo>,
+    /// If set, bonds are escrowed without minting until they add up to this amount. Then all of them
+    /// are minted 1:1, so nobody can distort the exchange rate while the pool is tiny.
+    /// Unbonding and reinvesting are disabled until then, see `QueryMsg::BootstrapStatus`.
+    #[serde(default)]
+    pub bootstrap_min_deposit: Option<Uint128>,
+    /// The bech32 prefix of validator operator addresses on this chain, e.g. "junovaloper".
+    /// `validators` and the validators of later valset changes are rejected unless they have it.
+    /// Empty to accept any address.
+    #[serde(default)]
+    pub valoper_prefix: String,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
     /// Implements the Cw20 receiver interface, allowing Sending wyAsset in order to unbond
     Receive(Cw20ReceiveMsg),
+    /// Unbonds the wyAsset of `owner` like `ReceiveMsg::Unbond`, for wallets that can not use `Send`.
+    /// The owner has to grant the hub an allowance for `amount` first, which allows anyone to unbond it.
+    /// While an unbond fee is set, only the owner can. The claim is created for the owner.
+    UnbondFrom { owner: String, amount: Uint128 },
     /// Set staking Asset to bond to mint wyAsset
     Bond {},
+    /// Mints up to `limit` (at most [`crate::contract::MAX_BOOTSTRAP_MINTS`]) of the escrowed bootstrap deposits,
+    /// once they reached `InstantiateMsg::bootstrap_min_deposit`. The bond that reaches it mints the first batch,
+    /// this is only needed if there are more depositors. Can be called by anyone.
+    FinalizeBootstrap {
+        #[serde(default)]
+        limit: Option<u32>,
+    },
+    /// Bond the sent staking Asset on behalf of multiple recipients at once.
+    /// The payment must equal the sum of the recipient amounts.
+    /// At most [`crate::contract::MAX_BOND_MANY_RECIPIENTS`] recipients are allowed per call.
+    BondMany { recipients: Vec<(String, Uint128)> },
     /// Claim the tokens you previously withdrew after the unbonding period has passed
     Claim {},
+    /// Same as [`ExecuteMsg::Claim`], but only claims mature claims until `max_amount` is reached.
+    /// Claims are only paid as a whole, so the ones that don't fit anymore stay claimable.
+    ClaimUpTo { max_amount: Uint128 },
+    /// Claims exactly `amount` from your mature claims, oldest first.
+    /// A claim that is only partially needed is reduced and stays claimable.
+    /// Fails if the mature claims or the liquid balance of the hub do not cover `amount`.
+    ClaimAmount { amount: Uint128 },
+    /// Allows `recipient` to claim your mature claims using `ExecuteMsg::ClaimFor`.
+    /// The tokens are sent to `recipient`, so only set this to a contract that forwards them to you,
+    /// like the bond router. `None` removes the recipient again.
+    SetClaimRecipient { recipient: Option<String> },
+    /// Buys the claim of `owner` released at `release_at` (in seconds) that was offered with `ReceiveMsg::UnbondForSale`.
+    /// The sent native tokens have to equal the claim amount minus the discount on the claim.
+    /// They are sent to `owner` and the claim is moved to the sender.
+    /// Can only be called by a buyer registered with `ExecuteMsg::RegisterClaimBuyer`.
+    BuyClaim { owner: String, release_at: u64 },
+    /// Claims the mature claims of `owner` and sends the tokens to the sender.
+    /// Can only be called by the claim recipient `owner` set with `ExecuteMsg::SetClaimRecipient`.
+    ClaimFor { owner: String },
     /// Reinvest should be called periodically (and permissionlessly) by a cronjob to
     ///  trigger delegations, unbonding, and exchange rate recalculations.
-    Reinvest {},
+    ///
+    /// If `validators` is set, only the rewards of those validators are withdrawn in this call.
+    /// The delegations are only settled (and the epoch only advances) once all validators were processed,
+    /// either by further calls with the remaining validators or by a call with `None`.
+    Reinvest {
+        #[serde(default)]
+        validators: Option<Vec<String>>,
+    },
+
+    /// Same as [`ExecuteMsg::Reinvest`] for all validators, but without waiting for the epoch, e.g. during an incident.
+    /// The epoch is not advanced, so the regular reinvests keep their rhythm.
+    /// Fails if the last reinvest was less than [`crate::contract::FORCE_REINVEST_INTERVAL`] seconds ago.
+    /// Can only be called by the owner.
+    ForceReinvest {},
 
     /// Triggers slashing detection, updating the exchange rate
     CheckSlash {},
     /// Sets new list of validators
     SetValidators {
-        new_validators: Vec<(String, Decimal)>,
+        new_validators: Vec<ValidatorEntry>,
+        /// Free-form tag describing who initiated the change, e.g. `gauge:{epoch}`.
+        /// It is emitted as the `source` of the `wyndlsd-valset_change` event.
+        /// At most [`crate::contract::MAX_VALSET_CHANGE_SOURCE_LEN`] characters.
+        #[serde(default)]
+        source: Option<String>,
+        /// The last block time (in seconds) at which the change may be executed.
+        /// A change that waited too long in a multisig or the mempool is refused.
+        #[serde(default)]
+        deadline: Option<u64>,
+        /// The `total_bonded` of the supply the weights were chosen for. The change is refused
+        /// if it moved by more than [`crate::contract::MAX_TOTAL_BONDED_DRIFT`] since.
+        #[serde(default)]
+        expected_total_bonded: Option<Uint128>,
     },
-    /// Updates the liquidity discount used for the [`QueryMsg::TargetValue`] query
+    /// Executes the next stage of a valset change that did not fit into the free redelegation entries.
+    /// The redelegations of pairs of validators that reached [`crate::contract::MAX_REDELEGATION_ENTRIES`]
+    /// wait until their earlier redelegations completed, see `QueryMsg::PendingValsetChange`.
+    /// Can be called by anyone.
+    ContinueValsetChange {},
+    /// Updates the liquidity discount used for the [`QueryMsg::TargetValue`] query.
+    /// Can be called by the owner or the operator.
     UpdateLiquidityDiscount { new_discount: Decimal },
+    /// Sets the part of the native tokens that is kept when unbonding, at most 2%.
+    /// Deferred unbonds are not charged. The fee can change by at most `max_discount_change_per_update`
+    /// and only once per `min_discount_update_interval`, like the liquidity discount. Can only be called by the owner.
+    UpdateUnbondFee { unbond_fee: Decimal },
+    /// Updates the relative slashing amount above which a validator is reported as tombstoned
+    /// by [`ExecuteMsg::CheckSlash`]. Must be in `(0, 0.5]`.
+    UpdateTombstoneThreshold { new_threshold: Decimal },
+    /// Updates the safety margin (in seconds) around unbondings where slashing detection is not allowed.
+    /// Must be smaller than half of the time between two unbondings.
+    UpdateSlashingSafetyMargin { new_margin: u64 },
+    /// Sets the minimum number of seconds between two reinvests, `None` disables the check.
+    /// Can be called by the owner or the operator.
+    UpdateMinReinvestSpacing { min_reinvest_spacing: Option<u64> },
+    /// Sets the minimum number of blocks per (unbonding) epoch, `None` disables the check.
+    /// Can be called by the owner or the operator.
+    UpdateMinBlocksPerEpoch { min_blocks_per_epoch: Option<u64> },
+    /// Sets the minimum number of seconds between bonding and unbonding from the same address, `0` disables the check
+    UpdateMinHoldingPeriod { min_holding_period: u64 },
+    /// Sets the minimum rewards a reinvest has to withdraw, smaller reinvests are skipped
+    /// unless claims need to be covered or bonds wait to be delegated. `0` disables the check
+    UpdateMinReinvestAmount { min_reinvest_amount: Uint128 },
+    /// Sets the smallest amount a reinvest delegates to a single validator.
+    /// Smaller amounts stay in the balance until the next reinvests. Can only be called by the owner.
+    UpdateMinDelegation { min_delegation: Uint128 },
+    /// Sets the vesting schedule for the commission. If set, the commission is bonded
+    /// and the lsd tokens are held by the hub until they vested. `None` sends the commission directly.
+    /// This only applies to commission taken afterwards, existing tranches keep their schedule.
+    UpdateCommissionVesting {
+        commission_vesting: Option<CommissionVesting>,
+    },
+    /// Sets how the surplus of a reinvest is split between the validators
+    UpdateSurplusStrategy { surplus_strategy: SurplusStrategy },
+    /// Stops delegating on reinvest, e.g. during a chain upgrade with slashing risk.
+    /// Rewards and deposits accumulate in the balance, while claims and undelegations still work.
+    /// Can be called by the owner or the operator.
+    SuspendDelegations {},
+    /// Reverts `ExecuteMsg::SuspendDelegations`, so the next reinvest delegates the accumulated balance.
+    /// Can be called by the owner or the operator.
+    ResumeDelegations {},
+    /// Sets the contract that is notified with a [`ReinvestHookMsg`] after every reinvest.
+    /// If it fails, the reinvest still succeeds. `None` removes it. Can only be called by the owner.
+    UpdateReinvestHook { hook: Option<String> },
+    /// Sets for how many seconds validators that are added to the validator set are on probation
+    /// and the most they get during that time, as a share of a reinvest's surplus or of the redelegated tokens.
+    /// The withheld part goes to the other validators. `0` disables the probation.
+    /// Can only be called by the owner.
+    UpdateValidatorProbation {
+        new_validator_probation: u64,
+        probation_max_weight: Decimal,
+    },
+    /// Sets by how much a validator's commission may increase between two reinvests before it is reported
+    /// and whether the weight of a reported validator is set to zero. Can only be called by the owner.
+    UpdateCommissionSpikeHandling {
+        max_commission_increase_alert: Decimal,
+        auto_zero_weight_on_spike: bool,
+    },
+    /// Sets on which reinvests the accumulated surplus is delegated: only on every `delegate_every_n_epochs`-th one.
+    /// Rewards are still withdrawn and their commission taken on every reinvest. `0` and `1` delegate every time.
+    /// Can be called by the owner or the operator.
+    UpdateDelegationCadence { delegate_every_n_epochs: u64 },
+    /// Makes reinvests fail while the issued tokens don't match the total supply of the lsd token,
+    /// see `QueryMsg::IssuedReconciliation`. Can only be called by the owner.
+    UpdateStrictSupplyCheck { strict_supply_check: bool },
+    /// Sets whether reinvests redelegate part of the drift between the delegations and the weights of the validator set.
+    /// Can only be called by the owner.
+    UpdateRebalanceOnReinvest { rebalance_on_reinvest: bool },
+    /// Sets whether valset changes route the redelegations of pairs without a free redelegation entry
+    /// through another validator of the new set, instead of waiting for `ExecuteMsg::ContinueValsetChange`.
+    /// The second hop is executed by the first reinvest after the first one completed. Can only be called by the owner.
+    UpdateRedelegationHops { redelegation_hops: bool },
+    /// Sets the most the exchange rate can grow with one reinvest, `None` for no limit.
+    /// Rewards above it are deferred to the next reinvests. Can only be called by the owner.
+    UpdateMaxRateGrowth {
+        max_rate_growth_per_epoch: Option<Decimal>,
+    },
+    /// Allows `contract` to buy claims with `ExecuteMsg::BuyClaim`. Can only be called by the owner.
+    RegisterClaimBuyer { contract: String },
+    /// Removes `contract` from the claim buyers again. Can only be called by the owner.
+    DeregisterClaimBuyer { contract: String },
+    /// Sets the most lsd tokens that can be issued, `None` for no limit.
+    /// If the lsd token has a mint cap, this can not be higher. Can only be called by the owner.
+    UpdateIssuanceCap { issuance_cap: Option<Uint128> },
+    /// Sets the issued tokens to the total supply of the lsd token, which is the source of truth
+    /// if they diverged. Can only be called by the owner.
+    SyncIssued {},
+    /// Sets the claims counter of the supply to the sum of all stored claims.
+    /// The arguments have to be taken from a complete `QueryMsg::ClaimsIntegrity` scan, which is only valid
+    /// until the counter changes again. Can only be called by the owner.
+    RepairClaimsCounter {
+        computed_sum: Uint128,
+        scanned_until: Option<String>,
+        checkpoint: String,
+    },
+    /// Sends all vested commission lsd tokens to the treasury. Can only be called by the treasury.
+    ClaimVestedCommission {},
+    /// Moves the next unbonding window one unbonding epoch further, so the next reinvest does not undelegate.
+    /// This gives the owner a chance to rebalance first. Claims that were already created keep
+    /// their release time and can only be claimed once the delayed undelegation completed.
+    SkipNextUnbond {},
+    /// Deposits native tokens that are delegated like everything else, but still belong to the owner.
+    /// Their staking rewards go to the holders, so this boosts the yield without a plain donation.
+    /// Can only be called by the owner.
+    DepositBoost {},
+    /// Undelegates up to the deposited boost principal through the normal unbonding process,
+    /// creating a claim for the treasury. Can only be called by the owner.
+    WithdrawBoost { amount: Uint128 },
+    /// Distributes the whole balance of `denom` held by this contract to the current holders.
+    /// Each holder gets their part when their lsd tokens are unbonded, see [`crate::state::EXTERNAL_REWARDS`].
+    /// Only the lsd tokens issued at registration are entitled, tokens bonded later get nothing when they exit.
+    /// Registering the same denom again restarts the distribution with the current balance and holders.
+    /// Can only be called by the owner.
+    RegisterExternalReward { denom: String },
+    /// Sends what is left of the external reward of `denom` to the treasury and stops distributing it,
+    /// for the part of holders that never exit (like the lsd tokens in a pool).
+    /// Only possible [`crate::contract::EXTERNAL_REWARD_SWEEP_DELAY`] after the registration.
+    /// Can only be called by the owner.
+    SweepExternalReward { denom: String },
+    /// Sets the operator, which can do the operational actions of the owner that don't touch
+    /// the validator set or the treasury. `None` revokes it. Can only be called by the owner.
+    SetOperator { operator: Option<String> },
+}
+
+#[cw_serde]
+pub struct CommissionVesting {
+    /// The number of seconds after the creation of a tranche before anything can be claimed
+    pub cliff_seconds: u64,
+    /// The number of seconds after the creation of a tranche until it is fully vested.
+    /// The tokens vest linearly over this duration.
+    pub duration_seconds: u64,
+}
+
+/// A validator of the validator set and its weight
+#[cw_serde]
+pub struct ValidatorConfig {
+    pub address: String,
+    pub weight: Decimal,
+    /// The most that is delegated to this validator. Whatever is above it is delegated to the validators
+    /// without a cap instead. If all validators have a cap, the rest stays undelegated.
+    #[serde(default)]
+    pub max_delegation: Option<Uint128>,
+}
+
+impl From<(String, Decimal)> for ValidatorConfig {
+    fn from((address, weight): (String, Decimal)) -> Self {
+        Self {
+            address,
+            weight,
+            max_delegation: None,
+        }
+    }
+}
+
+/// A validator in [`ExecuteMsg::SetValidators`].
+/// The `[address, weight]` pairs of older versions are still accepted.
+#[cw_serde]
+#[serde(untagged)]
+pub enum ValidatorEntry {
+    Config(ValidatorConfig),
+    Weighted(String, Decimal),
+}
+
+impl From<ValidatorEntry> for ValidatorConfig {
+    fn from(entry: ValidatorEntry) -> Self {
+        match entry {
+            ValidatorEntry::Config(config) => config,
+            ValidatorEntry::Weighted(address, weight) => (address, weight).into(),
+        }
+    }
+}
+
+impl From<ValidatorConfig> for ValidatorEntry {
+    fn from(config: ValidatorConfig) -> Self {
+        ValidatorEntry::Config(config)
+    }
+}
+
+impl From<(String, Decimal)> for ValidatorEntry {
+    fn from((address, weight): (String, Decimal)) -> Self {
+        ValidatorEntry::Weighted(address, weight)
+    }
+}
+
+/// How the surplus of a reinvest is delegated
+#[cw_serde]
+#[derive(Default)]
+pub enum SurplusStrategy {
+    /// Split by the weights of the validator set, which moves the delegations towards the targets
+    #[default]
+    TargetWeights,
+    /// Split pro-rata to the current delegations of the validators in the validator set.
+    /// This does not change the relative distribution, so there is no churn if it drifted from the targets.
+    /// Falls back to the weights if nothing is delegated to the validator set yet.
+    ProportionalToBonded,
+}
+
+impl std::fmt::Display for SurplusStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SurplusStrategy::TargetWeights => write!(f, "target_weights"),
+            SurplusStrategy::ProportionalToBonded => write!(f, "proportional_to_bonded"),
+        }
+    }
+}
+
+/// Sent to the `reinvest_hook` after every reinvest
+#[cw_serde]
+pub enum ReinvestHookMsg {
+    /// The exchange rate that was recorded by the reinvest
+    Reinvested { exchange_rate: Decimal },
+}
+
+/// Messages that can only be sent by the chain itself, e.g. by a cron module
+#[cw_serde]
+pub enum SudoMsg {
+    /// Same as [`ExecuteMsg::Reinvest`]
+    Reinvest {},
+    /// Same as [`ExecuteMsg::CheckSlash`]
+    CheckSlash {},
+    /// Verifies that the stored supply is consistent and returns an error otherwise,
+    /// so the chain stops the schedule instead of compounding a corrupted state
+    AssertInvariants {},
+    /// Sets how much and how often `ExecuteMsg::UpdateLiquidityDiscount` can change the liquidity discount.
+    /// This is only available to governance, so the owner cannot lift the limits right before a big change.
+    /// Governance is the intended slower path, there is no timelocked owner message for it.
+    /// The step must be above 0% and at most 100%, the interval at most [`crate::contract::MAX_DISCOUNT_UPDATE_INTERVAL`].
+    UpdateDiscountLimits {
+        max_discount_change_per_update: Decimal,
+        min_discount_update_interval: u64,
+    },
 }
 
 #[cw_serde]
 pub enum ReceiveMsg {
     /// Submit an unbonding request to the current unbonding queue by sending your cw20 tokens
     Unbond {},
+    /// Same as [`ReceiveMsg::Unbond`], but the claim stays denominated in lsd tokens.
+    /// They keep earning rewards and are exposed to slashing like any other lsd token
+    /// and are only converted to native tokens at the exchange rate at the time of claiming.
+    UnbondDeferredRate {},
+    /// Passes the sent lsd tokens on to the given recipients, split by the given weights.
+    /// The weights must add up to 1 and the rounding remainder goes to the first recipient.
+    /// At most [`crate::contract::MAX_REDISTRIBUTE_RECIPIENTS`] recipients are allowed per call.
+    Redistribute { recipients: Vec<(String, Decimal)> },
+    /// Burns the sent claim vouchers and pays their native value to the sender.
+    /// Only vouchers of unbonds that were released already can be redeemed, see `QueryMsg::VoucherBatches`.
+    RedeemVoucher {},
+    /// Same as [`ReceiveMsg::Unbond`], but a registered claim buyer can buy the claim before it is released,
+    /// paying its amount minus `discount` to the sender right away.
+    /// `discount` can be at most [`crate::contract::MAX_CLAIM_SALE_DISCOUNT`].
+    UnbondForSale { discount: Decimal },
 }
 
 #[cw_serde]
@@ -87,19 +428,56 @@ pub enum QueryMsg {
 
     /// Outstanding claims generated by a previous call to Unbond{}. After the chain's native
     /// unbonding period, they can be withdrawn.
-    #[returns(cw_controllers::ClaimsResponse)]
-    Claims { address: String },
+    /// All claims are returned, unless `start_after` (the position of the last claim received) or `limit` is set.
+    /// `status` only returns the matured or pending claims, the positions are counted among those.
+    #[returns(crate::claim::ClaimsResponse)]
+    Claims {
+        address: String,
+        #[serde(default)]
+        start_after: Option<u32>,
+        #[serde(default)]
+        limit: Option<u32>,
+        #[serde(default)]
+        status: Option<ClaimStatus>,
+    },
+
+    /// Outstanding claims generated by a previous call to UnbondDeferredRate{}.
+    /// The amounts are denominated in lsd tokens and converted to native tokens when claimed.
+    /// Paginated the same way as `Claims`.
+    #[returns(crate::claim::ClaimsResponse)]
+    DeferredClaims {
+        address: String,
+        #[serde(default)]
+        start_after: Option<u32>,
+        #[serde(default)]
+        limit: Option<u32>,
+    },
 
     /// The current validator set and percentages where the tokens are being distributed.
     #[returns(ValidatorSetResponse)]
     ValidatorSet {},
 
+    /// Like `ValidatorSet`, but together with the amount bonded to each validator and the resulting actual weight.
+    /// Validators that were removed from the set, but still have tokens bonded, are included with a target weight of 0.
+    #[returns(ValidatorSetWithBondedResponse)]
+    ValidatorSetWithBonded {},
+
     /// The last time Reinvest was called, and the next time it can be called.
     /// To enable a cron job to ping when needed
     #[returns(ReinvestResponse)]
     LastReinvest {},
 
-    /// Returns data about TVL, issued tokens, claims
+    /// The timestamps of the most recent reinvests and the average time between them.
+    /// This helps operators to see if the reinvest cadence is too tight.
+    #[returns(ReinvestHistoryResponse)]
+    ReinvestHistory {},
+
+    /// An estimate of the yearly reward rate, derived from the exchange rate growth between the last two reinvests.
+    /// Slashings in that time are not counted against it. This is not a prediction, the next epochs can differ.
+    #[returns(RewardRateResponse)]
+    RewardRate {},
+
+    /// Returns data about TVL, issued tokens, claims, together with the backing assets and exchange rate
     #[returns(SupplyResponse)]
     Supply {},
 
@@ -108,10 +486,268 @@ pub enum QueryMsg {
     #[returns(ExchangeRateResponse)]
     ExchangeRate {},
 
+    /// Returns the exchange rate as an exact fraction of native tokens (`assets`) per issued lsd token.
+    /// Use this instead of `ExchangeRate` for integer math, since the `Decimal` is rounded.
+    #[returns(ExchangeRateRawResponse)]
+    ExchangeRateRaw {},
+
     /// Like `ExchangeRate` but with the liquidity discount applied.
     /// This is used by the stable swap contract to determine what price to concentrate the liquidity around
     #[returns(TargetValueResponse)]
     TargetValue {},
+
+    /// Returns whether unbonding is currently possible, and a reason if it is not.
+    /// This allows a UI to disable unbonding instead of submitting a failing transaction.
+    #[returns(CanUnbondResponse)]
+    CanUnbond {},
+
+    /// Returns whether `ExecuteMsg::CheckSlash` can currently run, or when it can run next
+    /// if an unbonding completes within the `slashing_safety_margin`.
+    #[returns(CheckSlashAvailableResponse)]
+    CheckSlashAvailable {},
+
+    /// Compares the claims counter of the supply with the sum of all stored claims.
+    /// Big maps are scanned over multiple queries, pass the `continuation` of the last response
+    /// until it is `complete`.
+    #[returns(ClaimsIntegrityResponse)]
+    ClaimsIntegrity {
+        #[serde(default)]
+        continuation: Option<ClaimsScanContinuation>,
+    },
+
+    /// Returns the recorded slashings, which reduce the claims released during their unbonding period
+    #[returns(SlashingEventsResponse)]
+    SlashingEvents {},
+
+    /// Returns everything a pair needs to know about the lsd token in one query.
+    /// This is cheaper than querying `Config`, `Supply` and `TargetValue` separately.
+    #[returns(AssetInfoResponse)]
+    AssetInfo {},
+
+    /// Returns the parameters that determine when unbondings are batched together.
+    /// This allows a frontend to explain to users why their claim is released at a certain time.
+    #[returns(MaxConcurrentUnbondingsResponse)]
+    MaxConcurrentUnbondings {},
+
+    /// Returns an estimate of the commission the next reinvest would take, based on the rewards
+    /// currently pending on the validators that reinvest withdraws from.
+    /// The actual amounts can differ slightly, since rewards keep accruing until the reinvest is executed.
+    #[returns(PreviewCommissionResponse)]
+    PreviewCommission {},
+
+    /// Returns the most recent changes to `total_bonded`, oldest first.
+    /// Only the last [`crate::state::BONDED_JOURNAL_LEN`] entries are kept.
+    /// Pages hold at most [`crate::pagination::MAX_LIMIT`] entries.
+    #[returns(BondedJournalResponse)]
+    BondedJournal {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    /// Returns the vesting schedule and all commission tranches that were not fully claimed yet,
+    /// together with how much of them is vested.
+    #[returns(CommissionVestingResponse)]
+    CommissionVesting {},
+
+    /// Returns how the native value of the lsd tokens held by `address` changed since the given time
+    /// (in seconds), split into the effect of slashings and rewards.
+    /// This uses the exchange rate snapshot taken closest before `since`, so it fails if `since` is
+    /// before the earliest snapshot.
+    #[returns(ValueChangeResponse)]
+    ValueChange { address: String, since: u64 },
+
+    /// Returns the exchange rate of the latest snapshot taken at or before `time` (in seconds),
+    /// e.g. to weigh the lsd token balances of a past block by their native value.
+    /// Snapshots are taken on every reinvest that is not skipped as dust and on every slashing.
+    /// They are never pruned, so this only fails
+    /// if `time` is before the first snapshot.
+    #[returns(ExchangeRateAtResponse)]
+    ExchangeRateAt { time: u64 },
+
+    /// Checks the accounting invariants, like `SudoMsg::AssertInvariants`, but reports each of them
+    /// instead of failing. `false` in any field indicates a bug or an undetected slashing.
+    #[returns(HealthResponse)]
+    Health {},
+
+    /// Compares the issued tokens the hub tracks with the total supply of the lsd token
+    #[returns(IssuedReconciliationResponse)]
+    IssuedReconciliation {},
+
+    /// Returns the redelegations `ExecuteMsg::SetValidators` would currently execute for the given validator set,
+    /// and the resulting delegations. This uses the same calculation, so it fails in the same cases.
+    #[returns(PreviewValsetChangeResponse)]
+    PreviewValsetChange { new_validators: Vec<ValidatorEntry> },
+
+    /// Same as `PreviewValsetChange`, but also reports how many messages the change executes
+    /// and whether any pair of validators would exceed the chain's limit of pending redelegations,
+    /// counting the redelegations of earlier `ExecuteMsg::SetValidators` calls that did not complete yet.
+    #[returns(SimulateValsetChangeResponse)]
+    SimulateValsetChange { new_validators: Vec<ValidatorEntry> },
+
+    /// Returns the target of a valset change that is executed in stages, the redelegations that are still needed
+    /// to reach it and when `ExecuteMsg::ContinueValsetChange` can execute the next stage
+    #[returns(PendingValsetChangeResponse)]
+    PendingValsetChange {},
+
+    /// Returns the external rewards that are still being distributed and, if `address` is set,
+    /// how much of them that address would get for unbonding all of its lsd tokens now.
+    #[returns(ExternalRewardsResponse)]
+    ExternalRewards { address: Option<String> },
+
+    /// Returns the commission of each validator, as seen by the last reinvest that processed it
+    #[returns(ValidatorCommissionsResponse)]
+    ValidatorCommissions {},
+
+    /// Returns the validators that were detected as tombstoned by `ExecuteMsg::CheckSlash`,
+    /// with the last time (in seconds) they were detected
+    #[returns(TombstonedValidatorsResponse)]
+    TombstonedValidators {},
+
+    /// Returns how many tokens of the claims are still delegated, because they were not undelegated yet,
+    /// and their part of the rewards that were not withdrawn yet.
+    /// These rewards go to the remaining holders: unbonding stops earning at the unbond, not at the claim.
+    #[returns(AccruedButUnbondedValueResponse)]
+    AccruedButUnbondedValue {},
+
+    /// Returns the claim vouchers that were not redeemed yet, by the time their unbonds are released,
+    /// and how many of them can be redeemed now
+    #[returns(VoucherBatchesResponse)]
+    VoucherBatches {},
+
+    /// Combines `Config`, `Supply`, `ExchangeRate`, `TargetValue` and `ValidatorSetWithBonded`,
+    /// as well as the claims and lsd token balance of `address` if set, into one response.
+    /// All values are calculated from the same supply, so they are consistent with each other.
+    #[returns(DashboardResponse)]
+    Dashboard { address: Option<String> },
+
+    /// Returns how many lsd tokens `ExecuteMsg::Bond` would mint for `amount` native tokens in this block.
+    ///
+    /// When `ExecuteMsg::Bond` runs, the paid tokens are already part of the balance of the hub,
+    /// so it uses `balance - paid` as the balance before the deposit. A query sees the balance without
+    /// the deposit, so it must use the current balance as is. Calculating the amount from
+    /// `ExchangeRate` or `Supply` and the balance *with* the deposit is off by the deposit amount.
+    #[returns(SimulateBondResponse)]
+    SimulateBond { amount: Uint128 },
+
+    /// Returns how many lsd tokens have to be unbonded in this block to get a claim of at least `native` tokens,
+    /// taking the unbond fee into account. This is the inverse of the calculation of `ExecuteMsg::Unbond`.
+    #[returns(SharesToRedeemResponse)]
+    SharesToRedeem { native: Uint128 },
+
+    /// Returns the state of the bootstrap phase, see `InstantiateMsg::bootstrap_min_deposit`
+    #[returns(BootstrapStatusResponse)]
+    BootstrapStatus {},
+
+    /// Reports whether the balance of the hub can pay the claims right away,
+    /// or how much has to be undelegated for them, which takes the full unbonding period
+    #[returns(BufferUtilizationResponse)]
+    BufferUtilization {},
+}
+
+#[cw_serde]
+pub struct BootstrapStatusResponse {
+    /// Whether the hub is still bootstrapping. If not, the other fields are zero.
+    pub active: bool,
+    /// The deposits needed before anything is minted
+    pub min_deposit: Uint128,
+    /// The sum of all deposits so far
+    pub deposited: Uint128,
+    /// The part of `deposited` that was minted already
+    pub minted: Uint128,
+}
+
+#[cw_serde]
+pub struct BufferUtilizationResponse {
+    /// The native tokens held by the hub
+    pub buffer: Uint128,
+    /// The native tokens currently unbonding for claims
+    pub unbonding: Uint128,
+    /// The claims (including the current value of the deferred claims) that are not covered by the unbonding tokens,
+    /// so they have to be paid from the buffer. These are the matured claims and those waiting for their undelegation.
+    pub awaiting_payout: Uint128,
+    /// What the next reinvest in an unbonding epoch undelegates for the claims, after adding the pending rewards
+    /// (minus commission) to the buffer. `0` if the buffer and the unbonding tokens cover all claims.
+    pub shortfall: Uint128,
+    /// `awaiting_payout / buffer`, at most `1`. Below `1`, the buffer pays all claims that are due instantly.
+    pub utilization: Decimal,
+}
+
+#[cw_serde]
+pub struct SharesToRedeemResponse {
+    /// The lsd tokens to unbond, rounded up. This can be more than was issued if `native` is too high.
+    pub shares: Uint128,
+}
+
+#[cw_serde]
+pub struct SimulateBondResponse {
+    /// The lsd tokens minted for the bond, rounded down just like in `ExecuteMsg::Bond`
+    pub lsd_tokens: Uint128,
+    /// Whether the bond would fail, because it exceeds the issuance cap
+    pub exceeds_issuance_cap: bool,
+}
+
+#[cw_serde]
+pub struct DashboardResponse {
+    pub config: ConfigResponse,
+    pub supply: SupplyResponse,
+    pub exchange_rate: Decimal,
+    pub target_value: Decimal,
+    pub validators: Vec<ValidatorWithBonded>,
+    /// Only set if an address was given
+    pub account: Option<DashboardAccount>,
+}
+
+#[cw_serde]
+pub struct DashboardAccount {
+    pub address: String,
+    /// Same as the first page of `QueryMsg::Claims`
+    pub claims: crate::claim::ClaimsResponse,
+    /// The lsd token balance of the address
+    pub lsd_balance: Uint128,
+}
+
+#[cw_serde]
+pub struct VoucherBatchesResponse {
+    /// Time (in seconds) of the release and the outstanding vouchers, oldest first
+    pub batches: Vec<(u64, Uint128)>,
+    pub redeemable: Uint128,
+}
+
+#[cw_serde]
+pub struct AccruedButUnbondedValueResponse {
+    /// The native tokens of the claims that are neither unbonding nor in the balance of the hub
+    pub still_delegated: Uint128,
+    /// The part of the pending rewards (after commission) earned by `still_delegated`
+    pub accrued_rewards: Uint128,
+    /// The earliest time (in seconds) at which `still_delegated` is undelegated, the same as in
+    /// [`MaxConcurrentUnbondingsResponse::next_unbond`]
+    pub next_unbond: u64,
+}
+
+#[cw_serde]
+pub struct ValidatorCommissionsResponse {
+    pub commissions: Vec<(String, Decimal)>,
+}
+
+#[cw_serde]
+pub struct TombstonedValidatorsResponse {
+    pub validators: Vec<(String, u64)>,
+}
+
+#[cw_serde]
+pub struct ExternalRewardsResponse {
+    pub rewards: Vec<ExternalRewardResponse>,
+    /// The external rewards the given address would currently get, empty if no address was given
+    pub entitlements: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct ExternalRewardResponse {
+    pub denom: String,
+    /// The amount that is left to distribute
+    pub amount: Uint128,
+    /// The lsd tokens that are still entitled to a part of it
+    pub shares: Uint128,
 }
 
 #[cw_serde]
@@ -120,6 +756,22 @@ pub struct ValidatorSetResponse {
     pub validator_set: Vec<(String, Decimal)>,
 }
 
+#[cw_serde]
+pub struct ValidatorSetWithBondedResponse {
+    pub validators: Vec<ValidatorWithBonded>,
+}
+
+#[cw_serde]
+pub struct ValidatorWithBonded {
+    pub validator: String,
+    /// The weight of the validator in the validator set
+    pub target_weight: Decimal,
+    /// The amount of native tokens currently bonded to the validator
+    pub bonded: Uint128,
+    /// `bonded` divided by the total bonded amount
+    pub actual_weight: Decimal,
+}
+
 #[cw_serde]
 pub struct ReinvestResponse {
     /// The time in seconds the last reinvest occured
@@ -128,13 +780,48 @@ pub struct ReinvestResponse {
     pub next_reinvest: u64,
 }
 
+#[cw_serde]
+#[derive(Default)]
+pub struct RewardRateResponse {
+    /// The estimated yearly reward rate of the lsd token, e.g. `0.1` for 10%.
+    /// This is `0` if there were less than two reinvests yet.
+    pub apr: Decimal,
+    /// The time (in seconds) of the second to last reinvest, which the estimate starts at
+    pub from: u64,
+    /// The time (in seconds) of the last reinvest, which the estimate ends at
+    pub to: u64,
+}
+
+#[cw_serde]
+pub struct ReinvestHistoryResponse {
+    /// The times in seconds of the most recent reinvests, oldest first
+    pub reinvests: Vec<u64>,
+    /// The average number of seconds between those reinvests, `None` if there were less than two
+    pub average_spacing: Option<u64>,
+    /// The configured minimum number of seconds between two reinvests
+    pub min_reinvest_spacing: Option<u64>,
+}
+
 #[cw_serde]
 pub struct ExchangeRateResponse {
     /// Current exchange rate between the LSD token (always appreciating) and the underlying native token.
-    /// This should satisfy: `exchange_rate * lsd_amount = native_amount`
+    /// This should satisfy: `exchange_rate * lsd_amount = native_amount`, both in base units.
+    /// See [`ConfigResponse`] for converting it to display units.
     pub exchange_rate: Decimal,
 }
 
+/// Exact version of `ExchangeRateResponse`.
+/// Bonding `tokens` issues `tokens * issued / assets` lsd tokens (rounded down) and unbonding `shares`
+/// is worth `shares * assets / issued` native tokens (rounded down).
+/// If either of them is zero, the contract bonds 1:1.
+#[cw_serde]
+pub struct ExchangeRateRawResponse {
+    /// The native tokens backing all issued lsd tokens
+    pub assets: Uint128,
+    /// The total amount of lsd tokens issued
+    pub issued: Uint128,
+}
+
 /// Like `ExchangeRateResponse` but with the liquidity discount applied
 #[cw_serde]
 pub struct TargetValueResponse {
@@ -142,6 +829,247 @@ pub struct TargetValueResponse {
     pub target_value: Decimal,
 }
 
+#[cw_serde]
+pub struct AssetInfoResponse {
+    /// The denomination of the native staking token
+    pub bond_denom: String,
+    /// The address of the lsd token contract
+    pub lsd_token: Addr,
+    /// Same as [`ExchangeRateResponse::exchange_rate`]
+    pub exchange_rate: Decimal,
+    /// Same as [`TargetValueResponse::target_value`]
+    pub target_value: Decimal,
+}
+
+#[cw_serde]
+pub struct PreviewCommissionResponse {
+    /// The rewards currently pending on all validators in the validator set
+    pub gross_rewards: Uint128,
+    /// The part of the rewards that would be sent to the treasury
+    pub commission: Uint128,
+    /// The part of the rewards that would be reinvested
+    pub net_rewards: Uint128,
+}
+
+#[cw_serde]
+pub struct MaxConcurrentUnbondingsResponse {
+    /// The maximum number of unbonding queue entries per validator at any time
+    pub max_concurrent_unbondings: u64,
+    /// The time in seconds between two unbonding batches
+    pub unbond_epoch_seconds: u64,
+    /// The time in seconds at which the next unbonding batch starts.
+    /// Unbonding now creates a claim that is released `unbond_period` after this.
+    pub next_unbond: u64,
+    /// The staking module's unbonding time, in seconds
+    pub unbond_period: u64,
+}
+
+/// Why `total_bonded` changed
+#[cw_serde]
+pub enum BondedChangeReason {
+    /// Reinvest delegated rewards and new deposits
+    Delegate,
+    /// Reinvest undelegated tokens to cover claims
+    Undelegate,
+    /// Redelegation to a new validator set
+    Redelegate,
+    /// Slashing was detected
+    Slash,
+}
+
+#[cw_serde]
+pub struct PreviewValsetChangeResponse {
+    pub redelegations: Vec<Redelegation>,
+    /// The delegations to each validator after the redelegations
+    pub new_balances: Vec<(String, Uint128)>,
+}
+
+#[cw_serde]
+pub struct PendingValsetChangeResponse {
+    /// The validator set the delegations are moved to, `None` if no valset change is pending
+    pub target: Option<Vec<(String, Decimal)>>,
+    /// The redelegations that are still needed to reach the target
+    pub remaining: Vec<Redelegation>,
+    /// When the next stage can be executed (in seconds), `None` if no valset change is pending
+    pub ready_at: Option<u64>,
+    /// The redelegations that were routed through an intermediate validator and still need their second hop.
+    /// They are executed by reinvests, independent of the `target`.
+    #[serde(default)]
+    pub hops: Vec<crate::state::RedelegationHop>,
+}
+
+#[cw_serde]
+pub struct SimulateValsetChangeResponse {
+    pub redelegations: Vec<Redelegation>,
+    /// The delegations to each validator after the redelegations
+    pub new_balances: Vec<(String, Uint128)>,
+    /// The number of staking messages `ExecuteMsg::SetValidators` would execute
+    pub message_count: u32,
+    /// The redelegations for each pair of validators
+    pub pairs: Vec<RedelegationPair>,
+    /// Whether any of the `pairs` exceeds the limit, in which case the change would fail
+    pub exceeds_entry_cap: bool,
+}
+
+#[cw_serde]
+pub struct RedelegationPair {
+    pub src: String,
+    pub dst: String,
+    /// The number of redelegations of this change
+    pub redelegations: u32,
+    /// The number of earlier redelegations that are not completed yet
+    pub pending: u32,
+    /// Whether `redelegations + pending` is more than [`crate::contract::MAX_REDELEGATION_ENTRIES`]
+    pub exceeds_entry_cap: bool,
+}
+
+#[cw_serde]
+pub struct Redelegation {
+    pub src: String,
+    pub dst: String,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct IssuedReconciliationResponse {
+    /// `issued` of the supply
+    pub issued_counter: Uint128,
+    /// The total supply reported by the lsd token
+    pub cw20_total_supply: Uint128,
+    /// The absolute difference of the two
+    pub diff: Uint128,
+}
+
+#[cw_serde]
+pub struct HealthResponse {
+    /// Whether the stored per-validator delegations add up to `total_bonded`
+    pub bonded_sum_matches_total_bonded: bool,
+    /// Whether `issued` matches the total supply of the lsd token
+    pub issued_matches_cw20_supply: bool,
+    /// Whether the claims are covered by the balance and the bonded and unbonding tokens
+    pub claims_backed_by_coverage: bool,
+    /// Whether the unbonding queue (including mature entries that were not cleaned up yet)
+    /// adds up to `total_unbonding`
+    pub total_unbonding_matches_unbonding_map: bool,
+}
+
+#[cw_serde]
+pub struct ValueChangeResponse {
+    /// The current lsd token balance of the address
+    pub lsd_balance: Uint128,
+    /// The native value of `lsd_balance` at the exchange rate of the snapshot
+    pub value_then: Uint128,
+    /// The native value of `lsd_balance` at the current exchange rate
+    pub value_now: Uint128,
+    /// How much of the value was lost to slashings since the snapshot, e.g. `0.1` for 10%
+    pub slash_impact: Decimal,
+    /// How much the value grew through rewards since the snapshot, e.g. `0.05` for 5%.
+    /// `value_now` is roughly `value_then * (1 - slash_impact) * (1 + reward_growth)`.
+    pub reward_growth: Decimal,
+}
+
+#[cw_serde]
+pub struct CommissionVestingResponse {
+    /// The schedule for new tranches, `None` if the commission is not vested
+    pub commission_vesting: Option<CommissionVesting>,
+    /// The tranches, oldest first
+    pub tranches: Vec<VestingTrancheResponse>,
+    /// The amount of lsd tokens the treasury can claim right now
+    pub claimable: Uint128,
+}
+
+#[cw_serde]
+pub struct VestingTrancheResponse {
+    /// The time (in seconds) the tranche was created
+    pub created: u64,
+    /// The total amount of lsd tokens in the tranche
+    pub amount: Uint128,
+    /// The amount that was already sent to the treasury
+    pub claimed: Uint128,
+    /// The amount that is vested, including the claimed tokens
+    pub vested: Uint128,
+    /// The amount that is not vested yet
+    pub unvested: Uint128,
+}
+
+#[cw_serde]
+pub struct BondedJournalEntry {
+    /// Sequence number of this entry
+    pub id: u64,
+    /// The time in seconds of the change
+    pub time: u64,
+    /// The block height of the change
+    pub height: u64,
+    pub reason: BondedChangeReason,
+    /// The signed change of `total_bonded`, as a string because it might not fit into a json number
+    pub delta: String,
+    /// `total_bonded` after the change
+    pub new_total: Uint128,
+}
+
+#[cw_serde]
+pub struct BondedJournalResponse {
+    pub entries: Vec<BondedJournalEntry>,
+    /// The `start_after` for the next page, `None` if there are no more entries
+    #[serde(default)]
+    pub next_start_after: Option<u64>,
+}
+
+#[cw_serde]
+pub struct ExchangeRateAtResponse {
+    pub exchange_rate: Decimal,
+    /// When the snapshot was taken (in seconds), at or before the requested time
+    pub snapshot_time: u64,
+}
+
+#[cw_serde]
+pub struct CanUnbondResponse {
+    pub can_unbond: bool,
+    /// Explains why unbonding is not possible, `None` if it is
+    pub reason: Option<String>,
+}
+
+#[cw_serde]
+pub struct CheckSlashAvailableResponse {
+    pub available: bool,
+    /// The next time (in seconds) it is available, `None` if it is available now
+    pub next_available: Option<u64>,
+}
+
+/// Where a `ClaimsIntegrity` scan stopped and what it found so far
+#[cw_serde]
+pub struct ClaimsScanContinuation {
+    pub start_after: String,
+    pub computed_sum: Uint128,
+    pub scanned_addresses: u64,
+}
+
+#[cw_serde]
+pub struct ClaimsIntegrityResponse {
+    /// The claims counter of the supply
+    pub stored_counter: Uint128,
+    /// The sum of the claims scanned so far, adjusted for slashing
+    pub computed_sum: Uint128,
+    pub scanned_addresses: u64,
+    /// Whether all claims were scanned
+    pub complete: bool,
+    /// The last address scanned so far, `None` if there are no claims
+    pub scanned_until: Option<String>,
+    /// Pass this to the next query to continue the scan, `None` if it is complete
+    pub continuation: Option<ClaimsScanContinuation>,
+    /// Has to be passed to `ExecuteMsg::RepairClaimsCounter`, `None` if the scan is not complete
+    pub checkpoint: Option<String>,
+}
+
+#[cw_serde]
+pub struct SlashingEventsResponse {
+    pub slashings: Vec<Slashing>,
+}
+
+/// All amounts of the hub are in base units and the exchange rate is unit-for-unit: one base unit of the
+/// lsd token is worth `exchange_rate` base units of the bond denom, whatever the decimals are.
+/// In display units, one lsd token is worth `exchange_rate * 10^(token_decimals - bond_denom_decimals)`
+/// bond tokens, so the two only match if the decimals are the same.
 #[cw_serde]
 pub struct ConfigResponse {
     pub owner: Addr,
@@ -150,11 +1078,68 @@ pub struct ConfigResponse {
     pub commission: Decimal,
     pub epoch_period: u64,
     pub unbond_period: u64,
+    #[serde(default)]
+    pub surplus_strategy: SurplusStrategy,
+    #[serde(default)]
+    pub delegations_suspended: bool,
+    #[serde(default)]
+    pub reinvest_hook: Option<Addr>,
+    #[serde(default)]
+    pub new_validator_probation: u64,
+    #[serde(default)]
+    pub probation_max_weight: Decimal,
+    /// The number of decimals of the bond denom, as set on instantiation
+    #[serde(default)]
+    pub bond_denom_decimals: u8,
+    /// The number of decimals of the lsd token, as reported by the token contract
+    #[serde(default)]
+    pub token_decimals: u8,
+    /// The decimals `token_decimals` were checked against on instantiation, `None` if they were not checked
+    #[serde(default)]
+    pub expected_bond_decimals: Option<u8>,
+    #[serde(default)]
+    pub max_commission_increase_alert: Decimal,
+    #[serde(default)]
+    pub auto_zero_weight_on_spike: bool,
+    #[serde(default)]
+    pub delegate_every_n_epochs: u64,
+    #[serde(default)]
+    pub strict_supply_check: bool,
+    #[serde(default)]
+    pub unbond_fee: Decimal,
+    #[serde(default)]
+    pub issuance_cap: Option<Uint128>,
+    /// The claim voucher, `None` if unbonding creates claims
+    #[serde(default)]
+    pub voucher_contract: Option<Addr>,
+    #[serde(default)]
+    pub rebalance_on_reinvest: bool,
+    /// Can do the operational actions of the owner, see `ExecuteMsg::SetOperator`
+    #[serde(default)]
+    pub operator: Option<Addr>,
+    #[serde(default)]
+    pub redelegation_hops: bool,
+    #[serde(default)]
+    pub max_rate_growth_per_epoch: Option<Decimal>,
+    #[serde(default)]
+    pub min_delegation: Uint128,
+    #[serde(default)]
+    pub valoper_prefix: String,
 }
 
 #[cw_serde]
 pub struct SupplyResponse {
     pub supply: Supply,
+    /// The current native balance of the contract
+    #[serde(default)]
+    pub balance: Uint128,
+    /// The native tokens backing all issued lsd tokens:
+    /// `total_bonded + total_unbonding + balance - claims`
+    #[serde(default)]
+    pub backing_assets: Uint128,
+    /// Same as [`ExchangeRateResponse::exchange_rate`]
+    #[serde(default)]
+    pub exchange_rate: Decimal,
 }
 
 #[cw_serde]
@@ -171,6 +1156,8 @@ pub struct Supply {
     /// the total amount of tokens that are currently unbonding
     /// this should always be equal to `supply.unbonding.into_iter().map(|u| u.amount).sum()`
     pub total_unbonding: Uint128,
+    /// how many of the issued derivative tokens are locked in deferred claims
+    pub deferred_claim_shares: Uint128,
 }
 
 #[cw_serde]
@@ -0,0 +1,122 @@
+use cosmwasm_std::Uint128;
+
+use crate::{multitest::suite::SuiteBuilder, state::BOOST_PRINCIPAL, ContractError};
+
+const HOUR: u64 = 60 * 60;
+const EPOCH: u64 = 23 * HOUR;
+
+#[test]
+fn boost_raises_reward_income() {
+    let delegator = "delegator";
+    let owner = "owner";
+    let amount = 1_000_000u128;
+    let mut boosted = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount), (owner, amount)])
+        .build();
+    let mut plain = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .build();
+
+    // only the owner can deposit a boost
+    let err = boosted.deposit_boost(delegator, amount).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    for suite in [&mut boosted, &mut plain] {
+        suite.bond(delegator, amount).unwrap();
+        suite.update_time(EPOCH);
+        suite.reinvest().unwrap();
+    }
+
+    // the boost doesn't move the exchange rate
+    let rate = boosted.query_exchange_rate().unwrap();
+    boosted.deposit_boost(owner, amount).unwrap();
+    assert_eq!(boosted.query_exchange_rate().unwrap(), rate);
+    assert_eq!(
+        BOOST_PRINCIPAL
+            .query(&boosted.app.wrap(), boosted.hub.clone())
+            .unwrap(),
+        Uint128::new(amount)
+    );
+
+    for _ in 0..5 {
+        for suite in [&mut boosted, &mut plain] {
+            suite.update_time(EPOCH);
+            suite.reinvest().unwrap();
+        }
+    }
+
+    // the boost is delegated and its rewards go to the holders
+    let delegated: u128 = boosted
+        .query_delegations()
+        .unwrap()
+        .iter()
+        .map(|d| d.amount.amount.u128())
+        .sum();
+    assert!(delegated >= 2 * amount);
+    assert!(boosted.query_exchange_rate().unwrap() > plain.query_exchange_rate().unwrap());
+}
+
+#[test]
+fn withdraw_boost_returns_the_principal() {
+    let delegator = "delegator";
+    let owner = "owner";
+    let treasury = "treasury";
+    let amount = 1_000_000u128;
+    let boost = 500_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount), (owner, boost)])
+        .build();
+
+    suite.bond(delegator, amount).unwrap();
+    suite.deposit_boost(owner, boost).unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+
+    // only the owner can withdraw, and at most the principal
+    let err = suite.withdraw_boost(treasury, boost).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    let err = suite.withdraw_boost(owner, boost + 1).unwrap_err();
+    assert_eq!(
+        ContractError::InsufficientBoostPrincipal {
+            principal: Uint128::new(boost)
+        },
+        err.downcast().unwrap()
+    );
+
+    let mut rate = suite.query_exchange_rate().unwrap();
+    suite.withdraw_boost(owner, boost).unwrap();
+    assert!(suite.query_exchange_rate().unwrap() >= rate);
+    assert_eq!(
+        BOOST_PRINCIPAL
+            .query(&suite.app.wrap(), suite.hub.clone())
+            .unwrap(),
+        Uint128::zero()
+    );
+    let claims = suite.query_claims(treasury.to_string()).unwrap();
+    assert_eq!(claims.len(), 1);
+    assert_eq!(claims[0].amount, Uint128::new(boost));
+
+    // the holders' rate never drops while the principal is undelegated
+    for _ in 0..36 {
+        suite.update_time(EPOCH);
+        suite.reinvest().unwrap();
+        let new_rate = suite.query_exchange_rate().unwrap();
+        assert!(
+            new_rate >= rate,
+            "rate dropped from {} to {}",
+            rate,
+            new_rate
+        );
+        rate = new_rate;
+    }
+
+    // the treasury gets exactly the principal back (on top of the commission it received)
+    let before = suite.query_balance(treasury, "FUN").unwrap();
+    suite.claim(treasury).unwrap();
+    assert_eq!(
+        suite.query_balance(treasury, "FUN").unwrap() - before,
+        boost
+    );
+}
@@ -0,0 +1,117 @@
+use cosmwasm_std::{Decimal, Uint128};
+
+use crate::{contract::MAX_BOOTSTRAP_MINTS, multitest::suite::SuiteBuilder, ContractError};
+
+const HOUR: u64 = 60 * 60;
+const EPOCH: u64 = 23 * HOUR;
+
+#[test]
+fn deposits_are_minted_together_once_the_threshold_is_reached() {
+    let depositors = ["alice", "bob", "carol"];
+    let whale = "whale";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![
+            (depositors[0], 100_000),
+            (depositors[1], 200_000),
+            (depositors[2], 300_000),
+            (whale, 1_000_000),
+        ])
+        .with_bootstrap(1_000_000)
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    for (i, depositor) in depositors.iter().enumerate() {
+        suite.bond(depositor, 100_000 * (i as u128 + 1)).unwrap();
+        assert_eq!(suite.query_cw20_balance(depositor, &lsd).unwrap(), 0);
+    }
+    let status = suite.query_bootstrap_status().unwrap();
+    assert!(status.active);
+    assert_eq!(status.deposited.u128(), 600_000);
+    assert_eq!(status.minted, Uint128::zero());
+
+    // nothing can be unbonded or reinvested yet, and the deposits are not ready to be minted
+    let err = suite.unbond(depositors[0], &lsd, 1).unwrap_err();
+    assert_eq!(ContractError::Bootstrapping {}, err.downcast().unwrap());
+    suite.update_time(EPOCH);
+    let err = suite.reinvest().unwrap_err();
+    assert_eq!(ContractError::Bootstrapping {}, err.downcast().unwrap());
+    let err = suite.finalize_bootstrap(None).unwrap_err();
+    assert_eq!(
+        ContractError::BootstrapThresholdNotReached {
+            deposited: Uint128::new(600_000),
+            min_deposit: Uint128::new(1_000_000),
+        },
+        err.downcast().unwrap()
+    );
+
+    // the deposit that crosses the threshold mints everything at 1:1
+    suite.bond(whale, 500_000).unwrap();
+    for (i, depositor) in depositors.iter().enumerate() {
+        assert_eq!(
+            suite.query_cw20_balance(depositor, &lsd).unwrap(),
+            100_000 * (i as u128 + 1)
+        );
+    }
+    assert_eq!(suite.query_cw20_balance(whale, &lsd).unwrap(), 500_000);
+    assert!(!suite.query_bootstrap_status().unwrap().active);
+    assert_eq!(suite.query_exchange_rate().unwrap(), Decimal::one());
+
+    // then the hub works as usual
+    suite.bond(whale, 500_000).unwrap();
+    assert_eq!(suite.query_cw20_balance(whale, &lsd).unwrap(), 1_000_000);
+    suite.reinvest().unwrap();
+    suite.unbond(depositors[0], &lsd, 100_000).unwrap();
+}
+
+#[test]
+fn donation_during_bootstrap_does_not_change_the_minted_amounts() {
+    let (attacker, victim) = ("attacker", "victim");
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(attacker, 1), (victim, 999_999)])
+        .with_bootstrap(1_000_000)
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    suite.bond(attacker, 1).unwrap();
+    suite.mint_to_hub(500_000, "FUN").unwrap();
+    suite.bond(victim, 999_999).unwrap();
+
+    assert_eq!(suite.query_cw20_balance(attacker, &lsd).unwrap(), 1);
+    assert_eq!(suite.query_cw20_balance(victim, &lsd).unwrap(), 999_999);
+    // the donation is shared by everyone, in proportion to their deposits
+    assert_eq!(suite.query_exchange_rate().unwrap(), Decimal::percent(150));
+}
+
+#[test]
+fn many_depositors_are_minted_in_batches() {
+    let depositors: Vec<_> = (0..MAX_BOOTSTRAP_MINTS + 5)
+        .map(|i| format!("depositor{i:02}"))
+        .collect();
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(depositors.iter().map(|d| (d.as_str(), 1_000)).collect())
+        .with_bootstrap(1_000 * depositors.len() as u128)
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    for depositor in &depositors {
+        suite.bond(depositor, 1_000).unwrap();
+    }
+    // the last deposit minted the first batch
+    let status = suite.query_bootstrap_status().unwrap();
+    assert!(status.active);
+    assert_eq!(status.minted.u128(), 1_000 * MAX_BOOTSTRAP_MINTS as u128);
+
+    suite.finalize_bootstrap(Some(2)).unwrap();
+    assert_eq!(
+        suite.query_bootstrap_status().unwrap().minted.u128(),
+        1_000 * (MAX_BOOTSTRAP_MINTS as u128 + 2)
+    );
+    suite.finalize_bootstrap(None).unwrap();
+    assert!(!suite.query_bootstrap_status().unwrap().active);
+    for depositor in &depositors {
+        assert_eq!(suite.query_cw20_balance(depositor, &lsd).unwrap(), 1_000);
+    }
+
+    let err = suite.finalize_bootstrap(None).unwrap_err();
+    assert_eq!(ContractError::NotBootstrapping {}, err.downcast().unwrap());
+}
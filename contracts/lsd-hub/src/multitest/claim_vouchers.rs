@@ -0,0 +1,127 @@
+use cosmwasm_std::{Addr, Uint128};
+use cw_multi_test::Executor;
+
+use super::suite::SuiteBuilder;
+use crate::ContractError;
+
+const HOUR: u64 = 60 * 60;
+
+#[test]
+fn vouchers_can_be_transferred_and_redeemed_once_released() {
+    let (delegator, buyer) = ("delegator", "buyer");
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .with_claim_vouchers()
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+    let voucher = suite.query_voucher_token().unwrap();
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+
+    // unbonding mints vouchers instead of creating a claim
+    suite.unbond(delegator, &lsd, 400_000).unwrap();
+    assert!(suite
+        .query_claims(delegator.to_string())
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        suite.query_cw20_balance(delegator, &voucher).unwrap(),
+        400_000
+    );
+    let batches = suite.query_voucher_batches().unwrap();
+    assert_eq!(batches.batches.len(), 1);
+    assert_eq!(batches.batches[0].1, Uint128::new(400_000));
+    assert_eq!(batches.redeemable, Uint128::zero());
+
+    // the vouchers are a regular cw20
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(delegator),
+            voucher.clone(),
+            &cw20::Cw20ExecuteMsg::Transfer {
+                recipient: buyer.to_string(),
+                amount: Uint128::new(150_000),
+            },
+            &[],
+        )
+        .unwrap();
+    let err = suite.redeem_voucher(buyer, 150_000).unwrap_err();
+    assert_eq!(
+        ContractError::ImmatureVoucher {
+            available: Uint128::zero()
+        },
+        err.downcast().unwrap()
+    );
+
+    // keep reinvesting until the unbonding is released
+    let release_at = batches.batches[0].0;
+    while suite.app.block_info().time.seconds() <= release_at {
+        suite.update_time(23 * HOUR);
+        suite.reinvest().unwrap();
+    }
+
+    // whoever holds the vouchers gets the native tokens
+    suite.redeem_voucher(buyer, 150_000).unwrap();
+    assert_eq!(suite.query_balance(buyer, "FUN").unwrap(), 150_000);
+    assert_eq!(suite.query_cw20_balance(buyer, &voucher).unwrap(), 0);
+
+    // vouchers of a later unbond can not be redeemed with the released batch
+    suite.unbond(delegator, &lsd, 100_000).unwrap();
+    let later = suite.query_cw20_balance(delegator, &voucher).unwrap() - 250_000;
+    // the exchange rate grew with the reinvests
+    assert!(later > 100_000);
+    let err = suite.redeem_voucher(delegator, 350_000).unwrap_err();
+    assert_eq!(
+        ContractError::ImmatureVoucher {
+            available: Uint128::new(250_000)
+        },
+        err.downcast().unwrap()
+    );
+    suite.redeem_voucher(delegator, 250_000).unwrap();
+    assert_eq!(suite.query_balance(delegator, "FUN").unwrap(), 250_000);
+
+    // only the vouchers of the last unbond are left, and they are still counted as claims
+    let batches = suite.query_voucher_batches().unwrap();
+    assert_eq!(batches.batches.len(), 1);
+    assert_eq!(batches.batches[0].1, Uint128::new(later));
+    assert_eq!(
+        suite.query_supply().unwrap().supply.claims,
+        Uint128::new(later)
+    );
+    let integrity = suite.query_claims_integrity(None).unwrap();
+    assert_eq!(integrity.computed_sum, integrity.stored_counter);
+    assert_eq!(
+        suite.query_cw20_balance(delegator, &voucher).unwrap(),
+        later
+    );
+}
+
+#[test]
+fn only_vouchers_can_be_redeemed() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000)])
+        .with_claim_vouchers()
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+    suite.bond(delegator, 1_000).unwrap();
+
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(delegator),
+            lsd,
+            &cw20::Cw20ExecuteMsg::Send {
+                contract: suite.hub.to_string(),
+                amount: Uint128::new(1_000),
+                msg: cosmwasm_std::to_json_binary(&crate::msg::ReceiveMsg::RedeemVoucher {})
+                    .unwrap(),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::InvalidToken {}, err.downcast().unwrap());
+}
@@ -0,0 +1,154 @@
+use cosmwasm_std::{assert_approx_eq, Decimal, Uint128};
+
+use crate::{multitest::suite::SuiteBuilder, state::SUPPLY};
+
+const DAY: u64 = 24 * HOUR;
+const HOUR: u64 = 60 * 60;
+
+#[test]
+fn deferred_claim_earns_rewards_while_unbonding() {
+    let delegators = &["regular", "deferred"];
+    let amount = 1_000_000u128;
+    let unbond_amount = 500_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegators[0], amount), (delegators[1], amount)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    suite.bond(delegators[0], amount).unwrap();
+    suite.bond(delegators[1], amount).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+
+    // both unbond the same amount at the same exchange rate, but in different modes
+    suite.unbond(delegators[0], &lsd, unbond_amount).unwrap();
+    suite
+        .unbond_deferred(delegators[1], &lsd, unbond_amount)
+        .unwrap();
+    assert_eq!(
+        suite.query_claims(delegators[0].to_string()).unwrap()[0].amount,
+        Uint128::new(unbond_amount)
+    );
+    let deferred = suite
+        .query_deferred_claims(delegators[1].to_string())
+        .unwrap();
+    assert_eq!(deferred.len(), 1);
+    assert_eq!(deferred[0].amount, Uint128::new(unbond_amount));
+
+    // the deferred shares are still issued, but not part of the claims
+    let supply = SUPPLY.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
+    assert_eq!(supply.issued.u128(), 2 * amount - unbond_amount);
+    assert_eq!(supply.claims.u128(), unbond_amount);
+    assert_eq!(supply.deferred_claim_shares.u128(), unbond_amount);
+
+    // both claims are undelegated in the next epoch
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+    let supply = SUPPLY.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
+    assert!(!supply.total_unbonding.is_zero());
+
+    // keep reinvesting until the claims are released
+    for _ in 0..31 {
+        suite.update_time(23 * HOUR);
+        suite.reinvest().unwrap();
+    }
+    let rate = suite.query_exchange_rate().unwrap();
+    assert!(rate > Decimal::one());
+
+    // the regular claim was fixed at unbond time
+    suite.claim(delegators[0]).unwrap();
+    assert_eq!(
+        suite.query_balance(delegators[0], "FUN").unwrap(),
+        unbond_amount
+    );
+
+    // the deferred claim is converted at the current rate, just like the tokens that were kept
+    suite.claim(delegators[1]).unwrap();
+    let deferred_payout = suite.query_balance(delegators[1], "FUN").unwrap();
+    assert!(deferred_payout > unbond_amount);
+    assert_approx_eq!(
+        deferred_payout,
+        Uint128::new(unbond_amount).mul_floor(rate).u128(),
+        "0.0001"
+    );
+    assert!(
+        suite.query_exchange_rate().unwrap() >= rate,
+        "claiming should not lower the exchange rate"
+    );
+
+    // the shares are gone now
+    let supply = SUPPLY.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
+    assert_eq!(supply.issued.u128(), 2 * amount - 2 * unbond_amount);
+    assert_eq!(supply.deferred_claim_shares, Uint128::zero());
+    assert_eq!(
+        suite.query_cw20_balance(suite.hub.as_str(), &lsd).unwrap(),
+        0
+    );
+    assert!(suite
+        .query_deferred_claims(delegators[1].to_string())
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn deferred_claim_is_slashed_through_the_exchange_rate() {
+    let delegators = &["regular", "deferred"];
+    let amount = 1_000_000u128;
+    let unbond_amount = 500_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegators[0], amount), (delegators[1], amount)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    suite.bond(delegators[0], amount).unwrap();
+    suite.bond(delegators[1], amount).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+
+    suite.unbond(delegators[0], &lsd, unbond_amount).unwrap();
+    suite
+        .unbond_deferred(delegators[1], &lsd, unbond_amount)
+        .unwrap();
+
+    // undelegate for both claims, then get slashed by 10% while unbonding
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+    suite.update_time(HOUR);
+    suite.slash("testvaloper1", Decimal::percent(10)).unwrap();
+    suite.check_slash().unwrap();
+
+    // the regular claims only take the slashing of their part of the unbonding tokens
+    let supply = SUPPLY.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
+    assert_approx_eq!(supply.claims.u128(), unbond_amount * 9 / 10, "0.0001");
+    assert_eq!(supply.deferred_claim_shares.u128(), unbond_amount);
+    // and the deferred claims lose the same as the holders
+    let rate = suite.query_exchange_rate().unwrap();
+    assert!(rate > Decimal::percent(89) && rate < Decimal::percent(91));
+
+    // keep reinvesting until the claims are released
+    for _ in 0..30 {
+        suite.update_time(23 * HOUR);
+        suite.reinvest().unwrap();
+    }
+    suite.update_time(DAY);
+    let rate = suite.query_exchange_rate().unwrap();
+
+    suite.claim(delegators[0]).unwrap();
+    let regular_payout = suite.query_balance(delegators[0], "FUN").unwrap();
+    assert_approx_eq!(regular_payout, unbond_amount * 9 / 10, "0.0001");
+
+    // the deferred claim lost the slashed amount as well, but earned the rewards since then
+    suite.claim(delegators[1]).unwrap();
+    let deferred_payout = suite.query_balance(delegators[1], "FUN").unwrap();
+    assert!(deferred_payout < unbond_amount);
+    assert!(deferred_payout > regular_payout);
+    assert_approx_eq!(
+        deferred_payout,
+        Uint128::new(unbond_amount).mul_floor(rate).u128(),
+        "0.0001"
+    );
+
+    let supply = SUPPLY.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
+    assert_eq!(supply.deferred_claim_shares, Uint128::zero());
+    assert_eq!(supply.issued.u128(), 2 * amount - 2 * unbond_amount);
+}
@@ -1,11 +1,23 @@
 use std::{collections::HashMap, str::FromStr};
 
-use crate::{state::SUPPLY, ContractError};
-
-use super::suite::SuiteBuilder;
-
-use crate::state::{unbonding_info_num_epochs, unbonding_info_total_entries, BONDED};
+use crate::{
+    claim::{ClaimProgress, ClaimStatus},
+    contract::MAX_CLAIM_SALE_DISCOUNT,
+    msg::{ExchangeRateRawResponse, SudoMsg},
+    state::SUPPLY,
+    ContractError,
+};
+
+use super::suite::{event_attribute, SuiteBuilder};
+use crate::events::{BOND_EVENT, UNBOND_EVENT};
+
+use crate::contract::{MAX_DISCOUNT_UPDATE_INTERVAL, MAX_REDELEGATION_ENTRIES};
+use crate::state::{
+    unbonding_info_num_epochs, unbonding_info_total_entries, BONDED, REDELEGATION_ENTRIES,
+    STAKE_INFO,
+};
 use cosmwasm_std::{assert_approx_eq, coin, Decimal, Delegation, Uint128};
+use cw_multi_test::AppResponse;
 
 const DAY: u64 = 24 * HOUR;
 const HOUR: u64 = 60 * 60;
@@ -430,6 +442,13 @@ fn target_value() {
             .unwrap(),
         ContractError::InvalidLiquidityDiscount {}
     ));
+    // allow the discount to jump in one step
+    suite
+        .sudo(SudoMsg::UpdateDiscountLimits {
+            max_discount_change_per_update: Decimal::percent(4),
+            min_discount_update_interval: DAY,
+        })
+        .unwrap();
     suite
         .update_liquidity_discount("owner", Decimal::percent(10))
         .unwrap();
@@ -486,6 +505,291 @@ fn target_value() {
     assert_eq!(exchange_rate * Decimal::percent(90), target_value4);
 }
 
+#[test]
+fn liquidity_discount_changes_gradually() {
+    let mut suite = SuiteBuilder::new()
+        .with_liquidity_discount(Decimal::percent(4))
+        .build();
+
+    // too big steps are rejected
+    let err = suite
+        .update_liquidity_discount("owner", Decimal::percent(7))
+        .unwrap_err();
+    assert_eq!(
+        ContractError::DiscountChangeTooLarge {
+            max_step: Decimal::percent(2)
+        },
+        err.downcast().unwrap()
+    );
+
+    suite
+        .update_liquidity_discount("owner", Decimal::percent(6))
+        .unwrap();
+
+    // too frequent updates as well
+    let next_allowed = suite.app.block_info().time.seconds() + DAY;
+    suite.update_time(DAY - 1);
+    let err = suite
+        .update_liquidity_discount("owner", Decimal::percent(8))
+        .unwrap_err();
+    assert_eq!(
+        ContractError::DiscountUpdateTooSoon { next_allowed },
+        err.downcast().unwrap()
+    );
+
+    // going step by step reaches the target
+    for discount in [8, 10, 12] {
+        suite.update_time(DAY);
+        suite
+            .update_liquidity_discount("owner", Decimal::percent(discount))
+            .unwrap();
+    }
+    assert_eq!(suite.query_target_value().unwrap(), Decimal::percent(88));
+
+    // governance can change the limits, but not remove them or freeze the discount
+    for (max_discount_change_per_update, min_discount_update_interval) in [
+        (Decimal::zero(), DAY),
+        (Decimal::percent(101), DAY),
+        (Decimal::percent(10), MAX_DISCOUNT_UPDATE_INTERVAL + 1),
+    ] {
+        let err = suite
+            .sudo(SudoMsg::UpdateDiscountLimits {
+                max_discount_change_per_update,
+                min_discount_update_interval,
+            })
+            .unwrap_err();
+        assert_eq!(
+            ContractError::InvalidDiscountLimits {},
+            err.downcast().unwrap()
+        );
+    }
+    suite
+        .sudo(SudoMsg::UpdateDiscountLimits {
+            max_discount_change_per_update: Decimal::percent(10),
+            min_discount_update_interval: 0,
+        })
+        .unwrap();
+    suite
+        .update_liquidity_discount("owner", Decimal::percent(2))
+        .unwrap();
+    assert_eq!(suite.query_target_value().unwrap(), Decimal::percent(98));
+}
+
+#[test]
+fn unbond_fee_stays_with_the_remaining_holders() {
+    let holders = ["exiter", "stayer"];
+    // unbonds half of the exiter's tokens with the given fee and claims them.
+    // Returns the claim, the fee and the raw exchange rate before and after the unbond
+    let unbond_with_fee = |fee: Decimal| {
+        let mut suite = SuiteBuilder::new()
+            .with_initial_balances(vec![(holders[0], 1_000_000), (holders[1], 1_000_000)])
+            .build();
+        let lsd = suite.query_lsd_token().unwrap();
+        for holder in holders {
+            suite.bond(holder, 1_000_000).unwrap();
+        }
+        suite.update_time(23 * HOUR);
+        suite.reinvest().unwrap();
+        suite.update_time(23 * HOUR);
+        suite.reinvest().unwrap();
+
+        suite.update_unbond_fee("owner", fee).unwrap();
+        assert_eq!(suite.query_config().unwrap().unbond_fee, fee);
+        let rate_before = suite.query_exchange_rate_raw().unwrap();
+        let res = suite.unbond(holders[0], &lsd, 500_000).unwrap();
+        let fee_amount: u128 = event_attribute(&res, UNBOND_EVENT, "fee_amount")
+            .unwrap()
+            .parse()
+            .unwrap();
+        let rate_after = suite.query_exchange_rate_raw().unwrap();
+
+        let claim = suite.query_claims(holders[0].to_string()).unwrap()[0]
+            .amount
+            .u128();
+        suite.update_time(23 * HOUR);
+        suite.reinvest().unwrap();
+        suite.update_time(28 * DAY + HOUR);
+        suite.claim(holders[0]).unwrap();
+        assert_eq!(suite.query_balance(holders[0], "FUN").unwrap(), claim);
+        suite.sudo(SudoMsg::AssertInvariants {}).unwrap();
+
+        (claim, fee_amount, rate_before, rate_after)
+    };
+
+    // without a fee, the claim is worth all of the burned tokens, as before
+    let (native, fee_amount, before, after) = unbond_with_fee(Decimal::zero());
+    assert_eq!(fee_amount, 0);
+    assert!(native > 500_000);
+    assert_eq!(
+        Uint128::new(500_000).multiply_ratio(before.assets, before.issued),
+        Uint128::new(native)
+    );
+    assert_eq!(after.assets, before.assets - Uint128::new(native));
+
+    // with a fee, the unbonder gets 99% and the rest stays in the assets of the remaining holders
+    let (claim, fee_amount, fee_before, fee_after) = unbond_with_fee(Decimal::percent(1));
+    assert_eq!(fee_before, before);
+    assert_eq!(claim, native * 99 / 100);
+    assert_eq!(claim + fee_amount, native);
+    assert_eq!(fee_after.assets, before.assets - Uint128::new(claim));
+    assert_eq!(fee_after.issued, after.issued);
+    let rate = |raw: &ExchangeRateRawResponse| Decimal::from_ratio(raw.assets, raw.issued);
+    assert!(rate(&fee_after) > rate(&after));
+    assert!(rate(&fee_after) > rate(&fee_before));
+}
+
+#[test]
+fn unbond_fee_changes_gradually() {
+    let mut suite = SuiteBuilder::new().build();
+
+    let err = suite
+        .update_unbond_fee("random", Decimal::percent(1))
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    let err = suite
+        .update_unbond_fee("owner", Decimal::percent(3))
+        .unwrap_err();
+    assert_eq!(ContractError::InvalidUnbondFee {}, err.downcast().unwrap());
+
+    suite
+        .update_unbond_fee("owner", Decimal::percent(1))
+        .unwrap();
+    let next_allowed = suite.app.block_info().time.seconds() + DAY;
+    let err = suite
+        .update_unbond_fee("owner", Decimal::percent(2))
+        .unwrap_err();
+    assert_eq!(
+        ContractError::UnbondFeeUpdateTooSoon { next_allowed },
+        err.downcast().unwrap()
+    );
+
+    // governance can tighten the step
+    suite
+        .sudo(SudoMsg::UpdateDiscountLimits {
+            max_discount_change_per_update: Decimal::permille(5),
+            min_discount_update_interval: DAY,
+        })
+        .unwrap();
+    suite.update_time(DAY);
+    let err = suite
+        .update_unbond_fee("owner", Decimal::percent(2))
+        .unwrap_err();
+    assert_eq!(
+        ContractError::UnbondFeeChangeTooLarge {
+            max_step: Decimal::permille(5)
+        },
+        err.downcast().unwrap()
+    );
+    suite
+        .update_unbond_fee("owner", Decimal::permille(5))
+        .unwrap();
+}
+
+#[test]
+fn asset_info_matches_separate_queries() {
+    let delegator = "delegator1";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 2_000_000)])
+        .with_liquidity_discount(Decimal::percent(6))
+        .with_periods(DAY, 28 * DAY)
+        .build();
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    // accumulate some rewards, so the exchange rate is not 1
+    suite.update_time(DAY);
+    suite.reinvest().unwrap();
+    suite.update_time(DAY);
+    suite.reinvest().unwrap();
+
+    let info = suite.query_asset_info().unwrap();
+    assert_eq!(info.bond_denom, "FUN");
+    assert_eq!(info.lsd_token, suite.query_lsd_token().unwrap());
+    assert_eq!(info.exchange_rate, suite.query_exchange_rate().unwrap());
+    assert_eq!(info.target_value, suite.query_target_value().unwrap());
+    assert!(info.exchange_rate > Decimal::one());
+}
+
+#[test]
+fn validator_set_with_bonded_shows_drift() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .with_validators(vec![
+            ("testvaloper1", Decimal::percent(60)),
+            ("testvaloper2", Decimal::percent(40)),
+        ])
+        .with_periods(DAY, 28 * DAY)
+        .build();
+
+    // nothing bonded yet
+    let validators = suite.query_validator_set_with_bonded().unwrap();
+    assert_eq!(validators.len(), 2);
+    assert_eq!(validators[0].target_weight, Decimal::percent(60));
+    assert_eq!(validators[0].bonded, Uint128::zero());
+    assert_eq!(validators[0].actual_weight, Decimal::zero());
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(DAY);
+    suite.reinvest().unwrap();
+
+    let validators = suite.query_validator_set_with_bonded().unwrap();
+    assert_eq!(validators[0].bonded, Uint128::new(600_000));
+    assert_eq!(validators[0].actual_weight, Decimal::percent(60));
+    assert_eq!(validators[1].bonded, Uint128::new(400_000));
+    assert_eq!(validators[1].actual_weight, Decimal::percent(40));
+
+    // slashing moves the actual weights away from the targets
+    suite.slash("testvaloper1", Decimal::percent(10)).unwrap();
+    suite.check_slash().unwrap();
+    let validators = suite.query_validator_set_with_bonded().unwrap();
+    assert_eq!(validators[0].target_weight, Decimal::percent(60));
+    assert_eq!(validators[0].bonded, Uint128::new(540_000));
+    assert_eq!(
+        validators[0].actual_weight,
+        Decimal::from_ratio(540_000u128, 940_000u128)
+    );
+    assert_eq!(
+        validators[1].actual_weight,
+        Decimal::from_ratio(400_000u128, 940_000u128)
+    );
+}
+
+#[test]
+fn exchange_rate_raw_matches_minted_shares() {
+    let delegators = &["delegator1", "delegator2"];
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegators[0], 1_000_000), (delegators[1], 1_000_000)])
+        .with_periods(DAY, 28 * DAY)
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    // nothing issued yet, so bonding is 1:1
+    let raw = suite.query_exchange_rate_raw().unwrap();
+    assert_eq!(raw.issued, Uint128::zero());
+
+    suite.bond(delegators[0], 1_000_000).unwrap();
+    // accumulate some rewards, so the exchange rate is not 1
+    for _ in 0..3 {
+        suite.update_time(DAY);
+        suite.reinvest().unwrap();
+    }
+
+    let raw = suite.query_exchange_rate_raw().unwrap();
+    let supply = suite.query_supply().unwrap();
+    assert_eq!(raw.issued, supply.supply.issued);
+    assert!(raw.assets > raw.issued);
+
+    // an awkward amount, so the rounding matters
+    let amount = 777_777u128;
+    suite.bond(delegators[1], amount).unwrap();
+    assert_eq!(
+        suite.query_cw20_balance(delegators[1], &lsd).unwrap(),
+        Uint128::new(amount)
+            .multiply_ratio(raw.issued, raw.assets)
+            .u128()
+    );
+}
+
 #[test]
 fn commission() {
     let delegator = "delegator";
@@ -561,6 +865,170 @@ fn tiny_weights() {
     assert_eq!(delegations[1].amount.amount, Uint128::new(999998));
 }
 
+#[test]
+fn delegations_below_the_minimum_are_kept_in_the_balance() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .with_validators(vec![
+            ("testvaloper1", Decimal::from_str("0.000001").unwrap()),
+            ("testvaloper2", Decimal::from_str("0.00000001").unwrap()),
+            ("testvaloper3", Decimal::from_str("0.99999899").unwrap()),
+        ])
+        .with_periods(DAY, 28 * DAY)
+        .build();
+    assert_eq!(suite.query_config().unwrap().min_delegation.u128(), 1);
+    let err = suite.update_min_delegation(delegator, 10).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    suite.update_min_delegation("owner", 10).unwrap();
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(DAY);
+    suite.reinvest().unwrap();
+
+    // testvaloper1 would get 2 tokens (see `tiny_weights`), which is below the minimum
+    let delegations = suite.query_delegations().unwrap();
+    assert_eq!(delegations.len(), 1);
+    assert_eq!(delegations[0].validator, "testvaloper3");
+    assert_eq!(delegations[0].amount.amount, Uint128::new(999_998));
+    // the dust is retained and still backs the lsd tokens
+    assert_eq!(suite.query_balance(suite.hub.as_str(), "FUN").unwrap(), 2);
+    let supply = SUPPLY.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
+    assert_eq!(supply.total_bonded.u128(), 999_998);
+    assert_eq!(suite.query_exchange_rate().unwrap(), Decimal::one());
+}
+
+#[test]
+fn paginated_claims() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .build();
+    suite.bond(delegator, 1_000_000).unwrap();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    for amount in [100, 200, 300] {
+        suite.unbond(delegator, &lsd, amount).unwrap();
+    }
+
+    // without pagination, all claims are returned
+    let all = suite.query_claims_page(delegator, None, None).unwrap();
+    assert_eq!(all.claims.len(), 3);
+    assert_eq!(all.next_start_after, None);
+
+    let first = suite.query_claims_page(delegator, None, Some(2)).unwrap();
+    assert_eq!(first.claims, all.claims[..2]);
+    assert_eq!(first.next_start_after, Some(1));
+
+    let second = suite
+        .query_claims_page(delegator, first.next_start_after, Some(2))
+        .unwrap();
+    assert_eq!(second.claims, all.claims[2..]);
+    assert_eq!(second.next_start_after, None);
+}
+
+#[test]
+fn claims_filtered_by_status() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .build();
+    suite.bond(delegator, 1_000_000).unwrap();
+    let lsd = suite.query_lsd_token().unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+
+    // two claims released at different times
+    suite.unbond(delegator, &lsd, 100_000).unwrap();
+    for _ in 0..6 {
+        suite.update_time(23 * HOUR);
+        suite.reinvest().unwrap();
+    }
+    suite.unbond(delegator, &lsd, 200_000).unwrap();
+    let all = suite.query_claims(delegator.to_string()).unwrap();
+    assert!(all[0].release_at < all[1].release_at);
+
+    // nothing matured yet
+    assert!(suite
+        .query_claims_with_status(delegator, ClaimStatus::Matured)
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        suite
+            .query_claims_with_status(delegator, ClaimStatus::Pending)
+            .unwrap(),
+        all
+    );
+
+    // the first one matures
+    let now = suite.app.block_info().time;
+    suite.update_time(all[0].release_at.seconds() - now.seconds());
+    assert_eq!(
+        suite
+            .query_claims_with_status(delegator, ClaimStatus::Matured)
+            .unwrap(),
+        all[..1]
+    );
+    assert_eq!(
+        suite
+            .query_claims_with_status(delegator, ClaimStatus::Pending)
+            .unwrap(),
+        all[1..]
+    );
+    assert_eq!(
+        suite
+            .query_claims_with_status(delegator, ClaimStatus::All)
+            .unwrap(),
+        all
+    );
+}
+
+#[test]
+fn claim_estimates_follow_the_undelegation() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .build();
+    suite.bond(delegator, 1_000_000).unwrap();
+    let lsd = suite.query_lsd_token().unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+
+    // the claim waits for the next undelegation
+    suite.unbond(delegator, &lsd, 100_000).unwrap();
+    let claims = suite.query_claims_page(delegator, None, None).unwrap();
+    let release_at = claims.claims[0].release_at;
+    assert_eq!(claims.estimates.len(), 1);
+    assert_eq!(
+        claims.estimates[0].status,
+        ClaimProgress::AwaitingUnbondEpoch
+    );
+    assert_eq!(claims.estimates[0].estimated_available_at, release_at);
+
+    // the undelegation happens on the next reinvest
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+    let estimates = suite
+        .query_claims_page(delegator, None, None)
+        .unwrap()
+        .estimates;
+    assert_eq!(estimates[0].status, ClaimProgress::Unbonding);
+    assert!(estimates[0].estimated_available_at >= release_at);
+
+    // once the undelegation completed, the claim can be paid out
+    let available_at = estimates[0].estimated_available_at;
+    let now = suite.app.block_info().time;
+    suite.update_time(available_at.seconds() - now.seconds());
+    suite.process_native_unbonding();
+    suite.reinvest().unwrap();
+    let estimates = suite
+        .query_claims_page(delegator, None, None)
+        .unwrap()
+        .estimates;
+    assert_eq!(estimates[0].status, ClaimProgress::Payable);
+    assert_eq!(estimates[0].estimated_available_at, release_at);
+}
+
 #[test]
 fn simple_bond_unbond_claim() {
     let delegators = &["delegator1", "delegator2"];
@@ -612,6 +1080,172 @@ fn simple_bond_unbond_claim() {
     );
 }
 
+#[test]
+fn claim_for_pays_the_recipient() {
+    let delegator = "delegator";
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    suite.bond(delegator, amount).unwrap();
+    suite.unbond(delegator, &lsd, amount).unwrap();
+    suite.update_time(23 * HOUR + 28 * DAY);
+
+    // nobody can claim for the delegator until they set a recipient
+    let err = suite.claim_for("router", delegator).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    suite
+        .set_claim_recipient(delegator, Some("router"))
+        .unwrap();
+    let err = suite.claim_for("other", delegator).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    suite.claim_for("router", delegator).unwrap();
+    assert_eq!(suite.query_balance("router", "FUN").unwrap(), amount);
+    assert_eq!(suite.query_balance(delegator, "FUN").unwrap(), 0);
+    assert!(suite
+        .query_claims(delegator.to_string())
+        .unwrap()
+        .is_empty());
+
+    // removing the recipient revokes the permission
+    suite.set_claim_recipient(delegator, None).unwrap();
+    let err = suite.claim_for("router", delegator).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+}
+
+#[test]
+fn claim_offered_for_sale_is_bought() {
+    let delegator = "delegator";
+    let buyer = "market_maker";
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount), (buyer, amount)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    // only the owner registers buyers
+    let err = suite.register_claim_buyer(buyer, buyer).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    suite.register_claim_buyer("owner", buyer).unwrap();
+
+    suite.bond(delegator, amount).unwrap();
+    let err = suite
+        .unbond_for_sale(delegator, &lsd, 600_000, Decimal::percent(11))
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidClaimDiscount {
+            max: MAX_CLAIM_SALE_DISCOUNT
+        },
+        err.downcast().unwrap()
+    );
+    suite
+        .unbond_for_sale(delegator, &lsd, 600_000, Decimal::percent(2))
+        .unwrap();
+    // this one is released at the same time, but not for sale
+    suite.unbond(delegator, &lsd, 400_000).unwrap();
+    let claims = suite.query_claims(delegator.to_string()).unwrap();
+    assert_eq!(claims[0].sale_discount, Some(Decimal::percent(2)));
+    assert_eq!(claims[1].sale_discount, None);
+    let release_at = claims[0].release_at.seconds();
+    let price = 588_000u128;
+
+    // only registered buyers can buy, and only for the exact price
+    let err = suite
+        .buy_claim("stranger", delegator, release_at, price)
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    let err = suite
+        .buy_claim(buyer, delegator, release_at, price - 1)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::ClaimPriceMismatch {
+            price: price.into(),
+            paid: (price - 1).into()
+        },
+        err.downcast().unwrap()
+    );
+
+    suite
+        .buy_claim(buyer, delegator, release_at, price)
+        .unwrap();
+    assert_eq!(suite.query_balance(delegator, "FUN").unwrap(), price);
+    let claims = suite.query_claims(delegator.to_string()).unwrap();
+    assert_eq!(claims.len(), 1);
+    assert_eq!(claims[0].amount.u128(), 400_000);
+    let bought = suite.query_claims(buyer.to_string()).unwrap();
+    assert_eq!(bought.len(), 1);
+    assert_eq!(bought[0].amount.u128(), 600_000);
+    assert_eq!(bought[0].sale_discount, None);
+
+    // the claim is not for sale anymore
+    let err = suite
+        .buy_claim(buyer, delegator, release_at, price)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::ClaimNotForSale {
+            owner: delegator.to_string(),
+            release_at
+        },
+        err.downcast().unwrap()
+    );
+
+    // the buyer claims at maturity
+    suite.update_time(23 * HOUR + 28 * DAY);
+    suite.claim(buyer).unwrap();
+    assert_eq!(
+        suite.query_balance(buyer, "FUN").unwrap(),
+        amount - price + 600_000
+    );
+}
+
+#[test]
+fn operator_does_operational_actions_only() {
+    let mut suite = SuiteBuilder::new().build();
+    assert_eq!(suite.query_config().unwrap().operator, None);
+
+    // only the owner can set the operator
+    let err = suite
+        .set_operator("operator", Some("operator"))
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    suite.set_operator("owner", Some("operator")).unwrap();
+    assert_eq!(
+        suite.query_config().unwrap().operator.unwrap().as_str(),
+        "operator"
+    );
+
+    suite
+        .update_liquidity_discount("operator", Decimal::percent(5))
+        .unwrap();
+    suite.suspend_delegations("operator").unwrap();
+    assert!(suite.query_config().unwrap().delegations_suspended);
+
+    // the validator set, force reinvests and the operator itself stay with the owner
+    let err = suite.force_reinvest("operator").unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    let err = suite
+        .set_validators(
+            "operator",
+            vec![("testvaloper2".to_string(), Decimal::percent(100))],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    let err = suite.set_operator("operator", None).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    // revoking the operator removes its permissions
+    suite.set_operator("owner", None).unwrap();
+    assert_eq!(suite.query_config().unwrap().operator, None);
+    let err = suite
+        .update_liquidity_discount("operator", Decimal::percent(6))
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+}
+
 #[test]
 fn bond_unbond() {
     let delegator = "delegator";
@@ -938,3 +1572,848 @@ fn unbond_epoch_handling() {
     assert_eq!(unbonding_info_num_epochs(&storage), 2);
     assert_eq!(unbonding_info_total_entries(&storage).unwrap(), 6);
 }
+
+#[test]
+fn bond_many_matches_single_bond() {
+    let delegator = "delegator";
+    let distributor = "distributor";
+    let recipients = [
+        ("member1", 300_000u128),
+        ("member2", 125_000),
+        ("member3", 77_777),
+        ("member4", 1),
+        ("member5", 9),
+    ];
+    let total: u128 = recipients.iter().map(|(_, amount)| amount).sum();
+
+    // get the exchange rate away from 1, so the split has to round
+    let build = || {
+        let mut suite = SuiteBuilder::new()
+            .with_initial_balances(vec![(delegator, 1_000_000), (distributor, total)])
+            .build();
+        suite.bond(delegator, 1_000_000).unwrap();
+        suite.update_time(DAY);
+        suite.reinvest().unwrap();
+        suite.update_time(DAY);
+        suite.reinvest().unwrap();
+        assert!(suite.query_exchange_rate().unwrap() > Decimal::one());
+        suite
+    };
+
+    // bonding everything at once as a reference
+    let mut reference = build();
+    reference.bond(distributor, total).unwrap();
+    let lsd = reference.query_lsd_token().unwrap();
+    let single_bond_shares = reference.query_cw20_balance(distributor, &lsd).unwrap();
+
+    let mut suite = build();
+    let issued_before = SUPPLY
+        .query(&suite.app.wrap(), suite.hub.clone())
+        .unwrap()
+        .issued;
+    let res = suite.bond_many(distributor, &recipients, total).unwrap();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    let minted: Vec<_> = recipients
+        .iter()
+        .map(|(addr, _)| suite.query_cw20_balance(addr, &lsd).unwrap())
+        .collect();
+    assert_eq!(minted.iter().sum::<u128>(), single_bond_shares);
+    let issued_after = SUPPLY
+        .query(&suite.app.wrap(), suite.hub.clone())
+        .unwrap()
+        .issued;
+    assert_eq!((issued_after - issued_before).u128(), single_bond_shares);
+
+    // everyone but the first gets their rounded down share, the first one also gets the dust
+    let shares_for = |amount: u128| amount * single_bond_shares / total;
+    for (i, (_, amount)) in recipients.iter().enumerate().skip(1) {
+        assert_eq!(minted[i], shares_for(*amount));
+    }
+    assert_eq!(minted[3], 0, "one token is worth less than one share");
+    assert!(minted[0] > shares_for(recipients[0].1));
+
+    // the recipient without shares gets no bond event either
+    let bonded: Vec<_> = res
+        .events
+        .iter()
+        .filter(|e| e.ty == format!("wasm-{BOND_EVENT}"))
+        .flat_map(|e| &e.attributes)
+        .filter(|a| a.key == "recipient")
+        .map(|a| a.value.as_str())
+        .collect();
+    assert_eq!(bonded, ["member1", "member2", "member3", "member5"]);
+}
+
+#[test]
+fn bond_many_validation() {
+    let distributor = "distributor";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(distributor, 1_000_000)])
+        .build();
+
+    let err = suite
+        .bond_many(distributor, &[("member1", 100), ("member2", 100)], 300)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::PaymentMismatch {
+            paid: Uint128::new(300),
+            expected: Uint128::new(200)
+        },
+        err.downcast().unwrap()
+    );
+
+    let err = suite
+        .bond_many(distributor, &[("member1", 100), ("member2", 0)], 100)
+        .unwrap_err();
+    assert_eq!(ContractError::ZeroBondAmount {}, err.downcast().unwrap());
+
+    let names: Vec<_> = (0..=crate::contract::MAX_BOND_MANY_RECIPIENTS)
+        .map(|i| format!("member{i}"))
+        .collect();
+    let recipients: Vec<_> = names.iter().map(|name| (name.as_str(), 1)).collect();
+    let err = suite
+        .bond_many(distributor, &recipients, recipients.len() as u128)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::TooManyRecipients {
+            max: crate::contract::MAX_BOND_MANY_RECIPIENTS
+        },
+        err.downcast().unwrap()
+    );
+}
+
+#[test]
+fn supply_includes_backing_assets() {
+    let delegator = "delegator";
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .build();
+
+    suite.bond(delegator, amount).unwrap();
+    suite.update_time(DAY);
+    suite.reinvest().unwrap();
+    suite.update_time(DAY);
+    suite.reinvest().unwrap();
+
+    // unbond and undelegate something, so claims and unbonding are non-zero
+    suite
+        .unbond(delegator, &suite.query_lsd_token().unwrap(), amount / 4)
+        .unwrap();
+    suite.update_time(5 * DAY);
+    suite.reinvest().unwrap();
+
+    let resp = suite.query_supply().unwrap();
+    let supply = resp.supply;
+    assert!(!supply.claims.is_zero());
+    assert!(!supply.total_unbonding.is_zero());
+    assert_eq!(
+        resp.balance.u128(),
+        suite.query_balance(suite.hub.as_str(), "FUN").unwrap()
+    );
+    assert_eq!(
+        resp.backing_assets,
+        supply.total_bonded + supply.total_unbonding + resp.balance - supply.claims
+    );
+    assert_eq!(resp.exchange_rate, suite.query_exchange_rate().unwrap());
+    assert_eq!(
+        resp.exchange_rate,
+        Decimal::from_ratio(resp.backing_assets, supply.issued)
+    );
+}
+
+#[test]
+fn simulate_bond_matches_bond() {
+    let delegators = &["delegator1", "delegator2"];
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegators[0], 1_000_000), (delegators[1], 1_000_000)])
+        .with_issuance_cap(1_800_000)
+        .build();
+    suite.bond(delegators[0], 1_000_000).unwrap();
+    suite.update_time(DAY);
+    suite.reinvest().unwrap();
+    // make the exchange rate uneven, so a wrong balance would change the result
+    suite.mint_to_hub(333_337, "FUN").unwrap();
+
+    let amount = 777_777u128;
+    let simulation = suite.query_simulate_bond(amount).unwrap();
+    assert!(!simulation.exceeds_issuance_cap);
+    let lsd = suite.query_lsd_token().unwrap();
+    suite.bond(delegators[1], amount).unwrap();
+    assert_eq!(
+        suite.query_cw20_balance(delegators[1], &lsd).unwrap(),
+        simulation.lsd_tokens.u128()
+    );
+
+    // the cap is checked just like when bonding
+    let simulation = suite.query_simulate_bond(amount).unwrap();
+    assert!(simulation.exceeds_issuance_cap);
+}
+
+#[test]
+fn shares_to_redeem_covers_the_target() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .build();
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(DAY);
+    suite.reinvest().unwrap();
+    // make the exchange rate uneven, so both roundings matter
+    suite.mint_to_hub(333_337, "FUN").unwrap();
+    suite
+        .update_unbond_fee("owner", Decimal::permille(15))
+        .unwrap();
+
+    let target = 400_001u128;
+    let shares = suite.query_shares_to_redeem(target).unwrap();
+    let lsd = suite.query_lsd_token().unwrap();
+    suite.unbond(delegator, &lsd, shares.u128()).unwrap();
+
+    let claim = suite.query_claims(delegator.to_string()).unwrap()[0]
+        .amount
+        .u128();
+    assert!(claim >= target, "{claim} < {target}");
+}
+
+#[test]
+fn dashboard_matches_individual_queries() {
+    let delegator = "delegator";
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .with_validators(vec![
+            ("testvaloper1", Decimal::percent(60)),
+            ("testvaloper2", Decimal::percent(40)),
+        ])
+        .build();
+
+    suite.bond(delegator, amount).unwrap();
+    suite.update_time(DAY);
+    suite.reinvest().unwrap();
+    let lsd = suite.query_lsd_token().unwrap();
+    suite.unbond(delegator, &lsd, amount / 4).unwrap();
+    suite.update_time(DAY);
+    suite.reinvest().unwrap();
+
+    let dashboard = suite.query_dashboard(None).unwrap();
+    assert_eq!(dashboard.config, suite.query_config().unwrap());
+    assert_eq!(dashboard.supply, suite.query_supply().unwrap());
+    assert_eq!(
+        dashboard.exchange_rate,
+        suite.query_exchange_rate().unwrap()
+    );
+    assert_eq!(dashboard.target_value, suite.query_target_value().unwrap());
+    assert_eq!(
+        dashboard.validators,
+        suite.query_validator_set_with_bonded().unwrap()
+    );
+    assert_eq!(dashboard.account, None);
+
+    // with an address, its claims and lsd tokens are included
+    let account = suite
+        .query_dashboard(Some(delegator))
+        .unwrap()
+        .account
+        .unwrap();
+    assert_eq!(account.address, delegator);
+    assert_eq!(
+        account.claims,
+        suite.query_claims_page(delegator, None, None).unwrap()
+    );
+    assert_eq!(account.claims.claims.len(), 1);
+    assert_eq!(
+        account.lsd_balance.u128(),
+        suite.query_cw20_balance(delegator, &lsd).unwrap()
+    );
+}
+
+#[test]
+fn preview_valset_change_matches_set_validators() {
+    let delegator = "delegator";
+    let amount = 3_333_333u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .with_validators(vec![
+            ("testvaloper1", Decimal::percent(50)),
+            ("testvaloper2", Decimal::percent(50)),
+        ])
+        .with_registered_validators(vec!["testvaloper3".to_string()])
+        .with_periods(23 * HOUR, 28 * DAY)
+        .build();
+
+    // nothing is bonded yet, so nothing would be redelegated
+    let new_validators = vec![
+        ("testvaloper2".to_string(), Decimal::percent(30)),
+        ("testvaloper3".to_string(), Decimal::percent(70)),
+    ];
+    let preview = suite
+        .query_preview_valset_change(new_validators.clone())
+        .unwrap();
+    assert!(preview.redelegations.is_empty());
+    assert!(preview.new_balances.is_empty());
+
+    suite.bond(delegator, amount).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+
+    let bonded_before = BONDED.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
+    let preview = suite
+        .query_preview_valset_change(new_validators.clone())
+        .unwrap();
+    // the preview doesn't change anything
+    assert_eq!(
+        BONDED.query(&suite.app.wrap(), suite.hub.clone()).unwrap(),
+        bonded_before
+    );
+    assert!(!preview.redelegations.is_empty());
+    assert!(preview
+        .redelegations
+        .iter()
+        .all(|r| r.dst == "testvaloper3" && r.src != "testvaloper3"));
+
+    suite.set_validators("owner", new_validators).unwrap();
+
+    // the stored delegations are the same as previewed
+    let bonded = BONDED.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
+    assert_eq!(preview.new_balances, bonded);
+
+    // and so are the actual delegations
+    let delegations: HashMap<_, _> = suite
+        .query_delegations()
+        .unwrap()
+        .into_iter()
+        .map(|d| (d.validator, d.amount.amount))
+        .collect();
+    for (validator, amount) in &preview.new_balances {
+        assert_eq!(delegations[validator], *amount);
+    }
+    let redelegated: Uint128 = preview.redelegations.iter().map(|r| r.amount).sum();
+    assert_eq!(delegations["testvaloper3"], redelegated);
+}
+
+#[test]
+fn simulate_valset_change_flags_the_redelegation_entry_cap() {
+    let delegator = "delegator";
+    let amount = 3_333_333u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .with_validators(vec![
+            ("testvaloper1", Decimal::percent(50)),
+            ("testvaloper2", Decimal::percent(50)),
+        ])
+        .with_registered_validators(vec!["testvaloper3".to_string()])
+        .with_periods(23 * HOUR, 28 * DAY)
+        .build();
+    suite.bond(delegator, amount).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+
+    let new_validators = vec![
+        ("testvaloper2".to_string(), Decimal::percent(30)),
+        ("testvaloper3".to_string(), Decimal::percent(70)),
+    ];
+    let simulation = suite
+        .query_simulate_valset_change(new_validators.clone())
+        .unwrap();
+    let preview = suite
+        .query_preview_valset_change(new_validators.clone())
+        .unwrap();
+    assert_eq!(simulation.redelegations, preview.redelegations);
+    assert_eq!(simulation.new_balances, preview.new_balances);
+    assert_eq!(simulation.message_count, 2);
+    assert_eq!(simulation.pairs.len(), 2);
+    assert!(simulation
+        .pairs
+        .iter()
+        .all(|p| p.redelegations == 1 && p.pending == 0 && !p.exceeds_entry_cap));
+    assert!(!simulation.exceeds_entry_cap);
+    suite.set_validators("owner", new_validators).unwrap();
+
+    // moving more to the same validator counts the pending redelegation of the last change
+    let reshuffle = vec![
+        ("testvaloper2".to_string(), Decimal::percent(10)),
+        ("testvaloper3".to_string(), Decimal::percent(90)),
+    ];
+    let simulation = suite
+        .query_simulate_valset_change(reshuffle.clone())
+        .unwrap();
+    assert_eq!(simulation.pairs.len(), 1);
+    let pair = &simulation.pairs[0];
+    assert_eq!(
+        (pair.src.as_str(), pair.dst.as_str()),
+        ("testvaloper2", "testvaloper3")
+    );
+    assert_eq!((pair.redelegations, pair.pending), (1, 1));
+    assert!(!simulation.exceeds_entry_cap);
+
+    // with all entries of the pair used up by earlier changes, the reshuffle would fail on chain
+    let now = suite.app.block_info().time.seconds();
+    suite.update_hub_storage(|storage| {
+        REDELEGATION_ENTRIES
+            .save(
+                storage,
+                ("testvaloper2", "testvaloper3"),
+                &vec![now + DAY; MAX_REDELEGATION_ENTRIES as usize],
+            )
+            .unwrap()
+    });
+    let simulation = suite
+        .query_simulate_valset_change(reshuffle.clone())
+        .unwrap();
+    assert!(simulation.pairs[0].exceeds_entry_cap);
+    assert!(simulation.exceeds_entry_cap);
+
+    // once they completed, there is room again
+    suite.update_time(DAY);
+    let simulation = suite.query_simulate_valset_change(reshuffle).unwrap();
+    assert_eq!(simulation.pairs[0].pending, 0);
+    assert!(!simulation.exceeds_entry_cap);
+}
+
+fn waiting_redelegations(res: &AppResponse) -> Option<String> {
+    res.events
+        .iter()
+        .filter(|e| e.ty == "wasm")
+        .flat_map(|e| &e.attributes)
+        .find(|a| a.key == "waiting_redelegations")
+        .map(|a| a.value.clone())
+}
+
+#[test]
+fn valset_change_waits_for_free_redelegation_entries() {
+    let delegator = "delegator";
+    let amount = 3_333_333u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .with_validators(vec![
+            ("testvaloper1", Decimal::percent(50)),
+            ("testvaloper2", Decimal::percent(50)),
+        ])
+        .with_registered_validators(vec!["testvaloper3".to_string()])
+        .with_periods(23 * HOUR, 28 * DAY)
+        .build();
+    suite.bond(delegator, amount).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+
+    let err = suite.continue_valset_change(delegator).unwrap_err();
+    assert_eq!(
+        ContractError::NoPendingValsetChange {},
+        err.downcast().unwrap()
+    );
+
+    // all entries from the first to the third validator are used up by earlier changes
+    let now = suite.app.block_info().time.seconds();
+    suite.update_hub_storage(|storage| {
+        REDELEGATION_ENTRIES
+            .save(
+                storage,
+                ("testvaloper1", "testvaloper3"),
+                &vec![now + DAY; MAX_REDELEGATION_ENTRIES as usize],
+            )
+            .unwrap()
+    });
+
+    let new_validators = vec![
+        ("testvaloper2".to_string(), Decimal::percent(30)),
+        ("testvaloper3".to_string(), Decimal::percent(70)),
+    ];
+    let preview = suite
+        .query_preview_valset_change(new_validators.clone())
+        .unwrap();
+    assert_eq!(preview.redelegations.len(), 2);
+
+    // only the redelegation from the second validator is executed now
+    let bonded_before = BONDED.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
+    let res = suite
+        .set_validators("owner", new_validators.clone())
+        .unwrap();
+    assert_eq!(waiting_redelegations(&res).as_deref(), Some("1"));
+    let bonded = BONDED.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
+    assert_eq!(bonded[0], bonded_before[0]);
+    assert_eq!(bonded[0].0, "testvaloper1");
+    assert_eq!(suite.query_validator_set().unwrap(), new_validators.clone());
+
+    let pending = suite.query_pending_valset_change().unwrap();
+    assert_eq!(pending.target, Some(new_validators));
+    assert_eq!(pending.remaining.len(), 1);
+    assert_eq!(
+        (
+            pending.remaining[0].src.as_str(),
+            pending.remaining[0].dst.as_str()
+        ),
+        ("testvaloper1", "testvaloper3")
+    );
+    assert_eq!(pending.ready_at, Some(now + DAY));
+
+    // the next stage has to wait until the earlier redelegations completed
+    let err = suite.continue_valset_change(delegator).unwrap_err();
+    assert_eq!(
+        ContractError::ValsetChangeNotReady {
+            ready_at: now + DAY
+        },
+        err.downcast().unwrap()
+    );
+
+    // then anyone can continue the change
+    suite.update_time(DAY);
+    let res = suite.continue_valset_change(delegator).unwrap();
+    assert_eq!(waiting_redelegations(&res).as_deref(), Some("0"));
+    let bonded = BONDED.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
+    assert_eq!(bonded, preview.new_balances);
+    let delegations: HashMap<_, _> = suite
+        .query_delegations()
+        .unwrap()
+        .into_iter()
+        .map(|d| (d.validator, d.amount.amount))
+        .collect();
+    assert_eq!(delegations, bonded.into_iter().collect());
+
+    let pending = suite.query_pending_valset_change().unwrap();
+    assert_eq!(pending.target, None);
+    assert!(pending.remaining.is_empty());
+    let err = suite.continue_valset_change(delegator).unwrap_err();
+    assert_eq!(
+        ContractError::NoPendingValsetChange {},
+        err.downcast().unwrap()
+    );
+}
+
+#[test]
+fn capped_redelegation_is_routed_through_another_validator() {
+    let delegator = "delegator";
+    let amount = 3_333_333u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .with_validators(vec![
+            ("testvaloper1", Decimal::percent(50)),
+            ("testvaloper2", Decimal::percent(50)),
+        ])
+        .with_registered_validators(vec!["testvaloper3".to_string()])
+        .with_periods(23 * HOUR, 28 * DAY)
+        .build();
+    suite.bond(delegator, amount).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+    suite.update_redelegation_hops("owner", true).unwrap();
+
+    // all entries from the first to the third validator are used up by earlier changes
+    let now = suite.app.block_info().time.seconds();
+    suite.update_hub_storage(|storage| {
+        REDELEGATION_ENTRIES
+            .save(
+                storage,
+                ("testvaloper1", "testvaloper3"),
+                &vec![now + DAY; MAX_REDELEGATION_ENTRIES as usize],
+            )
+            .unwrap()
+    });
+
+    let new_validators = vec![
+        ("testvaloper2".to_string(), Decimal::percent(30)),
+        ("testvaloper3".to_string(), Decimal::percent(70)),
+    ];
+    let preview = suite
+        .query_preview_valset_change(new_validators.clone())
+        .unwrap();
+    let routed = preview
+        .redelegations
+        .iter()
+        .find(|r| r.src == "testvaloper1")
+        .unwrap()
+        .amount;
+
+    // nothing waits, the first validator's tokens move to the second one for now
+    let res = suite
+        .set_validators("owner", new_validators.clone())
+        .unwrap();
+    assert_eq!(waiting_redelegations(&res), None);
+    let bonded = BONDED.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
+    assert!(bonded.iter().all(|(v, _)| v != "testvaloper1"));
+    let pending = suite.query_pending_valset_change().unwrap();
+    assert_eq!(pending.target, None);
+    assert_eq!(pending.hops.len(), 1);
+    let hop = &pending.hops[0];
+    assert_eq!(
+        (hop.intermediate.as_str(), hop.dst_validator.as_str()),
+        ("testvaloper2", "testvaloper3")
+    );
+    assert_eq!(hop.amount, routed);
+    assert_eq!(hop.ready_at, now + 28 * DAY);
+
+    // the second hop has to wait until the first one completed
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+    assert_eq!(suite.query_pending_valset_change().unwrap().hops.len(), 1);
+
+    suite.update_time(28 * DAY);
+    suite.reinvest().unwrap();
+    assert!(suite.query_pending_valset_change().unwrap().hops.is_empty());
+    let bonded: HashMap<_, _> = BONDED
+        .query(&suite.app.wrap(), suite.hub.clone())
+        .unwrap()
+        .into_iter()
+        .collect();
+    // the reinvests delegated rewards on top of the target balances
+    for (validator, target) in preview.new_balances {
+        assert!(bonded[&validator] >= target);
+    }
+    let delegations: HashMap<_, _> = suite
+        .query_delegations()
+        .unwrap()
+        .into_iter()
+        .map(|d| (d.validator, d.amount.amount))
+        .collect();
+    assert_eq!(delegations, bonded);
+}
+
+#[test]
+fn failed_redelegation_reverts_set_validators() {
+    let delegator = "delegator";
+    let amount = 3_333_333u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .with_validators(vec![
+            ("testvaloper1", Decimal::percent(50)),
+            ("testvaloper2", Decimal::percent(50)),
+        ])
+        .with_periods(23 * HOUR, 28 * DAY)
+        .build();
+
+    suite.bond(delegator, amount).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+
+    let hub = suite.hub.clone();
+    let bonded_before = BONDED.query(&suite.app.wrap(), hub.clone()).unwrap();
+    let supply_before = SUPPLY.query(&suite.app.wrap(), hub.clone()).unwrap();
+    let stake_info_before = STAKE_INFO.query(&suite.app.wrap(), hub.clone()).unwrap();
+    let delegations_before = suite.query_delegations().unwrap();
+    let storage_before = suite.app.dump_wasm_raw(&hub);
+
+    // the staking module rejects redelegating to a validator it does not know, like a jailed one
+    suite
+        .set_validators(
+            "owner",
+            vec![
+                ("testvaloper1".to_string(), Decimal::percent(50)),
+                ("testvaloper_jailed".to_string(), Decimal::percent(50)),
+            ],
+        )
+        .unwrap_err();
+
+    // nothing was partially updated
+    assert_eq!(
+        BONDED.query(&suite.app.wrap(), hub.clone()).unwrap(),
+        bonded_before
+    );
+    assert_eq!(
+        SUPPLY.query(&suite.app.wrap(), hub.clone()).unwrap(),
+        supply_before
+    );
+    assert_eq!(
+        STAKE_INFO.query(&suite.app.wrap(), hub.clone()).unwrap(),
+        stake_info_before
+    );
+    assert_eq!(suite.app.dump_wasm_raw(&hub), storage_before);
+    assert_eq!(suite.query_delegations().unwrap(), delegations_before);
+}
+
+#[test]
+fn unbond_from_with_allowance() {
+    let delegator = "delegator";
+    let custodian = "custodian";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+
+    // the burn fails without an allowance, so nothing is unbonded
+    suite
+        .unbond_from(custodian, delegator, 400_000)
+        .unwrap_err();
+    assert!(suite
+        .query_claims(delegator.to_string())
+        .unwrap()
+        .is_empty());
+
+    suite
+        .increase_hub_allowance(delegator, &lsd, 400_000)
+        .unwrap();
+    suite.unbond_from(custodian, delegator, 400_000).unwrap();
+
+    // the claim belongs to the owner of the tokens, not the caller
+    let claims = suite.query_claims(delegator.to_string()).unwrap();
+    assert_eq!(claims.len(), 1);
+    assert_eq!(claims[0].amount.u128(), 400_000);
+    assert!(suite
+        .query_claims(custodian.to_string())
+        .unwrap()
+        .is_empty());
+    assert_eq!(suite.query_cw20_balance(delegator, &lsd).unwrap(), 600_000);
+
+    // the allowance is used up
+    suite.unbond_from(delegator, delegator, 1).unwrap_err();
+}
+
+#[test]
+fn unbond_from_with_fee_is_only_for_the_owner() {
+    let delegator = "delegator";
+    let custodian = "custodian";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+    suite
+        .increase_hub_allowance(delegator, &lsd, 400_000)
+        .unwrap();
+    suite
+        .update_unbond_fee("owner", Decimal::percent(1))
+        .unwrap();
+
+    // a third party can not force the owner out at a loss
+    let err = suite
+        .unbond_from(custodian, delegator, 400_000)
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(ContractError::UnbondFromWithFee {}, err);
+    assert_eq!(
+        suite.query_cw20_balance(delegator, &lsd).unwrap(),
+        1_000_000
+    );
+
+    // the owner can still choose to pay it
+    let res = suite.unbond_from(delegator, delegator, 400_000).unwrap();
+    assert_ne!(
+        event_attribute(&res, UNBOND_EVENT, "fee_amount"),
+        Some("0".to_string())
+    );
+    assert_eq!(suite.query_cw20_balance(delegator, &lsd).unwrap(), 600_000);
+}
+
+#[test]
+fn mismatched_decimals_are_reported() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .with_decimals(9, 6)
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    let config = suite.query_config().unwrap();
+    assert_eq!(config.bond_denom_decimals, 6);
+    assert_eq!(config.token_decimals, 9);
+    let token_info: cw20::TokenInfoResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&lsd, &cw20::Cw20QueryMsg::TokenInfo {})
+        .unwrap();
+    assert_eq!(token_info.decimals, config.token_decimals);
+
+    // the exchange rate is unit-for-unit in base units, regardless of the decimals
+    suite.bond(delegator, 1_000_000).unwrap();
+    let exchange_rate = suite.query_exchange_rate().unwrap();
+    assert_eq!(exchange_rate, Decimal::one());
+    let lsd_balance = suite.query_cw20_balance(delegator, &lsd).unwrap();
+    assert_eq!(
+        Uint128::new(lsd_balance).mul_floor(exchange_rate),
+        Uint128::new(1_000_000)
+    );
+
+    // so one displayed lsd token is worth 1000 displayed native tokens
+    let display_rate = exchange_rate
+        * Decimal::from_ratio(
+            10u128.pow(config.token_decimals.into()),
+            10u128.pow(config.bond_denom_decimals.into()),
+        );
+    assert_eq!(display_rate, Decimal::from_ratio(1000u128, 1u128));
+}
+
+#[test]
+fn issuance_cap_limits_bonds() {
+    let user = "user";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(user, 2_000)])
+        .with_issuance_cap(1_000)
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+    assert_eq!(
+        suite.query_config().unwrap().issuance_cap,
+        Some(Uint128::new(1_000))
+    );
+
+    suite.bond(user, 600).unwrap();
+    // one more than the cap
+    let err = suite.bond(user, 401).unwrap_err();
+    assert_eq!(
+        ContractError::IssuanceCapReached {},
+        err.downcast().unwrap()
+    );
+    // exactly the cap
+    suite.bond(user, 400).unwrap();
+    assert_eq!(suite.query_cw20_balance(user, &lsd).unwrap(), 1_000);
+    let err = suite.bond_many(user, &[(user, 1)], 1).unwrap_err();
+    assert_eq!(
+        ContractError::IssuanceCapReached {},
+        err.downcast().unwrap()
+    );
+
+    let err = suite.update_issuance_cap(user, Some(500)).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    // the mint cap of the lsd token was set when instantiating, so it is the upper limit
+    for issuance_cap in [Some(1_001), None] {
+        let err = suite
+            .update_issuance_cap("owner", issuance_cap)
+            .unwrap_err();
+        assert_eq!(
+            ContractError::IssuanceCapAboveTokenCap {
+                token_cap: Uint128::new(1_000)
+            },
+            err.downcast().unwrap()
+        );
+    }
+    suite.update_issuance_cap("owner", Some(500)).unwrap();
+    assert_eq!(
+        suite.query_config().unwrap().issuance_cap,
+        Some(Uint128::new(500))
+    );
+
+    // unbonding below the lowered cap allows bonding again
+    suite.unbond(user, &lsd, 600).unwrap();
+    suite.bond(user, 100).unwrap();
+    let err = suite.bond(user, 1).unwrap_err();
+    assert_eq!(
+        ContractError::IssuanceCapReached {},
+        err.downcast().unwrap()
+    );
+}
+
+#[test]
+fn issuance_cap_without_token_cap() {
+    let user = "user";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(user, 2_000)])
+        .build();
+    assert_eq!(suite.query_config().unwrap().issuance_cap, None);
+
+    suite.update_issuance_cap("owner", Some(1_000)).unwrap();
+    let err = suite.bond(user, 1_001).unwrap_err();
+    assert_eq!(
+        ContractError::IssuanceCapReached {},
+        err.downcast().unwrap()
+    );
+
+    suite.update_issuance_cap("owner", None).unwrap();
+    suite.bond(user, 2_000).unwrap();
+}
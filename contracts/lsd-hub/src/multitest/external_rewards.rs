@@ -0,0 +1,167 @@
+use cosmwasm_std::{coin, Uint128};
+
+use super::suite::SuiteBuilder;
+use crate::contract::EXTERNAL_REWARD_SWEEP_DELAY;
+use crate::ContractError;
+
+const HOUR: u64 = 60 * 60;
+
+#[test]
+fn external_reward_is_drained_by_exiting_holders() {
+    let holders = &["big", "small"];
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(holders[0], 750_000), (holders[1], 250_000)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    suite.bond(holders[0], 750_000).unwrap();
+    suite.bond(holders[1], 250_000).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+
+    // the incentive module sends some other tokens to the hub
+    let reward = 1_000_003u128;
+    suite.mint_to_hub(reward, "uincent").unwrap();
+
+    let err = suite
+        .register_external_reward("random", "uincent")
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    let err = suite.register_external_reward("owner", "FUN").unwrap_err();
+    assert_eq!(
+        ContractError::InvalidExternalReward {
+            denom: "FUN".to_string()
+        },
+        err.downcast().unwrap()
+    );
+    suite.register_external_reward("owner", "uincent").unwrap();
+
+    let rewards = suite.query_external_rewards(Some(holders[0])).unwrap();
+    assert_eq!(rewards.rewards.len(), 1);
+    assert_eq!(rewards.rewards[0].amount, Uint128::new(reward));
+    assert_eq!(rewards.rewards[0].shares, Uint128::new(1_000_000));
+    assert_eq!(rewards.entitlements, vec![coin(750_002, "uincent")]);
+
+    // the big holder exits in two steps, getting their part of what is left each time
+    suite.unbond(holders[0], &lsd, 500_000).unwrap();
+    assert_eq!(suite.query_balance(holders[0], "uincent").unwrap(), 500_001);
+    // the small holder keeps earning on their deferred claim, so they only get their part when claiming
+    suite.unbond_deferred(holders[1], &lsd, 250_000).unwrap();
+    assert_eq!(suite.query_balance(holders[1], "uincent").unwrap(), 0);
+    suite.unbond(holders[0], &lsd, 250_000).unwrap();
+    assert_eq!(
+        suite.query_balance(holders[0], "uincent").unwrap(),
+        500_001 + 250_001
+    );
+
+    let rewards = suite.query_external_rewards(Some(holders[1])).unwrap();
+    assert_eq!(rewards.rewards[0].amount, Uint128::new(250_001));
+    assert_eq!(rewards.rewards[0].shares, Uint128::new(250_000));
+    // the deferred shares are held by the hub now
+    assert!(rewards.entitlements.is_empty());
+
+    // keep reinvesting until the claims are released
+    for _ in 0..32 {
+        suite.update_time(23 * HOUR);
+        suite.reinvest().unwrap();
+    }
+    suite.claim(holders[1]).unwrap();
+    assert_eq!(suite.query_balance(holders[1], "uincent").unwrap(), 250_001);
+
+    // everything was distributed
+    assert_eq!(
+        suite.query_balance(suite.hub.as_str(), "uincent").unwrap(),
+        0
+    );
+    assert!(suite
+        .query_external_rewards(None)
+        .unwrap()
+        .rewards
+        .is_empty());
+}
+
+#[test]
+fn tokens_bonded_after_registration_get_no_external_reward() {
+    let holder = "holder";
+    let attacker = "attacker";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(holder, 1_000_000), (attacker, 5_000_000)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    suite.bond(holder, 1_000_000).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+
+    let reward = 1_000_000u128;
+    suite.mint_to_hub(reward, "uincent").unwrap();
+    suite.register_external_reward("owner", "uincent").unwrap();
+
+    // bonding more than was issued at registration and exiting right away does not drain the pool
+    suite.bond(attacker, 5_000_000).unwrap();
+    let attacker_shares = suite.query_cw20_balance(attacker, &lsd).unwrap();
+    assert!(suite
+        .query_external_rewards(Some(attacker))
+        .unwrap()
+        .entitlements
+        .is_empty());
+    suite.unbond(attacker, &lsd, attacker_shares).unwrap();
+    assert_eq!(suite.query_balance(attacker, "uincent").unwrap(), 0);
+
+    // the holder at registration still gets all of it
+    let holder_shares = suite.query_cw20_balance(holder, &lsd).unwrap();
+    suite.unbond(holder, &lsd, holder_shares).unwrap();
+    assert_eq!(suite.query_balance(holder, "uincent").unwrap(), reward);
+    assert!(suite
+        .query_external_rewards(None)
+        .unwrap()
+        .rewards
+        .is_empty());
+}
+
+#[test]
+fn unclaimed_external_reward_is_swept_to_the_treasury() {
+    let holder = "holder";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(holder, 1_000_000)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    suite.bond(holder, 1_000_000).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+
+    let reward = 1_000_000u128;
+    suite.mint_to_hub(reward, "uincent").unwrap();
+    suite.register_external_reward("owner", "uincent").unwrap();
+    let registered_at = suite.app.block_info().time.seconds();
+
+    // a quarter exits and takes its part, the rest is never unbonded
+    suite.unbond(holder, &lsd, 250_000).unwrap();
+    assert_eq!(suite.query_balance(holder, "uincent").unwrap(), 250_000);
+
+    let err = suite.sweep_external_reward("owner", "uincent").unwrap_err();
+    assert_eq!(
+        ContractError::ExternalRewardSweepTooEarly {
+            sweepable_at: registered_at + EXTERNAL_REWARD_SWEEP_DELAY
+        },
+        err.downcast().unwrap()
+    );
+
+    suite.update_time(EXTERNAL_REWARD_SWEEP_DELAY);
+    let err = suite
+        .sweep_external_reward("random", "uincent")
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    suite.sweep_external_reward("owner", "uincent").unwrap();
+    assert_eq!(suite.query_balance("treasury", "uincent").unwrap(), 750_000);
+    assert!(suite
+        .query_external_rewards(None)
+        .unwrap()
+        .rewards
+        .is_empty());
+
+    // the remaining holders exit without it
+    suite.unbond(holder, &lsd, 750_000).unwrap();
+    assert_eq!(suite.query_balance(holder, "uincent").unwrap(), 250_000);
+}
@@ -0,0 +1,58 @@
+use cosmwasm_std::Decimal;
+
+use crate::msg::BondedChangeReason;
+use crate::multitest::suite::SuiteBuilder;
+
+const HOUR: u64 = 60 * 60;
+const EPOCH: u64 = 23 * HOUR;
+
+#[test]
+fn journal_deltas_sum_to_total_bonded() {
+    let delegator = "delegator";
+
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .build();
+
+    // nothing bonded yet
+    assert!(suite.query_bonded_journal(None, None).unwrap().is_empty());
+
+    suite.bond(delegator, amount).unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+
+    // unbond half, which is undelegated in the next unbonding epoch
+    let lsd = suite.query_lsd_token().unwrap();
+    suite.unbond(delegator, &lsd, amount / 2).unwrap();
+    suite.update_time(5 * EPOCH);
+    suite.reinvest().unwrap();
+
+    suite.slash("testvaloper1", Decimal::percent(1)).unwrap();
+    suite.check_slash().unwrap();
+
+    let journal = suite.query_bonded_journal(None, None).unwrap();
+    let reasons: Vec<_> = journal.iter().map(|e| e.reason.clone()).collect();
+    assert_eq!(
+        reasons,
+        vec![
+            BondedChangeReason::Delegate,
+            BondedChangeReason::Undelegate,
+            BondedChangeReason::Slash
+        ]
+    );
+
+    let total_bonded = suite.query_tvl().unwrap();
+    let sum: i128 = journal
+        .iter()
+        .map(|e| e.delta.parse::<i128>().unwrap())
+        .sum();
+    assert_eq!(sum, total_bonded.u128() as i128);
+    assert_eq!(journal.last().unwrap().new_total, total_bonded);
+
+    // pagination
+    let page = suite
+        .query_bonded_journal(Some(journal[0].id), Some(1))
+        .unwrap();
+    assert_eq!(page, vec![journal[1].clone()]);
+}
@@ -1,5 +1,15 @@
+pub mod boost;
+pub mod bootstrap;
+pub mod claim_vouchers;
+pub mod deferred;
 pub mod deposit;
+pub mod external_rewards;
+pub mod journal;
+pub mod redistribute;
 pub mod reinvest;
+pub mod reinvest_hook;
 pub mod sad_path;
 pub mod slashing;
+pub mod sudo;
 pub mod suite;
+pub mod vesting;
@@ -0,0 +1,113 @@
+use std::str::FromStr;
+
+use cosmwasm_std::Decimal;
+
+use super::suite::SuiteBuilder;
+use crate::{contract::MAX_REDISTRIBUTE_RECIPIENTS, ContractError};
+
+#[test]
+fn redistribute_splits_by_weight() {
+    let incentives = "incentives";
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(incentives, amount)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+    suite.bond(incentives, amount).unwrap();
+
+    let recipients = [
+        (
+            "member1",
+            Decimal::from_str("0.333333333333333333").unwrap(),
+        ),
+        (
+            "member2",
+            Decimal::from_str("0.166666666666666667").unwrap(),
+        ),
+        ("member3", Decimal::from_str("0.5").unwrap()),
+    ];
+    let sent = 999_997u128;
+    suite
+        .redistribute(incentives, &lsd, sent, &recipients)
+        .unwrap();
+
+    let received: Vec<_> = recipients
+        .iter()
+        .map(|(addr, _)| suite.query_cw20_balance(addr, &lsd).unwrap())
+        .collect();
+    // everyone but the first gets their rounded down share, the first one also gets the dust
+    assert_eq!(received[1], 166_666);
+    assert_eq!(received[2], 499_998);
+    assert_eq!(received[0], 333_333);
+    assert_eq!(received.iter().sum::<u128>(), sent);
+
+    // nothing stays with the hub
+    assert_eq!(
+        suite.query_cw20_balance(suite.hub.as_str(), &lsd).unwrap(),
+        0
+    );
+    assert_eq!(
+        suite.query_cw20_balance(incentives, &lsd).unwrap(),
+        amount - sent
+    );
+}
+
+#[test]
+fn redistribute_validation() {
+    let incentives = "incentives";
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(incentives, amount)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+    suite.bond(incentives, amount).unwrap();
+
+    let err = suite
+        .redistribute(
+            incentives,
+            &lsd,
+            100,
+            &[
+                ("member1", Decimal::percent(50)),
+                ("member2", Decimal::percent(49)),
+            ],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidRedistributeWeights {},
+        err.downcast().unwrap()
+    );
+
+    let err = suite.redistribute(incentives, &lsd, 100, &[]).unwrap_err();
+    assert_eq!(
+        ContractError::InvalidRedistributeWeights {},
+        err.downcast().unwrap()
+    );
+
+    let names: Vec<_> = (0..=MAX_REDISTRIBUTE_RECIPIENTS)
+        .map(|i| format!("member{i}"))
+        .collect();
+    let weight = Decimal::from_ratio(1u128, names.len() as u128);
+    let recipients: Vec<_> = names.iter().map(|name| (name.as_str(), weight)).collect();
+    let err = suite
+        .redistribute(incentives, &lsd, 100, &recipients)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::TooManyRecipients {
+            max: MAX_REDISTRIBUTE_RECIPIENTS
+        },
+        err.downcast().unwrap()
+    );
+
+    // only the lsd token is accepted
+    let other_token = suite.other_token_contract.clone();
+    let err = suite
+        .redistribute(
+            incentives,
+            &other_token,
+            100,
+            &[("member1", Decimal::one())],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::InvalidToken {}, err.downcast().unwrap());
+}
@@ -1,9 +1,14 @@
 use std::{collections::HashMap, str::FromStr};
 
-use cosmwasm_std::Decimal;
+use cosmwasm_std::{assert_approx_eq, Decimal, Order, Uint128};
 
-use crate::multitest::suite::SuiteBuilder;
-use crate::state::BONDED;
+use crate::events::{COMMISSION_SPIKE_EVENT, RATE_GROWTH_CAPPED_EVENT, REINVEST_EVENT};
+use crate::msg::{SurplusStrategy, ValidatorConfig};
+use crate::multitest::suite::{event_attribute, Suite, SuiteBuilder};
+use crate::state::{
+    BONDED, CONFIG, REDELEGATION_ENTRIES, SUPPLY, UNBONDING, VALIDATOR_COMMISSIONS,
+};
+use crate::ContractError;
 
 const HOUR: u64 = 60 * 60;
 const DAY: u64 = 24 * HOUR;
@@ -159,3 +164,1323 @@ fn bonded_updated_correctly() {
         "half of the tokens are unbonded, but rewards and other half should still be there"
     );
 }
+
+#[test]
+fn min_blocks_per_epoch_guard() {
+    let delegator = "delegator";
+
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .with_periods(EPOCH, 28 * DAY)
+        .build();
+
+    // only the owner can set it
+    let err = suite
+        .update_min_blocks_per_epoch("notowner", Some(100))
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    suite
+        .update_min_blocks_per_epoch("owner", Some(100))
+        .unwrap();
+
+    suite.bond(delegator, amount).unwrap();
+
+    // time passes, but no blocks are produced
+    suite.update_time_only(EPOCH);
+    let err = suite.reinvest().unwrap_err();
+    assert_eq!(
+        ContractError::EpochBlocksNotReached {
+            remaining_seconds: 0,
+            remaining_blocks: 100,
+        },
+        err.downcast().unwrap()
+    );
+
+    // normal path, both time and height advance
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+
+    // time is skewed forward by a full epoch, without any blocks
+    suite.update_time_only(EPOCH);
+    let err = suite.reinvest().unwrap_err();
+    assert_eq!(
+        ContractError::EpochBlocksNotReached {
+            remaining_seconds: 0,
+            remaining_blocks: 100,
+        },
+        err.downcast().unwrap()
+    );
+
+    // once the blocks catch up, the epoch can advance again
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+
+    // enough blocks, but the time is not reached yet
+    suite.update_time(HOUR);
+    let err = suite.reinvest().unwrap_err();
+    assert_eq!(
+        ContractError::EpochBlocksNotReached {
+            remaining_seconds: EPOCH - HOUR,
+            remaining_blocks: 0,
+        },
+        err.downcast().unwrap()
+    );
+
+    // disabling the guard restores the time-only behaviour
+    suite.update_min_blocks_per_epoch("owner", None).unwrap();
+    let err = suite.reinvest().unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::EpochNotReached { .. }
+    ));
+}
+
+#[test]
+fn preview_commission_matches_reinvest() {
+    let delegator = "delegator";
+
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .with_validators(vec![
+            ("testvaloper1", Decimal::percent(50)),
+            ("testvaloper2", Decimal::percent(50)),
+        ])
+        .build();
+
+    // nothing bonded yet, so there is nothing to take commission from
+    let preview = suite.query_preview_commission().unwrap();
+    assert_eq!(preview.gross_rewards.u128(), 0);
+    assert_eq!(preview.commission.u128(), 0);
+
+    suite.bond(delegator, amount).unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+
+    // wait for some rewards to accrue
+    suite.update_time(EPOCH);
+    let preview = suite.query_preview_commission().unwrap();
+    assert!(!preview.commission.is_zero());
+    assert_eq!(
+        preview.gross_rewards,
+        preview.commission + preview.net_rewards
+    );
+
+    let treasury_before = suite.query_balance("treasury", "FUN").unwrap();
+    suite.reinvest().unwrap();
+    let commission = suite.query_balance("treasury", "FUN").unwrap() - treasury_before;
+    cosmwasm_std::assert_approx_eq!(preview.commission.u128(), commission, "0.001");
+}
+
+#[test]
+fn skip_next_unbond() {
+    let delegator = "delegator";
+
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .with_periods(EPOCH, 28 * DAY)
+        .build();
+
+    suite.bond(delegator, amount).unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+    suite
+        .unbond(delegator, &suite.query_lsd_token().unwrap(), amount / 2)
+        .unwrap();
+
+    // only the owner can skip
+    let err = suite.skip_next_unbond(delegator).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    suite.skip_next_unbond("owner").unwrap();
+
+    // the next reinvest does not undelegate
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+    let supply = crate::state::SUPPLY
+        .query(&suite.app.wrap(), suite.hub.clone())
+        .unwrap();
+    assert!(supply.total_unbonding.is_zero());
+
+    // but the one after the skipped unbonding epoch (4 days) does
+    suite.update_time(4 * EPOCH);
+    suite.reinvest().unwrap();
+    let supply = crate::state::SUPPLY
+        .query(&suite.app.wrap(), suite.hub.clone())
+        .unwrap();
+    assert!(!supply.total_unbonding.is_zero());
+}
+
+#[test]
+fn no_undelegation_when_unbonding_completes() {
+    let delegator = "delegator";
+    let amount = 1_000_000u128;
+    let unbond_amount = 500_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    suite.bond(delegator, amount).unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+
+    // unbond and undelegate for the claim
+    suite.unbond(delegator, &lsd, unbond_amount).unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+    let supply = SUPPLY.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
+    assert!(!supply.total_unbonding.is_zero());
+    let claims = supply.claims;
+
+    // reinvest exactly when the unbonding completes
+    suite.update_time(28 * DAY);
+    suite.reinvest().unwrap();
+
+    // the completed unbonding covers the claim, so nothing is undelegated again
+    let supply = SUPPLY.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
+    assert_eq!(supply.total_unbonding.u128(), 0);
+    assert_eq!(supply.claims, claims);
+    let storage = suite.read_hub_storage();
+    assert_eq!(
+        UNBONDING
+            .range(&storage, None, None, Order::Ascending)
+            .count(),
+        0
+    );
+
+    // and the surplus is not delegated and undelegated again in the next epoch
+    let bonded = supply.total_bonded;
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+    let supply = SUPPLY.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
+    assert_eq!(supply.total_unbonding.u128(), 0);
+    assert!(supply.total_bonded >= bonded);
+
+    suite.claim(delegator).unwrap();
+    assert_eq!(
+        suite.query_balance(delegator, "FUN").unwrap(),
+        claims.u128()
+    );
+}
+
+#[test]
+fn reinvest_validator_subset() {
+    let delegator = "delegator";
+    let amount = 1_000_000u128;
+    let validators = vec![
+        ("testvaloper1", Decimal::percent(50)),
+        ("testvaloper2", Decimal::percent(50)),
+    ];
+    let mut partial = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .with_validators(validators.clone())
+        .build();
+    let mut all_at_once = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .with_validators(validators)
+        .build();
+
+    for suite in [&mut partial, &mut all_at_once] {
+        suite.bond(delegator, amount).unwrap();
+        suite.update_time(EPOCH);
+        suite.reinvest().unwrap();
+        suite.update_time(EPOCH);
+    }
+    let next_epoch = CONFIG
+        .query(&partial.app.wrap(), partial.hub.clone())
+        .unwrap()
+        .next_epoch;
+
+    // only withdraw from the first validator
+    partial.reinvest_validators(&["testvaloper1"]).unwrap();
+    let rewards = partial.query_balance(partial.hub.as_str(), "FUN").unwrap();
+    assert!(rewards > 0, "rewards should be withdrawn");
+    // nothing is delegated and the epoch did not advance yet
+    let supply = SUPPLY
+        .query(&partial.app.wrap(), partial.hub.clone())
+        .unwrap();
+    assert_eq!(supply.total_bonded.u128(), amount);
+    assert_eq!(
+        CONFIG
+            .query(&partial.app.wrap(), partial.hub.clone())
+            .unwrap()
+            .next_epoch,
+        next_epoch
+    );
+
+    // the processed validator can not be processed again in this epoch, and unknown ones are rejected
+    for validator in ["testvaloper1", "testvaloper3"] {
+        let err = partial.reinvest_validators(&[validator]).unwrap_err();
+        assert_eq!(
+            ContractError::ValidatorNotPending {
+                validator: validator.to_string()
+            },
+            err.downcast().unwrap()
+        );
+    }
+    let err = partial.reinvest_validators(&[]).unwrap_err();
+    assert_eq!(
+        ContractError::EmptyValidatorSubset {},
+        err.downcast().unwrap()
+    );
+
+    // processing the remaining validator completes the reinvest
+    partial.reinvest_validators(&["testvaloper2"]).unwrap();
+    assert!(
+        CONFIG
+            .query(&partial.app.wrap(), partial.hub.clone())
+            .unwrap()
+            .next_epoch
+            > next_epoch
+    );
+    all_at_once.reinvest().unwrap();
+
+    // the result is the same as processing all validators at once
+    let partial_supply = SUPPLY
+        .query(&partial.app.wrap(), partial.hub.clone())
+        .unwrap();
+    let all_supply = SUPPLY
+        .query(&all_at_once.app.wrap(), all_at_once.hub.clone())
+        .unwrap();
+    assert!(partial_supply.total_bonded.u128() > amount + rewards);
+    assert_approx_eq!(
+        partial_supply.total_bonded,
+        all_supply.total_bonded,
+        "0.00001"
+    );
+    assert_eq!(
+        partial.query_balance(partial.hub.as_str(), "FUN").unwrap(),
+        0
+    );
+    // the commission is rounded separately for each part
+    let partial_commission = partial.query_balance("treasury", "FUN").unwrap();
+    let all_commission = all_at_once.query_balance("treasury", "FUN").unwrap();
+    assert!(partial_commission.abs_diff(all_commission) <= 1);
+
+    // the next epoch has to be reached again
+    let err = partial.reinvest_validators(&["testvaloper1"]).unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::EpochNotReached { .. }
+    ));
+}
+
+#[test]
+fn reinvest_without_subset_completes_partial_reinvest() {
+    let delegator = "delegator";
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .with_validators(vec![
+            ("testvaloper1", Decimal::percent(50)),
+            ("testvaloper2", Decimal::percent(50)),
+        ])
+        .build();
+
+    suite.bond(delegator, amount).unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+    suite.update_time(EPOCH);
+
+    suite.reinvest_validators(&["testvaloper2"]).unwrap();
+    // `None` processes the remaining validators and settles the delegations
+    suite.reinvest().unwrap();
+    assert_eq!(suite.query_balance(suite.hub.as_str(), "FUN").unwrap(), 0);
+    let supply = SUPPLY.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
+    assert!(supply.total_bonded.u128() > amount);
+
+    let err = suite.reinvest().unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::EpochNotReached { .. }
+    ));
+}
+
+#[test]
+fn dust_reinvest_is_skipped() {
+    let delegator = "delegator";
+
+    let amount = 10_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .with_min_reinvest_amount(100)
+        .build();
+
+    // the deposit is above the threshold
+    suite.bond(delegator, amount).unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+    let delegated = suite.query_delegations().unwrap()[0].amount.amount.u128();
+    assert_eq!(delegated, amount);
+
+    let snapshot = suite.app.block_info().time.seconds();
+
+    // one epoch of rewards is not
+    suite.update_time(EPOCH);
+    let res = suite.reinvest().unwrap();
+    assert_eq!(
+        event_attribute(&res, REINVEST_EVENT, "status"),
+        Some("skipped_dust".to_string())
+    );
+    // the commission was not taken yet, so there is no new snapshot
+    let now = suite.app.block_info().time.seconds();
+    assert_eq!(
+        suite.query_exchange_rate_at(now).unwrap().snapshot_time,
+        snapshot
+    );
+    assert_eq!(
+        suite.query_delegations().unwrap()[0].amount.amount.u128(),
+        delegated
+    );
+    assert_eq!(suite.query_balance("treasury", "FUN").unwrap(), 0);
+    let skipped = suite.query_balance(suite.hub.as_str(), "FUN").unwrap();
+    assert!(skipped > 0 && skipped < 100);
+    // the rewards still count towards the exchange rate
+    assert!(suite.query_exchange_rate().unwrap() > Decimal::one());
+
+    // once enough accumulated, everything is delegated and the commission is taken on all of it
+    let mut epochs = 1;
+    while suite.query_delegations().unwrap()[0].amount.amount.u128() == delegated {
+        assert!(epochs < 10, "accumulated rewards were never delegated");
+        suite.update_time(EPOCH);
+        suite.reinvest().unwrap();
+        epochs += 1;
+    }
+    let commission = suite.query_balance("treasury", "FUN").unwrap();
+    let delegated_rewards = suite.query_delegations().unwrap()[0].amount.amount.u128() - delegated;
+    assert!(delegated_rewards >= 100);
+    assert_approx_eq!(
+        commission,
+        (commission + delegated_rewards) * 5 / 100,
+        "0.05"
+    );
+    assert_eq!(suite.query_balance(suite.hub.as_str(), "FUN").unwrap(), 0);
+}
+
+#[test]
+fn bonds_are_delegated_despite_dust_rewards() {
+    let delegator = "delegator";
+
+    let amount = 10_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 2 * amount)])
+        .with_min_reinvest_amount(100)
+        .build();
+    suite.bond(delegator, amount).unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+
+    // the rewards are dust, but the new bond is not left in the balance
+    suite.bond(delegator, amount).unwrap();
+    suite.update_time(EPOCH);
+    let res = suite.reinvest().unwrap();
+    assert_eq!(
+        event_attribute(&res, REINVEST_EVENT, "status"),
+        Some("delegated".to_string())
+    );
+    assert!(suite.query_delegations().unwrap()[0].amount.amount.u128() > 2 * amount);
+    assert_eq!(suite.query_balance(suite.hub.as_str(), "FUN").unwrap(), 0);
+}
+
+#[test]
+fn update_min_reinvest_amount() {
+    let mut suite = SuiteBuilder::new().build();
+
+    let err = suite.update_min_reinvest_amount("random", 5).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    suite.update_min_reinvest_amount("owner", 5).unwrap();
+    let config = CONFIG.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
+    assert_eq!(config.min_reinvest_amount.u128(), 5);
+}
+
+/// Drifts the delegations away from the 50/50 target by slashing one validator,
+/// then bonds more and returns how much each validator got from the next reinvest
+fn surplus_after_drift(strategy: SurplusStrategy) -> (u128, u128) {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_300_000)])
+        .with_validators(vec![
+            ("testvaloper1", Decimal::percent(50)),
+            ("testvaloper2", Decimal::percent(50)),
+        ])
+        .with_periods(DAY, 28 * DAY)
+        .build();
+    suite.update_surplus_strategy("owner", strategy).unwrap();
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(DAY);
+    suite.reinvest().unwrap();
+
+    suite.slash("testvaloper1", Decimal::percent(50)).unwrap();
+    suite.check_slash().unwrap();
+    let before = suite.query_validator_set_with_bonded().unwrap();
+    assert_eq!(before[0].bonded.u128(), 250_000);
+    assert_eq!(before[1].bonded.u128(), 500_000);
+
+    suite.bond(delegator, 300_000).unwrap();
+    suite.update_time(DAY);
+    suite.reinvest().unwrap();
+    let after = suite.query_validator_set_with_bonded().unwrap();
+    (
+        (after[0].bonded - before[0].bonded).u128(),
+        (after[1].bonded - before[1].bonded).u128(),
+    )
+}
+
+#[test]
+fn surplus_strategies_from_drifted_state() {
+    // the target weights split the surplus evenly, moving towards 50/50
+    let (first, second) = surplus_after_drift(SurplusStrategy::TargetWeights);
+    assert_approx_eq!(first, second, "0.0001");
+
+    // delegating proportionally keeps the drifted 1:2 distribution
+    let (first, second) = surplus_after_drift(SurplusStrategy::ProportionalToBonded);
+    assert_approx_eq!(2 * first, second, "0.0001");
+    assert!(first > 100_000);
+}
+
+#[test]
+fn update_surplus_strategy() {
+    let mut suite = SuiteBuilder::new().build();
+    assert_eq!(
+        suite.query_config().unwrap().surplus_strategy,
+        SurplusStrategy::TargetWeights
+    );
+
+    let err = suite
+        .update_surplus_strategy("random", SurplusStrategy::ProportionalToBonded)
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    suite
+        .update_surplus_strategy("owner", SurplusStrategy::ProportionalToBonded)
+        .unwrap();
+    assert_eq!(
+        suite.query_config().unwrap().surplus_strategy,
+        SurplusStrategy::ProportionalToBonded
+    );
+}
+
+#[test]
+fn reward_rate_from_last_reinvests() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .build();
+
+    // no estimate before there were two reinvests
+    let rate = suite.query_reward_rate().unwrap();
+    assert_eq!(rate.apr, Decimal::zero());
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+    assert_eq!(suite.query_reward_rate().unwrap().apr, Decimal::zero());
+
+    for _ in 0..2 {
+        suite.update_time(EPOCH);
+        suite.reinvest().unwrap();
+    }
+    let rate = suite.query_reward_rate().unwrap();
+    assert_eq!(rate.to - rate.from, EPOCH);
+    // 80% staking apr, minus 5% validator commission and 5% treasury commission
+    assert_approx_eq!(rate.apr.atomics(), Decimal::permille(722).atomics(), "0.01");
+}
+
+#[test]
+fn capped_validator_spills_over() {
+    let delegator = "delegator";
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .with_validators(vec![
+            ("testvaloper1", Decimal::percent(50)),
+            ("testvaloper2", Decimal::percent(25)),
+            ("testvaloper3", Decimal::percent(25)),
+        ])
+        .with_periods(EPOCH, 28 * DAY)
+        .build();
+    let config = |address: &str, weight, max_delegation: Option<u128>| ValidatorConfig {
+        address: address.to_string(),
+        weight: Decimal::percent(weight),
+        max_delegation: max_delegation.map(Into::into),
+    };
+
+    suite
+        .set_validator_configs(
+            "owner",
+            vec![
+                config("testvaloper1", 50, Some(100_000)),
+                config("testvaloper2", 25, None),
+                config("testvaloper3", 25, None),
+            ],
+        )
+        .unwrap();
+    suite.bond(delegator, amount).unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+
+    // the capped validator only gets up to its cap, the rest is split between the others
+    let bonded = BONDED.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
+    assert_eq!(
+        HashMap::from([
+            ("testvaloper1".to_string(), 100_000u128.into()),
+            ("testvaloper2".to_string(), 450_000u128.into()),
+            ("testvaloper3".to_string(), 450_000u128.into()),
+        ]),
+        bonded.into_iter().collect()
+    );
+
+    // moving the cap redelegates the same way
+    suite
+        .set_validator_configs(
+            "owner",
+            vec![
+                config("testvaloper1", 50, None),
+                config("testvaloper2", 25, Some(200_000)),
+                config("testvaloper3", 25, None),
+            ],
+        )
+        .unwrap();
+    let bonded = BONDED.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
+    // 50_000 above the cap are split 2:1, the first uncapped validator gets the rounding remainder
+    assert_eq!(
+        HashMap::from([
+            ("testvaloper1".to_string(), 533_334u128.into()),
+            ("testvaloper2".to_string(), 200_000u128.into()),
+            ("testvaloper3".to_string(), 266_666u128.into()),
+        ]),
+        bonded.into_iter().collect()
+    );
+    let delegations: HashMap<_, _> = suite
+        .query_delegations()
+        .unwrap()
+        .into_iter()
+        .map(|d| (d.validator, d.amount.amount.u128()))
+        .collect();
+    assert_eq!(delegations["testvaloper2"], 200_000);
+}
+
+#[test]
+fn suspended_delegations_accumulate_until_resumed() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_500_000)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+    let delegated = |suite: &super::suite::Suite| -> u128 {
+        suite
+            .query_delegations()
+            .unwrap()
+            .iter()
+            .map(|d| d.amount.amount.u128())
+            .sum()
+    };
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+    assert_eq!(delegated(&suite), 1_000_000);
+
+    let err = suite.suspend_delegations(delegator).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    suite.suspend_delegations("owner").unwrap();
+    assert!(suite.query_config().unwrap().delegations_suspended);
+
+    // new deposits and rewards stay in the balance, but still count towards the exchange rate
+    suite.bond(delegator, 500_000).unwrap();
+    let rate_before = suite.query_exchange_rate().unwrap();
+    suite.update_time(EPOCH);
+    let res = suite.reinvest().unwrap();
+    assert_eq!(
+        event_attribute(&res, REINVEST_EVENT, "status"),
+        Some("suspended".to_string())
+    );
+    assert_eq!(delegated(&suite), 1_000_000);
+    assert!(suite.query_balance(suite.hub.as_str(), "FUN").unwrap() > 500_000);
+    let rate_suspended = suite.query_exchange_rate().unwrap();
+    assert!(rate_suspended > rate_before);
+
+    // unbonding still works and is paid from the balance
+    suite.unbond(delegator, &lsd, 100_000).unwrap();
+
+    suite.resume_delegations("owner").unwrap();
+    assert!(!suite.query_config().unwrap().delegations_suspended);
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+
+    // everything but the claims is delegated again, without a jump in the exchange rate
+    let supply = SUPPLY.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
+    assert_eq!(
+        suite.query_balance(suite.hub.as_str(), "FUN").unwrap(),
+        supply.claims.u128()
+    );
+    assert!(delegated(&suite) > 1_400_000);
+    let rate_resumed = suite.query_exchange_rate().unwrap();
+    assert!(rate_resumed >= rate_suspended);
+    assert_approx_eq!(rate_resumed.atomics(), rate_suspended.atomics(), "0.01");
+
+    suite.update_time(28 * DAY);
+    suite.claim(delegator).unwrap();
+    assert!(suite.query_balance(delegator, "FUN").unwrap() >= 100_000);
+}
+
+#[test]
+fn deferred_delegations_are_delegated_every_n_epochs() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+    let delegated = |suite: &super::suite::Suite| -> u128 {
+        suite
+            .query_delegations()
+            .unwrap()
+            .iter()
+            .map(|d| d.amount.amount.u128())
+            .sum()
+    };
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+    assert_eq!(delegated(&suite), 1_000_000);
+
+    let err = suite.update_delegation_cadence(delegator, 3).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    suite.update_delegation_cadence("owner", 3).unwrap();
+    assert_eq!(suite.query_config().unwrap().delegate_every_n_epochs, 3);
+
+    // the rewards of the first two epochs accumulate in the balance
+    let mut buffer = 0;
+    for _ in 0..2 {
+        suite.update_time(EPOCH);
+        let res = suite.reinvest().unwrap();
+        assert_eq!(
+            event_attribute(&res, REINVEST_EVENT, "status"),
+            Some("deferred".to_string())
+        );
+        assert_eq!(delegated(&suite), 1_000_000);
+        let balance = suite.query_balance(suite.hub.as_str(), "FUN").unwrap();
+        assert!(balance > buffer);
+        buffer = balance;
+    }
+
+    // the third one delegates all of it
+    suite.update_time(EPOCH);
+    let res = suite.reinvest().unwrap();
+    assert_eq!(
+        event_attribute(&res, REINVEST_EVENT, "status"),
+        Some("delegated".to_string())
+    );
+    assert!(delegated(&suite) > 1_000_000 + buffer);
+    assert_eq!(suite.query_balance(suite.hub.as_str(), "FUN").unwrap(), 0);
+
+    // undelegating for claims is not deferred
+    suite.unbond(delegator, &lsd, 500_000).unwrap();
+    suite.update_time(EPOCH);
+    let res = suite.reinvest().unwrap();
+    assert_eq!(
+        event_attribute(&res, REINVEST_EVENT, "status"),
+        Some("undelegated".to_string())
+    );
+}
+
+#[test]
+fn claims_are_not_released_before_their_unbonding() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+
+    // unbond in every epoch, so most claims are created between two unbonding epochs
+    // and wait for the reinvest that reaches the next one
+    let mut releases = vec![];
+    for _ in 0..12 {
+        suite.unbond(delegator, &lsd, 50_000).unwrap();
+        suite.update_time(EPOCH);
+        let res = suite.reinvest().unwrap();
+        releases.push(
+            event_attribute(&res, REINVEST_EVENT, "unbonding_release_at")
+                .map(|a| a.parse::<u64>().unwrap())
+                .filter(|release_at| *release_at != 0),
+        );
+    }
+    assert!(releases.iter().filter(|r| r.is_some()).count() > 1);
+
+    let claims = suite.query_claims(delegator.to_string()).unwrap();
+    assert_eq!(claims.len(), 12);
+    let mut checked = 0;
+    for (i, claim) in claims.iter().enumerate() {
+        // the claim is backed by the first undelegation after it was created
+        if let Some(release) = releases[i..].iter().flatten().next() {
+            assert!(claim.release_at.seconds() >= *release);
+            checked += 1;
+        }
+    }
+    assert!(checked > 4);
+}
+
+#[test]
+fn exchange_rate_at_past_snapshots() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .build();
+    suite.bond(delegator, 1_000_000).unwrap();
+
+    // every reinvest takes a snapshot
+    let mut snapshots = vec![];
+    for _ in 0..5 {
+        suite.update_time(EPOCH);
+        suite.reinvest().unwrap();
+        snapshots.push((
+            suite.app.block_info().time.seconds(),
+            suite.query_exchange_rate().unwrap(),
+        ));
+    }
+    assert!(snapshots[4].1 > snapshots[0].1);
+
+    // a time in the middle of the window returns the snapshot before it
+    let (time, rate) = snapshots[2];
+    let res = suite.query_exchange_rate_at(time + 10 * HOUR).unwrap();
+    assert_eq!(res.exchange_rate, rate);
+    assert_eq!(res.snapshot_time, time);
+    let res = suite.query_exchange_rate_at(time).unwrap();
+    assert_eq!(res.snapshot_time, time);
+
+    // snapshots are never pruned, so the oldest one is still available
+    let (first_time, first_rate) = snapshots[0];
+    let res = suite.query_exchange_rate_at(first_time).unwrap();
+    assert_eq!(res.exchange_rate, first_rate);
+    let err = suite.query_exchange_rate_at(first_time - 1).unwrap_err();
+    assert!(err.to_string().contains("the earliest snapshot is from"));
+
+    // later times return the latest snapshot
+    let res = suite.query_exchange_rate_at(snapshots[4].0 + DAY).unwrap();
+    assert_eq!(res.snapshot_time, snapshots[4].0);
+}
+
+#[test]
+fn new_validator_is_capped_during_probation() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 2_000_000)])
+        .with_registered_validators(vec!["testvaloper2".to_string()])
+        .build();
+    let bonded_share = |suite: &Suite, validator: &str| {
+        let bonded: HashMap<_, _> = BONDED
+            .query(&suite.app.wrap(), suite.hub.clone())
+            .unwrap()
+            .into_iter()
+            .collect();
+        let total: Uint128 = bonded.values().sum();
+        Decimal::from_ratio(bonded.get(validator).copied().unwrap_or_default(), total)
+    };
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+
+    let err = suite
+        .update_validator_probation(delegator, 10 * DAY, Decimal::percent(2))
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    let err = suite
+        .update_validator_probation("owner", 10 * DAY, Decimal::percent(101))
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidProbationMaxWeight {},
+        err.downcast().unwrap()
+    );
+    suite
+        .update_validator_probation("owner", 10 * DAY, Decimal::percent(2))
+        .unwrap();
+    let config = suite.query_config().unwrap();
+    assert_eq!(config.new_validator_probation, 10 * DAY);
+    assert_eq!(config.probation_max_weight, Decimal::percent(2));
+
+    let validators = vec![
+        ("testvaloper1".to_string(), Decimal::percent(70)),
+        ("testvaloper2".to_string(), Decimal::percent(30)),
+    ];
+    // the preview takes the probation into account as well
+    let preview = suite
+        .query_preview_valset_change(validators.clone())
+        .unwrap();
+    suite.set_validators("owner", validators.clone()).unwrap();
+    assert_eq!(
+        preview.new_balances,
+        BONDED.query(&suite.app.wrap(), suite.hub.clone()).unwrap()
+    );
+    assert!(bonded_share(&suite, "testvaloper2") <= Decimal::percent(2));
+
+    // reinvests only give it a small part of the surplus
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+    let share = bonded_share(&suite, "testvaloper2");
+    assert!(!share.is_zero());
+    assert!(share <= Decimal::percent(2));
+
+    // once the probation is over, the next valset change brings it to its weight
+    suite.update_time(10 * DAY);
+    suite.reinvest().unwrap();
+    suite.set_validators("owner", validators).unwrap();
+    assert_approx_eq!(
+        bonded_share(&suite, "testvaloper2").atomics(),
+        Decimal::percent(30).atomics(),
+        "0.0001"
+    );
+}
+
+#[test]
+fn commission_spike_is_reported() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .with_validators(vec![
+            ("testvaloper1", Decimal::percent(50)),
+            ("testvaloper2", Decimal::percent(50)),
+        ])
+        .build();
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+    // the first reinvest only records the commissions
+    assert_eq!(
+        suite.query_validator_commissions().unwrap(),
+        vec![
+            ("testvaloper1".to_string(), Decimal::percent(5)),
+            ("testvaloper2".to_string(), Decimal::percent(5)),
+        ]
+    );
+
+    let err = suite
+        .update_commission_spike_handling(delegator, Decimal::percent(1), false)
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    let err = suite
+        .update_commission_spike_handling("owner", Decimal::percent(101), false)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidCommissionIncreaseAlert {},
+        err.downcast().unwrap()
+    );
+    suite
+        .update_commission_spike_handling("owner", Decimal::percent(1), false)
+        .unwrap();
+
+    // cw-multi-test cannot change the commission of a validator, so we lower the one recorded by
+    // the last reinvest instead. To the hub, this is the same as an increase from 0% to 5%.
+    let lower_recorded_commission = |suite: &mut Suite| {
+        suite.update_hub_storage(|storage| {
+            VALIDATOR_COMMISSIONS
+                .save(storage, "testvaloper2", &Decimal::zero())
+                .unwrap()
+        })
+    };
+    lower_recorded_commission(&mut suite);
+    suite.update_time(EPOCH);
+    let res = suite.reinvest().unwrap();
+    assert_eq!(
+        event_attribute(&res, COMMISSION_SPIKE_EVENT, "validator"),
+        Some("testvaloper2".to_string())
+    );
+    assert_eq!(
+        event_attribute(&res, COMMISSION_SPIKE_EVENT, "before"),
+        Some("0".to_string())
+    );
+    assert_eq!(
+        event_attribute(&res, COMMISSION_SPIKE_EVENT, "after"),
+        Some("0.05".to_string())
+    );
+    assert_eq!(
+        event_attribute(&res, COMMISSION_SPIKE_EVENT, "weight_removed"),
+        Some("false".to_string())
+    );
+    assert_eq!(
+        suite.query_validator_set().unwrap()[1],
+        ("testvaloper2".to_string(), Decimal::percent(50))
+    );
+
+    // without a change, nothing is reported
+    suite.update_time(EPOCH);
+    let res = suite.reinvest().unwrap();
+    assert_eq!(
+        event_attribute(&res, COMMISSION_SPIKE_EVENT, "validator"),
+        None
+    );
+
+    // the weight is removed if configured
+    suite
+        .update_commission_spike_handling("owner", Decimal::percent(1), true)
+        .unwrap();
+    lower_recorded_commission(&mut suite);
+    suite.update_time(EPOCH);
+    let res = suite.reinvest().unwrap();
+    assert_eq!(
+        event_attribute(&res, COMMISSION_SPIKE_EVENT, "weight_removed"),
+        Some("true".to_string())
+    );
+    assert_eq!(
+        suite.query_validator_set().unwrap(),
+        vec![
+            ("testvaloper1".to_string(), Decimal::one()),
+            ("testvaloper2".to_string(), Decimal::zero()),
+        ]
+    );
+}
+
+#[test]
+fn owner_can_force_a_reinvest_within_the_epoch() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .build();
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+    let next_epoch = CONFIG
+        .query(&suite.app.wrap(), suite.hub.clone())
+        .unwrap()
+        .next_epoch;
+
+    suite.update_time(2 * HOUR);
+    let err = suite.reinvest().unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::EpochNotReached { .. }
+    ));
+
+    let err = suite.force_reinvest(delegator).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    let rate_before = suite.query_exchange_rate().unwrap();
+    suite.force_reinvest("owner").unwrap();
+    // the rewards of the last two hours were compounded, but the epoch stays the same
+    assert!(suite.query_exchange_rate().unwrap() > rate_before);
+    assert_eq!(
+        CONFIG
+            .query(&suite.app.wrap(), suite.hub.clone())
+            .unwrap()
+            .next_epoch,
+        next_epoch
+    );
+
+    // at most once per hour
+    suite.update_time(HOUR / 2);
+    let err = suite.force_reinvest("owner").unwrap_err();
+    let now = suite.app.block_info().time.seconds();
+    assert_eq!(
+        ContractError::ReinvestTooSoon {
+            next_reinvest: now + HOUR / 2
+        },
+        err.downcast().unwrap()
+    );
+    suite.update_time(HOUR / 2);
+    suite.force_reinvest("owner").unwrap();
+
+    // the regular reinvest is still possible once the epoch is reached
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+}
+
+#[test]
+fn claims_earn_for_the_remaining_holders_until_undelegated() {
+    let (leaver, stayer) = ("leaver", "stayer");
+    let amount = 500_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(leaver, amount), (stayer, amount)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+    suite.bond(leaver, amount).unwrap();
+    suite.bond(stayer, amount).unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+
+    // the claim is fixed, but the tokens stay delegated until the next reinvest
+    suite.unbond(leaver, &lsd, amount).unwrap();
+    let value = suite.query_accrued_but_unbonded_value().unwrap();
+    assert_eq!(value.still_delegated.u128(), amount);
+    assert_eq!(value.accrued_rewards, Uint128::zero());
+    // the next reinvest is the earliest undelegation
+    assert_eq!(
+        value.next_unbond,
+        suite.app.block_info().time.seconds() + EPOCH
+    );
+
+    // half of the delegated tokens belong to the claim, so half of the rewards were earned by it
+    suite.update_time(EPOCH);
+    let net_rewards = suite.query_preview_commission().unwrap().net_rewards;
+    let value = suite.query_accrued_but_unbonded_value().unwrap();
+    assert!(!value.accrued_rewards.is_zero());
+    assert_eq!(
+        value.accrued_rewards,
+        net_rewards.multiply_ratio(1u128, 2u128)
+    );
+
+    // the reinvest undelegates the claim and gives all rewards to the remaining holder
+    suite.reinvest().unwrap();
+    let raw = suite.query_exchange_rate_raw().unwrap();
+    assert_eq!(raw.issued.u128(), amount);
+    assert_eq!(raw.assets, Uint128::new(amount) + net_rewards);
+    let value = suite.query_accrued_but_unbonded_value().unwrap();
+    assert_eq!(value.still_delegated, Uint128::zero());
+    assert_eq!(value.accrued_rewards, Uint128::zero());
+}
+
+#[test]
+fn rebalance_on_reinvest_reduces_drift() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .with_validators(vec![
+            ("testvaloper1", Decimal::percent(50)),
+            ("testvaloper2", Decimal::percent(50)),
+        ])
+        .build();
+    let drift = |suite: &Suite| {
+        let bonded: HashMap<_, _> = BONDED
+            .query(&suite.app.wrap(), suite.hub.clone())
+            .unwrap()
+            .into_iter()
+            .collect();
+        bonded["testvaloper1"]
+            .abs_diff(bonded["testvaloper2"])
+            .u128()
+    };
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+    assert!(drift(&suite) <= 1);
+
+    // the slashing (below the tombstone threshold) leaves the first validator below its weight
+    suite.slash("testvaloper1", Decimal::percent(2)).unwrap();
+    suite.check_slash().unwrap();
+    let slashed_drift = drift(&suite);
+    assert_eq!(slashed_drift, 10_000);
+
+    // reinvests split the rewards by weight, so they keep the drift
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+    assert!(drift(&suite) + 1 >= slashed_drift);
+
+    let err = suite
+        .update_rebalance_on_reinvest(delegator, true)
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    suite.update_rebalance_on_reinvest("owner", true).unwrap();
+    assert!(suite.query_config().unwrap().rebalance_on_reinvest);
+
+    // now they redelegate toward the weights
+    let mut last_drift = drift(&suite);
+    for _ in 0..2 {
+        suite.update_time(EPOCH);
+        suite.reinvest().unwrap();
+        let current = drift(&suite);
+        assert!(current <= last_drift);
+        last_drift = current;
+        // the staking module sees the same delegations as the hub
+        let delegations = suite.query_delegations().unwrap();
+        let bonded = BONDED.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
+        for (validator, amount) in bonded {
+            let delegation = delegations
+                .iter()
+                .find(|d| d.validator == validator)
+                .unwrap();
+            assert_eq!(delegation.amount.amount, amount);
+        }
+    }
+    assert!(last_drift < slashed_drift / 100);
+}
+
+#[test]
+fn rebalance_skips_validators_still_receiving_a_redelegation() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .with_validators(vec![
+            ("testvaloper1", Decimal::percent(50)),
+            ("testvaloper2", Decimal::percent(50)),
+        ])
+        .build();
+    let bonded = |suite: &Suite| -> HashMap<_, _> {
+        BONDED
+            .query(&suite.app.wrap(), suite.hub.clone())
+            .unwrap()
+            .into_iter()
+            .map(|(validator, amount)| (validator, amount.u128()))
+            .collect()
+    };
+    let entries = |suite: &Suite, src: &str, dst: &str| {
+        REDELEGATION_ENTRIES
+            .query(&suite.app.wrap(), suite.hub.clone(), (src, dst))
+            .unwrap()
+            .unwrap_or_default()
+    };
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+    suite.update_rebalance_on_reinvest("owner", true).unwrap();
+
+    // the first validator is below its weight and receives a redelegation
+    suite.slash("testvaloper1", Decimal::percent(2)).unwrap();
+    suite.check_slash().unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+    assert_eq!(entries(&suite, "testvaloper2", "testvaloper1").len(), 1);
+
+    // in the next epoch it is above its weight, but cannot redelegate before the incoming one completed
+    suite.slash("testvaloper2", Decimal::percent(2)).unwrap();
+    suite.check_slash().unwrap();
+    let before = bonded(&suite);
+    assert!(before["testvaloper1"] > before["testvaloper2"] + 2 * 1000);
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+    assert!(entries(&suite, "testvaloper1", "testvaloper2").is_empty());
+    assert!(bonded(&suite)["testvaloper1"] >= before["testvaloper1"]);
+
+    // once it completed, the drift is corrected
+    for _ in 0..32 {
+        suite.update_time(EPOCH);
+        suite.reinvest().unwrap();
+    }
+    assert!(!entries(&suite, "testvaloper1", "testvaloper2").is_empty());
+    let after = bonded(&suite);
+    assert!(
+        after["testvaloper1"].abs_diff(after["testvaloper2"])
+            < before["testvaloper1"] - before["testvaloper2"]
+    );
+}
+
+#[test]
+fn rate_growth_cap_amortizes_windfalls() {
+    let delegator = "delegator";
+    let amount = 1_000_000u128;
+    let max_growth = Decimal::permille(5);
+    let mut capped = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .build();
+    let mut plain = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .build();
+    for suite in [&mut capped, &mut plain] {
+        suite.bond(delegator, amount).unwrap();
+        suite.update_time(EPOCH);
+        suite.reinvest().unwrap();
+    }
+
+    let err = capped
+        .update_max_rate_growth(delegator, Some(max_growth))
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    let err = capped
+        .update_max_rate_growth("owner", Some(Decimal::zero()))
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidMaxRateGrowth {},
+        err.downcast().unwrap()
+    );
+    capped
+        .update_max_rate_growth("owner", Some(max_growth))
+        .unwrap();
+    assert_eq!(
+        capped.query_config().unwrap().max_rate_growth_per_epoch,
+        Some(max_growth)
+    );
+
+    // the rewards of a normal epoch are below the cap
+    for suite in [&mut capped, &mut plain] {
+        suite.update_time(EPOCH);
+        let resp = suite.reinvest().unwrap();
+        assert!(event_attribute(&resp, RATE_GROWTH_CAPPED_EVENT, "deferred").is_none());
+    }
+    assert_eq!(
+        capped.query_exchange_rate().unwrap(),
+        plain.query_exchange_rate().unwrap()
+    );
+
+    // ten epochs worth of rewards are only recognized up to the cap
+    let mut rate = capped.query_exchange_rate().unwrap();
+    let mut capped_epochs = 0;
+    let mut elapsed = 10 * EPOCH;
+    loop {
+        capped.update_time(elapsed);
+        plain.update_time(elapsed);
+        elapsed = EPOCH;
+        plain.reinvest().unwrap();
+        let resp = capped.reinvest().unwrap();
+        let new_rate = capped.query_exchange_rate().unwrap();
+        assert!(new_rate > rate);
+        assert!(new_rate <= rate * (Decimal::one() + max_growth));
+        rate = new_rate;
+        if event_attribute(&resp, RATE_GROWTH_CAPPED_EVENT, "deferred").is_none() {
+            break;
+        }
+        capped_epochs += 1;
+        assert!(
+            capped_epochs < 15,
+            "the deferred rewards were never released"
+        );
+    }
+    assert!(capped_epochs > 1);
+
+    // once released, the holders got about the same rewards as without the cap
+    let plain_rate = plain.query_exchange_rate().unwrap();
+    let tolerance = Decimal::from_str("1.001").unwrap();
+    assert!(rate * tolerance >= plain_rate);
+    assert!(rate <= plain_rate * tolerance);
+}
+
+#[test]
+fn buffer_utilization_reports_the_next_undelegation() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    let buffer = suite.query_buffer_utilization().unwrap();
+    assert_eq!(buffer.buffer.u128(), 1_000_000);
+    assert_eq!(buffer.shortfall, Uint128::zero());
+    assert_eq!(buffer.utilization, Decimal::zero());
+
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+    suite.unbond(delegator, &lsd, 400_000).unwrap();
+    suite.update_time(EPOCH);
+
+    // everything is delegated, so the claim has to wait for the undelegation
+    let buffer = suite.query_buffer_utilization().unwrap();
+    assert!(buffer.awaiting_payout.u128() >= 400_000);
+    assert_eq!(buffer.utilization, Decimal::one());
+    assert!(!buffer.shortfall.is_zero());
+
+    let res = suite.reinvest().unwrap();
+    assert_eq!(
+        event_attribute(&res, REINVEST_EVENT, "status"),
+        Some("undelegated".to_string())
+    );
+    assert_eq!(
+        event_attribute(&res, REINVEST_EVENT, "amount"),
+        Some(buffer.shortfall.to_string())
+    );
+
+    // now the claim is covered by the unbonding tokens
+    let buffer = suite.query_buffer_utilization().unwrap();
+    assert_eq!(buffer.shortfall, Uint128::zero());
+    assert!(buffer.unbonding.u128() + buffer.buffer.u128() >= 400_000);
+}
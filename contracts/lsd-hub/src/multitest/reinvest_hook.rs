@@ -0,0 +1,149 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    to_json_binary, Addr, Binary, Decimal, Deps, DepsMut, Empty, Env, MessageInfo, Response,
+    StdError, StdResult,
+};
+use cw_multi_test::{Contract, ContractWrapper, Executor};
+use cw_storage_plus::Item;
+
+use super::suite::{Suite, SuiteBuilder};
+use crate::msg::ReinvestHookMsg;
+use crate::ContractError;
+
+const HOUR: u64 = 60 * 60;
+
+mod mock_hook {
+    use super::*;
+
+    #[cw_serde]
+    pub struct InstantiateMsg {
+        /// Makes the hook fail on every notification
+        pub fail: bool,
+    }
+
+    const FAIL: Item<bool> = Item::new("fail");
+    /// The exchange rates received so far
+    pub const RATES: Item<Vec<Decimal>> = Item::new("rates");
+
+    pub fn instantiate(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: InstantiateMsg,
+    ) -> StdResult<Response> {
+        FAIL.save(deps.storage, &msg.fail)?;
+        RATES.save(deps.storage, &vec![])?;
+        Ok(Response::new())
+    }
+
+    pub fn execute(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: ReinvestHookMsg,
+    ) -> StdResult<Response> {
+        if FAIL.load(deps.storage)? {
+            return Err(StdError::generic_err("hook is broken"));
+        }
+        let ReinvestHookMsg::Reinvested { exchange_rate } = msg;
+        RATES.update(deps.storage, |mut rates| -> StdResult<_> {
+            rates.push(exchange_rate);
+            Ok(rates)
+        })?;
+        Ok(Response::new())
+    }
+
+    pub fn query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        to_json_binary(&Empty {})
+    }
+}
+
+fn contract_mock_hook() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new_with_empty(
+        mock_hook::execute,
+        mock_hook::instantiate,
+        mock_hook::query,
+    ))
+}
+
+fn instantiate_hook(suite: &mut Suite, fail: bool) -> Addr {
+    let code_id = suite.app.store_code(contract_mock_hook());
+    suite
+        .app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked("owner"),
+            &mock_hook::InstantiateMsg { fail },
+            &[],
+            "hook",
+            None,
+        )
+        .unwrap()
+}
+
+#[test]
+fn hook_receives_the_new_exchange_rate() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .build();
+    let hook = instantiate_hook(&mut suite, false);
+
+    let err = suite
+        .update_reinvest_hook(delegator, Some(&hook))
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    suite.update_reinvest_hook("owner", Some(&hook)).unwrap();
+    assert_eq!(
+        suite.query_config().unwrap().reinvest_hook,
+        Some(hook.clone())
+    );
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    for _ in 0..2 {
+        suite.update_time(23 * HOUR);
+        suite.reinvest().unwrap();
+    }
+
+    let rates = mock_hook::RATES.query(&suite.app.wrap(), hook).unwrap();
+    assert_eq!(rates.len(), 2);
+    assert_eq!(rates[0], Decimal::one());
+    // the second reinvest compounded the rewards of the first epoch
+    assert!(rates[1] > rates[0]);
+    assert_eq!(rates[1], suite.query_exchange_rate().unwrap());
+}
+
+#[test]
+fn broken_hook_does_not_block_reinvest() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .build();
+    let hook = instantiate_hook(&mut suite, true);
+    suite.update_reinvest_hook("owner", Some(&hook)).unwrap();
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(23 * HOUR);
+    let res = suite.reinvest().unwrap();
+    assert!(res
+        .events
+        .iter()
+        .flat_map(|e| &e.attributes)
+        .any(|a| a.key == "reinvest_hook_error"));
+    // the reinvest still delegated everything
+    assert_eq!(
+        suite.query_delegations().unwrap()[0].amount.amount.u128(),
+        1_000_000
+    );
+
+    // removing the hook stops the notifications
+    suite.update_reinvest_hook("owner", None).unwrap();
+    assert_eq!(suite.query_config().unwrap().reinvest_hook, None);
+    suite.update_time(23 * HOUR);
+    let res = suite.reinvest().unwrap();
+    assert!(!res
+        .events
+        .iter()
+        .flat_map(|e| &e.attributes)
+        .any(|a| a.key == "reinvest_hook_error"));
+}
@@ -1,8 +1,13 @@
-use crate::ContractError;
+use crate::{
+    contract::MAX_VALSET_CHANGE_SOURCE_LEN,
+    events::{CLAIM_EVENT, VALSET_CHANGE_EVENT},
+    state::{Supply, SUPPLY},
+    ContractError,
+};
 
-use super::suite::SuiteBuilder;
+use super::suite::{event_attribute, SuiteBuilder};
 
-use cosmwasm_std::{Decimal, Uint128};
+use cosmwasm_std::{Decimal, StdResult, Uint128};
 
 const DAY: u64 = 24 * HOUR;
 const HOUR: u64 = 60 * 60;
@@ -75,3 +80,456 @@ fn bond_with_native_unbond_with_wrong_token() {
         ContractError::InvalidToken {}
     ));
 }
+
+#[test]
+fn unbond_before_holding_period() {
+    let delegator = "delegator";
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    // only the owner can set the holding period
+    let err = suite
+        .update_min_holding_period("random", HOUR)
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(ContractError::Unauthorized {}, err);
+    suite.update_min_holding_period("owner", HOUR).unwrap();
+
+    suite.bond(delegator, amount).unwrap();
+    let until = suite.app.block_info().time.seconds() + HOUR;
+
+    // unbonding right after bonding is not possible, in either mode
+    let err = suite
+        .unbond(delegator, &lsd, amount / 2)
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(ContractError::HoldingPeriodNotMet { until }, err);
+    let err = suite
+        .unbond_deferred(delegator, &lsd, amount / 2)
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(ContractError::HoldingPeriodNotMet { until }, err);
+
+    // after the holding period, it works
+    suite.update_time(HOUR);
+    suite.unbond(delegator, &lsd, amount / 2).unwrap();
+    assert_eq!(
+        suite.query_claims(delegator.to_string()).unwrap()[0].amount,
+        Uint128::new(amount / 2)
+    );
+}
+
+#[test]
+fn bond_many_for_others_does_not_restart_their_holding_period() {
+    let holder = "holder";
+    let griefer = "griefer";
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(holder, amount), (griefer, 10)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+    suite.update_min_holding_period("owner", HOUR).unwrap();
+
+    suite.bond(holder, amount).unwrap();
+    suite.update_time(HOUR - 1);
+    // bonding dust for the holder right before their holding period ends
+    suite.bond_many(griefer, &[(holder, 1)], 1).unwrap();
+    suite.update_time(1);
+
+    suite.unbond(holder, &lsd, amount).unwrap();
+}
+
+#[test]
+fn claim_failure_causes() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+
+    // no claims at all
+    let err = suite.claim(delegator).unwrap_err();
+    assert_eq!(ContractError::NothingToClaim {}, err.downcast().unwrap());
+
+    // claim exists, but is not mature yet
+    suite.unbond(delegator, &lsd, 100_000).unwrap();
+    let release_at = suite.query_claims(delegator.to_string()).unwrap()[0].release_at;
+    let err = suite.claim(delegator).unwrap_err();
+    assert_eq!(
+        ContractError::ClaimsNotMature {
+            next_release_at: release_at.seconds()
+        },
+        err.downcast().unwrap()
+    );
+
+    // claim is mature, but the undelegation was never triggered, so there is nothing to pay it with
+    suite.update_time(23 * HOUR + 28 * DAY);
+    let err = suite.claim(delegator).unwrap_err();
+    assert_eq!(
+        ContractError::InsufficientBuffer {
+            shortfall: Uint128::new(100_000)
+        },
+        err.downcast().unwrap()
+    );
+
+    // some liquidity, but not enough for the claim
+    suite.set_hub_balance(50_000);
+    let err = suite.claim(delegator).unwrap_err();
+    assert_eq!(
+        ContractError::AwaitingUndelegation {},
+        err.downcast().unwrap()
+    );
+}
+
+#[test]
+fn partial_claim_reports_shortfall() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+
+    suite.unbond(delegator, &lsd, 100_000).unwrap();
+    suite.unbond(delegator, &lsd, 200_000).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+    suite.update_time(28 * DAY + HOUR);
+
+    // only enough liquidity for the first claim
+    suite.set_hub_balance(150_000);
+    let res = suite.claim(delegator).unwrap();
+    let attribute = |key: &str| event_attribute(&res, CLAIM_EVENT, key);
+    assert_eq!(attribute("requested"), Some("300000".to_string()));
+    assert_eq!(attribute("paid"), Some("100000".to_string()));
+    assert_eq!(attribute("remaining_matured"), Some("200000".to_string()));
+    assert_eq!(
+        attribute("shortfall_reason"),
+        Some("awaiting_undelegation".to_string())
+    );
+    assert_eq!(suite.query_balance(delegator, "FUN").unwrap(), 100_000);
+
+    // the rest is paid once the liquidity is there, without reporting a shortfall
+    suite.set_hub_balance(250_000);
+    let res = suite.claim(delegator).unwrap();
+    assert_eq!(
+        event_attribute(&res, CLAIM_EVENT, "shortfall_reason"),
+        Some("none".to_string())
+    );
+    assert_eq!(suite.query_balance(delegator, "FUN").unwrap(), 300_000);
+
+    // and then there is nothing left
+    let err = suite.claim(delegator).unwrap_err();
+    assert_eq!(ContractError::NothingToClaim {}, err.downcast().unwrap());
+}
+
+#[test]
+fn claim_up_to_leaves_the_rest_claimable() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+
+    suite.unbond(delegator, &lsd, 100_000).unwrap();
+    suite.unbond(delegator, &lsd, 200_000).unwrap();
+    suite.unbond(delegator, &lsd, 300_000).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+    suite.update_time(28 * DAY + HOUR);
+
+    // the first two claims fit, the last one does not
+    let res = suite.claim_up_to(delegator, 350_000).unwrap();
+    let attribute = |key: &str| event_attribute(&res, CLAIM_EVENT, key);
+    assert_eq!(attribute("paid"), Some("300000".to_string()));
+    assert_eq!(
+        attribute("shortfall_reason"),
+        Some("max_amount".to_string())
+    );
+    assert_eq!(suite.query_balance(delegator, "FUN").unwrap(), 300_000);
+    let claims = suite.query_claims(delegator.to_string()).unwrap();
+    assert_eq!(claims.len(), 1);
+    assert_eq!(claims[0].amount.u128(), 300_000);
+
+    // claims are only paid as a whole
+    let err = suite.claim_up_to(delegator, 299_999).unwrap_err();
+    assert_eq!(
+        ContractError::MaxAmountTooLow {
+            max_amount: 299_999u128.into()
+        },
+        err.downcast().unwrap()
+    );
+
+    // the rest is still claimable
+    suite.claim(delegator).unwrap();
+    assert_eq!(suite.query_balance(delegator, "FUN").unwrap(), 600_000);
+    assert!(suite
+        .query_claims(delegator.to_string())
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn claim_amount_splits_claims_oldest_first() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+
+    suite.unbond(delegator, &lsd, 100_000).unwrap();
+    suite.unbond(delegator, &lsd, 200_000).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+    suite.update_time(28 * DAY + HOUR);
+
+    // all of the first claim and part of the second one
+    let res = suite.claim_amount(delegator, 250_000).unwrap();
+    let attribute = |key: &str| event_attribute(&res, CLAIM_EVENT, key);
+    assert_eq!(attribute("paid"), Some("250000".to_string()));
+    assert_eq!(attribute("remaining_matured"), Some("50000".to_string()));
+    assert_eq!(suite.query_balance(delegator, "FUN").unwrap(), 250_000);
+    let claims = suite.query_claims(delegator.to_string()).unwrap();
+    assert_eq!(claims.len(), 1);
+    assert_eq!(claims[0].amount.u128(), 50_000);
+
+    // the rest of the split claim is still claimable
+    suite.claim(delegator).unwrap();
+    assert_eq!(suite.query_balance(delegator, "FUN").unwrap(), 300_000);
+    assert!(suite
+        .query_claims(delegator.to_string())
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn claim_amount_failure_causes() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+
+    suite.unbond(delegator, &lsd, 100_000).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+    suite.update_time(28 * DAY + HOUR);
+    // this one is not mature yet
+    suite.unbond(delegator, &lsd, 200_000).unwrap();
+
+    // more than what is mature
+    let err = suite.claim_amount(delegator, 100_001).unwrap_err();
+    assert_eq!(
+        ContractError::InsufficientMatureClaims {
+            available: 100_000u128.into()
+        },
+        err.downcast().unwrap()
+    );
+
+    // mature, but the hub cannot pay all of it, so nothing is paid
+    suite.set_hub_balance(50_000);
+    let err = suite.claim_amount(delegator, 60_000).unwrap_err();
+    assert_eq!(
+        ContractError::AwaitingUndelegation {},
+        err.downcast().unwrap()
+    );
+    assert_eq!(suite.query_balance(delegator, "FUN").unwrap(), 0);
+    let claims = suite.query_claims(delegator.to_string()).unwrap();
+    assert_eq!(claims[0].amount.u128(), 100_000);
+
+    // what the balance covers can be claimed
+    suite.claim_amount(delegator, 50_000).unwrap();
+    assert_eq!(suite.query_balance(delegator, "FUN").unwrap(), 50_000);
+}
+
+#[test]
+fn corrupted_claims_counter_is_repaired() {
+    let delegators = ["alice", "bob"];
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegators[0], 1_000_000), (delegators[1], 1_000_000)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    for delegator in delegators {
+        suite.bond(delegator, 1_000_000).unwrap();
+    }
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+    suite.unbond(delegators[0], &lsd, 100_000).unwrap();
+    suite.unbond(delegators[1], &lsd, 200_000).unwrap();
+
+    let integrity = suite.query_claims_integrity(None).unwrap();
+    assert_eq!(integrity.stored_counter.u128(), 300_000);
+    assert_eq!(integrity.computed_sum.u128(), 300_000);
+    assert_eq!(integrity.scanned_addresses, 2);
+    assert!(integrity.complete);
+    assert_eq!(integrity.continuation, None);
+
+    // some bug added to the counter without creating a claim
+    suite.update_hub_storage(|storage| {
+        SUPPLY
+            .update(storage, |supply| -> StdResult<Supply> {
+                Ok(Supply {
+                    claims: supply.claims + Uint128::new(50_000),
+                    ..supply
+                })
+            })
+            .unwrap();
+    });
+    let integrity = suite.query_claims_integrity(None).unwrap();
+    assert_eq!(integrity.stored_counter.u128(), 350_000);
+    assert_eq!(integrity.computed_sum.u128(), 300_000);
+
+    let err = suite
+        .repair_claims_counter(delegators[0], &integrity)
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    // the checkpoint is outdated once the counter changes
+    suite.unbond(delegators[0], &lsd, 100_000).unwrap();
+    let err = suite
+        .repair_claims_counter("owner", &integrity)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidClaimsCheckpoint {},
+        err.downcast().unwrap()
+    );
+
+    let integrity = suite.query_claims_integrity(None).unwrap();
+    assert_eq!(integrity.stored_counter.u128(), 450_000);
+    assert_eq!(integrity.computed_sum.u128(), 400_000);
+    suite.repair_claims_counter("owner", &integrity).unwrap();
+
+    let integrity = suite.query_claims_integrity(None).unwrap();
+    assert_eq!(integrity.stored_counter, integrity.computed_sum);
+
+    // the claims are paid as usual
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+    suite.update_time(28 * DAY + HOUR);
+    suite.claim(delegators[0]).unwrap();
+    suite.claim(delegators[1]).unwrap();
+    let integrity = suite.query_claims_integrity(None).unwrap();
+    assert_eq!(integrity.stored_counter.u128(), 0);
+    assert_eq!(integrity.computed_sum.u128(), 0);
+}
+
+#[test]
+fn valset_change_source_too_long() {
+    let mut suite = SuiteBuilder::new().build();
+    let validators = vec![("testvaloper1".to_string(), Decimal::one())];
+
+    let err = suite
+        .set_validators_with_source(
+            "owner",
+            validators.clone(),
+            &"a".repeat(MAX_VALSET_CHANGE_SOURCE_LEN + 1),
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::ValsetChangeSourceTooLong {
+            max: MAX_VALSET_CHANGE_SOURCE_LEN
+        },
+        err.downcast().unwrap()
+    );
+
+    let source = "a".repeat(MAX_VALSET_CHANGE_SOURCE_LEN);
+    let res = suite
+        .set_validators_with_source("owner", validators, &source)
+        .unwrap();
+    assert_eq!(
+        event_attribute(&res, VALSET_CHANGE_EVENT, "source"),
+        Some(source)
+    );
+}
+
+#[test]
+fn account_address_in_valset_is_rejected() {
+    let valoper = "junovaloper1t8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaaa";
+    let account = "juno1t8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaaa";
+    let mut suite = SuiteBuilder::new()
+        .with_validators(vec![(valoper, Decimal::one())])
+        .with_valoper_prefix("junovaloper")
+        .build();
+
+    let err = suite
+        .set_validators(
+            "owner",
+            vec![
+                (valoper.to_string(), Decimal::percent(50)),
+                (account.to_string(), Decimal::percent(50)),
+            ],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidValidatorAddress {
+            address: account.to_string()
+        },
+        err.downcast().unwrap()
+    );
+
+    suite
+        .set_validators("owner", vec![(valoper.to_string(), Decimal::one())])
+        .unwrap();
+}
+
+#[test]
+fn stale_valset_change_is_refused() {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, 1_000_000)])
+        .build();
+    suite.bond(delegator, 1_000_000).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+    let validators = vec![("testvaloper1".to_string(), Decimal::one())];
+    let now = suite.app.block_info().time.seconds();
+
+    let err = suite
+        .set_validators_with_intent("owner", validators.clone(), Some(now - 1), None)
+        .unwrap_err();
+    assert_eq!(ContractError::IntentExpired {}, err.downcast().unwrap());
+
+    // 5% of the expectation is less than the 100_000 that were bonded in addition
+    let err = suite
+        .set_validators_with_intent("owner", validators.clone(), None, Some(900_000))
+        .unwrap_err();
+    assert_eq!(
+        ContractError::StateDrifted {
+            expected: Uint128::new(900_000),
+            actual: Uint128::new(1_000_000)
+        },
+        err.downcast().unwrap()
+    );
+
+    // within the deadline and the tolerance
+    suite
+        .set_validators_with_intent("owner", validators.clone(), Some(now), Some(960_000))
+        .unwrap();
+    assert_eq!(suite.query_validator_set().unwrap(), validators);
+}
@@ -1,8 +1,9 @@
 use cosmwasm_std::{assert_approx_eq, ContractInfo, Decimal, Env, Order, Uint128};
 
 use crate::{
-    multitest::suite::SuiteBuilder,
-    state::{BONDED, SUPPLY, UNBONDING},
+    events::SLASH_EVENT,
+    multitest::suite::{event_attribute, SuiteBuilder},
+    state::{range_unbondings, BONDED, SLASHINGS, SUPPLY},
     ContractError,
 };
 use test_case::test_case;
@@ -129,6 +130,41 @@ fn timing_check(wait_time: u64) {
     assert_eq!(ContractError::UnbondingTooClose {}, err.downcast().unwrap());
 }
 
+// a single token of drift is always rounding, even for small delegations
+#[test_case(1_000, 1 => false; "one token on a small delegation")]
+#[test_case(1_000_000, 1 => false; "one token on a big delegation")]
+// anything more is a slashing if it is above the relative threshold of 0.001%
+#[test_case(1_000, 2 => true; "two tokens on a small delegation")]
+#[test_case(1_000_000, 9 => false; "just below the relative threshold")]
+#[test_case(1_000_000, 10 => true; "exactly the relative threshold")]
+#[test_case(1_000_000, 1_000 => true; "liveness slash")]
+fn slashing_tolerance(amount: u128, drift: u128) -> bool {
+    let delegator = "delegator";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .build();
+    suite.bond(delegator, amount).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+
+    suite
+        .slash("testvaloper1", Decimal::from_ratio(drift, amount))
+        .unwrap();
+    suite.check_slash().unwrap();
+
+    let supply = SUPPLY.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
+    let slashed = supply.total_bonded.u128() < amount;
+    let slashings = SLASHINGS
+        .query(&suite.app.wrap(), suite.hub.clone())
+        .unwrap();
+    assert_eq!(
+        slashed,
+        !slashings.is_empty(),
+        "a slashing should be recorded exactly when the bonded amount is marked down"
+    );
+    slashed
+}
+
 #[test]
 fn pending_claims_slashed() {
     let delegator = "delegator";
@@ -170,8 +206,7 @@ fn pending_claims_slashed() {
     let supply = SUPPLY.query(&suite.app.wrap(), suite.hub.clone()).unwrap();
 
     let storage = suite.read_hub_storage();
-    let unbonding: Vec<_> = UNBONDING
-        .range(&storage, None, None, Order::Ascending)
+    let unbonding: Vec<_> = range_unbondings(&storage, None, None, Order::Ascending)
         .map(|ub| ub.unwrap())
         .collect();
     // allow a bit of rounding error
@@ -224,3 +259,308 @@ fn pending_claims_slashed() {
         "should be rounded down, if at all"
     );
 }
+
+#[test]
+fn update_slashing_parameters() {
+    let mut suite = SuiteBuilder::new().build();
+
+    let err = suite
+        .update_tombstone_threshold("notowner", Decimal::percent(5))
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    let err = suite
+        .update_slashing_safety_margin("notowner", HOUR)
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    for invalid in [Decimal::zero(), Decimal::permille(501), Decimal::one()] {
+        let err = suite
+            .update_tombstone_threshold("owner", invalid)
+            .unwrap_err();
+        assert_eq!(
+            ContractError::InvalidTombstoneThreshold {},
+            err.downcast().unwrap()
+        );
+    }
+    suite
+        .update_tombstone_threshold("owner", Decimal::percent(50))
+        .unwrap();
+    suite
+        .update_tombstone_threshold("owner", Decimal::permille(1))
+        .unwrap();
+
+    // unbonding epoch is 28 / 7 = 4 days, so the margin must be below 2 days
+    let err = suite
+        .update_slashing_safety_margin("owner", 2 * DAY)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidSlashingSafetyMargin {},
+        err.downcast().unwrap()
+    );
+    suite
+        .update_slashing_safety_margin("owner", 2 * DAY - 1)
+        .unwrap();
+    suite.update_slashing_safety_margin("owner", 0).unwrap();
+}
+
+#[test]
+fn tombstone_threshold_changes_detection() {
+    let delegator = "delegator";
+
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .build();
+
+    suite.bond(delegator, amount).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+
+    let tombstoned = |res: &cw_multi_test::AppResponse| {
+        event_attribute(res, SLASH_EVENT, "tombstoned").filter(|t| !t.is_empty())
+    };
+
+    // 5% slash is below the threshold
+    suite
+        .update_tombstone_threshold("owner", Decimal::percent(10))
+        .unwrap();
+    suite.slash("testvaloper1", Decimal::percent(5)).unwrap();
+    let res = suite.check_slash().unwrap();
+    assert_eq!(tombstoned(&res), None);
+    assert_eq!(suite.query_tombstoned_validators().unwrap(), vec![]);
+
+    // same slash is reported with a lower threshold
+    suite
+        .update_tombstone_threshold("owner", Decimal::percent(3))
+        .unwrap();
+    suite.slash("testvaloper1", Decimal::percent(5)).unwrap();
+    let res = suite.check_slash().unwrap();
+    assert_eq!(tombstoned(&res), Some("testvaloper1".to_string()));
+    assert_eq!(
+        suite.query_tombstoned_validators().unwrap(),
+        vec![(
+            "testvaloper1".to_string(),
+            suite.app.block_info().time.seconds()
+        )]
+    );
+}
+
+#[test]
+fn slashings_recorded_without_stored_events() {
+    let delegator = "delegator";
+
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .build();
+    assert_eq!(suite.query_slashing_events().unwrap(), vec![]);
+
+    // contracts deployed before slashings were recorded have nothing stored
+    suite.update_hub_storage(|storage| SLASHINGS.remove(storage));
+    assert_eq!(suite.query_slashing_events().unwrap(), vec![]);
+
+    // slash a pending unbonding
+    suite.bond(delegator, amount).unwrap();
+    let lsd = suite.query_lsd_token().unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+    suite.unbond(delegator, &lsd, amount / 2).unwrap();
+    suite.update_time(5 * 23 * HOUR);
+    suite.reinvest().unwrap();
+    suite.slash("testvaloper1", Decimal::percent(10)).unwrap();
+    suite.update_time(5 * MINUTE);
+    suite.check_slash().unwrap();
+    assert_eq!(suite.query_slashing_events().unwrap().len(), 1);
+}
+
+#[test]
+fn check_slash_availability() {
+    let delegator = "delegator";
+
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .build();
+
+    suite.bond(delegator, amount).unwrap();
+    let lsd = suite.query_lsd_token().unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+    let available = suite.query_check_slash_available().unwrap();
+    assert!(available.available);
+    assert_eq!(available.next_available, None);
+
+    // trigger an unbonding
+    suite.unbond(delegator, &lsd, amount / 2).unwrap();
+    suite.update_time(5 * 23 * HOUR);
+    suite.reinvest().unwrap();
+    let completion = suite.app.block_info().time.seconds() + 28 * DAY;
+
+    // shortly before the unbonding completes, detection has to wait until the margin passed
+    suite.update_time(28 * DAY - 5 * MINUTE);
+    let available = suite.query_check_slash_available().unwrap();
+    assert!(!available.available);
+    assert_eq!(available.next_available, Some(completion + 10 * MINUTE));
+    let err = suite.check_slash().unwrap_err();
+    assert_eq!(ContractError::UnbondingTooClose {}, err.downcast().unwrap());
+
+    suite.update_time(15 * MINUTE);
+    let available = suite.query_check_slash_available().unwrap();
+    assert!(available.available);
+    assert_eq!(available.next_available, None);
+    suite.check_slash().unwrap();
+}
+
+#[test]
+fn safety_margin_changes_detection() {
+    let delegator = "delegator";
+
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .build();
+
+    suite.bond(delegator, amount).unwrap();
+    let lsd = suite.query_lsd_token().unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+
+    // trigger an unbonding
+    suite.unbond(delegator, &lsd, amount / 2).unwrap();
+    suite.update_time(5 * 23 * HOUR);
+    suite.reinvest().unwrap();
+
+    // one hour before the unbonding completes, the default 10 minute margin allows detection
+    suite.update_time(28 * DAY - HOUR);
+    suite.check_slash().unwrap();
+
+    // but a 2 hour margin does not
+    suite
+        .update_slashing_safety_margin("owner", 2 * HOUR)
+        .unwrap();
+    let err = suite.check_slash().unwrap_err();
+    assert_eq!(ContractError::UnbondingTooClose {}, err.downcast().unwrap());
+}
+
+#[test]
+fn value_change_splits_slashing_and_rewards() {
+    let delegator = "delegator";
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .build();
+
+    suite.bond(delegator, amount).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+    let since = suite.app.block_info().time.seconds();
+    let rate_then = suite.query_exchange_rate().unwrap();
+
+    // there is no snapshot before the first reinvest
+    let err = suite.query_value_change(delegator, since - 1).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains(&format!("the earliest snapshot is from {}", since)),
+        "unexpected error: {}",
+        err
+    );
+
+    // a couple of reward epochs with a slashing in between
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+    suite.update_time(HOUR);
+    suite.slash("testvaloper1", Decimal::percent(10)).unwrap();
+    suite.check_slash().unwrap();
+    suite.update_time(22 * HOUR);
+    suite.reinvest().unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+    let rate_now = suite.query_exchange_rate().unwrap();
+
+    let change = suite.query_value_change(delegator, since).unwrap();
+    assert_eq!(change.lsd_balance.u128(), amount);
+    assert_eq!(change.value_then, change.lsd_balance.mul_floor(rate_then));
+    assert_eq!(change.value_now, change.lsd_balance.mul_floor(rate_now));
+    assert!(
+        change.slash_impact > Decimal::percent(9) && change.slash_impact < Decimal::percent(11),
+        "slash impact should be about 10%: {}",
+        change.slash_impact
+    );
+    assert!(!change.reward_growth.is_zero());
+
+    // both parts multiply out to the total change
+    let expected = change
+        .value_then
+        .mul_floor(Decimal::one() - change.slash_impact)
+        .mul_floor(Decimal::one() + change.reward_growth);
+    assert_approx_eq!(expected, change.value_now, "0.0001");
+
+    // looking at the time after the slashing only shows rewards
+    let after_slash = suite.app.block_info().time.seconds() - 23 * HOUR;
+    let change = suite.query_value_change(delegator, after_slash).unwrap();
+    assert_eq!(change.slash_impact, Decimal::zero());
+    assert!(!change.reward_growth.is_zero());
+}
+
+#[test]
+fn removed_validator_unbonding_still_completes() {
+    let delegator = "delegator";
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .with_validators(vec![
+            ("testvaloper1", Decimal::percent(50)),
+            ("testvaloper2", Decimal::percent(50)),
+        ])
+        .build();
+    suite.bond(delegator, amount).unwrap();
+    let lsd = suite.query_lsd_token().unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+
+    // undelegate from both validators
+    suite.unbond(delegator, &lsd, 400_000).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+    let storage = suite.read_hub_storage();
+    let unbonding: Vec<_> = range_unbondings(&storage, None, None, Order::Ascending)
+        .map(|ub| ub.unwrap())
+        .collect();
+    assert!(unbonding
+        .iter()
+        .flat_map(|(_, ubs)| ubs)
+        .any(|ub| ub.validator == "testvaloper1"));
+
+    // testvaloper1 leaves the set while its unbonding is still in flight
+    suite
+        .set_validators("owner", vec![("testvaloper2".to_string(), Decimal::one())])
+        .unwrap();
+    assert!(suite
+        .query_delegations()
+        .unwrap()
+        .iter()
+        .all(|d| d.validator != "testvaloper1"));
+
+    // the slashing check still knows about it
+    let res = suite.check_slash().unwrap();
+    assert_eq!(
+        event_attribute(&res, SLASH_EVENT, "unobserved"),
+        Some("testvaloper1".to_string())
+    );
+
+    // and the unbonding completes, so the claim can be paid out
+    let claim = suite.query_claims(delegator.to_string()).unwrap()[0].clone();
+    let now = suite.app.block_info().time.seconds();
+    suite.update_time(claim.release_at.seconds() - now);
+    suite.process_native_unbonding();
+    let before = suite.query_balance(delegator, "FUN").unwrap();
+    suite.claim(delegator).unwrap();
+    assert_eq!(
+        suite.query_balance(delegator, "FUN").unwrap(),
+        before + claim.amount.u128()
+    );
+    assert!(suite
+        .query_claims(delegator.to_string())
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,168 @@
+use cosmwasm_std::{Addr, Decimal, Uint128};
+
+use crate::{
+    events::SLASH_EVENT,
+    msg::{HealthResponse, IssuedReconciliationResponse, SudoMsg},
+    multitest::suite::{event_attribute, SuiteBuilder},
+    ContractError,
+};
+
+use cw_multi_test::{AppResponse, Executor};
+
+const HOUR: u64 = 60 * 60;
+
+fn has_attribute(res: &AppResponse, key: &str, value: &str) -> bool {
+    res.events
+        .iter()
+        .flat_map(|e| &e.attributes)
+        .any(|a| a.key == key && a.value == value)
+}
+
+#[test]
+fn sudo_reinvest_check_slash_and_invariants() {
+    let delegator = "delegator";
+
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .build();
+
+    suite.bond(delegator, amount).unwrap();
+    let res = suite.sudo(SudoMsg::AssertInvariants {}).unwrap();
+    assert!(has_attribute(&res, "valid", "true"));
+
+    // the chain triggers the reinvest once the epoch is reached
+    suite.update_time(23 * HOUR);
+    suite.sudo(SudoMsg::Reinvest {}).unwrap();
+    let delegations = suite.query_delegations().unwrap();
+    assert_eq!(delegations.len(), 1);
+    assert_eq!(delegations[0].amount.amount.u128(), amount);
+
+    // the epoch guard still applies
+    let err = suite.sudo(SudoMsg::Reinvest {}).unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::EpochNotReached { .. }
+    ));
+    suite.sudo(SudoMsg::AssertInvariants {}).unwrap();
+
+    // nothing to detect yet
+    let res = suite.sudo(SudoMsg::CheckSlash {}).unwrap();
+    assert_eq!(
+        event_attribute(&res, SLASH_EVENT, "slashed"),
+        Some("false".to_string())
+    );
+
+    // slashing is detected and the state stays consistent
+    suite.slash("testvaloper1", Decimal::permille(1)).unwrap();
+    let res = suite.sudo(SudoMsg::CheckSlash {}).unwrap();
+    assert_eq!(
+        event_attribute(&res, SLASH_EVENT, "slashed"),
+        Some("true".to_string())
+    );
+    suite.sudo(SudoMsg::AssertInvariants {}).unwrap();
+}
+
+#[test]
+fn health_reports_each_invariant() {
+    let delegator = "delegator";
+
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+    let healthy = HealthResponse {
+        bonded_sum_matches_total_bonded: true,
+        issued_matches_cw20_supply: true,
+        claims_backed_by_coverage: true,
+        total_unbonding_matches_unbonding_map: true,
+    };
+    assert_eq!(suite.query_health().unwrap(), healthy);
+
+    suite.bond(delegator, amount).unwrap();
+    assert_eq!(suite.query_health().unwrap(), healthy);
+
+    suite.unbond(delegator, &lsd, amount / 2).unwrap();
+    assert_eq!(suite.query_health().unwrap(), healthy);
+
+    // nothing was delegated yet, so the balance backs the claims.
+    // Here some of those tokens went missing
+    suite.set_hub_balance(amount / 4);
+    let health = suite.query_health().unwrap();
+    assert!(!health.claims_backed_by_coverage);
+    assert!(health.bonded_sum_matches_total_bonded);
+    assert!(health.issued_matches_cw20_supply);
+    assert!(health.total_unbonding_matches_unbonding_map);
+}
+
+#[test]
+fn issued_mismatch_halts_reinvest_until_synced() {
+    let delegator = "delegator";
+
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+
+    suite.bond(delegator, amount).unwrap();
+    suite.update_time(23 * HOUR);
+    suite.reinvest().unwrap();
+    assert_eq!(
+        suite.query_issued_reconciliation().unwrap(),
+        IssuedReconciliationResponse {
+            issued_counter: Uint128::new(amount),
+            cw20_total_supply: Uint128::new(amount),
+            diff: Uint128::zero(),
+        }
+    );
+
+    let err = suite
+        .update_strict_supply_check(delegator, true)
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    suite.update_strict_supply_check("owner", true).unwrap();
+    assert!(suite.query_config().unwrap().strict_supply_check);
+
+    // the holder burns some lsd tokens on the token contract directly
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(delegator),
+            lsd,
+            &cw20::Cw20ExecuteMsg::Burn {
+                amount: 1_000u128.into(),
+            },
+            &[],
+        )
+        .unwrap();
+    let reconciliation = suite.query_issued_reconciliation().unwrap();
+    assert_eq!(reconciliation.cw20_total_supply.u128(), amount - 1_000);
+    assert_eq!(reconciliation.diff.u128(), 1_000);
+    assert!(!suite.query_health().unwrap().issued_matches_cw20_supply);
+
+    // compounding is halted until the owner adopts the token's supply
+    suite.update_time(23 * HOUR);
+    let err = suite.reinvest().unwrap_err();
+    assert_eq!(
+        ContractError::SupplyMismatch {
+            issued: Uint128::new(amount),
+            cw20_total_supply: Uint128::new(amount - 1_000),
+        },
+        err.downcast().unwrap()
+    );
+    let err = suite.sync_issued(delegator).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    let rate_before = suite.query_exchange_rate().unwrap();
+    suite.sync_issued("owner").unwrap();
+    assert_eq!(
+        suite.query_issued_reconciliation().unwrap().diff,
+        Uint128::zero()
+    );
+    // the remaining holders own the burned part of the assets now
+    assert!(suite.query_exchange_rate().unwrap() > rate_before);
+
+    suite.reinvest().unwrap();
+    suite.sudo(SudoMsg::AssertInvariants {}).unwrap();
+}
@@ -1,9 +1,21 @@
 use crate::{
-    claim::{Claim, ClaimsResponse},
+    claim::{Claim, ClaimStatus, ClaimsResponse},
     msg::{
-        ConfigResponse, ExchangeRateResponse, ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg,
-        SupplyResponse, TargetValueResponse, TokenInitInfo, ValidatorSetResponse,
+        AccruedButUnbondedValueResponse, AssetInfoResponse, BondedJournalEntry,
+        BondedJournalResponse, BootstrapStatusResponse, BufferUtilizationResponse,
+        CheckSlashAvailableResponse, ClaimsIntegrityResponse, ClaimsScanContinuation,
+        CommissionVesting, CommissionVestingResponse, ConfigResponse, DashboardResponse,
+        ExchangeRateAtResponse, ExchangeRateRawResponse, ExchangeRateResponse, ExecuteMsg,
+        ExternalRewardsResponse, HealthResponse, InstantiateMsg, IssuedReconciliationResponse,
+        PendingValsetChangeResponse, PreviewCommissionResponse, PreviewValsetChangeResponse,
+        QueryMsg, ReceiveMsg, RewardRateResponse, SharesToRedeemResponse, SimulateBondResponse,
+        SimulateValsetChangeResponse, SlashingEventsResponse, SudoMsg, SupplyResponse,
+        SurplusStrategy, TargetValueResponse, TokenInitInfo, TombstonedValidatorsResponse,
+        ValidatorCommissionsResponse, ValidatorConfig, ValidatorSetResponse,
+        ValidatorSetWithBondedResponse, ValidatorWithBonded, ValueChangeResponse,
+        VoucherBatchesResponse,
     },
+    state::Slashing,
 };
 use anyhow::Result as AnyResult;
 use cosmwasm_std::{
@@ -13,7 +25,7 @@ use cosmwasm_std::{
 use cw20::{BalanceResponse, Cw20Coin, Cw20QueryMsg};
 use cw20_base::msg::InstantiateMsg as Cw20InstantiateMsg;
 use cw_multi_test::{
-    App, AppResponse, Contract, ContractWrapper, Executor, StakingInfo, StakingSudo, SudoMsg,
+    App, AppResponse, BankSudo, Contract, ContractWrapper, Executor, StakingInfo, StakingSudo,
 };
 
 fn contract_hub() -> Box<dyn Contract<Empty>> {
@@ -22,11 +34,23 @@ fn contract_hub() -> Box<dyn Contract<Empty>> {
         crate::contract::instantiate,
         crate::contract::query,
     )
-    .with_reply(crate::contract::reply);
+    .with_reply(crate::contract::reply)
+    .with_sudo(crate::contract::sudo);
 
     Box::new(contract)
 }
 
+/// Returns the value of `key` in the first hub event of type `ty`, see [`crate::events`]
+pub fn event_attribute(res: &AppResponse, ty: &str, key: &str) -> Option<String> {
+    let ty = format!("wasm-{ty}");
+    res.events
+        .iter()
+        .filter(|e| e.ty == ty)
+        .flat_map(|e| &e.attributes)
+        .find(|a| a.key == key)
+        .map(|a| a.value.clone())
+}
+
 fn store_token_code() -> Box<dyn Contract<Empty>> {
     let contract = ContractWrapper::new_with_empty(
         cw20_base::contract::execute,
@@ -49,6 +73,14 @@ pub struct SuiteBuilder {
     pub epoch_period: u64,
     pub unbond_period: u64,
     pub liquidity_discount: Decimal,
+    pub min_reinvest_amount: u128,
+    pub lsd_decimals: u8,
+    pub bond_denom_decimals: Option<u8>,
+    pub expected_bond_decimals: Option<u8>,
+    pub issuance_cap: Option<u128>,
+    pub claim_vouchers: bool,
+    pub bootstrap_min_deposit: Option<u128>,
+    pub valoper_prefix: String,
 }
 
 const DAY: u64 = 24 * HOUR;
@@ -69,9 +101,51 @@ impl SuiteBuilder {
             epoch_period: 23 * HOUR,
             unbond_period: 28 * DAY,
             liquidity_discount: Decimal::percent(4),
+            min_reinvest_amount: 0,
+            lsd_decimals: 6,
+            bond_denom_decimals: None,
+            expected_bond_decimals: None,
+            issuance_cap: None,
+            claim_vouchers: false,
+            bootstrap_min_deposit: None,
+            valoper_prefix: String::new(),
         }
     }
 
+    pub fn with_decimals(mut self, lsd_decimals: u8, bond_denom_decimals: u8) -> Self {
+        self.lsd_decimals = lsd_decimals;
+        self.bond_denom_decimals = Some(bond_denom_decimals);
+        self
+    }
+
+    pub fn with_expected_bond_decimals(mut self, expected_bond_decimals: u8) -> Self {
+        self.expected_bond_decimals = Some(expected_bond_decimals);
+        self
+    }
+
+    pub fn with_issuance_cap(mut self, issuance_cap: u128) -> Self {
+        self.issuance_cap = Some(issuance_cap);
+        self
+    }
+
+    /// Makes unbonding mint claim vouchers, see `InstantiateMsg::voucher_init`
+    pub fn with_claim_vouchers(mut self) -> Self {
+        self.claim_vouchers = true;
+        self
+    }
+
+    /// Escrows the bonds until `min_deposit` was deposited, see `InstantiateMsg::bootstrap_min_deposit`
+    pub fn with_bootstrap(mut self, min_deposit: u128) -> Self {
+        self.bootstrap_min_deposit = Some(min_deposit);
+        self
+    }
+
+    /// Rejects validators without the prefix, see `InstantiateMsg::valoper_prefix`
+    pub fn with_valoper_prefix(mut self, prefix: &str) -> Self {
+        self.valoper_prefix = prefix.to_owned();
+        self
+    }
+
     pub fn with_initial_balances(mut self, balances: Vec<(&str, u128)>) -> Self {
         let initial_balances = balances
             .into_iter()
@@ -89,6 +163,11 @@ impl SuiteBuilder {
         self
     }
 
+    pub fn with_min_reinvest_amount(mut self, amount: u128) -> Self {
+        self.min_reinvest_amount = amount;
+        self
+    }
+
     pub fn with_validators(mut self, validators: Vec<(&str, Decimal)>) -> Self {
         let validators = validators
             .into_iter()
@@ -192,13 +271,27 @@ impl SuiteBuilder {
                         cw20_code_id: cw20_id,
                         name: "funLSD".to_string(),
                         symbol: "fLSD".to_string(),
-                        decimals: 6,
+                        decimals: self.lsd_decimals,
                         initial_balances: vec![],
                         marketing: None,
                     },
                     liquidity_discount: self.liquidity_discount,
                     tombstone_treshold: Decimal::percent(3),
                     slashing_safety_margin: 10 * 60,
+                    bond_denom_decimals: self.bond_denom_decimals,
+                    expected_bond_decimals: self.expected_bond_decimals,
+                    issuance_cap: self.issuance_cap.map(Uint128::new),
+                    voucher_init: self.claim_vouchers.then(|| TokenInitInfo {
+                        label: "voucher".to_string(),
+                        cw20_code_id: cw20_id,
+                        name: "funClaim".to_string(),
+                        symbol: "fCLAIM".to_string(),
+                        decimals: self.lsd_decimals,
+                        initial_balances: vec![],
+                        marketing: None,
+                    }),
+                    bootstrap_min_deposit: self.bootstrap_min_deposit.map(Uint128::new),
+                    valoper_prefix: self.valoper_prefix,
                 },
                 &[],
                 "hub",
@@ -206,6 +299,17 @@ impl SuiteBuilder {
             )
             .unwrap();
 
+        // most tests work with small amounts, so the dust threshold is disabled unless a test sets it
+        app.execute_contract(
+            Addr::unchecked("owner"),
+            hub.clone(),
+            &ExecuteMsg::UpdateMinReinvestAmount {
+                min_reinvest_amount: self.min_reinvest_amount.into(),
+            },
+            &[],
+        )
+        .unwrap();
+
         let other_token_contract = app
             .instantiate_contract(
                 cw20_id,
@@ -248,11 +352,53 @@ impl Suite {
             })
     }
 
+    /// update block's time without producing any blocks, like a proposer skewing the timestamp
+    pub fn update_time_only(&mut self, time_update: u64) {
+        self.app
+            .update_block(|block: &mut cosmwasm_std::BlockInfo| {
+                block.time = block.time.plus_seconds(time_update);
+            })
+    }
+
     pub fn reinvest(&mut self) -> AnyResult<AppResponse> {
         self.app.execute_contract(
             Addr::unchecked("anyone"),
             self.hub.clone(),
-            &ExecuteMsg::Reinvest {},
+            &ExecuteMsg::Reinvest { validators: None },
+            &[],
+        )
+    }
+
+    pub fn force_reinvest(&mut self, sender: &str) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::ForceReinvest {},
+            &[],
+        )
+    }
+
+    /// Reinvests, only withdrawing the rewards of the given validators
+    pub fn reinvest_validators(&mut self, validators: &[&str]) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked("anyone"),
+            self.hub.clone(),
+            &ExecuteMsg::Reinvest {
+                validators: Some(validators.iter().map(|v| v.to_string()).collect()),
+            },
+            &[],
+        )
+    }
+
+    pub fn update_unbond_fee(
+        &mut self,
+        sender: &str,
+        unbond_fee: Decimal,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::UpdateUnbondFee { unbond_fee },
             &[],
         )
     }
@@ -270,152 +416,1076 @@ impl Suite {
         )
     }
 
-    pub fn bond(&mut self, sender: &str, amount: u128) -> AnyResult<AppResponse> {
+    pub fn update_tombstone_threshold(
+        &mut self,
+        sender: &str,
+        new_threshold: Decimal,
+    ) -> AnyResult<AppResponse> {
         self.app.execute_contract(
             Addr::unchecked(sender),
             self.hub.clone(),
-            &ExecuteMsg::Bond {},
-            &coins(amount, "FUN"),
+            &ExecuteMsg::UpdateTombstoneThreshold { new_threshold },
+            &[],
         )
     }
 
-    pub fn unbond(
+    pub fn update_min_holding_period(
         &mut self,
         sender: &str,
-        token_contract: &Addr,
-        balance: u128,
+        min_holding_period: u64,
     ) -> AnyResult<AppResponse> {
-        let msg = to_json_binary(&ReceiveMsg::Unbond {})?;
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::UpdateMinHoldingPeriod { min_holding_period },
+            &[],
+        )
+    }
 
+    pub fn update_surplus_strategy(
+        &mut self,
+        sender: &str,
+        surplus_strategy: SurplusStrategy,
+    ) -> AnyResult<AppResponse> {
         self.app.execute_contract(
             Addr::unchecked(sender),
-            token_contract.clone(),
-            &cw20::Cw20ExecuteMsg::Send {
-                contract: self.hub.clone().to_string(),
-                amount: balance.into(),
-                msg,
+            self.hub.clone(),
+            &ExecuteMsg::UpdateSurplusStrategy { surplus_strategy },
+            &[],
+        )
+    }
+
+    pub fn update_reinvest_hook(
+        &mut self,
+        sender: &str,
+        hook: Option<&Addr>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::UpdateReinvestHook {
+                hook: hook.map(Addr::to_string),
             },
             &[],
         )
     }
 
-    pub fn check_slash(&mut self) -> AnyResult<AppResponse> {
+    pub fn update_validator_probation(
+        &mut self,
+        sender: &str,
+        new_validator_probation: u64,
+        probation_max_weight: Decimal,
+    ) -> AnyResult<AppResponse> {
         self.app.execute_contract(
-            Addr::unchecked("anyone"),
+            Addr::unchecked(sender),
             self.hub.clone(),
-            &ExecuteMsg::CheckSlash {},
+            &ExecuteMsg::UpdateValidatorProbation {
+                new_validator_probation,
+                probation_max_weight,
+            },
             &[],
         )
     }
 
-    pub fn claim(&mut self, sender: &str) -> AnyResult<AppResponse> {
+    pub fn update_commission_spike_handling(
+        &mut self,
+        sender: &str,
+        max_commission_increase_alert: Decimal,
+        auto_zero_weight_on_spike: bool,
+    ) -> AnyResult<AppResponse> {
         self.app.execute_contract(
             Addr::unchecked(sender),
             self.hub.clone(),
-            &ExecuteMsg::Claim {},
+            &ExecuteMsg::UpdateCommissionSpikeHandling {
+                max_commission_increase_alert,
+                auto_zero_weight_on_spike,
+            },
             &[],
         )
     }
 
-    /// returns address' balance of native token
-    pub fn set_validators(
+    pub fn suspend_delegations(&mut self, sender: &str) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::SuspendDelegations {},
+            &[],
+        )
+    }
+
+    pub fn update_delegation_cadence(
         &mut self,
         sender: &str,
-        new_validators: Vec<(String, Decimal)>,
+        delegate_every_n_epochs: u64,
     ) -> AnyResult<AppResponse> {
         self.app.execute_contract(
             Addr::unchecked(sender),
             self.hub.clone(),
-            &ExecuteMsg::SetValidators { new_validators },
+            &ExecuteMsg::UpdateDelegationCadence {
+                delegate_every_n_epochs,
+            },
             &[],
         )
     }
-    pub fn query_balance(&self, user: &str, denom: &str) -> AnyResult<u128> {
-        Ok(self.app.wrap().query_balance(user, denom)?.amount.u128())
+
+    pub fn update_strict_supply_check(
+        &mut self,
+        sender: &str,
+        strict_supply_check: bool,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::UpdateStrictSupplyCheck {
+                strict_supply_check,
+            },
+            &[],
+        )
     }
 
-    /// Queries all delegations of the hub contract
-    pub fn query_delegations(&self) -> AnyResult<Vec<Delegation>> {
-        Ok(self.app.wrap().query_all_delegations(&self.hub)?)
+    pub fn update_rebalance_on_reinvest(
+        &mut self,
+        sender: &str,
+        rebalance_on_reinvest: bool,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::UpdateRebalanceOnReinvest {
+                rebalance_on_reinvest,
+            },
+            &[],
+        )
     }
 
-    /// Queries all full delegations of the hub contract
-    pub fn query_full_delegations(&self) -> AnyResult<Vec<FullDelegation>> {
-        let s = self
-            .query_validator_set()?
-            .into_iter()
-            .map(|(val, _)| {
-                self.app
-                    .wrap()
-                    .query_delegation(&self.hub, &Addr::unchecked(val))
-            })
-            .collect::<StdResult<Vec<_>>>()?;
-        Ok(s.into_iter().flatten().collect())
+    pub fn update_redelegation_hops(
+        &mut self,
+        sender: &str,
+        redelegation_hops: bool,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::UpdateRedelegationHops { redelegation_hops },
+            &[],
+        )
     }
 
-    pub fn query_cw20_balance(&self, user: &str, contract: &Addr) -> AnyResult<u128> {
-        let balance: BalanceResponse = self.app.wrap().query_wasm_smart(
-            contract,
-            &Cw20QueryMsg::Balance {
-                address: user.to_owned(),
+    pub fn update_min_delegation(
+        &mut self,
+        sender: &str,
+        min_delegation: u128,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::UpdateMinDelegation {
+                min_delegation: min_delegation.into(),
             },
-        )?;
-        Ok(balance.balance.into())
+            &[],
+        )
     }
 
-    pub fn query_contract_admin(&self, contract: &Addr) -> AnyResult<String> {
-        let contract_info: ContractInfoResponse =
-            self.app.wrap().query_wasm_contract_info(contract)?;
-        Ok(contract_info.admin.map(Into::into).unwrap_or_default())
+    pub fn update_max_rate_growth(
+        &mut self,
+        sender: &str,
+        max_rate_growth_per_epoch: Option<Decimal>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::UpdateMaxRateGrowth {
+                max_rate_growth_per_epoch,
+            },
+            &[],
+        )
     }
 
-    pub fn query_exchange_rate(&self) -> AnyResult<Decimal> {
-        let resp: ExchangeRateResponse = self
-            .app
-            .wrap()
-            .query_wasm_smart(self.hub.clone(), &QueryMsg::ExchangeRate {})?;
-        Ok(resp.exchange_rate)
+    pub fn update_issuance_cap(
+        &mut self,
+        sender: &str,
+        issuance_cap: Option<u128>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::UpdateIssuanceCap {
+                issuance_cap: issuance_cap.map(Uint128::new),
+            },
+            &[],
+        )
     }
 
-    pub fn query_tvl(&self) -> AnyResult<Uint128> {
-        Ok(self
-            .app
-            .wrap()
-            .query_wasm_smart::<SupplyResponse>(self.hub.clone(), &QueryMsg::Supply {})?
-            .supply
-            .total_bonded)
+    pub fn sync_issued(&mut self, sender: &str) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::SyncIssued {},
+            &[],
+        )
     }
 
-    pub fn query_target_value(&self) -> AnyResult<Decimal> {
-        let resp: TargetValueResponse = self
+    pub fn resume_delegations(&mut self, sender: &str) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::ResumeDelegations {},
+            &[],
+        )
+    }
+
+    pub fn update_min_reinvest_amount(
+        &mut self,
+        sender: &str,
+        min_reinvest_amount: u128,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::UpdateMinReinvestAmount {
+                min_reinvest_amount: min_reinvest_amount.into(),
+            },
+            &[],
+        )
+    }
+
+    pub fn query_check_slash_available(&self) -> AnyResult<CheckSlashAvailableResponse> {
+        Ok(self
             .app
             .wrap()
-            .query_wasm_smart(self.hub.clone(), &QueryMsg::TargetValue {})?;
-        Ok(resp.target_value)
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::CheckSlashAvailable {})?)
     }
 
-    pub fn query_lsd_token(&self) -> AnyResult<Addr> {
-        let balance: ConfigResponse = self
+    pub fn query_slashing_events(&self) -> AnyResult<Vec<Slashing>> {
+        let res: SlashingEventsResponse = self
             .app
             .wrap()
-            .query_wasm_smart(self.hub.clone(), &QueryMsg::Config {})?;
-        Ok(balance.token_contract)
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::SlashingEvents {})?;
+        Ok(res.slashings)
     }
 
-    pub fn query_claims(&self, claim_addr: String) -> AnyResult<Vec<Claim>> {
-        let claims: ClaimsResponse = self.app.wrap().query_wasm_smart(
+    pub fn query_claims_integrity(
+        &self,
+        continuation: Option<ClaimsScanContinuation>,
+    ) -> AnyResult<ClaimsIntegrityResponse> {
+        Ok(self.app.wrap().query_wasm_smart(
             self.hub.clone(),
-            &QueryMsg::Claims {
-                address: claim_addr,
-            },
-        )?;
-        Ok(claims.claims)
+            &QueryMsg::ClaimsIntegrity { continuation },
+        )?)
+    }
+
+    pub fn repair_claims_counter(
+        &mut self,
+        sender: &str,
+        integrity: &ClaimsIntegrityResponse,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::RepairClaimsCounter {
+                computed_sum: integrity.computed_sum,
+                scanned_until: integrity.scanned_until.clone(),
+                checkpoint: integrity.checkpoint.clone().unwrap_or_default(),
+            },
+            &[],
+        )
+    }
+
+    pub fn update_slashing_safety_margin(
+        &mut self,
+        sender: &str,
+        new_margin: u64,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::UpdateSlashingSafetyMargin { new_margin },
+            &[],
+        )
+    }
+
+    pub fn update_min_blocks_per_epoch(
+        &mut self,
+        sender: &str,
+        min_blocks_per_epoch: Option<u64>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::UpdateMinBlocksPerEpoch {
+                min_blocks_per_epoch,
+            },
+            &[],
+        )
+    }
+
+    pub fn update_commission_vesting(
+        &mut self,
+        sender: &str,
+        commission_vesting: Option<CommissionVesting>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::UpdateCommissionVesting { commission_vesting },
+            &[],
+        )
+    }
+
+    pub fn skip_next_unbond(&mut self, sender: &str) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::SkipNextUnbond {},
+            &[],
+        )
+    }
+
+    pub fn deposit_boost(&mut self, sender: &str, amount: u128) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::DepositBoost {},
+            &coins(amount, "FUN"),
+        )
+    }
+
+    pub fn withdraw_boost(&mut self, sender: &str, amount: u128) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::WithdrawBoost {
+                amount: amount.into(),
+            },
+            &[],
+        )
+    }
+
+    pub fn claim_vested_commission(&mut self, sender: &str) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::ClaimVestedCommission {},
+            &[],
+        )
+    }
+
+    pub fn bond(&mut self, sender: &str, amount: u128) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::Bond {},
+            &coins(amount, "FUN"),
+        )
+    }
+
+    pub fn bond_many(
+        &mut self,
+        sender: &str,
+        recipients: &[(&str, u128)],
+        amount: u128,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::BondMany {
+                recipients: recipients
+                    .iter()
+                    .map(|(addr, amount)| (addr.to_string(), Uint128::new(*amount)))
+                    .collect(),
+            },
+            &coins(amount, "FUN"),
+        )
+    }
+
+    pub fn unbond(
+        &mut self,
+        sender: &str,
+        token_contract: &Addr,
+        balance: u128,
+    ) -> AnyResult<AppResponse> {
+        let msg = to_json_binary(&ReceiveMsg::Unbond {})?;
+
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            token_contract.clone(),
+            &cw20::Cw20ExecuteMsg::Send {
+                contract: self.hub.clone().to_string(),
+                amount: balance.into(),
+                msg,
+            },
+            &[],
+        )
+    }
+
+    /// Unbonds like [`Self::unbond`], but offers the claim to the claim buyers at `discount`
+    pub fn unbond_for_sale(
+        &mut self,
+        sender: &str,
+        token_contract: &Addr,
+        amount: u128,
+        discount: Decimal,
+    ) -> AnyResult<AppResponse> {
+        let msg = to_json_binary(&ReceiveMsg::UnbondForSale { discount })?;
+
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            token_contract.clone(),
+            &cw20::Cw20ExecuteMsg::Send {
+                contract: self.hub.clone().to_string(),
+                amount: amount.into(),
+                msg,
+            },
+            &[],
+        )
+    }
+
+    /// Sends `amount` claim vouchers to the hub to redeem them
+    pub fn redeem_voucher(&mut self, sender: &str, amount: u128) -> AnyResult<AppResponse> {
+        let voucher = self.query_voucher_token()?;
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            voucher,
+            &cw20::Cw20ExecuteMsg::Send {
+                contract: self.hub.to_string(),
+                amount: amount.into(),
+                msg: to_json_binary(&ReceiveMsg::RedeemVoucher {})?,
+            },
+            &[],
+        )
+    }
+
+    /// Allows the hub to burn `amount` lsd tokens of `owner`
+    pub fn increase_hub_allowance(
+        &mut self,
+        owner: &str,
+        token_contract: &Addr,
+        amount: u128,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(owner),
+            token_contract.clone(),
+            &cw20::Cw20ExecuteMsg::IncreaseAllowance {
+                spender: self.hub.to_string(),
+                amount: amount.into(),
+                expires: None,
+            },
+            &[],
+        )
+    }
+
+    pub fn unbond_from(
+        &mut self,
+        sender: &str,
+        owner: &str,
+        amount: u128,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::UnbondFrom {
+                owner: owner.to_string(),
+                amount: amount.into(),
+            },
+            &[],
+        )
+    }
+
+    pub fn unbond_deferred(
+        &mut self,
+        sender: &str,
+        token_contract: &Addr,
+        balance: u128,
+    ) -> AnyResult<AppResponse> {
+        let msg = to_json_binary(&ReceiveMsg::UnbondDeferredRate {})?;
+
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            token_contract.clone(),
+            &cw20::Cw20ExecuteMsg::Send {
+                contract: self.hub.clone().to_string(),
+                amount: balance.into(),
+                msg,
+            },
+            &[],
+        )
+    }
+
+    pub fn redistribute(
+        &mut self,
+        sender: &str,
+        token_contract: &Addr,
+        amount: u128,
+        recipients: &[(&str, Decimal)],
+    ) -> AnyResult<AppResponse> {
+        let msg = to_json_binary(&ReceiveMsg::Redistribute {
+            recipients: recipients
+                .iter()
+                .map(|(addr, weight)| (addr.to_string(), *weight))
+                .collect(),
+        })?;
+
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            token_contract.clone(),
+            &cw20::Cw20ExecuteMsg::Send {
+                contract: self.hub.clone().to_string(),
+                amount: amount.into(),
+                msg,
+            },
+            &[],
+        )
+    }
+
+    pub fn check_slash(&mut self) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked("anyone"),
+            self.hub.clone(),
+            &ExecuteMsg::CheckSlash {},
+            &[],
+        )
+    }
+
+    /// Calls the hub's sudo entry point, like the chain's cron module would
+    pub fn sudo(&mut self, msg: SudoMsg) -> AnyResult<AppResponse> {
+        self.app.wasm_sudo(self.hub.clone(), &msg)
+    }
+
+    pub fn set_claim_recipient(
+        &mut self,
+        sender: &str,
+        recipient: Option<&str>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::SetClaimRecipient {
+                recipient: recipient.map(Into::into),
+            },
+            &[],
+        )
+    }
+
+    pub fn set_operator(&mut self, sender: &str, operator: Option<&str>) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::SetOperator {
+                operator: operator.map(Into::into),
+            },
+            &[],
+        )
+    }
+
+    pub fn claim_for(&mut self, sender: &str, owner: &str) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::ClaimFor {
+                owner: owner.to_string(),
+            },
+            &[],
+        )
+    }
+
+    pub fn register_claim_buyer(&mut self, sender: &str, contract: &str) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::RegisterClaimBuyer {
+                contract: contract.to_string(),
+            },
+            &[],
+        )
+    }
+
+    pub fn buy_claim(
+        &mut self,
+        sender: &str,
+        owner: &str,
+        release_at: u64,
+        funds: u128,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::BuyClaim {
+                owner: owner.to_string(),
+                release_at,
+            },
+            &coins(funds, "FUN"),
+        )
+    }
+
+    pub fn claim(&mut self, sender: &str) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::Claim {},
+            &[],
+        )
+    }
+
+    pub fn claim_up_to(&mut self, sender: &str, max_amount: u128) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::ClaimUpTo {
+                max_amount: max_amount.into(),
+            },
+            &[],
+        )
+    }
+
+    pub fn claim_amount(&mut self, sender: &str, amount: u128) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::ClaimAmount {
+                amount: amount.into(),
+            },
+            &[],
+        )
+    }
+
+    /// returns address' balance of native token
+    pub fn set_validators(
+        &mut self,
+        sender: &str,
+        new_validators: Vec<(String, Decimal)>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::SetValidators {
+                new_validators: new_validators.into_iter().map(Into::into).collect(),
+                source: None,
+                deadline: None,
+                expected_total_bonded: None,
+            },
+            &[],
+        )
+    }
+
+    pub fn continue_valset_change(&mut self, sender: &str) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::ContinueValsetChange {},
+            &[],
+        )
+    }
+
+    pub fn set_validators_with_source(
+        &mut self,
+        sender: &str,
+        new_validators: Vec<(String, Decimal)>,
+        source: &str,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::SetValidators {
+                new_validators: new_validators.into_iter().map(Into::into).collect(),
+                source: Some(source.to_string()),
+                deadline: None,
+                expected_total_bonded: None,
+            },
+            &[],
+        )
+    }
+
+    pub fn set_validators_with_intent(
+        &mut self,
+        sender: &str,
+        new_validators: Vec<(String, Decimal)>,
+        deadline: Option<u64>,
+        expected_total_bonded: Option<u128>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::SetValidators {
+                new_validators: new_validators.into_iter().map(Into::into).collect(),
+                source: None,
+                deadline,
+                expected_total_bonded: expected_total_bonded.map(Into::into),
+            },
+            &[],
+        )
+    }
+
+    pub fn set_validator_configs(
+        &mut self,
+        sender: &str,
+        new_validators: Vec<ValidatorConfig>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::SetValidators {
+                new_validators: new_validators.into_iter().map(Into::into).collect(),
+                source: None,
+                deadline: None,
+                expected_total_bonded: None,
+            },
+            &[],
+        )
+    }
+    pub fn query_balance(&self, user: &str, denom: &str) -> AnyResult<u128> {
+        Ok(self.app.wrap().query_balance(user, denom)?.amount.u128())
+    }
+
+    /// Queries all delegations of the hub contract
+    pub fn query_delegations(&self) -> AnyResult<Vec<Delegation>> {
+        Ok(self.app.wrap().query_all_delegations(&self.hub)?)
+    }
+
+    /// Queries all full delegations of the hub contract
+    pub fn query_full_delegations(&self) -> AnyResult<Vec<FullDelegation>> {
+        let s = self
+            .query_validator_set()?
+            .into_iter()
+            .map(|(val, _)| {
+                self.app
+                    .wrap()
+                    .query_delegation(&self.hub, &Addr::unchecked(val))
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        Ok(s.into_iter().flatten().collect())
+    }
+
+    pub fn query_cw20_balance(&self, user: &str, contract: &Addr) -> AnyResult<u128> {
+        let balance: BalanceResponse = self.app.wrap().query_wasm_smart(
+            contract,
+            &Cw20QueryMsg::Balance {
+                address: user.to_owned(),
+            },
+        )?;
+        Ok(balance.balance.into())
+    }
+
+    pub fn query_contract_admin(&self, contract: &Addr) -> AnyResult<String> {
+        let contract_info: ContractInfoResponse =
+            self.app.wrap().query_wasm_contract_info(contract)?;
+        Ok(contract_info.admin.map(Into::into).unwrap_or_default())
+    }
+
+    pub fn query_exchange_rate(&self) -> AnyResult<Decimal> {
+        let resp: ExchangeRateResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::ExchangeRate {})?;
+        Ok(resp.exchange_rate)
+    }
+
+    pub fn query_exchange_rate_raw(&self) -> AnyResult<ExchangeRateRawResponse> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::ExchangeRateRaw {})?)
+    }
+
+    pub fn query_supply(&self) -> AnyResult<SupplyResponse> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::Supply {})?)
+    }
+
+    pub fn query_simulate_bond(&self, amount: u128) -> AnyResult<SimulateBondResponse> {
+        Ok(self.app.wrap().query_wasm_smart(
+            self.hub.clone(),
+            &QueryMsg::SimulateBond {
+                amount: amount.into(),
+            },
+        )?)
+    }
+
+    pub fn finalize_bootstrap(&mut self, limit: Option<u32>) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked("anyone"),
+            self.hub.clone(),
+            &ExecuteMsg::FinalizeBootstrap { limit },
+            &[],
+        )
+    }
+
+    pub fn query_bootstrap_status(&self) -> AnyResult<BootstrapStatusResponse> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::BootstrapStatus {})?)
+    }
+
+    pub fn query_buffer_utilization(&self) -> AnyResult<BufferUtilizationResponse> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::BufferUtilization {})?)
+    }
+
+    pub fn query_shares_to_redeem(&self, native: u128) -> AnyResult<Uint128> {
+        let resp: SharesToRedeemResponse = self.app.wrap().query_wasm_smart(
+            self.hub.clone(),
+            &QueryMsg::SharesToRedeem {
+                native: native.into(),
+            },
+        )?;
+        Ok(resp.shares)
+    }
+
+    pub fn query_dashboard(&self, address: Option<&str>) -> AnyResult<DashboardResponse> {
+        Ok(self.app.wrap().query_wasm_smart(
+            self.hub.clone(),
+            &QueryMsg::Dashboard {
+                address: address.map(str::to_string),
+            },
+        )?)
+    }
+
+    pub fn query_commission_vesting(&self) -> AnyResult<CommissionVestingResponse> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::CommissionVesting {})?)
+    }
+
+    pub fn query_tvl(&self) -> AnyResult<Uint128> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart::<SupplyResponse>(self.hub.clone(), &QueryMsg::Supply {})?
+            .supply
+            .total_bonded)
+    }
+
+    pub fn query_bonded_journal(
+        &self,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> AnyResult<Vec<BondedJournalEntry>> {
+        let resp: BondedJournalResponse = self.app.wrap().query_wasm_smart(
+            self.hub.clone(),
+            &QueryMsg::BondedJournal { start_after, limit },
+        )?;
+        Ok(resp.entries)
+    }
+
+    pub fn query_target_value(&self) -> AnyResult<Decimal> {
+        let resp: TargetValueResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::TargetValue {})?;
+        Ok(resp.target_value)
+    }
+
+    pub fn query_asset_info(&self) -> AnyResult<AssetInfoResponse> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::AssetInfo {})?)
+    }
+
+    pub fn query_accrued_but_unbonded_value(&self) -> AnyResult<AccruedButUnbondedValueResponse> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::AccruedButUnbondedValue {})?)
+    }
+
+    pub fn query_preview_commission(&self) -> AnyResult<PreviewCommissionResponse> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::PreviewCommission {})?)
+    }
+
+    pub fn query_exchange_rate_at(&self, time: u64) -> AnyResult<ExchangeRateAtResponse> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::ExchangeRateAt { time })?)
+    }
+
+    pub fn query_value_change(&self, address: &str, since: u64) -> AnyResult<ValueChangeResponse> {
+        Ok(self.app.wrap().query_wasm_smart(
+            self.hub.clone(),
+            &QueryMsg::ValueChange {
+                address: address.to_string(),
+                since,
+            },
+        )?)
+    }
+
+    pub fn query_preview_valset_change(
+        &self,
+        new_validators: Vec<(String, Decimal)>,
+    ) -> AnyResult<PreviewValsetChangeResponse> {
+        Ok(self.app.wrap().query_wasm_smart(
+            self.hub.clone(),
+            &QueryMsg::PreviewValsetChange {
+                new_validators: new_validators.into_iter().map(Into::into).collect(),
+            },
+        )?)
+    }
+
+    pub fn query_simulate_valset_change(
+        &self,
+        new_validators: Vec<(String, Decimal)>,
+    ) -> AnyResult<SimulateValsetChangeResponse> {
+        Ok(self.app.wrap().query_wasm_smart(
+            self.hub.clone(),
+            &QueryMsg::SimulateValsetChange {
+                new_validators: new_validators.into_iter().map(Into::into).collect(),
+            },
+        )?)
+    }
+
+    pub fn query_pending_valset_change(&self) -> AnyResult<PendingValsetChangeResponse> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::PendingValsetChange {})?)
+    }
+
+    pub fn query_health(&self) -> AnyResult<HealthResponse> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::Health {})?)
+    }
+
+    pub fn query_issued_reconciliation(&self) -> AnyResult<IssuedReconciliationResponse> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::IssuedReconciliation {})?)
+    }
+
+    pub fn query_reward_rate(&self) -> AnyResult<RewardRateResponse> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::RewardRate {})?)
+    }
+
+    pub fn query_config(&self) -> AnyResult<ConfigResponse> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::Config {})?)
+    }
+
+    pub fn query_validator_commissions(&self) -> AnyResult<Vec<(String, Decimal)>> {
+        let resp: ValidatorCommissionsResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::ValidatorCommissions {})?;
+        Ok(resp.commissions)
+    }
+
+    pub fn query_tombstoned_validators(&self) -> AnyResult<Vec<(String, u64)>> {
+        let resp: TombstonedValidatorsResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::TombstonedValidators {})?;
+        Ok(resp.validators)
+    }
+
+    pub fn query_voucher_token(&self) -> AnyResult<Addr> {
+        Ok(self
+            .query_config()?
+            .voucher_contract
+            .expect("hub was instantiated without claim vouchers"))
+    }
+
+    pub fn query_voucher_batches(&self) -> AnyResult<VoucherBatchesResponse> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::VoucherBatches {})?)
+    }
+
+    pub fn query_lsd_token(&self) -> AnyResult<Addr> {
+        let balance: ConfigResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::Config {})?;
+        Ok(balance.token_contract)
+    }
+
+    pub fn query_claims(&self, claim_addr: String) -> AnyResult<Vec<Claim>> {
+        let claims: ClaimsResponse = self.app.wrap().query_wasm_smart(
+            self.hub.clone(),
+            &QueryMsg::Claims {
+                address: claim_addr,
+                start_after: None,
+                limit: None,
+                status: None,
+            },
+        )?;
+        Ok(claims.claims)
+    }
+
+    pub fn query_claims_with_status(
+        &self,
+        claim_addr: &str,
+        status: ClaimStatus,
+    ) -> AnyResult<Vec<Claim>> {
+        let claims: ClaimsResponse = self.app.wrap().query_wasm_smart(
+            self.hub.clone(),
+            &QueryMsg::Claims {
+                address: claim_addr.to_string(),
+                start_after: None,
+                limit: None,
+                status: Some(status),
+            },
+        )?;
+        Ok(claims.claims)
+    }
+
+    pub fn query_claims_page(
+        &self,
+        claim_addr: &str,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    ) -> AnyResult<ClaimsResponse> {
+        Ok(self.app.wrap().query_wasm_smart(
+            self.hub.clone(),
+            &QueryMsg::Claims {
+                address: claim_addr.to_string(),
+                start_after,
+                limit,
+                status: None,
+            },
+        )?)
+    }
+
+    pub fn query_deferred_claims(&self, claim_addr: String) -> AnyResult<Vec<Claim>> {
+        let claims: ClaimsResponse = self.app.wrap().query_wasm_smart(
+            self.hub.clone(),
+            &QueryMsg::DeferredClaims {
+                address: claim_addr,
+                start_after: None,
+                limit: None,
+            },
+        )?;
+        Ok(claims.claims)
     }
 
     /// Processes the native unbonding queue
     /// This is done while updating the block
     pub fn process_native_unbonding(&mut self) {}
 
+    pub fn query_validator_set_with_bonded(&self) -> AnyResult<Vec<ValidatorWithBonded>> {
+        let resp: ValidatorSetWithBondedResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(self.hub.clone(), &QueryMsg::ValidatorSetWithBonded {})?;
+        Ok(resp.validators)
+    }
+
     pub fn query_validator_set(&self) -> AnyResult<Vec<(String, Decimal)>> {
         let vals: ValidatorSetResponse = self
             .app
@@ -433,6 +1503,73 @@ impl Suite {
         storage
     }
 
+    /// Gives raw access to the storage of the hub, e.g. to simulate corrupted state
+    pub fn update_hub_storage(&mut self, f: impl FnOnce(&mut dyn Storage)) {
+        let hub = self.hub.clone();
+        f(self.app.contract_storage_mut(&hub).as_mut());
+    }
+
+    /// Sends newly minted tokens to the hub, like the chain's incentive module would
+    pub fn mint_to_hub(&mut self, amount: u128, denom: &str) -> AnyResult<AppResponse> {
+        self.app.sudo(
+            BankSudo::Mint {
+                to_address: self.hub.to_string(),
+                amount: coins(amount, denom),
+            }
+            .into(),
+        )
+    }
+
+    pub fn register_external_reward(
+        &mut self,
+        sender: &str,
+        denom: &str,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::RegisterExternalReward {
+                denom: denom.to_string(),
+            },
+            &[],
+        )
+    }
+
+    pub fn sweep_external_reward(&mut self, sender: &str, denom: &str) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.hub.clone(),
+            &ExecuteMsg::SweepExternalReward {
+                denom: denom.to_string(),
+            },
+            &[],
+        )
+    }
+
+    pub fn query_external_rewards(
+        &self,
+        address: Option<&str>,
+    ) -> AnyResult<ExternalRewardsResponse> {
+        Ok(self.app.wrap().query_wasm_smart(
+            self.hub.clone(),
+            &QueryMsg::ExternalRewards {
+                address: address.map(Into::into),
+            },
+        )?)
+    }
+
+    /// Overwrites the native balance of the hub, e.g. to simulate missing liquidity
+    pub fn set_hub_balance(&mut self, amount: u128) {
+        let hub = self.hub.clone();
+        self.app
+            .init_modules(|router, _, storage| {
+                router
+                    .bank
+                    .init_balance(storage, &hub, coins(amount, "FUN"))
+            })
+            .unwrap();
+    }
+
     pub fn slash(&mut self, validator: &str, amount: Decimal) -> AnyResult<AppResponse> {
         self.app.sudo(
             StakingSudo::Slash {
@@ -0,0 +1,140 @@
+use crate::msg::CommissionVesting;
+use crate::multitest::suite::SuiteBuilder;
+use crate::ContractError;
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const EPOCH: u64 = 23 * HOUR;
+
+#[test]
+fn update_commission_vesting() {
+    let mut suite = SuiteBuilder::new().build();
+    let vesting = CommissionVesting {
+        cliff_seconds: 10 * DAY,
+        duration_seconds: 30 * DAY,
+    };
+
+    let err = suite
+        .update_commission_vesting("someone", Some(vesting.clone()))
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    let err = suite
+        .update_commission_vesting(
+            "owner",
+            Some(CommissionVesting {
+                cliff_seconds: 31 * DAY,
+                duration_seconds: 30 * DAY,
+            }),
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidCommissionVesting {},
+        err.downcast().unwrap()
+    );
+
+    suite
+        .update_commission_vesting("owner", Some(vesting.clone()))
+        .unwrap();
+    assert_eq!(
+        suite.query_commission_vesting().unwrap().commission_vesting,
+        Some(vesting)
+    );
+}
+
+#[test]
+fn commission_vests_over_time() {
+    let delegator = "delegator";
+    let treasury = "treasury";
+
+    let amount = 1_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(delegator, amount)])
+        .build();
+    let lsd = suite.query_lsd_token().unwrap();
+    suite
+        .update_commission_vesting(
+            "owner",
+            Some(CommissionVesting {
+                cliff_seconds: 10 * DAY,
+                duration_seconds: 30 * DAY,
+            }),
+        )
+        .unwrap();
+
+    suite.bond(delegator, amount).unwrap();
+    suite.update_time(EPOCH);
+    suite.reinvest().unwrap();
+
+    // every reinvest with rewards creates a tranche
+    for _ in 0..3 {
+        suite.update_time(EPOCH);
+        let rate = suite.query_exchange_rate().unwrap();
+        suite.reinvest().unwrap();
+        assert!(
+            suite.query_exchange_rate().unwrap() > rate,
+            "holders should still receive the rest of the rewards"
+        );
+    }
+    let vesting = suite.query_commission_vesting().unwrap();
+    assert_eq!(vesting.tranches.len(), 3);
+    let total: u128 = vesting.tranches.iter().map(|t| t.amount.u128()).sum();
+    assert!(total > 0);
+
+    // the commission is held by the hub as lsd instead of being sent to the treasury
+    assert_eq!(suite.query_balance(treasury, "FUN").unwrap(), 0);
+    assert_eq!(
+        suite.query_cw20_balance(suite.hub.as_str(), &lsd).unwrap(),
+        total
+    );
+
+    // nothing is claimable before the cliff
+    assert!(vesting.claimable.is_zero());
+    let err = suite.claim_vested_commission(treasury).unwrap_err();
+    assert_eq!(ContractError::NothingToClaim {}, err.downcast().unwrap());
+    let err = suite.claim_vested_commission(delegator).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    // after the cliff of the first tranche, it vested linearly since its creation
+    suite.update_time(10 * DAY - 2 * EPOCH);
+    let vesting = suite.query_commission_vesting().unwrap();
+    let first = &vesting.tranches[0];
+    assert_eq!(
+        first.vested,
+        first.amount.multiply_ratio(10 * DAY, 30 * DAY)
+    );
+    assert_eq!(first.vested + first.unvested, first.amount);
+    assert!(vesting.tranches[1..].iter().all(|t| t.vested.is_zero()));
+    assert_eq!(vesting.claimable, first.vested);
+
+    suite.claim_vested_commission(treasury).unwrap();
+    let claimed = suite.query_cw20_balance(treasury, &lsd).unwrap();
+    assert_eq!(claimed, first.vested.u128());
+    let err = suite.claim_vested_commission(treasury).unwrap_err();
+    assert_eq!(ContractError::NothingToClaim {}, err.downcast().unwrap());
+
+    // linear release afterwards
+    suite.update_time(5 * DAY);
+    let vesting = suite.query_commission_vesting().unwrap();
+    assert_eq!(
+        vesting.tranches[0].vested,
+        vesting.tranches[0]
+            .amount
+            .multiply_ratio(15 * DAY, 30 * DAY)
+    );
+    assert!(vesting.tranches[0].claimed.u128() == claimed);
+
+    // everything is released after the full duration and the tranches are removed
+    suite.update_time(30 * DAY);
+    suite.claim_vested_commission(treasury).unwrap();
+    assert_eq!(suite.query_cw20_balance(treasury, &lsd).unwrap(), total);
+    assert_eq!(
+        suite.query_cw20_balance(suite.hub.as_str(), &lsd).unwrap(),
+        0
+    );
+    assert!(suite
+        .query_commission_vesting()
+        .unwrap()
+        .tranches
+        .is_empty());
+}
@@ -0,0 +1,188 @@
+use cosmwasm_schema::{
+    cw_serde,
+    serde::{de::DeserializeOwned, Serialize},
+};
+use cosmwasm_std::{Order, StdResult, Storage};
+use cw_storage_plus::{Bound, KeyDeserialize, Map, PrimaryKey};
+
+/// The page size used by list queries if no `limit` is given
+pub const DEFAULT_LIMIT: u32 = 10;
+/// The biggest page size list queries return, bigger limits are capped to this
+pub const MAX_LIMIT: u32 = 30;
+
+/// One page of a list query.
+/// `next_start_after` is the `start_after` to pass to get the next page, `None` if this is the last one.
+#[cw_serde]
+pub struct PageResult<T, K> {
+    pub items: Vec<T>,
+    pub next_start_after: Option<K>,
+}
+
+/// Returns the page size for the given `limit`.
+/// Every page contains at least one item (if there are any left), so a limit of 0 is treated as 1.
+pub fn clamp_limit(limit: Option<u32>) -> usize {
+    limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT) as usize
+}
+
+/// Returns the entries of `map` with keys after `start_after` in ascending order, converted with `f`
+pub fn paginate_map<'a, K, V, T>(
+    map: &Map<K, V>,
+    storage: &dyn Storage,
+    start_after: Option<K>,
+    limit: Option<u32>,
+    f: impl Fn(K::Output, V) -> StdResult<T>,
+) -> StdResult<PageResult<T, K::Output>>
+where
+    K: PrimaryKey<'a> + KeyDeserialize,
+    K::Output: Clone + 'static,
+    V: Serialize + DeserializeOwned,
+{
+    let limit = clamp_limit(limit);
+    // load one more than needed to know if there is another page
+    let mut entries = map
+        .range(
+            storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit + 1)
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = if entries.len() > limit {
+        entries.truncate(limit);
+        entries.last().map(|(key, _)| key.clone())
+    } else {
+        None
+    };
+
+    Ok(PageResult {
+        items: entries
+            .into_iter()
+            .map(|(key, value)| f(key, value))
+            .collect::<StdResult<_>>()?,
+        next_start_after,
+    })
+}
+
+/// Same as [`paginate_map`], but for lists that are stored as a whole.
+/// The key of an item is its position in the list.
+pub fn paginate_vec<T>(
+    items: Vec<T>,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+) -> PageResult<T, u32> {
+    let limit = clamp_limit(limit);
+    let start = start_after.map_or(0, |pos| (pos as usize).saturating_add(1));
+    let len = items.len();
+
+    let items: Vec<_> = items.into_iter().skip(start).take(limit).collect();
+    let next_start_after = if start + items.len() < len {
+        Some((start + items.len() - 1) as u32)
+    } else {
+        None
+    };
+
+    PageResult {
+        items,
+        next_start_after,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::MockStorage;
+
+    const NUMBERS: Map<u64, u64> = Map::new("numbers");
+
+    fn storage_with(count: u64) -> MockStorage {
+        let mut storage = MockStorage::new();
+        for i in 0..count {
+            NUMBERS.save(&mut storage, i, &(i * 10)).unwrap();
+        }
+        storage
+    }
+
+    fn page(
+        storage: &dyn Storage,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> PageResult<u64, u64> {
+        paginate_map(&NUMBERS, storage, start_after, limit, |_, value| Ok(value)).unwrap()
+    }
+
+    #[test]
+    fn default_limit() {
+        let storage = storage_with(25);
+
+        let first = page(&storage, None, None);
+        assert_eq!(first.items, (0..10).map(|i| i * 10).collect::<Vec<_>>());
+        assert_eq!(first.next_start_after, Some(9));
+
+        let second = page(&storage, first.next_start_after, None);
+        assert_eq!(second.items[0], 100);
+        assert_eq!(second.next_start_after, Some(19));
+
+        let last = page(&storage, second.next_start_after, None);
+        assert_eq!(last.items.len(), 5);
+        assert_eq!(last.next_start_after, None);
+    }
+
+    #[test]
+    fn limit_boundaries() {
+        let storage = storage_with(50);
+
+        // zero still returns one item, so paging always makes progress
+        let zero = page(&storage, None, Some(0));
+        assert_eq!(zero.items, vec![0]);
+        assert_eq!(zero.next_start_after, Some(0));
+
+        // too big limits are capped
+        let big = page(&storage, None, Some(u32::MAX));
+        assert_eq!(big.items.len(), MAX_LIMIT as usize);
+        assert_eq!(big.next_start_after, Some(MAX_LIMIT as u64 - 1));
+
+        // exactly the remaining items does not report another page
+        let exact = page(&storage, Some(19), Some(MAX_LIMIT));
+        assert_eq!(exact.items.len(), 30);
+        assert_eq!(exact.next_start_after, None);
+    }
+
+    #[test]
+    fn start_after_last_element() {
+        let storage = storage_with(5);
+
+        let result = page(&storage, Some(4), None);
+        assert!(result.items.is_empty());
+        assert_eq!(result.next_start_after, None);
+
+        // empty map
+        let result = page(&MockStorage::new(), None, None);
+        assert!(result.items.is_empty());
+        assert_eq!(result.next_start_after, None);
+    }
+
+    #[test]
+    fn vec_pages_match_map_pages() {
+        let storage = storage_with(25);
+        let items: Vec<u64> = (0..25).map(|i| i * 10).collect();
+
+        for (start_after, limit) in [
+            (None, None),
+            (None, Some(0)),
+            (Some(3), Some(7)),
+            (Some(10), Some(100)),
+            (Some(24), None),
+            (Some(30), None),
+        ] {
+            let vec_page = paginate_vec(items.clone(), start_after, limit);
+            let map_page = page(&storage, start_after.map(u64::from), limit);
+            assert_eq!(vec_page.items, map_page.items);
+            assert_eq!(
+                vec_page.next_start_after.map(u64::from),
+                map_page.next_start_after
+            );
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//! Slippage checks shared by the messages that let the sender set a limit on what they receive
+use cosmwasm_std::Uint128;
+
+use crate::ContractError;
+
+/// Fails with [`ContractError::SlippageExceeded`] if `actual` is below `min`.
+/// Without a `min`, any amount is accepted.
+pub fn ensure_min(actual: Uint128, min: Option<Uint128>) -> Result<(), ContractError> {
+    match min {
+        Some(expected) if actual < expected => {
+            Err(ContractError::SlippageExceeded { expected, actual })
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ensure_min_rejects_only_shortfalls() {
+        let amount = Uint128::new(1_000);
+        ensure_min(amount, None).unwrap();
+        ensure_min(amount, Some(Uint128::zero())).unwrap();
+        ensure_min(amount, Some(amount)).unwrap();
+
+        let err = ensure_min(amount, Some(amount + Uint128::one())).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::SlippageExceeded {
+                expected: Uint128::new(1_001),
+                actual: amount,
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Slippage exceeded, expected at least 1001, but got 1000"
+        );
+    }
+}
@@ -1,13 +1,16 @@
+use std::collections::BTreeSet;
 use std::ops::{Deref, DerefMut};
 
 use crate::ContractError;
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    Addr, Decimal, Deps, Env, Order, OverflowError, StdError, StdResult, Storage, Uint128,
+    ensure, ensure_eq, Addr, Coin, Decimal, Deps, Empty, Env, Order, OverflowError, StdError,
+    StdResult, Storage, Timestamp, Uint128,
 };
 use cw_storage_plus::{Bound, Item, Map};
 
-use crate::claim::Claims;
+use crate::claim::{Claim, Claims};
+use crate::msg::{BondedChangeReason, BondedJournalEntry, CommissionVesting, SurplusStrategy};
 
 #[cw_serde]
 pub struct Config {
@@ -42,47 +45,531 @@ pub struct Config {
     /// The idea here is that no one will want to buy the staking token at exactly the price of the underlying,
     /// because they are locked and can potentially be slashed. So we apply a discount to the price.
     pub liquidity_discount: Decimal,
+
+    /// The minimum number of seconds between two reinvests, checked in addition to `next_epoch`.
+    /// This allows to slow down the reinvest cadence without changing the epoch rhythm.
+    pub min_reinvest_spacing: Option<u64>,
+
+    /// The minimum number of blocks between two epochs (and between two unbonding epochs),
+    /// checked in addition to the time. This protects against proposers skewing block timestamps.
+    pub min_blocks_per_epoch: Option<u64>,
+    /// The block height at which the last reinvest epoch started
+    #[serde(default)]
+    pub last_epoch_height: u64,
+    /// The block height at which the last unbonding epoch started
+    #[serde(default)]
+    pub last_unbond_height: u64,
+
+    /// If set, the commission is bonded and the resulting lsd tokens vest according to this schedule,
+    /// instead of sending the commission to the treasury directly.
+    pub commission_vesting: Option<CommissionVesting>,
+
+    /// The minimum number of seconds between an address' last bond and its next unbond.
+    /// This deters bonding right before a reinvest and unbonding right after it. It can be circumvented
+    /// by transferring the lsd tokens to another address first, so it only protects against the direct path.
+    #[serde(default)]
+    pub min_holding_period: u64,
+
+    /// Reinvests that withdraw less rewards than this are skipped as long as no claims need to be covered
+    /// and nothing else is waiting to be delegated. The rewards stay in the balance (so they still count
+    /// towards the exchange rate) until enough accumulated.
+    /// Contracts migrated from an older version start with `0`, so their reinvests behave as before.
+    #[serde(default)]
+    pub min_reinvest_amount: Uint128,
+
+    /// The maximum amount a single `ExecuteMsg::UpdateLiquidityDiscount` can change the liquidity discount by,
+    /// so the target value of the pool cannot be moved abruptly. Only changeable via `SudoMsg::UpdateDiscountLimits`
+    #[serde(default = "default_max_discount_change_per_update")]
+    pub max_discount_change_per_update: Decimal,
+    /// The minimum number of seconds between two `ExecuteMsg::UpdateLiquidityDiscount`.
+    /// Only changeable via `SudoMsg::UpdateDiscountLimits`
+    #[serde(default = "default_min_discount_update_interval")]
+    pub min_discount_update_interval: u64,
+    /// The time (in seconds) of the last `ExecuteMsg::UpdateLiquidityDiscount`, `0` if it was never updated
+    #[serde(default)]
+    pub last_discount_update: u64,
+
+    /// How the surplus of a reinvest is split between the validators
+    #[serde(default)]
+    pub surplus_strategy: SurplusStrategy,
+    /// If set, reinvests don't delegate anything and keep the surplus in the balance.
+    /// Undelegations for claims still happen as usual.
+    #[serde(default)]
+    pub delegations_suspended: bool,
+    /// This contract is notified with a [`crate::msg::ReinvestHookMsg`] after every reinvest
+    #[serde(default)]
+    pub reinvest_hook: Option<Addr>,
+
+    /// The number of seconds after a validator was added to the validator set during which it gets
+    /// at most `probation_max_weight` of a reinvest's surplus or of the redelegated tokens.
+    /// `0` disables the probation.
+    #[serde(default)]
+    pub new_validator_probation: u64,
+    /// The most a validator on probation gets, see `new_validator_probation`
+    #[serde(default)]
+    pub probation_max_weight: Decimal,
+
+    /// The number of decimals of the bond denom. The contract only calculates in base units, so this is
+    /// only reported to integrators, see [`crate::msg::ConfigResponse`].
+    #[serde(default = "default_bond_denom_decimals")]
+    pub bond_denom_decimals: u8,
+    /// The decimals the lsd token was required to have on instantiation, see `InstantiateMsg::expected_bond_decimals`
+    #[serde(default)]
+    pub expected_bond_decimals: Option<u8>,
+
+    /// A reinvest reports validators whose commission increased by more than this since the previous reinvest,
+    /// e.g. `0.1` for an increase from 5% to more than 15%
+    #[serde(default = "default_max_commission_increase_alert")]
+    pub max_commission_increase_alert: Decimal,
+    /// If set, the weight of a reported validator is set to zero and the other weights are scaled up.
+    /// Its delegation is only moved with the next `ExecuteMsg::SetValidators`.
+    #[serde(default)]
+    pub auto_zero_weight_on_spike: bool,
+
+    /// The surplus is only delegated on every n-th reinvest and kept in the balance in between,
+    /// to save the delegation messages. Undelegations for claims are never deferred.
+    /// `0` and `1` delegate on every reinvest.
+    #[serde(default)]
+    pub delegate_every_n_epochs: u64,
+
+    /// If set, reinvests fail while `Supply::issued` differs from the total supply of the lsd token by more
+    /// than [`crate::contract::ISSUED_DUST_TOLERANCE`], until the owner adopts it with `ExecuteMsg::SyncIssued`
+    #[serde(default)]
+    pub strict_supply_check: bool,
+
+    /// The part of the native tokens that is kept when unbonding, see `ExecuteMsg::UpdateUnbondFee`.
+    /// It stays in the hub, so it goes to the remaining holders through the exchange rate.
+    #[serde(default)]
+    pub unbond_fee: Decimal,
+    /// The time (in seconds) of the last `ExecuteMsg::UpdateUnbondFee`, `0` if it was never updated
+    #[serde(default)]
+    pub last_unbond_fee_update: u64,
+
+    /// The most lsd tokens that can be issued, `None` for no limit. Bonds that would issue more fail.
+    /// It can not be raised above the cap of the lsd token, which is set when instantiating.
+    #[serde(default)]
+    pub issuance_cap: Option<Uint128>,
+
+    /// The cw20 that represents the claims of unbonds if the hub was instantiated with `InstantiateMsg::voucher_init`.
+    /// The vouchers are minted 1:1 with the native amount instead of creating claims and redeemed with `ReceiveMsg::RedeemVoucher`.
+    #[serde(default)]
+    pub voucher_contract: Option<Addr>,
+
+    /// If set, reinvests redelegate part of the drift between the delegations and the weights of the validator set,
+    /// see [`crate::contract::MAX_REBALANCE_REDELEGATIONS`]
+    #[serde(default)]
+    pub rebalance_on_reinvest: bool,
+
+    /// Can do the day-to-day actions of the owner that don't touch the validator set or the treasury,
+    /// see [`Config::ensure_operator_or_owner`]. Set by the owner with `ExecuteMsg::SetOperator`.
+    #[serde(default)]
+    pub operator: Option<Addr>,
+
+    /// If set, valset changes route the redelegations of pairs without a free entry through another validator
+    /// of the new set instead of waiting, see [`REDELEGATION_HOPS`]
+    #[serde(default)]
+    pub redelegation_hops: bool,
+
+    /// The most the exchange rate can grow with one reinvest, e.g. `0.01` for 1%. Rewards above that are
+    /// kept in [`DEFERRED_REWARDS`] and recognized by the next reinvests. `None` for no limit.
+    #[serde(default)]
+    pub max_rate_growth_per_epoch: Option<Decimal>,
+
+    /// Reinvests don't delegate less than this to a validator, since some chains reject delegations below a minimum.
+    /// The skipped amounts stay in the balance and are delegated with the next reinvests.
+    #[serde(default = "default_min_delegation")]
+    pub min_delegation: Uint128,
+
+    /// The bech32 prefix of validator operator addresses on this chain, e.g. "junovaloper".
+    /// Validators of valset changes have to match it, see [`crate::valoper`]. Empty to not check them.
+    #[serde(default)]
+    pub valoper_prefix: String,
+}
+
+/// The `Config::min_reinvest_amount` of new contracts
+pub const DEFAULT_MIN_REINVEST_AMOUNT: Uint128 = Uint128::new(1000);
+
+/// The `Config::bond_denom_decimals` of contracts that were instantiated without one or migrated from an older version
+pub const DEFAULT_BOND_DENOM_DECIMALS: u8 = 6;
+
+/// The `Config::min_delegation` of new contracts and of contracts that were migrated from an older version
+pub const DEFAULT_MIN_DELEGATION: Uint128 = Uint128::one();
+
+fn default_min_delegation() -> Uint128 {
+    DEFAULT_MIN_DELEGATION
+}
+
+fn default_bond_denom_decimals() -> u8 {
+    DEFAULT_BOND_DENOM_DECIMALS
+}
+
+/// The `Config::max_commission_increase_alert` of new contracts and of contracts that were migrated from an older version
+pub const DEFAULT_MAX_COMMISSION_INCREASE_ALERT: Decimal = Decimal::percent(10);
+
+fn default_max_commission_increase_alert() -> Decimal {
+    DEFAULT_MAX_COMMISSION_INCREASE_ALERT
+}
+
+/// The `Config::max_discount_change_per_update` of new contracts and of contracts that were migrated from an older version
+pub const DEFAULT_MAX_DISCOUNT_CHANGE_PER_UPDATE: Decimal = Decimal::percent(2);
+/// The `Config::min_discount_update_interval` of new contracts and of contracts that were migrated from an older version
+pub const DEFAULT_MIN_DISCOUNT_UPDATE_INTERVAL: u64 = 24 * 60 * 60;
+
+fn default_max_discount_change_per_update() -> Decimal {
+    DEFAULT_MAX_DISCOUNT_CHANGE_PER_UPDATE
+}
+
+fn default_min_discount_update_interval() -> u64 {
+    DEFAULT_MIN_DISCOUNT_UPDATE_INTERVAL
 }
 
 impl Config {
+    /// Fails until the lsd token was created. Before the reply to its instantiation, `token_contract` is empty
+    pub fn ensure_initialized(&self) -> Result<(), ContractError> {
+        ensure!(
+            !self.token_contract.as_str().is_empty(),
+            ContractError::NotInitialized {}
+        );
+        Ok(())
+    }
+
+    pub fn ensure_owner(&self, sender: &Addr) -> Result<(), ContractError> {
+        ensure_eq!(&self.owner, sender, ContractError::Unauthorized {});
+        Ok(())
+    }
+
+    /// Used for the operational actions: updating the liquidity discount, suspending and resuming delegations
+    /// and tweaking the reinvest cadence. Everything else stays with the owner.
+    pub fn ensure_operator_or_owner(&self, sender: &Addr) -> Result<(), ContractError> {
+        ensure!(
+            &self.owner == sender || self.operator.as_ref() == Some(sender),
+            ContractError::Unauthorized {}
+        );
+        Ok(())
+    }
+
+    /// Queries the total supply of the lsd token
+    pub fn token_total_supply(&self, deps: Deps) -> StdResult<Uint128> {
+        let cw20::TokenInfoResponse { total_supply, .. } = deps
+            .querier
+            .query_wasm_smart(&self.token_contract, &cw20::Cw20QueryMsg::TokenInfo {})?;
+        Ok(total_supply)
+    }
+
+    /// Queries the mint cap of the lsd token
+    pub fn token_cap(&self, deps: Deps) -> StdResult<Option<Uint128>> {
+        let minter: Option<cw20::MinterResponse> = deps
+            .querier
+            .query_wasm_smart(&self.token_contract, &cw20::Cw20QueryMsg::Minter {})?;
+        Ok(minter.and_then(|minter| minter.cap))
+    }
+
+    /// Whether having `issued` lsd tokens in total exceeds the `issuance_cap`
+    pub fn exceeds_issuance_cap(&self, issued: Uint128) -> bool {
+        self.issuance_cap.map_or(false, |cap| issued > cap)
+    }
+
     /// Progresses to the next reinvest epoch after the given timestamp, and returns that timestamp.
     /// Returns error if epoch has not passes.
     pub fn next_epoch_after(&mut self, env: &Env) -> Result<u64, ContractError> {
-        let timestamp = env.block.time.seconds();
-        if timestamp < self.next_epoch {
-            Err(ContractError::EpochNotReached {
-                next_epoch: self.next_epoch,
-            })
-        } else {
-            // calculate the next epoch, making sure it keeps the same rythm even if we don't call immediately
-            // and works even if we skip a few epochs
-            let epochs_until_then = (timestamp - self.next_epoch) / self.epoch_period;
-            self.next_epoch += (epochs_until_then + 1) * self.epoch_period;
-            Ok(self.next_epoch)
-        }
+        self.ensure_epoch_reached(env, self.next_epoch, self.last_epoch_height)?;
+
+        // calculate the next epoch, making sure it keeps the same rythm even if we don't call immediately
+        // and works even if we skip a few epochs
+        let epochs_until_then = (env.block.time.seconds() - self.next_epoch) / self.epoch_period;
+        self.next_epoch += (epochs_until_then + 1) * self.epoch_period;
+        self.last_epoch_height = env.block.height;
+        Ok(self.next_epoch)
     }
 
     /// Progresses to the next unbonding epoch after the given timestamp, and returns that timestamp.
     /// Returns error if epoch has not passes.
     pub fn next_unbond_after(&mut self, env: &Env) -> Result<u64, ContractError> {
-        let timestamp = env.block.time.seconds();
-        if timestamp < self.next_unbond {
-            Err(ContractError::EpochNotReached {
-                next_epoch: self.next_unbond,
-            })
+        self.ensure_epoch_reached(env, self.next_unbond, self.last_unbond_height)?;
+
+        // calculate the next epoch, making sure it keeps the same rythm even if we don't call immediately
+        // and works even if we skip a few epochs
+        let epoch_period = self.unbond_epoch();
+        let epochs_until_then = (env.block.time.seconds() - self.next_unbond) / epoch_period;
+        self.next_unbond += (epochs_until_then + 1) * epoch_period;
+        self.last_unbond_height = env.block.height;
+        Ok(self.next_unbond)
+    }
+
+    /// Returns the start of the first reinvest epoch at or after `next_unbond`.
+    /// That is the earliest a claim created now is undelegated, since undelegations only happen when reinvesting.
+    pub fn next_undelegation(&self) -> u64 {
+        if self.next_epoch >= self.next_unbond {
+            return self.next_epoch;
+        }
+        let epochs = (self.next_unbond - self.next_epoch).div_ceil(self.epoch_period);
+        self.next_epoch + epochs * self.epoch_period
+    }
+
+    /// Moves `next_unbond` to the unbonding epoch after the upcoming one and returns that timestamp.
+    /// If the unbonding epoch was already reached, it moves to the first epoch after the current time instead.
+    pub fn skip_next_unbond(&mut self, env: &Env) -> u64 {
+        let epoch_period = self.unbond_epoch();
+        let now = env.block.time.seconds();
+        if self.next_unbond > now {
+            self.next_unbond += epoch_period;
         } else {
-            // calculate the next epoch, making sure it keeps the same rythm even if we don't call immediately
-            // and works even if we skip a few epochs
-            let epoch_period = self.unbond_epoch();
-            let epochs_until_then = (timestamp - self.next_unbond) / epoch_period;
+            let epochs_until_then = (now - self.next_unbond) / epoch_period;
             self.next_unbond += (epochs_until_then + 1) * epoch_period;
-            Ok(self.next_unbond)
+        }
+        self.next_unbond
+    }
+
+    /// Returns an error if the epoch starting at `next_epoch` has not been reached yet, or
+    /// if `min_blocks_per_epoch` is set and not enough blocks passed since `last_height`.
+    fn ensure_epoch_reached(
+        &self,
+        env: &Env,
+        next_epoch: u64,
+        last_height: u64,
+    ) -> Result<(), ContractError> {
+        let remaining_seconds = next_epoch.saturating_sub(env.block.time.seconds());
+        match self.min_blocks_per_epoch {
+            None if remaining_seconds > 0 => Err(ContractError::EpochNotReached { next_epoch }),
+            None => Ok(()),
+            Some(min_blocks) => {
+                let remaining_blocks = (last_height + min_blocks).saturating_sub(env.block.height);
+                if remaining_seconds > 0 || remaining_blocks > 0 {
+                    Err(ContractError::EpochBlocksNotReached {
+                        remaining_seconds,
+                        remaining_blocks,
+                    })
+                } else {
+                    Ok(())
+                }
+            }
         }
     }
 
     pub fn unbond_epoch(&self) -> u64 {
         div_ceil(self.unbond_period, self.max_concurrent_unbondings)
     }
+
+    /// The margins around two consecutive unbondings must not overlap,
+    /// otherwise slashing detection would never be possible
+    pub fn is_valid_slashing_safety_margin(&self, margin: u64) -> bool {
+        margin < self.unbond_epoch() / 2
+    }
+
+    /// Applies the liquidity discount to the given exchange rate.
+    /// The result is rounded down, so it is always a conservative lower bound of
+    /// `exchange_rate * (1 - liquidity_discount)`, even for very small exchange rates.
+    pub fn target_value(&self, exchange_rate: Decimal) -> Decimal {
+        // `Decimal` multiplication truncates the full 256 bit product, so there is no rounding up here
+        exchange_rate * (Decimal::one() - self.liquidity_discount)
+    }
+}
+
+/// The time (in seconds) at which a validator was first added with `ExecuteMsg::SetValidators`.
+/// The validators of the initial validator set have no entry, they are never on probation.
+pub const VALIDATOR_FIRST_SEEN: Map<&str, u64> = Map::new("validator_first_seen");
+
+/// The completion times of the redelegations from the first to the second validator that may still be pending.
+/// The chain limits how many of them can exist for each pair, see [`crate::contract::MAX_REDELEGATION_ENTRIES`].
+pub const REDELEGATION_ENTRIES: Map<(&str, &str), Vec<u64>> = Map::new("redelegation_entries");
+
+/// Remembers a redelegation from `src` to `dst` that completes at `completes_at`,
+/// dropping the entries of the pair that completed before `now`
+pub fn record_redelegation(
+    storage: &mut dyn Storage,
+    src: &str,
+    dst: &str,
+    now: u64,
+    completes_at: u64,
+) -> StdResult<()> {
+    REDELEGATION_ENTRIES.update(storage, (src, dst), |entries| -> StdResult<_> {
+        let mut entries = entries.unwrap_or_default();
+        entries.retain(|&at| at > now);
+        entries.push(completes_at);
+        Ok(entries)
+    })?;
+    Ok(())
+}
+
+/// Returns the number of redelegations from `src` to `dst` that are not completed at `now`
+pub fn pending_redelegations(
+    storage: &dyn Storage,
+    src: &str,
+    dst: &str,
+    now: u64,
+) -> StdResult<u32> {
+    Ok(pending_redelegation_completions(storage, src, dst, now)?.len() as u32)
+}
+
+/// Returns the completion times of the redelegations from `src` to `dst` that are not completed at `now`
+pub fn pending_redelegation_completions(
+    storage: &dyn Storage,
+    src: &str,
+    dst: &str,
+    now: u64,
+) -> StdResult<Vec<u64>> {
+    Ok(REDELEGATION_ENTRIES
+        .may_load(storage, (src, dst))?
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|&at| at > now)
+        .collect())
+}
+
+/// Returns the validators that receive a redelegation which is not completed at `now`.
+/// The chain does not allow redelegating from them before that (transitive redelegation).
+pub fn receiving_redelegations(storage: &dyn Storage, now: u64) -> StdResult<BTreeSet<String>> {
+    REDELEGATION_ENTRIES
+        .range(storage, None, None, Order::Ascending)
+        .filter_map(|entry| match entry {
+            Ok(((_, dst), completions)) => {
+                completions.iter().any(|&at| at > now).then_some(Ok(dst))
+            }
+            Err(err) => Some(Err(err)),
+        })
+        .collect()
+}
+
+/// A redelegation of a valset change that was routed through an intermediate validator,
+/// because the pair of its source and destination had no free redelegation entry
+#[cw_serde]
+pub struct RedelegationHop {
+    pub intermediate: String,
+    pub dst_validator: String,
+    pub amount: Uint128,
+    /// When the redelegation to the intermediate completes (in seconds). The chain does not allow
+    /// redelegating from the intermediate before that.
+    pub ready_at: u64,
+}
+
+/// The second hops of the routed redelegations, which are executed by the first reinvest after their `ready_at`.
+/// Until then, their tokens count for their destination when calculating a valset change.
+pub const REDELEGATION_HOPS: Item<Vec<RedelegationHop>> = Item::new("redelegation_hops");
+
+/// The validator set a valset change is still moving the delegations to, because not all of its redelegations
+/// fit into the free redelegation entries. See `ExecuteMsg::ContinueValsetChange`.
+pub const PENDING_VALSET: Item<Vec<(String, Decimal)>> = Item::new("pending_valset");
+
+/// The commission of each validator at the last reinvest that processed it, see `Config::max_commission_increase_alert`
+pub const VALIDATOR_COMMISSIONS: Map<&str, Decimal> = Map::new("validator_commissions");
+
+/// The time (in seconds) each validator was last detected as tombstoned by `ExecuteMsg::CheckSlash`,
+/// see `Config::tombstone_treshold`
+pub const TOMBSTONED_VALIDATORS: Map<&str, u64> = Map::new("tombstoned_validators");
+
+/// The vouchers that were not redeemed yet, by the time the unbonds they were minted for are released.
+/// Vouchers are fungible, so they are redeemed from the released batches, oldest first.
+pub const VOUCHER_BATCHES: Map<u64, Uint128> = Map::new("voucher_batches");
+
+/// Returns how many vouchers can be redeemed at `now`
+pub fn released_vouchers(storage: &dyn Storage, now: u64) -> StdResult<Uint128> {
+    VOUCHER_BATCHES
+        .range(storage, None, Some(Bound::inclusive(now)), Order::Ascending)
+        .map(|r| r.map(|(_, amount)| amount))
+        .sum()
+}
+
+/// Removes `amount` vouchers from the batches released at `now`, oldest first, and returns the native tokens
+/// they are worth, after the slashings during their unbonding. The caller has to check that enough were released.
+pub fn redeem_vouchers(
+    storage: &mut dyn Storage,
+    now: u64,
+    amount: Uint128,
+    slashings: &[Slashing],
+) -> StdResult<Uint128> {
+    let released = VOUCHER_BATCHES
+        .range(storage, None, Some(Bound::inclusive(now)), Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut left = amount;
+    let mut paid = Uint128::zero();
+    for (release_at, outstanding) in released {
+        if left.is_zero() {
+            break;
+        }
+        let taken = left.min(outstanding);
+        left -= taken;
+        if taken == outstanding {
+            VOUCHER_BATCHES.remove(storage, release_at);
+        } else {
+            VOUCHER_BATCHES.save(storage, release_at, &(outstanding - taken))?;
+        }
+        paid += Claim {
+            amount: taken,
+            release_at: Timestamp::from_seconds(release_at),
+            unbond_epoch: None,
+            sale_discount: None,
+        }
+        .slashed_amount(slashings);
+    }
+    Ok(paid)
+}
+
+/// Returns the native tokens all outstanding vouchers are worth, like [`Claim::slashed_amount`]
+pub fn voucher_claims(storage: &dyn Storage, slashings: &[Slashing]) -> StdResult<Uint128> {
+    VOUCHER_BATCHES
+        .range(storage, None, None, Order::Ascending)
+        .map(|r| {
+            let (release_at, amount) = r?;
+            Ok(Claim {
+                amount,
+                release_at: Timestamp::from_seconds(release_at),
+                unbond_epoch: None,
+                sale_discount: None,
+            }
+            .slashed_amount(slashings))
+        })
+        .sum()
+}
+
+/// Returns whether `validator` is still on probation at `now`, see `Config::new_validator_probation`.
+/// A validator without an entry in [`VALIDATOR_FIRST_SEEN`] is only new if it is not in `current_validators`.
+pub fn on_probation(
+    storage: &dyn Storage,
+    config: &Config,
+    now: u64,
+    current_validators: &[(String, Decimal)],
+    validator: &str,
+) -> StdResult<bool> {
+    if config.new_validator_probation == 0 {
+        return Ok(false);
+    }
+    let first_seen = match VALIDATOR_FIRST_SEEN.may_load(storage, validator)? {
+        Some(first_seen) => first_seen,
+        None if current_validators.iter().any(|(v, _)| v == validator) => return Ok(false),
+        None => now,
+    };
+    Ok(now < first_seen + config.new_validator_probation)
+}
+
+/// The caps used when redelegating `total_bonded` to `validators` instead of `current_validators`.
+/// These are the `max_delegations`, lowered to `Config::probation_max_weight` of `total_bonded`
+/// for the validators on probation.
+pub fn redelegation_caps(
+    storage: &dyn Storage,
+    config: &Config,
+    now: u64,
+    current_validators: &[(String, Decimal)],
+    validators: &[(String, Decimal)],
+    max_delegations: &[(String, Uint128)],
+    total_bonded: Uint128,
+) -> StdResult<Vec<(String, Uint128)>> {
+    let probation_cap = total_bonded.mul_floor(config.probation_max_weight);
+    let mut caps = vec![];
+    for (validator, _) in validators {
+        let cap = max_delegations
+            .iter()
+            .find(|(addr, _)| addr == validator)
+            .map(|(_, cap)| *cap);
+        let cap = if on_probation(storage, config, now, current_validators, validator)? {
+            Some(cap.map_or(probation_cap, |cap| cap.min(probation_cap)))
+        } else {
+            cap
+        };
+        if let Some(cap) = cap {
+            caps.push((validator.clone(), cap));
+        }
+    }
+    Ok(caps)
 }
 
 /// Investment info is fixed at instantiation, and is used to control the function of the contract
@@ -90,6 +577,159 @@ impl Config {
 pub struct StakeInfo {
     /// All tokens are bonded to these validators with the given weights
     pub validators: Vec<(String, Decimal)>,
+    /// The most that is delegated to a validator, only for validators that have a cap
+    #[serde(default)]
+    pub max_delegations: Vec<(String, Uint128)>,
+}
+
+impl StakeInfo {
+    /// Returns the cap of the given validator, if it has one
+    pub fn max_delegation(&self, validator: &str) -> Option<Uint128> {
+        self.max_delegations
+            .iter()
+            .find(|(addr, _)| addr == validator)
+            .map(|(_, cap)| *cap)
+    }
+}
+
+/// How many entries are kept in [`BONDED_JOURNAL`]
+pub const BONDED_JOURNAL_LEN: u64 = 200;
+/// Ring buffer of the last [`BONDED_JOURNAL_LEN`] changes to `total_bonded`, indexed by a sequence number
+pub const BONDED_JOURNAL: Map<u64, BondedJournalEntry> = Map::new("bonded_journal");
+/// The sequence number of the next entry in [`BONDED_JOURNAL`]
+pub const BONDED_JOURNAL_NEXT_ID: Item<u64> = Item::new("bonded_journal_next_id");
+
+/// Records a change of `total_bonded` in the journal, dropping the oldest entry once it is full.
+/// Nothing is recorded if the total did not change.
+///
+/// This must be called after saving [`BONDED`]. In debug builds, it also verifies that the new total
+/// equals the sum of [`BONDED`], to catch any accounting bugs as early as possible.
+pub fn record_bonded_change(
+    storage: &mut dyn Storage,
+    env: &Env,
+    reason: BondedChangeReason,
+    old_total: Uint128,
+    new_total: Uint128,
+) -> Result<(), ContractError> {
+    #[cfg(debug_assertions)]
+    {
+        let bonded: Uint128 = BONDED.load(storage)?.into_iter().map(|(_, b)| b).sum();
+        if bonded != new_total {
+            return Err(ContractError::InvariantViolated {
+                reason: format!(
+                    "bonded sum {} does not match total_bonded {}",
+                    bonded, new_total
+                ),
+            });
+        }
+    }
+
+    if old_total == new_total {
+        return Ok(());
+    }
+
+    let id = BONDED_JOURNAL_NEXT_ID
+        .may_load(storage)?
+        .unwrap_or_default();
+    BONDED_JOURNAL.save(
+        storage,
+        id,
+        &BondedJournalEntry {
+            id,
+            time: env.block.time.seconds(),
+            height: env.block.height,
+            reason,
+            delta: (new_total.u128() as i128 - old_total.u128() as i128).to_string(),
+            new_total,
+        },
+    )?;
+    if id >= BONDED_JOURNAL_LEN {
+        BONDED_JOURNAL.remove(storage, id - BONDED_JOURNAL_LEN);
+    }
+    BONDED_JOURNAL_NEXT_ID.save(storage, &(id + 1))?;
+    Ok(())
+}
+
+/// The time (in seconds) of the last bond of each address, used to enforce `Config::min_holding_period`
+pub const LAST_BOND: Map<&Addr, u64> = Map::new("last_bond");
+
+/// The bootstrap phase set up by `InstantiateMsg::bootstrap_min_deposit`.
+/// Deposits are escrowed in [`BOOTSTRAP_DEPOSITS`] until they add up to `min_deposit`, then all of them are minted 1:1.
+/// It is removed once all escrowed deposits were minted.
+#[cw_serde]
+pub struct Bootstrap {
+    /// The deposits needed before anything is minted
+    pub min_deposit: Uint128,
+    /// The sum of all deposits so far
+    pub deposited: Uint128,
+    /// The part of `deposited` that was minted already
+    pub minted: Uint128,
+}
+
+impl Bootstrap {
+    /// Whether the deposits reached `min_deposit`, so they can be minted
+    pub fn threshold_reached(&self) -> bool {
+        self.deposited >= self.min_deposit
+    }
+}
+
+pub const BOOTSTRAP: Item<Bootstrap> = Item::new("bootstrap");
+/// The deposits of the bootstrap phase that were not minted yet
+pub const BOOTSTRAP_DEPOSITS: Map<&Addr, Uint128> = Map::new("bootstrap_deposits");
+
+/// Fails while the hub is in its bootstrap phase, see [`Bootstrap`]
+pub fn ensure_not_bootstrapping(storage: &dyn Storage) -> Result<(), ContractError> {
+    ensure!(!BOOTSTRAP.exists(storage), ContractError::Bootstrapping {});
+    Ok(())
+}
+
+/// Returns an error if `addr` bonded less than `Config::min_holding_period` seconds ago
+pub fn ensure_holding_period_met(
+    storage: &dyn Storage,
+    config: &Config,
+    env: &Env,
+    addr: &Addr,
+) -> Result<(), ContractError> {
+    if config.min_holding_period == 0 {
+        return Ok(());
+    }
+    if let Some(last_bond) = LAST_BOND.may_load(storage, addr)? {
+        let until = last_bond + config.min_holding_period;
+        ensure!(
+            env.block.time.seconds() >= until,
+            ContractError::HoldingPeriodNotMet { until }
+        );
+    }
+    Ok(())
+}
+
+/// Whether bonding, unbonding and reinvesting are paused. Not set means not paused.
+pub const PAUSED: Item<bool> = Item::new("paused");
+
+/// Returns an error if the contract is paused.
+/// Use this in all handlers that must not run while the contract is paused.
+pub fn ensure_not_paused(storage: &dyn Storage) -> Result<(), ContractError> {
+    if PAUSED.may_load(storage)?.unwrap_or_default() {
+        return Err(ContractError::Paused {});
+    }
+    Ok(())
+}
+
+/// Returns the weight of the given validator, or an error if it is not part of the validator set.
+/// Use this for all operations that target a specific validator.
+pub fn require_validator_in_set(
+    storage: &dyn Storage,
+    validator: &str,
+) -> Result<Decimal, ContractError> {
+    STAKE_INFO
+        .load(storage)?
+        .validators
+        .into_iter()
+        .find(|(v, _)| v == validator)
+        .map(|(_, weight)| weight)
+        .ok_or_else(|| ContractError::ValidatorNotInSet {
+            validator: validator.to_string(),
+        })
 }
 
 #[cw_serde]
@@ -101,6 +741,48 @@ pub struct Unbonding {
     /// The validator we are unbonding from
     #[serde(rename = "v")]
     pub validator: String,
+    /// The time (in seconds) the undelegation was sent, `0` for unbondings stored before it was recorded
+    #[serde(rename = "s")]
+    pub started_at: u64,
+}
+
+/// The format of [`Unbonding`] before `started_at` was added
+#[cw_serde]
+pub struct UnbondingV1 {
+    #[serde(rename = "a")]
+    pub amount: Uint128,
+    #[serde(rename = "v")]
+    pub validator: String,
+}
+
+impl From<UnbondingV1> for Unbonding {
+    fn from(unbonding: UnbondingV1) -> Self {
+        Unbonding {
+            amount: unbonding.amount,
+            validator: unbonding.validator,
+            started_at: 0,
+        }
+    }
+}
+
+/// The stored unbondings of one expiration. Old formats are only upgraded when they are read,
+/// and written in the latest format the next time the entry changes.
+/// Use [`load_unbondings`], [`save_unbondings`] and [`range_unbondings`] instead of [`UNBONDING`] directly.
+#[cw_serde]
+pub enum VersionedUnbondings {
+    V1(Vec<UnbondingV1>),
+    V2(Vec<Unbonding>),
+}
+
+impl VersionedUnbondings {
+    pub fn into_latest(self) -> Vec<Unbonding> {
+        match self {
+            VersionedUnbondings::V1(unbondings) => {
+                unbondings.into_iter().map(Unbonding::from).collect()
+            }
+            VersionedUnbondings::V2(unbondings) => unbondings,
+        }
+    }
 }
 
 /// how many tokens are currently bonded to each validator.
@@ -109,7 +791,47 @@ pub const BONDED: Item<Vec<(String, Uint128)>> = Item::new("bonded");
 
 /// Store all pending unbondings, indexed by the expiration (when it will be ready).
 /// We unbond in large groups, so expect a few entries each with many validators
-pub const UNBONDING: Map<u64, Vec<Unbonding>> = Map::new("unbonding");
+pub const UNBONDING: Map<u64, VersionedUnbondings> = Map::new("unbonding");
+
+/// The undelegations of the reinvests that undelegated for the claims, indexed by their start (in seconds),
+/// with the key of their batch in [`UNBONDING`]. Unlike those, they are kept after completing,
+/// so a claim can always be linked to the first undelegation at or after its `Claim::unbond_epoch`.
+pub const UNDELEGATIONS: Map<u64, u64> = Map::new("undelegations");
+
+/// Returns the unbondings expiring at `expiration`, upgraded to the latest format
+pub fn load_unbondings(
+    storage: &dyn Storage,
+    expiration: u64,
+) -> StdResult<Option<Vec<Unbonding>>> {
+    Ok(UNBONDING
+        .may_load(storage, expiration)?
+        .map(VersionedUnbondings::into_latest))
+}
+
+/// Stores the unbondings expiring at `expiration` in the latest format
+pub fn save_unbondings(
+    storage: &mut dyn Storage,
+    expiration: u64,
+    unbondings: &[Unbonding],
+) -> StdResult<()> {
+    UNBONDING.save(
+        storage,
+        expiration,
+        &VersionedUnbondings::V2(unbondings.to_vec()),
+    )
+}
+
+/// Iterates over the unbondings by expiration, upgraded to the latest format
+pub fn range_unbondings<'a>(
+    storage: &'a dyn Storage,
+    min: Option<Bound<'a, u64>>,
+    max: Option<Bound<'a, u64>>,
+    order: Order,
+) -> impl Iterator<Item = StdResult<(u64, Vec<Unbonding>)>> + 'a {
+    UNBONDING
+        .range(storage, min, max, order)
+        .map(|r| r.map(|(expiration, unbondings)| (expiration, unbondings.into_latest())))
+}
 
 /// Deletes all unbonding items that are mature and return the number of tokens that have
 /// completed unbonding
@@ -117,42 +839,124 @@ pub fn clean_unbonding(storage: &mut dyn Storage, env: &Env) -> StdResult<Uint12
     let time = env.block.time.seconds();
 
     // clean up all old ones
-    let mature = UNBONDING
-        .range(
-            storage,
-            None,
-            Some(Bound::inclusive(time)),
-            Order::Ascending,
-        )
-        .collect::<StdResult<Vec<_>>>()?;
+    let mature = range_unbondings(
+        storage,
+        None,
+        Some(Bound::inclusive(time)),
+        Order::Ascending,
+    )
+    .collect::<StdResult<Vec<_>>>()?;
     for (key, _) in &mature {
         UNBONDING.remove(storage, *key);
     }
 
     // sum up what we got
-    let freed = mature
+    let freed: Uint128 = mature
         .into_iter()
         .map(|(_, bonds)| bonds.into_iter().map(|u| u.amount).sum::<Uint128>())
         .sum();
+
+    // remember what was freed in this block, since the tokens only arrive at the end of the block
+    // in which the unbonding completes
+    if !freed.is_zero() {
+        let released = released_in_block(storage, env)?;
+        RELEASED.save(
+            storage,
+            &Released {
+                height: env.block.height,
+                amount: released + freed,
+            },
+        )?;
+    }
     Ok(freed)
 }
 
-/// Like clean_unbonding, but designed for readonly queries. Just counts how many unbonding
-/// items are mature but doesn't delete
-pub fn count_unbonding(storage: &dyn Storage, env: &Env) -> StdResult<Uint128> {
-    let time = env.block.time.seconds();
-    let mature = UNBONDING
+/// Stores a snapshot of the given exchange rate.
+/// `slash_multiplier` is how much the rate changed due to a slashing since the last snapshot,
+/// or `1` if there was none.
+pub fn record_rate_snapshot(
+    storage: &mut dyn Storage,
+    env: &Env,
+    rate: Decimal,
+    slash_multiplier: Decimal,
+) -> StdResult<()> {
+    let slash_factor = latest_rate_snapshot(storage)?
+        .map(|snapshot| snapshot.slash_factor)
+        .unwrap_or(Decimal::one());
+    RATE_SNAPSHOTS.save(
+        storage,
+        env.block.time.seconds(),
+        &RateSnapshot {
+            rate,
+            slash_factor: slash_factor * slash_multiplier,
+        },
+    )
+}
+
+pub fn latest_rate_snapshot(storage: &dyn Storage) -> StdResult<Option<RateSnapshot>> {
+    RATE_SNAPSHOTS
+        .range(storage, None, None, Order::Descending)
+        .next()
+        .transpose()
+        .map(|snapshot| snapshot.map(|(_, snapshot)| snapshot))
+}
+
+/// Returns the latest snapshot taken at or before `time`, together with the time it was taken.
+/// Fails if `time` is before the earliest snapshot. Snapshots are never pruned, so this succeeds for any
+/// time after the first reinvest.
+pub fn rate_snapshot_at(storage: &dyn Storage, time: u64) -> StdResult<(u64, RateSnapshot)> {
+    let snapshot = RATE_SNAPSHOTS
         .range(
             storage,
             None,
             Some(Bound::inclusive(time)),
-            Order::Ascending,
+            Order::Descending,
         )
-        .map(|r| {
-            let (_, bonds) = r?;
-            Ok(bonds.into_iter().map(|u| u.amount).sum::<Uint128>())
-        })
-        .collect::<StdResult<Vec<_>>>()?;
+        .next()
+        .transpose()?;
+    match snapshot {
+        Some(snapshot) => Ok(snapshot),
+        None => {
+            let earliest = RATE_SNAPSHOTS
+                .keys(storage, None, None, Order::Ascending)
+                .next()
+                .transpose()?;
+            Err(match earliest {
+                Some(earliest) => StdError::generic_err(format!(
+                    "No exchange rate snapshot before {}, the earliest snapshot is from {}",
+                    time, earliest
+                )),
+                None => StdError::generic_err("No exchange rate snapshots available yet"),
+            })
+        }
+    }
+}
+
+/// Returns how many tokens were removed from the unbonding queue in the current block.
+/// These might not be part of the contract balance yet.
+pub fn released_in_block(storage: &dyn Storage, env: &Env) -> StdResult<Uint128> {
+    Ok(RELEASED
+        .may_load(storage)?
+        .filter(|released| released.height == env.block.height)
+        .map(|released| released.amount)
+        .unwrap_or_default())
+}
+
+/// Like clean_unbonding, but designed for readonly queries. Just counts how many unbonding
+/// items are mature but doesn't delete
+pub fn count_unbonding(storage: &dyn Storage, env: &Env) -> StdResult<Uint128> {
+    let time = env.block.time.seconds();
+    let mature = range_unbondings(
+        storage,
+        None,
+        Some(Bound::inclusive(time)),
+        Order::Ascending,
+    )
+    .map(|r| {
+        let (_, bonds) = r?;
+        Ok(bonds.into_iter().map(|u| u.amount).sum::<Uint128>())
+    })
+    .collect::<StdResult<Vec<_>>>()?;
     let freed = mature.into_iter().sum();
     Ok(freed)
 }
@@ -162,7 +966,7 @@ pub fn unbondings_expiring_between(
     start: u64,
     end: u64,
 ) -> impl Iterator<Item = StdResult<(u64, Vec<Unbonding>)>> + '_ {
-    UNBONDING.range(
+    range_unbondings(
         storage,
         Some(Bound::exclusive(start)),
         Some(Bound::exclusive(end)),
@@ -170,17 +974,28 @@ pub fn unbondings_expiring_between(
     )
 }
 
+/// Returns the first time at or after `now` at which no unbonding expires within `margin` seconds,
+/// so `ExecuteMsg::CheckSlash` can run.
+pub fn next_slash_check(storage: &dyn Storage, now: u64, margin: u64) -> StdResult<u64> {
+    let mut at = now;
+    // moving past an unbonding can bring the next one into the window, so repeat until it is free
+    while let Some(last) =
+        unbondings_expiring_between(storage, at.saturating_sub(margin), at + margin).last()
+    {
+        let (expires, _) = last?;
+        at = expires + margin;
+    }
+    Ok(at)
+}
+
 /// Only for tests. How many different unbonding epochs are there.
 pub fn unbonding_info_num_epochs(storage: &dyn Storage) -> u64 {
-    UNBONDING
-        .range(storage, None, None, Order::Ascending)
-        .count() as u64
+    range_unbondings(storage, None, None, Order::Ascending).count() as u64
 }
 
 /// Only for tests. How many different unbonding entries there are over all epochs.
 pub fn unbonding_info_total_entries(storage: &dyn Storage) -> StdResult<u64> {
-    let mature = UNBONDING
-        .range(storage, None, None, Order::Ascending)
+    let mature = range_unbondings(storage, None, None, Order::Ascending)
         .map(|r| {
             let (_, bonds) = r?;
             Ok(bonds.len() as u64)
@@ -192,7 +1007,7 @@ pub fn unbonding_info_total_entries(storage: &dyn Storage) -> StdResult<u64> {
 
 /// Supply is dynamic and tracks the current supply of tokens in various states
 /// Locked value = bonded + unbonding - claims + deps.querier.balance()
-/// Promised shares = issued
+/// Promised shares = issued (including the deferred claim shares)
 /// Ratio = Locked / promised
 /// All actions besides reinvest should keep ratio
 #[cw_serde]
@@ -209,6 +1024,10 @@ pub struct Supply {
     /// the total amount of tokens that are currently unbonding
     /// this should always be equal to `supply.unbonding.into_iter().map(|u| u.amount).sum()`
     pub total_unbonding: Uint128,
+    /// how many of the issued derivative tokens are locked in deferred claims.
+    /// They are still part of `issued` and only converted to native tokens when claimed.
+    #[serde(default)]
+    pub deferred_claim_shares: Uint128,
 }
 
 impl Supply {
@@ -240,12 +1059,15 @@ impl Supply {
     ) -> StdResult<CleanedSupply> {
         let freed = clean_unbonding(storage, env)?;
         self.total_unbonding -= freed;
-        Ok(CleanedSupply(self))
+        let boost_principal = BOOST_PRINCIPAL.may_load(storage)?.unwrap_or_default();
+        let deferred_rewards = DEFERRED_REWARDS.may_load(storage)?.unwrap_or_default();
+        Ok(CleanedSupply(self, boost_principal, deferred_rewards))
     }
 }
 
 /// Wrapper around [`Supply`] that ensures old unbonding queue entries are cleaned up before updating the delegations.
-pub struct CleanedSupply(Supply);
+/// It also holds the [`BOOST_PRINCIPAL`] and the [`DEFERRED_REWARDS`], which are excluded from the assets.
+pub struct CleanedSupply(Supply, Uint128, Uint128);
 
 impl Deref for CleanedSupply {
     type Target = Supply;
@@ -266,7 +1088,9 @@ impl CleanedSupply {
         let mut supply = SUPPLY.load(storage)?;
         let freed = clean_unbonding(storage, env)?;
         supply.total_unbonding -= freed;
-        Ok(CleanedSupply(supply))
+        let boost_principal = BOOST_PRINCIPAL.may_load(storage)?.unwrap_or_default();
+        let deferred_rewards = DEFERRED_REWARDS.may_load(storage)?.unwrap_or_default();
+        Ok(CleanedSupply(supply, boost_principal, deferred_rewards))
     }
 
     /// Updates unbonding count but doesn't delete state (meant for queries)
@@ -274,13 +1098,18 @@ impl CleanedSupply {
         let mut supply = SUPPLY.load(storage)?;
         let freed = count_unbonding(storage, env)?;
         supply.total_unbonding -= freed;
-        Ok(CleanedSupply(supply))
+        let boost_principal = BOOST_PRINCIPAL.may_load(storage)?.unwrap_or_default();
+        let deferred_rewards = DEFERRED_REWARDS.may_load(storage)?.unwrap_or_default();
+        Ok(CleanedSupply(supply, boost_principal, deferred_rewards))
     }
 
     /// Returns the ratio of TVL / Outstanding shares.
     /// This should be maintained on deposits and withdrawals and only
     /// modify (increase) on reinvest.
     /// You must pass in the current balance of the contract (Bank balance)
+    ///
+    /// This is only meant for displaying the rate. Use [`Self::shares_for_tokens`] and
+    /// [`Self::tokens_for_shares`] to calculate actual amounts, since they don't round the ratio.
     pub fn tokens_per_share(&self, balance: Uint128) -> Decimal {
         // ensure that we return 1 at the beginning (when no ratio has been set)
         if self.issued.is_zero() {
@@ -290,21 +1119,91 @@ impl CleanedSupply {
         }
     }
 
-    /// This is 1/tokens_per_share, implemented here to reduce rounding
+    /// Returns the ratio of outstanding shares / TVL, the inverse of [`Self::tokens_per_share`].
+    /// Like that one, this is only meant for displaying the rate.
     pub fn shares_per_token(&self, balance: Uint128) -> Decimal {
         let assets = self.assets(balance);
-        // ensure that we return 1 at the beginning (when no ratio has been set)
-        if assets.is_zero() {
+        // same as `shares_for_tokens`, 1:1 at the beginning
+        if self.issued.is_zero() || assets.is_zero() {
             Decimal::one()
         } else {
             Decimal::from_ratio(self.issued, assets)
         }
     }
 
+    /// Returns how many lsd tokens the given `amount` of native tokens is worth, rounded down.
+    /// This calculates `amount * issued / assets` with a 256 bit intermediate value.
+    /// You must pass in the balance of the contract *before* the native tokens were received.
+    pub fn shares_for_tokens(&self, amount: Uint128, balance: Uint128) -> Uint128 {
+        let assets = self.assets(balance);
+        // ensure that we issue 1:1 at the beginning (when no ratio has been set)
+        if self.issued.is_zero() || assets.is_zero() {
+            amount
+        } else {
+            amount.multiply_ratio(self.issued, assets)
+        }
+    }
+
+    /// Returns how many native tokens the given `amount` of lsd tokens is worth, rounded down.
+    /// This calculates `amount * assets / issued` with a 256 bit intermediate value.
+    /// You must pass in the current balance of the contract (Bank balance)
+    pub fn tokens_for_shares(&self, amount: Uint128, balance: Uint128) -> Uint128 {
+        // ensure that we return 1:1 at the beginning (when no ratio has been set)
+        if self.issued.is_zero() {
+            amount
+        } else {
+            amount.multiply_ratio(self.assets(balance), self.issued)
+        }
+    }
+
+    /// Returns how many lsd tokens have to be unbonded for a claim of at least `claim` native tokens,
+    /// the inverse of [`Self::unbond_with_fee`]. Both steps round up, so the claim is never smaller.
+    /// You must pass in the current balance of the contract (Bank balance)
+    pub fn shares_for_claim(&self, claim: Uint128, balance: Uint128, fee: Decimal) -> Uint128 {
+        let native = claim.div_ceil(Decimal::one() - fee);
+        let assets = self.assets(balance);
+        // same as `tokens_for_shares`, 1:1 at the beginning
+        if self.issued.is_zero() || assets.is_zero() {
+            native
+        } else {
+            native.mul_ceil((self.issued, assets))
+        }
+    }
+
     /// Returns the total amount of native tokens that are backing all of the lsd tokens.
+    /// This does not include the boost principal, which belongs to the treasury.
+    /// You must pass in the current balance of the contract (Bank balance)
     #[inline]
-    fn assets(&self, balance: Uint128) -> Uint128 {
-        self.total_bonded + self.total_unbonding + balance - self.claims
+    pub fn assets(&self, balance: Uint128) -> Uint128 {
+        (self.total_bonded + self.total_unbonding + balance - self.claims)
+            .saturating_sub(self.1 + self.2)
+    }
+
+    /// Returns the native tokens deposited by the owner through `ExecuteMsg::DepositBoost`
+    #[inline]
+    pub fn boost_principal(&self) -> Uint128 {
+        self.1
+    }
+
+    /// Returns the rewards that were held back by `Config::max_rate_growth_per_epoch`
+    #[inline]
+    pub fn deferred_rewards(&self) -> Uint128 {
+        self.2
+    }
+
+    /// Stores the given `amount` as [`DEFERRED_REWARDS`] and excludes it from the assets from now on
+    pub fn set_deferred_rewards(
+        &mut self,
+        storage: &mut dyn Storage,
+        amount: Uint128,
+    ) -> StdResult<()> {
+        if amount.is_zero() {
+            DEFERRED_REWARDS.remove(storage);
+        } else {
+            DEFERRED_REWARDS.save(storage, &amount)?;
+        }
+        self.2 = amount;
+        Ok(())
     }
 
     /// Removes the given `amount` from the issued tokens and adds the corresponding native amount to claims.
@@ -312,11 +1211,23 @@ impl CleanedSupply {
     /// The amount parameter is denominated in lsd tokens.
     /// Note that this only updates the supply. Make sure to create a claim for the user as well.
     pub fn unbond(&mut self, amount: Uint128, balance: Uint128) -> Uint128 {
-        let native = amount.mul_floor(self.tokens_per_share(balance));
+        self.unbond_with_fee(amount, balance, Decimal::zero()).0
+    }
+
+    /// Same as [`Self::unbond`], but only `1 - fee` of the native amount is added to the claims.
+    /// The fee stays in the assets. Returns the native claim amount and the fee.
+    pub fn unbond_with_fee(
+        &mut self,
+        amount: Uint128,
+        balance: Uint128,
+        fee: Decimal,
+    ) -> (Uint128, Uint128) {
+        let native = self.tokens_for_shares(amount, balance);
+        let claim = native.mul_floor(Decimal::one() - fee);
         self.issued -= amount;
-        self.claims += native;
+        self.claims += claim;
 
-        native
+        (claim, native - claim)
     }
 }
 
@@ -326,6 +1237,24 @@ pub struct TmpState {
     pub balance: Uint128,
 }
 
+#[cw_serde]
+pub struct RateSnapshot {
+    /// The exchange rate at the time of the snapshot
+    #[serde(rename = "r")]
+    pub rate: Decimal,
+    /// The product of how much the exchange rate changed in all slashings up to this snapshot
+    #[serde(rename = "s")]
+    pub slash_factor: Decimal,
+}
+
+#[cw_serde]
+pub struct Released {
+    #[serde(rename = "h")]
+    pub height: u64,
+    #[serde(rename = "a")]
+    pub amount: Uint128,
+}
+
 #[cw_serde]
 pub struct Slashing {
     pub start: u64,
@@ -339,9 +1268,234 @@ pub const STAKE_INFO: Item<StakeInfo> = Item::new("stake_info");
 /// This item is used to store some temporary state between the message initiating the reinvest process
 /// and the reply we get after withdrawing the rewards.
 pub const TMP_STATE: Item<TmpState> = Item::new("tmp_state");
+/// Exchange rate snapshots, taken on every reinvest and every detected slashing, indexed by time (in seconds)
+pub const RATE_SNAPSHOTS: Map<u64, RateSnapshot> = Map::new("rate_snapshots");
+
+#[cw_serde]
+pub struct ReinvestProgress {
+    /// The `next_epoch` of the config when the partial reinvest started
+    pub epoch: u64,
+    /// The validators whose rewards were already withdrawn in this epoch
+    pub processed: Vec<String>,
+}
+
+/// Tracks a reinvest that is split over multiple calls of `ExecuteMsg::Reinvest { validators }`.
+/// Entries from earlier epochs are ignored.
+pub const REINVEST_PROGRESS: Item<ReinvestProgress> = Item::new("reinvest_progress");
+
+/// Native tokens deposited by the owner to boost the staking rewards of the holders.
+/// They are delegated like everything else, but excluded from the assets, so they don't change the exchange rate
+/// and can be withdrawn by the owner later.
+pub const BOOST_PRINCIPAL: Item<Uint128> = Item::new("boost_principal");
+
+/// Rewards that were withdrawn, but not added to the assets yet, because they would have grown the exchange rate
+/// by more than `Config::max_rate_growth_per_epoch`. They are delegated like everything else and released
+/// by the following reinvests under the same cap.
+pub const DEFERRED_REWARDS: Item<Uint128> = Item::new("deferred_rewards");
+
+/// Rewards of reinvests that were skipped because of `Config::min_reinvest_amount`.
+/// The commission on them is taken with the next reinvest that is not skipped.
+pub const SKIPPED_REWARDS: Item<Uint128> = Item::new("skipped_rewards");
+
+/// The number of reinvests since the surplus was last delegated, see `Config::delegate_every_n_epochs`
+pub const REINVESTS_WITHOUT_DELEGATION: Item<u64> = Item::new("reinvests_without_delegation");
+
+/// Tokens of another denom than the bond denom that are distributed to the holders as they exit
+#[cw_serde]
+pub struct ExternalReward {
+    /// The amount that is left to distribute
+    pub amount: Uint128,
+    /// The lsd tokens that were issued when the reward was registered and did not exit yet
+    pub shares: Uint128,
+    /// When the reward was registered, in seconds
+    pub registered_at: u64,
+}
+
+impl ExternalReward {
+    /// Returns the part of the remaining reward `shares` many lsd tokens are entitled to
+    pub fn share_of(&self, shares: Uint128) -> Uint128 {
+        if self.shares.is_zero() {
+            return Uint128::zero();
+        }
+        self.amount
+            .multiply_ratio(shares.min(self.shares), self.shares)
+    }
+}
+
+/// External rewards by denom, registered by the owner using `ExecuteMsg::RegisterExternalReward`.
+/// There is no accrual over time, every lsd token that exits (by unbonding or claiming a deferred claim)
+/// simply takes its part of what is left, until the number of lsd tokens issued at registration have exited.
+pub const EXTERNAL_REWARDS: Map<&str, ExternalReward> = Map::new("external_rewards");
+
+/// Lsd tokens that were bonded after an external reward was registered
+#[cw_serde]
+pub struct FreshShares {
+    /// The `ExternalReward::registered_at` of the registration the shares were bonded after
+    pub registered_at: u64,
+    pub shares: Uint128,
+}
+
+/// The lsd tokens each address bonded after the external reward of the denom was registered.
+/// They are not part of the snapshot, so they are the first to exit and get nothing.
+/// Entries of an earlier registration of the denom are stale and ignored.
+pub const EXTERNAL_REWARD_FRESH_SHARES: Map<(&str, &Addr), FreshShares> =
+    Map::new("external_reward_fresh_shares");
+
+/// Returns the lsd tokens `holder` bonded after `reward` of `denom` was registered
+pub fn fresh_shares(
+    storage: &dyn Storage,
+    denom: &str,
+    reward: &ExternalReward,
+    holder: &Addr,
+) -> StdResult<Uint128> {
+    Ok(EXTERNAL_REWARD_FRESH_SHARES
+        .may_load(storage, (denom, holder))?
+        .filter(|fresh| fresh.registered_at == reward.registered_at)
+        .map(|fresh| fresh.shares)
+        .unwrap_or_default())
+}
+
+/// Remembers that `shares` were bonded for `holder` after each of the registered external rewards
+pub fn record_fresh_shares(
+    storage: &mut dyn Storage,
+    holder: &Addr,
+    shares: Uint128,
+) -> StdResult<()> {
+    let rewards = EXTERNAL_REWARDS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (denom, reward) in rewards {
+        let fresh = fresh_shares(storage, &denom, &reward, holder)? + shares;
+        EXTERNAL_REWARD_FRESH_SHARES.save(
+            storage,
+            (denom.as_str(), holder),
+            &FreshShares {
+                registered_at: reward.registered_at,
+                shares: fresh,
+            },
+        )?;
+    }
+    Ok(())
+}
+
+/// Removes the part of each external reward the `shares` exiting from `holder` are entitled to and returns it.
+/// The shares `holder` bonded after the registration exit first and are not entitled to anything.
+pub fn take_external_rewards(
+    storage: &mut dyn Storage,
+    holder: &Addr,
+    shares: Uint128,
+) -> StdResult<Vec<Coin>> {
+    let rewards = EXTERNAL_REWARDS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut payout = vec![];
+    for (denom, mut reward) in rewards {
+        let fresh = fresh_shares(storage, &denom, &reward, holder)?;
+        let exiting_fresh = shares.min(fresh);
+        if !exiting_fresh.is_zero() {
+            EXTERNAL_REWARD_FRESH_SHARES.save(
+                storage,
+                (denom.as_str(), holder),
+                &FreshShares {
+                    registered_at: reward.registered_at,
+                    shares: fresh - exiting_fresh,
+                },
+            )?;
+        }
+        let entitled_shares = shares - exiting_fresh;
+        if entitled_shares.is_zero() {
+            continue;
+        }
+
+        let amount = reward.share_of(entitled_shares);
+        reward.amount -= amount;
+        reward.shares = reward.shares.saturating_sub(entitled_shares);
+        if reward.shares.is_zero() {
+            EXTERNAL_REWARDS.remove(storage, &denom);
+        } else {
+            EXTERNAL_REWARDS.save(storage, &denom, &reward)?;
+        }
+        if !amount.is_zero() {
+            payout.push(Coin { denom, amount });
+        }
+    }
+    Ok(payout)
+}
+
+/// The amount of tokens removed from the unbonding queue in the block with the given height.
+/// See [`released_in_block`].
+pub const RELEASED: Item<Released> = Item::new("released");
 pub const CLAIMS: Claims = Claims::new("claims");
+/// The address that may claim on behalf of an owner, set with `ExecuteMsg::SetClaimRecipient`
+pub const CLAIM_RECIPIENTS: Map<&Addr, Addr> = Map::new("claim_recipients");
+/// Contracts that can buy the claims offered with `ReceiveMsg::UnbondForSale`, see `ExecuteMsg::RegisterClaimBuyer`
+pub const CLAIM_BUYERS: Map<&Addr, Empty> = Map::new("claim_buyers");
+/// Claims created by `ReceiveMsg::UnbondDeferredRate`, denominated in lsd shares instead of native tokens
+pub const DEFERRED_CLAIMS: Claims = Claims::new("deferred_claims");
 pub const SLASHINGS: Item<Vec<Slashing>> = Item::new("slashings");
 
+/// Commission lsd tokens held by the hub until they vested, keyed by their creation time (in seconds)
+pub const COMMISSION_VESTING: Map<u64, VestingTranche> = Map::new("commission_vesting");
+
+#[cw_serde]
+pub struct VestingTranche {
+    /// The amount of lsd tokens in this tranche
+    pub amount: Uint128,
+    /// The amount that was already sent to the treasury
+    pub claimed: Uint128,
+    /// The schedule at the time this tranche was created
+    pub vesting: CommissionVesting,
+}
+
+impl VestingTranche {
+    /// Returns how much of the tranche created at `created` is vested at `now`, including the claimed tokens
+    pub fn vested(&self, created: u64, now: u64) -> Uint128 {
+        let elapsed = now.saturating_sub(created);
+        if elapsed < self.vesting.cliff_seconds {
+            Uint128::zero()
+        } else if elapsed >= self.vesting.duration_seconds {
+            self.amount
+        } else {
+            self.amount
+                .multiply_ratio(elapsed, self.vesting.duration_seconds)
+        }
+    }
+}
+
+/// How many reinvest timestamps are kept in [`REINVEST_HISTORY`]
+pub const REINVEST_HISTORY_LEN: usize = 10;
+/// Ring buffer of the timestamps (in seconds) of the last [`REINVEST_HISTORY_LEN`] reinvests, oldest first
+pub const REINVEST_HISTORY: Item<Vec<u64>> = Item::new("reinvest_history");
+
+/// Adds the given timestamp to the reinvest history, dropping the oldest entries once it is full
+pub fn record_reinvest(storage: &mut dyn Storage, time: u64) -> StdResult<()> {
+    let mut history = REINVEST_HISTORY.may_load(storage)?.unwrap_or_default();
+    if history.len() >= REINVEST_HISTORY_LEN {
+        history.drain(..=history.len() - REINVEST_HISTORY_LEN);
+    }
+    history.push(time);
+    REINVEST_HISTORY.save(storage, &history)
+}
+
+/// Returns the timestamp of the last reinvest, if there was one
+pub fn last_reinvest(storage: &dyn Storage) -> StdResult<Option<u64>> {
+    Ok(REINVEST_HISTORY
+        .may_load(storage)?
+        .and_then(|history| history.last().copied()))
+}
+
+/// Returns the average number of seconds between the reinvests in the given history,
+/// or `None` if there were less than two reinvests
+pub fn average_reinvest_spacing(history: &[u64]) -> Option<u64> {
+    match (history.first(), history.last()) {
+        (Some(first), Some(last)) if history.len() > 1 => {
+            Some((last - first) / (history.len() as u64 - 1))
+        }
+        _ => None,
+    }
+}
+
 /// Divides `numerator` by `denominator` and rounds up the result.
 /// This is needed because [`std`]'s implementation is currently unstable.
 /// ```rust
@@ -372,3 +1526,550 @@ pub fn div_ceil(numerator: u64, denominator: u64) -> u64 {
         d
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::Uint256;
+
+    use super::*;
+
+    /// Bank balance of the simulated hub contract together with its supply
+    struct Hub {
+        supply: CleanedSupply,
+        balance: Uint128,
+    }
+
+    impl Hub {
+        fn new(issued: u128, total_bonded: u128, balance: u128) -> Self {
+            Hub {
+                supply: CleanedSupply(
+                    Supply {
+                        bond_denom: "FUN".to_string(),
+                        issued: issued.into(),
+                        total_bonded: total_bonded.into(),
+                        claims: Uint128::zero(),
+                        total_unbonding: Uint128::zero(),
+                        deferred_claim_shares: Uint128::zero(),
+                    },
+                    Uint128::zero(),
+                    Uint128::zero(),
+                ),
+                balance: balance.into(),
+            }
+        }
+
+        /// Adds claims and unbonding tokens, which are part of the rate as well
+        fn with_unbonding(mut self, claims: u128, total_unbonding: u128) -> Self {
+            self.supply.claims = claims.into();
+            self.supply.total_unbonding = total_unbonding.into();
+            self
+        }
+
+        fn bond(&mut self, amount: u128) -> Uint128 {
+            let amount = Uint128::new(amount);
+            let issue = self.supply.shares_for_tokens(amount, self.balance);
+            self.balance += amount;
+            self.supply.issued += issue;
+            issue
+        }
+
+        fn unbond(&mut self, amount: Uint128) -> Uint128 {
+            self.supply.unbond(amount, self.balance)
+        }
+
+        fn assets(&self) -> Uint128 {
+            self.supply.assets(self.balance)
+        }
+
+        /// Panics if the assets per share decreased compared to the given previous state
+        #[track_caller]
+        fn assert_per_share_not_decreased(&self, old_assets: Uint128, old_issued: Uint128) {
+            // compare `old_assets / old_issued <= assets / issued` without rounding
+            let lhs = Uint256::from(old_assets) * Uint256::from(self.supply.issued);
+            let rhs = Uint256::from(self.assets()) * Uint256::from(old_issued);
+            assert!(
+                lhs <= rhs,
+                "assets per share decreased from {}/{} to {}/{}",
+                old_assets,
+                old_issued,
+                self.assets(),
+                self.supply.issued
+            );
+        }
+    }
+
+    fn config_with_discount(liquidity_discount: Decimal) -> Config {
+        Config {
+            owner: Addr::unchecked("owner"),
+            token_contract: Addr::unchecked("token"),
+            treasury: Addr::unchecked("treasury"),
+            commission: Decimal::percent(10),
+            epoch_period: 23 * 60 * 60,
+            unbond_period: 28 * 24 * 60 * 60,
+            max_concurrent_unbondings: 7,
+            next_epoch: 0,
+            next_unbond: 0,
+            tombstone_treshold: Decimal::percent(3),
+            slashing_safety_margin: 10 * 60,
+            min_reinvest_spacing: None,
+            commission_vesting: None,
+            min_holding_period: 0,
+            min_reinvest_amount: Uint128::zero(),
+            max_discount_change_per_update: DEFAULT_MAX_DISCOUNT_CHANGE_PER_UPDATE,
+            min_discount_update_interval: DEFAULT_MIN_DISCOUNT_UPDATE_INTERVAL,
+            last_discount_update: 0,
+            surplus_strategy: SurplusStrategy::TargetWeights,
+            delegations_suspended: false,
+            reinvest_hook: None,
+            new_validator_probation: 0,
+            probation_max_weight: Decimal::zero(),
+            bond_denom_decimals: DEFAULT_BOND_DENOM_DECIMALS,
+            expected_bond_decimals: None,
+            max_commission_increase_alert: DEFAULT_MAX_COMMISSION_INCREASE_ALERT,
+            auto_zero_weight_on_spike: false,
+            delegate_every_n_epochs: 0,
+            strict_supply_check: false,
+            unbond_fee: Decimal::zero(),
+            last_unbond_fee_update: 0,
+            issuance_cap: None,
+            voucher_contract: None,
+            rebalance_on_reinvest: false,
+            operator: None,
+            redelegation_hops: false,
+            max_rate_growth_per_epoch: None,
+            min_delegation: DEFAULT_MIN_DELEGATION,
+            valoper_prefix: String::new(),
+            min_blocks_per_epoch: None,
+            last_epoch_height: 0,
+            last_unbond_height: 0,
+            liquidity_discount,
+        }
+    }
+
+    #[test]
+    fn target_value_rounds_down() {
+        let rates = [
+            Decimal::one(),
+            Decimal::percent(50),
+            Decimal::permille(1),
+            Decimal::from_ratio(1u128, 3u128),
+            Decimal::from_ratio(2u128, 3_000_000u128),
+            Decimal::raw(7),
+            Decimal::raw(1),
+        ];
+        let discounts = [
+            Decimal::zero(),
+            Decimal::percent(4),
+            Decimal::from_ratio(1u128, 3u128),
+            Decimal::raw(1),
+        ];
+
+        for discount in discounts {
+            let config = config_with_discount(discount);
+            for rate in rates {
+                let target = config.target_value(rate);
+
+                // exact value is `rate * (1 - discount)`, calculated without rounding
+                let one = Uint256::from(Decimal::one().atomics());
+                let exact_numerator =
+                    Uint256::from(rate.atomics()) * (one - Uint256::from(discount.atomics()));
+                let target_numerator = Uint256::from(target.atomics()) * one;
+                assert!(
+                    target_numerator <= exact_numerator,
+                    "target value {} for rate {} and discount {} rounded up",
+                    target,
+                    rate,
+                    discount
+                );
+                // and it should not lose more than the last digit
+                assert!(exact_numerator - target_numerator < one);
+            }
+        }
+
+        assert_eq!(
+            config_with_discount(Decimal::percent(4)).target_value(Decimal::percent(50)),
+            Decimal::percent(48)
+        );
+        assert_eq!(
+            config_with_discount(Decimal::percent(4)).target_value(Decimal::permille(1)),
+            Decimal::raw(960_000_000_000_000)
+        );
+        // tiny rates round down to zero instead of up
+        assert_eq!(
+            config_with_discount(Decimal::percent(4)).target_value(Decimal::raw(1)),
+            Decimal::zero()
+        );
+    }
+
+    #[test]
+    fn unbond_fee_stays_in_the_assets() {
+        let mut hub = Hub::new(0, 0, 0);
+        hub.bond(2_000);
+        let (claim, fee) =
+            hub.supply
+                .unbond_with_fee(Uint128::new(1_000), hub.balance, Decimal::percent(1));
+        assert_eq!(claim, Uint128::new(990));
+        assert_eq!(fee, Uint128::new(10));
+        assert_eq!(hub.assets(), Uint128::new(1_010));
+        assert_eq!(
+            hub.supply.tokens_per_share(hub.balance),
+            Decimal::permille(1010)
+        );
+    }
+
+    #[test]
+    fn initial_rate_is_one() {
+        let mut hub = Hub::new(0, 0, 0);
+        assert_eq!(hub.bond(1_000), Uint128::new(1_000));
+        assert_eq!(hub.unbond(Uint128::new(400)), Uint128::new(400));
+        assert_eq!(
+            hub.supply.tokens_per_share(hub.balance),
+            Decimal::one(),
+            "rate should not change without rewards"
+        );
+    }
+
+    #[test]
+    fn large_supply_conversion_is_exact() {
+        // the Decimal based calculation used to lose a few units here
+        let mut hub = Hub::new(3_000_000_000_000_000_001, 3_999_999_999_999_999_999, 0);
+        let issued = hub.bond(2_999_999_999_999_999_999);
+        assert_eq!(
+            issued,
+            Uint128::new(2_999_999_999_999_999_999)
+                .multiply_ratio(3_000_000_000_000_000_001u128, 3_999_999_999_999_999_999u128)
+        );
+    }
+
+    /// Different rates, with and without claims and unbondings
+    fn rate_setups() -> Vec<Hub> {
+        vec![
+            Hub::new(1_000_000, 1_000_000, 0),
+            Hub::new(1_000_000, 1_050_000, 3).with_unbonding(20_003, 20_000),
+            Hub::new(999_999_999_999, 1_333_333_333_333, 17).with_unbonding(7, 0),
+            Hub::new(3_000_000, 1_000_001, 0).with_unbonding(0, 1_000),
+            Hub::new(7, 1_000_000_000_000, 3),
+        ]
+    }
+
+    #[test]
+    fn zero_issued_rate_is_one() {
+        // even with assets left over, the first bond is 1:1
+        for hub in [
+            Hub::new(0, 0, 0),
+            Hub::new(0, 0, 500),
+            Hub::new(0, 1_000, 7).with_unbonding(300, 200),
+        ] {
+            assert_eq!(hub.supply.tokens_per_share(hub.balance), Decimal::one());
+            assert_eq!(hub.supply.shares_per_token(hub.balance), Decimal::one());
+            assert_eq!(
+                hub.supply
+                    .shares_for_tokens(Uint128::new(1_234), hub.balance),
+                Uint128::new(1_234)
+            );
+            assert_eq!(
+                hub.supply
+                    .tokens_for_shares(Uint128::new(1_234), hub.balance),
+                Uint128::new(1_234)
+            );
+        }
+    }
+
+    #[test]
+    fn bond_then_unbond_round_trips_modulo_rounding() {
+        let amounts = [1u128, 2, 999, 1_000_001, 123_456_789_012];
+        for setup in rate_setups() {
+            let (assets, issued) = (setup.assets(), setup.supply.issued);
+            // one share is worth at most this much, so both roundings lose less than that together
+            let max_loss = Uint128::new(assets.u128().div_ceil(issued.u128()) + 1);
+
+            for amount in amounts {
+                let mut hub = Hub {
+                    supply: CleanedSupply((*setup.supply).clone(), setup.supply.1, setup.supply.2),
+                    balance: setup.balance,
+                };
+                let minted = hub.bond(amount);
+                let native = hub.unbond(minted);
+                let amount = Uint128::new(amount);
+                assert!(native <= amount, "got back {native} for {amount}");
+                assert!(
+                    amount - native <= max_loss,
+                    "lost {} of {amount}, at most {max_loss} expected",
+                    amount - native
+                );
+                // nothing but the rounding is left from the round trip
+                assert_eq!(hub.supply.issued, issued);
+                assert_eq!(hub.assets(), assets + amount - native);
+            }
+        }
+    }
+
+    #[test]
+    fn shares_for_claim_is_the_smallest_covering_amount() {
+        let claim_for = |hub: &Hub, shares: Uint128, fee| {
+            let mut supply = CleanedSupply((*hub.supply).clone(), hub.supply.1, hub.supply.2);
+            supply.unbond_with_fee(shares, hub.balance, fee).0
+        };
+        for hub in rate_setups() {
+            for fee in [Decimal::zero(), Decimal::permille(15)] {
+                for target in [1u128, 999, 100_003] {
+                    let target = Uint128::new(target);
+                    let shares = hub.supply.shares_for_claim(target, hub.balance, fee);
+                    assert!(claim_for(&hub, shares, fee) >= target);
+                    assert!(claim_for(&hub, shares - Uint128::one(), fee) < target);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn shares_per_token_inverts_tokens_per_share() {
+        let one = Uint256::from(Decimal::one().atomics());
+        for hub in rate_setups() {
+            let tokens_per_share = hub.supply.tokens_per_share(hub.balance);
+            let shares_per_token = hub.supply.shares_per_token(hub.balance);
+            let (assets, issued) = (
+                Uint256::from(hub.assets()),
+                Uint256::from(hub.supply.issued),
+            );
+
+            // both are the exact ratio, rounded down to the last digit
+            let t = Uint256::from(tokens_per_share.atomics());
+            assert!(t * issued <= assets * one && assets * one < (t + Uint256::one()) * issued);
+            let s = Uint256::from(shares_per_token.atomics());
+            assert!(s * assets <= issued * one && issued * one < (s + Uint256::one()) * assets);
+
+            // so their product is one, minus what the two roundings lost
+            let product = t * s;
+            let unit = one * one;
+            assert!(
+                product <= unit,
+                "{tokens_per_share} * {shares_per_token} > 1"
+            );
+            assert!(
+                product + t + s >= unit,
+                "{tokens_per_share} * {shares_per_token} is off by more than one digit each"
+            );
+        }
+    }
+
+    #[test]
+    fn repeated_bond_unbond_never_decreases_share_value() {
+        let amounts: [u128; 10] = [
+            1,
+            2,
+            3,
+            7,
+            999,
+            1_000_001,
+            333_333_333,
+            999_999_999_999,
+            1_000_000_000_007,
+            123_456_789_012_345,
+        ];
+        let setups: [(u128, u128, u128); 4] = [
+            (1_000_000, 1_000_000, 0),
+            (1_000_000_000_000_007, 1_333_333_333_333_333, 17),
+            (3_000_000_000_000_001, 9_999_999_999_999_999, 1),
+            (7, 1_000_000_000_000_000_000, 3),
+        ];
+
+        for (issued, total_bonded, balance) in setups {
+            let mut hub = Hub::new(issued, total_bonded, balance);
+
+            for i in 0..1_000 {
+                let amount = amounts[i % amounts.len()];
+
+                let (old_assets, old_issued) = (hub.assets(), hub.supply.issued);
+                let minted = hub.bond(amount);
+                hub.assert_per_share_not_decreased(old_assets, old_issued);
+
+                let (old_assets, old_issued) = (hub.assets(), hub.supply.issued);
+                let native = hub.unbond(minted);
+                assert!(
+                    native.u128() <= amount,
+                    "should never get back more than was bonded"
+                );
+                hub.assert_per_share_not_decreased(old_assets, old_issued);
+
+                // also unbond some of the pre-existing shares from time to time
+                if i % 3 == 0 && hub.supply.issued > Uint128::new(amount) {
+                    let (old_assets, old_issued) = (hub.assets(), hub.supply.issued);
+                    hub.unbond(Uint128::new(amount));
+                    hub.assert_per_share_not_decreased(old_assets, old_issued);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn paused_flag_is_enforced() {
+        let mut storage = cosmwasm_std::testing::MockStorage::new();
+        // not set means not paused
+        ensure_not_paused(&storage).unwrap();
+
+        PAUSED.save(&mut storage, &true).unwrap();
+        assert_eq!(
+            ensure_not_paused(&storage).unwrap_err(),
+            ContractError::Paused {}
+        );
+
+        PAUSED.save(&mut storage, &false).unwrap();
+        ensure_not_paused(&storage).unwrap();
+    }
+
+    #[test]
+    fn validator_must_be_in_set() {
+        let mut storage = cosmwasm_std::testing::MockStorage::new();
+        STAKE_INFO
+            .save(
+                &mut storage,
+                &StakeInfo {
+                    validators: vec![
+                        ("val1".to_string(), Decimal::percent(100)),
+                        ("val2".to_string(), Decimal::zero()),
+                    ],
+                    max_delegations: vec![],
+                },
+            )
+            .unwrap();
+
+        assert_eq!(
+            require_validator_in_set(&storage, "val1").unwrap(),
+            Decimal::percent(100)
+        );
+        // a validator with zero weight is still part of the set
+        assert_eq!(
+            require_validator_in_set(&storage, "val2").unwrap(),
+            Decimal::zero()
+        );
+        assert_eq!(
+            require_validator_in_set(&storage, "val3").unwrap_err(),
+            ContractError::ValidatorNotInSet {
+                validator: "val3".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn reinvest_history_keeps_last_entries() {
+        let mut storage = cosmwasm_std::testing::MockStorage::new();
+        assert_eq!(last_reinvest(&storage).unwrap(), None);
+
+        for i in 0..(REINVEST_HISTORY_LEN as u64 + 5) {
+            record_reinvest(&mut storage, 1000 + i * 3600).unwrap();
+        }
+
+        let history = REINVEST_HISTORY.load(&storage).unwrap();
+        assert_eq!(history.len(), REINVEST_HISTORY_LEN);
+        // oldest entries were dropped
+        assert_eq!(history[0], 1000 + 5 * 3600);
+        assert_eq!(
+            last_reinvest(&storage).unwrap(),
+            Some(1000 + (REINVEST_HISTORY_LEN as u64 + 4) * 3600)
+        );
+        assert_eq!(average_reinvest_spacing(&history), Some(3600));
+    }
+
+    #[test]
+    fn average_reinvest_spacing_needs_two_entries() {
+        assert_eq!(average_reinvest_spacing(&[]), None);
+        assert_eq!(average_reinvest_spacing(&[100]), None);
+        assert_eq!(average_reinvest_spacing(&[100, 200, 400]), Some(150));
+    }
+    #[test]
+    fn released_unbondings_are_tracked_per_block() {
+        let mut storage = cosmwasm_std::testing::MockStorage::new();
+        let mut env = cosmwasm_std::testing::mock_env();
+        let unbonding = |amount: u128| {
+            vec![Unbonding {
+                amount: amount.into(),
+                validator: "val1".to_string(),
+                started_at: 0,
+            }]
+        };
+        let now = env.block.time.seconds();
+        save_unbondings(&mut storage, now - 10, &unbonding(100)).unwrap();
+        save_unbondings(&mut storage, now, &unbonding(50)).unwrap();
+        save_unbondings(&mut storage, now + 10, &unbonding(20)).unwrap();
+
+        assert_eq!(released_in_block(&storage, &env).unwrap(), Uint128::zero());
+        assert_eq!(
+            clean_unbonding(&mut storage, &env).unwrap(),
+            Uint128::new(150)
+        );
+        assert_eq!(
+            released_in_block(&storage, &env).unwrap(),
+            Uint128::new(150)
+        );
+        // cleaning again in the same block doesn't change anything
+        assert_eq!(
+            clean_unbonding(&mut storage, &env).unwrap(),
+            Uint128::zero()
+        );
+        assert_eq!(
+            released_in_block(&storage, &env).unwrap(),
+            Uint128::new(150)
+        );
+
+        // in the next block, the tokens arrived
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(10);
+        assert_eq!(released_in_block(&storage, &env).unwrap(), Uint128::zero());
+        assert_eq!(
+            clean_unbonding(&mut storage, &env).unwrap(),
+            Uint128::new(20)
+        );
+        assert_eq!(released_in_block(&storage, &env).unwrap(), Uint128::new(20));
+    }
+
+    #[test]
+    fn v1_unbondings_are_upgraded_on_read() {
+        let mut storage = cosmwasm_std::testing::MockStorage::new();
+        let env = cosmwasm_std::testing::mock_env();
+        let now = env.block.time.seconds();
+        let v1 = |amount: u128| {
+            VersionedUnbondings::V1(vec![UnbondingV1 {
+                amount: amount.into(),
+                validator: "val1".to_string(),
+            }])
+        };
+        UNBONDING.save(&mut storage, now - 10, &v1(100)).unwrap();
+        UNBONDING.save(&mut storage, now + 10, &v1(20)).unwrap();
+
+        // every read path sees the latest format, with an unknown start
+        assert_eq!(
+            load_unbondings(&storage, now + 10).unwrap(),
+            Some(vec![Unbonding {
+                amount: Uint128::new(20),
+                validator: "val1".to_string(),
+                started_at: 0,
+            }])
+        );
+        assert_eq!(
+            unbondings_expiring_between(&storage, now - 20, now + 20)
+                .collect::<StdResult<Vec<_>>>()
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(count_unbonding(&storage, &env).unwrap(), Uint128::new(100));
+        assert_eq!(
+            clean_unbonding(&mut storage, &env).unwrap(),
+            Uint128::new(100)
+        );
+
+        // reading does not rewrite the entry, the next change does
+        assert!(matches!(
+            UNBONDING.load(&storage, now + 10).unwrap(),
+            VersionedUnbondings::V1(_)
+        ));
+        let mut unbondings = load_unbondings(&storage, now + 10).unwrap().unwrap();
+        unbondings[0].amount = Uint128::new(15);
+        save_unbondings(&mut storage, now + 10, &unbondings).unwrap();
+        assert_eq!(
+            UNBONDING.load(&storage, now + 10).unwrap(),
+            VersionedUnbondings::V2(unbondings)
+        );
+    }
+}
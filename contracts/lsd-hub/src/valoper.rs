@@ -0,0 +1,126 @@
+//! Format checks for validator operator addresses, so valsets with account addresses or typos are rejected
+//! right away instead of failing once the staking messages execute
+use crate::ContractError;
+
+/// The characters of the data part of a bech32 address
+const BECH32_CHARSET: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+/// A 20 byte address plus the 6 character checksum
+const MIN_DATA_LEN: usize = 38;
+/// The longest address bech32 allows
+const MAX_ADDRESS_LEN: usize = 90;
+
+/// Returns whether `address` looks like a bech32 encoded validator operator address with the given `prefix`,
+/// e.g. "junovaloper1...". The checksum is not verified.
+/// An empty `prefix` accepts any address, for contracts migrated from before the prefix was configured.
+pub fn is_valid_valoper(address: &str, prefix: &str) -> bool {
+    if prefix.is_empty() {
+        return true;
+    }
+    let data = match address
+        .strip_prefix(prefix)
+        .and_then(|rest| rest.strip_prefix('1'))
+    {
+        Some(data) => data,
+        None => return false,
+    };
+    address.len() <= MAX_ADDRESS_LEN
+        && data.len() >= MIN_DATA_LEN
+        && data.chars().all(|c| BECH32_CHARSET.contains(c))
+}
+
+/// Fails with [`ContractError::InvalidValidatorAddress`] for the first of `validators`
+/// that is not a valid validator operator address, see [`is_valid_valoper`].
+pub fn ensure_valopers<'a>(
+    validators: impl IntoIterator<Item = &'a str>,
+    prefix: &str,
+) -> Result<(), ContractError> {
+    match validators
+        .into_iter()
+        .find(|address| !is_valid_valoper(address, prefix))
+    {
+        Some(address) => Err(ContractError::InvalidValidatorAddress {
+            address: address.to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PREFIX: &str = "junovaloper";
+
+    #[test]
+    fn valoper_addresses_are_accepted() {
+        for address in [
+            "junovaloper1t8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaaa",
+            "junovaloper196ax4vc0lwpxndu9dyhvca7jhxp70rmcqcnylw",
+            // 32 byte addresses
+            "junovaloper1t8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaaat8ehvswxjfn3ejzkjtnt",
+        ] {
+            assert!(is_valid_valoper(address, PREFIX), "{address}");
+        }
+        ensure_valopers(
+            [
+                "junovaloper1t8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaaa",
+                "junovaloper1y0us8xvsvfvqkk9c6nt5cfyu5au5tww2wsdcwk",
+            ],
+            PREFIX,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn account_addresses_are_rejected() {
+        for address in [
+            "juno1t8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaaa",
+            "junovalcons1t8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaaa",
+            "osmovaloper1t8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaaa",
+        ] {
+            assert!(!is_valid_valoper(address, PREFIX), "{address}");
+        }
+
+        let err = ensure_valopers(
+            [
+                "junovaloper1t8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaaa",
+                "juno1t8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaaa",
+            ],
+            PREFIX,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidValidatorAddress {
+                address: "juno1t8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaaa".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn garbage_is_rejected() {
+        for address in [
+            "",
+            "junovaloper",
+            "junovaloper1",
+            "junovaloper1t8ehvswx",
+            // 'b', 'i', 'o' and '1' are not part of the charset
+            "junovaloper1b8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaaa",
+            "junovaloper1t8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaa1",
+            // bech32 is not mixed case
+            "JUNOVALOPER1T8EHVSWXJFN3EJZKJTNTCYRQWVMVUKNZMVTAAA",
+            "junovaloper1T8EHVSWXJFN3EJZKJTNTCYRQWVMVUKNZMVTAAA",
+            " junovaloper1t8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaaa",
+            "junovaloper1t8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaaa ",
+            &format!("junovaloper1{}", "q".repeat(80)),
+        ] {
+            assert!(!is_valid_valoper(address, PREFIX), "{address}");
+        }
+    }
+
+    #[test]
+    fn empty_prefix_accepts_anything() {
+        assert!(is_valid_valoper("testvaloper1", ""));
+        ensure_valopers(["juno1t8ehvswxjfn3ejzkjtntcyrqwvmvuknzmvtaaa", ""], "").unwrap();
+    }
+}
@@ -1,7 +1,7 @@
 use std::{cmp::Ordering, collections::BTreeMap};
 
-use crate::state::Supply;
-use cosmwasm_std::{coin, Decimal, StakingMsg, StdResult, Uint128};
+use crate::{msg::ValidatorConfig, state::Supply, ContractError};
+use cosmwasm_std::{coin, ensure_eq, Decimal, StakingMsg, Uint128};
 
 pub struct ValsetChange {
     pub messages: Vec<StakingMsg>,
@@ -11,11 +11,20 @@ pub struct ValsetChange {
 /// given the old balances and the target validator set.
 ///
 /// Please note that this assumes the old balances to be the actual staked tokens *without* undelegations.
+/// Returns an error if they don't add up to `supply.total_bonded`.
+///
+/// Validators in `max_delegations` get at most their cap, the rest goes to the validators without one
+/// (see [`spill_over_caps`]).
+///
+/// The messages only depend on the balances and weights, not on the order they are passed in:
+/// the validators are processed in address order, and the ones to redelegate from and to are sorted
+/// by amount (highest first), with the address breaking ties.
 pub fn valset_change_redelegation_messages<'a>(
     supply: &Supply,
     old_balances: impl Iterator<Item = (&'a String, Uint128)>,
     new_valset: impl Iterator<Item = (&'a String, Decimal)>,
-) -> StdResult<ValsetChange> {
+    max_delegations: &[(String, Uint128)],
+) -> Result<ValsetChange, ContractError> {
     let mut msgs: Vec<StakingMsg> = vec![];
 
     let mut delegate_from: Vec<(&String, Uint128)> = vec![];
@@ -23,15 +32,30 @@ pub fn valset_change_redelegation_messages<'a>(
 
     // collect sets into BTreeMap for faster lookup (and predictable order when collecting)
     let mut balances: BTreeMap<_, _> = old_balances.collect();
-    // Map this to amounts here (as we only use as amount below)
-    let new_valset: BTreeMap<_, Uint128> = new_valset
-        .into_iter()
-        .map(|(addr, weight)| (addr, supply.total_bonded.mul_floor(weight)))
+    // Map this to amounts here (as we only use as amount below).
+    // In address order, so the rounding remainder of the caps does not depend on the order of the valset
+    let mut new_valset: Vec<_> = new_valset.collect();
+    new_valset.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let (addrs, weights): (Vec<_>, Vec<_>) = new_valset.into_iter().unzip();
+    let mut amounts: Vec<_> = weights
+        .iter()
+        .map(|weight| supply.total_bonded.mul_floor(*weight))
         .collect();
-
-    debug_assert_eq!(
+    let caps: BTreeMap<_, _> = max_delegations.iter().map(|(k, v)| (k, *v)).collect();
+    let caps: Vec<_> = addrs.iter().map(|addr| caps.get(addr).copied()).collect();
+    // whatever cannot be placed stays with the validators it is delegated to now
+    spill_over_caps(&mut amounts, &weights, &caps);
+    let new_valset: BTreeMap<_, Uint128> = addrs.into_iter().zip(amounts).collect();
+
+    // the new amounts are calculated from the total, so an inconsistency would redelegate the wrong amounts
+    let bonded = balances.values().copied().sum::<Uint128>();
+    ensure_eq!(
         supply.total_bonded,
-        balances.values().copied().sum::<Uint128>()
+        bonded,
+        ContractError::BondedMismatch {
+            total_bonded: supply.total_bonded,
+            bonded
+        }
     );
 
     for (old_validator, old_amount) in balances.clone() {
@@ -65,9 +89,10 @@ pub fn valset_change_redelegation_messages<'a>(
         }
     }
 
-    // sorting from highest to lowest to (probably) reduce the number of messages
-    delegate_from.sort_unstable_by(|a, b| b.1.cmp(&a.1));
-    delegate_to.sort_unstable_by(|a, b| b.1.cmp(&a.1));
+    // sorting from highest to lowest to (probably) reduce the number of messages,
+    // equal amounts by address to keep the order of the messages deterministic
+    delegate_from.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    delegate_to.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
 
     // now i have two lists of validators to delegate from and to
     // i need to compute the amount to delegate from each validator
@@ -107,6 +132,111 @@ pub fn valset_change_redelegation_messages<'a>(
     })
 }
 
+/// Returns `balances` after the redelegations in `messages`, without the validators that have nothing left
+pub fn apply_redelegations(
+    balances: Vec<(String, Uint128)>,
+    messages: &[StakingMsg],
+) -> Vec<(String, Uint128)> {
+    let mut balances: BTreeMap<_, _> = balances.into_iter().collect();
+    for msg in messages {
+        if let StakingMsg::Redelegate {
+            src_validator,
+            dst_validator,
+            amount,
+        } = msg
+        {
+            *balances
+                .get_mut(src_validator)
+                .expect("redelegated from address with no stake") -= amount.amount;
+            *balances.entry(dst_validator.clone()).or_default() += amount.amount;
+        }
+    }
+    balances.into_iter().filter(|(_, b)| !b.is_zero()).collect()
+}
+
+/// Splits the validators into their weights and the caps of the validators that have one
+pub fn split_validator_configs(
+    validators: Vec<ValidatorConfig>,
+) -> (Vec<(String, Decimal)>, Vec<(String, Uint128)>) {
+    let max_delegations = validators
+        .iter()
+        .filter_map(|v| v.max_delegation.map(|cap| (v.address.clone(), cap)))
+        .collect();
+    let weights = validators
+        .into_iter()
+        .map(|v| (v.address, v.weight))
+        .collect();
+    (weights, max_delegations)
+}
+
+/// Lowers the `amounts` above their validator's cap to the cap and adds the excess to the validators
+/// without a cap, split by their `weights`. The slices are in the same order.
+/// Returns the part of the excess that could not be placed, because no validator without a cap has any weight.
+pub fn spill_over_caps(
+    amounts: &mut [Uint128],
+    weights: &[Decimal],
+    caps: &[Option<Uint128>],
+) -> Uint128 {
+    let mut excess = Uint128::zero();
+    for (amount, cap) in amounts.iter_mut().zip(caps) {
+        if let Some(cap) = cap {
+            if *amount > *cap {
+                excess += *amount - *cap;
+                *amount = *cap;
+            }
+        }
+    }
+
+    let uncapped: Vec<_> = weights
+        .iter()
+        .zip(caps)
+        .enumerate()
+        .filter(|(_, (weight, cap))| cap.is_none() && !weight.is_zero())
+        .map(|(i, (weight, _))| (i, *weight))
+        .collect();
+    let uncapped_weight: Decimal = uncapped.iter().map(|(_, weight)| weight).sum();
+    if excess.is_zero() || uncapped_weight.is_zero() {
+        return excess;
+    }
+
+    let mut spilled = Uint128::zero();
+    for (i, weight) in &uncapped {
+        let share = excess.multiply_ratio(weight.atomics(), uncapped_weight.atomics());
+        amounts[*i] += share;
+        spilled += share;
+    }
+    // the first one gets the rounding remainder
+    amounts[uncapped[0].0] += excess - spilled;
+    Uint128::zero()
+}
+
+/// Sets the weights of the `removed` validators to zero and scales up the others, so they add up to 1 again.
+/// The rounding remainder goes to the first validator that still has weight.
+/// Returns `false` and leaves the weights unchanged if no weight would be left.
+pub fn remove_weights(validators: &mut [(String, Decimal)], removed: &[&str]) -> bool {
+    let remaining: Decimal = validators
+        .iter()
+        .filter(|(addr, _)| !removed.contains(&addr.as_str()))
+        .map(|(_, weight)| *weight)
+        .sum();
+    if remaining.is_zero() {
+        return false;
+    }
+
+    for (addr, weight) in validators.iter_mut() {
+        *weight = if removed.contains(&addr.as_str()) {
+            Decimal::zero()
+        } else {
+            Decimal::from_ratio(weight.atomics(), remaining.atomics())
+        };
+    }
+    let total: Decimal = validators.iter().map(|(_, weight)| *weight).sum();
+    if let Some((_, weight)) = validators.iter_mut().find(|(_, weight)| !weight.is_zero()) {
+        *weight = *weight + Decimal::one() - total;
+    }
+    true
+}
+
 fn redelegate_msg(
     from: impl Into<String>,
     to: impl Into<String>,
@@ -161,6 +291,7 @@ mod tests {
             total_bonded: 1000u128.into(),
             claims: Uint128::zero(),
             total_unbonding: Uint128::zero(),
+            deferred_claim_shares: Uint128::zero(),
         };
 
         let mut balances = vec![
@@ -180,6 +311,56 @@ mod tests {
         assert_eq!(balances, new_balances, "should be a noop");
     }
 
+    #[test]
+    fn messages_do_not_depend_on_input_order() {
+        let supply = Supply {
+            bond_denom: "FUN".to_string(),
+            issued: 1_000_001u128.into(),
+            total_bonded: 1_000_001u128.into(),
+            claims: Uint128::zero(),
+            total_unbonding: Uint128::zero(),
+            deferred_claim_shares: Uint128::zero(),
+        };
+        let old_balances = vec![
+            ("a".to_string(), Uint128::new(250_000)),
+            ("b".to_string(), Uint128::new(250_001)),
+            ("c".to_string(), Uint128::new(250_000)),
+            ("f".to_string(), Uint128::new(250_000)),
+        ];
+        let third = Decimal::from_ratio(1u128, 3u128);
+        let new_valset = vec![
+            ("b".to_string(), third),
+            ("d".to_string(), third),
+            ("e".to_string(), third),
+        ];
+        // a, c and f are left with the same amount, d is capped, so a part of it spills over to the others
+        let max_delegations = vec![("d".to_string(), Uint128::new(300_000))];
+
+        let messages = |balances: &[(String, Uint128)], valset: &[(String, Decimal)]| {
+            let change = valset_change_redelegation_messages(
+                &supply,
+                balances.iter().map(|(v, b)| (v, *b)),
+                valset.iter().map(|(v, w)| (v, *w)),
+                &max_delegations,
+            )
+            .unwrap();
+            cosmwasm_std::to_json_vec(&change.messages).unwrap()
+        };
+
+        let expected = messages(&old_balances, &new_valset);
+        assert_eq!(messages(&old_balances, &new_valset), expected);
+
+        let mut permuted_balances = old_balances.clone();
+        permuted_balances.reverse();
+        let permuted_valset = vec![
+            new_valset[2].clone(),
+            new_valset[0].clone(),
+            new_valset[1].clone(),
+        ];
+        assert_eq!(messages(&permuted_balances, &permuted_valset), expected);
+        assert_eq!(messages(&old_balances, &permuted_valset), expected);
+    }
+
     #[test]
     fn rounding() {
         let supply = Supply {
@@ -188,6 +369,7 @@ mod tests {
             total_bonded: 4444u128.into(),
             claims: Uint128::zero(),
             total_unbonding: Uint128::zero(),
+            deferred_claim_shares: Uint128::zero(),
         };
 
         let initial_balances: HashMap<_, _> = vec![
@@ -228,6 +410,7 @@ mod tests {
             total_bonded: 1000u128.into(),
             claims: Uint128::zero(),
             total_unbonding: Uint128::zero(),
+            deferred_claim_shares: Uint128::zero(),
         };
         simulate_valset(
             &supply,
@@ -262,6 +445,7 @@ mod tests {
             total_bonded: 1000u128.into(),
             claims: Uint128::zero(),
             total_unbonding: Uint128::zero(),
+            deferred_claim_shares: Uint128::zero(),
         };
 
         // mock initial split of 500 tokens each
@@ -309,6 +493,118 @@ mod tests {
         );
     }
 
+    #[test]
+    fn mismatched_balances() {
+        let supply = Supply {
+            bond_denom: "FUN".to_string(),
+            issued: 1000u128.into(),
+            total_bonded: 1000u128.into(),
+            claims: Uint128::zero(),
+            total_unbonding: Uint128::zero(),
+            deferred_claim_shares: Uint128::zero(),
+        };
+
+        // a missed slashing, so the stored balances are lower than the total
+        let balances = vec![
+            ("a".to_string(), Uint128::new(500)),
+            ("b".to_string(), Uint128::new(450)),
+        ];
+        let new_valset = vec![("c".to_string(), Decimal::one())];
+
+        let err = valset_change_redelegation_messages(
+            &supply,
+            balances.iter().map(|(k, v)| (k, *v)),
+            new_valset.iter().map(|(k, v)| (k, *v)),
+            &[],
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            err,
+            ContractError::BondedMismatch {
+                total_bonded: Uint128::new(1000),
+                bonded: Uint128::new(950)
+            }
+        );
+    }
+
+    #[test]
+    fn capped_validator_spills_over() {
+        let supply = Supply {
+            bond_denom: "FUN".to_string(),
+            issued: 1000u128.into(),
+            total_bonded: 1000u128.into(),
+            claims: Uint128::zero(),
+            total_unbonding: Uint128::zero(),
+            deferred_claim_shares: Uint128::zero(),
+        };
+
+        let balances = vec![("a".to_string(), Uint128::new(1000))];
+        let new_valset = vec![
+            ("a".to_string(), Decimal::percent(50)),
+            ("b".to_string(), Decimal::percent(30)),
+            ("c".to_string(), Decimal::percent(20)),
+        ];
+
+        let ValsetChange { new_balances, .. } = valset_change_redelegation_messages(
+            &supply,
+            balances.iter().map(|(k, v)| (k, *v)),
+            new_valset.iter().map(|(k, v)| (k, *v)),
+            &[("a".to_string(), Uint128::new(200))],
+        )
+        .unwrap();
+        // the 300 above the cap are split 3:2 between the others
+        assert_eq!(
+            new_balances,
+            vec![
+                ("a".to_string(), Uint128::new(200)),
+                ("b".to_string(), Uint128::new(480)),
+                ("c".to_string(), Uint128::new(320)),
+            ]
+        );
+
+        // if everyone is capped, the excess is returned
+        let mut amounts = vec![Uint128::new(500), Uint128::new(500)];
+        let unplaced = spill_over_caps(
+            &mut amounts,
+            &[Decimal::percent(50), Decimal::percent(50)],
+            &[Some(Uint128::new(400)), Some(Uint128::new(600))],
+        );
+        assert_eq!(unplaced, Uint128::new(100));
+        assert_eq!(amounts, vec![Uint128::new(400), Uint128::new(500)]);
+    }
+
+    #[test]
+    fn removed_weights_are_renormalized() {
+        let mut validators = vec![
+            ("a".to_string(), Decimal::percent(50)),
+            ("b".to_string(), Decimal::percent(20)),
+            ("c".to_string(), Decimal::percent(30)),
+        ];
+        assert!(remove_weights(&mut validators, &["b"]));
+        assert_eq!(validators[1].1, Decimal::zero());
+        assert_eq!(
+            validators.iter().map(|(_, w)| *w).sum::<Decimal>(),
+            Decimal::one()
+        );
+        assert_eq!(validators[2].1, Decimal::from_ratio(3u128, 8u128));
+
+        // 1/3 and 2/3 cannot be represented exactly, the first one gets the remainder
+        let mut validators = vec![
+            ("a".to_string(), Decimal::percent(20)),
+            ("b".to_string(), Decimal::percent(40)),
+            ("c".to_string(), Decimal::percent(40)),
+        ];
+        assert!(remove_weights(&mut validators, &["c"]));
+        assert_eq!(validators[0].1 + validators[1].1, Decimal::one(),);
+        assert_eq!(validators[1].1, Decimal::from_ratio(2u128, 3u128));
+
+        // nothing is changed if no weight would be left
+        let before = validators.clone();
+        assert!(!remove_weights(&mut validators, &["a", "b"]));
+        assert_eq!(validators, before);
+    }
+
     fn valset_to_balances(
         supply: &Supply,
         valset: Vec<(String, Decimal)>,
@@ -367,6 +663,7 @@ mod tests {
             supply,
             old_balances.iter().map(|(k, v)| (k, *v)),
             new_valset.iter().map(|(k, v)| (k, *v)),
+            &[],
         )
         .unwrap();
 